@@ -462,6 +462,104 @@ fn gen_instruction_data_impl(formats: &[Rc<InstructionFormat>], fmt: &mut Format
             fmt.line("}");
         });
         fmt.line("}");
+
+        fmt.empty_line();
+
+        fmt.doc_comment(r#"
+            Map this instruction's operands and entity references through
+            `mapper`, producing a new `InstructionData`.
+
+            This is used to copy instructions from one function into another,
+            where every value, block, and entity the instruction refers to
+            needs to be translated into the destination function.
+        "#);
+        fmt.line("pub fn map(&self, mapper: &mut impl InstructionMapper) -> Self {");
+        fmt.indent(|fmt| {
+            fmt.line("match *self {");
+            fmt.indent(|fmt| {
+                for format in formats {
+                    let name = format!("Self::{}", format.name);
+                    let mut members = vec!["opcode"];
+
+                    if format.has_value_list {
+                        members.push("args");
+                    } else if format.num_value_operands == 1 {
+                        members.push("arg");
+                    } else if format.num_value_operands > 0 {
+                        members.push("args");
+                    }
+
+                    match format.num_block_operands {
+                        0 => {}
+                        1 => {
+                            members.push("destination");
+                        }
+                        _ => {
+                            members.push("blocks");
+                        }
+                    };
+
+                    for field in &format.imm_fields {
+                        members.push(field.member);
+                    }
+                    let members = members.join(", ");
+
+                    fmtln!(fmt, "{}{{{}}} => {{", name, members ); // beware the moustaches
+                    fmt.indent(|fmt| {
+                        fmtln!(fmt, "Self::{} {{", format.name);
+                        fmt.indent(|fmt| {
+                            fmtln!(fmt, "opcode,");
+
+                            if format.has_value_list {
+                                fmtln!(fmt, "args: mapper.map_value_list(args),");
+                            } else if format.num_value_operands == 1 {
+                                fmtln!(fmt, "arg: mapper.map_value(arg),");
+                            } else if format.num_value_operands > 0 {
+                                let args = (0..format.num_value_operands)
+                                    .map(|i| format!("mapper.map_value(args[{}])", i))
+                                    .collect::<Vec<_>>()
+                                    .join(", ");
+                                fmtln!(fmt, "args: [{}],", args);
+                            }
+
+                            match format.num_block_operands {
+                                0 => {}
+                                1 => {
+                                    fmtln!(fmt, "destination: mapper.map_block_call(destination),");
+                                }
+                                2 => {
+                                    fmtln!(fmt, "blocks: [mapper.map_block_call(blocks[0]), mapper.map_block_call(blocks[1])],");
+                                }
+                                _ => panic!("Too many block targets in instruction"),
+                            }
+
+                            for field in &format.imm_fields {
+                                let method = match field.kind.rust_type {
+                                    "ir::StackSlot" => Some("map_stack_slot"),
+                                    "ir::DynamicStackSlot" => Some("map_dynamic_stack_slot"),
+                                    "ir::GlobalValue" => Some("map_global_value"),
+                                    "ir::SigRef" => Some("map_sig_ref"),
+                                    "ir::FuncRef" => Some("map_func_ref"),
+                                    "ir::JumpTable" => Some("map_jump_table"),
+                                    "ir::Table" => Some("map_table"),
+                                    "ir::Constant" => Some("map_constant"),
+                                    "ir::Immediate" => Some("map_immediate"),
+                                    _ => None,
+                                };
+                                match method {
+                                    Some(method) => fmtln!(fmt, "{}: mapper.{}({}),", field.member, method, field.member),
+                                    None => fmtln!(fmt, "{},", field.member),
+                                }
+                            }
+                        });
+                        fmtln!(fmt, "}");
+                    });
+                    fmtln!(fmt, "}");
+                }
+            });
+            fmt.line("}");
+        });
+        fmt.line("}");
     });
     fmt.line("}");
 }
@@ -221,6 +221,49 @@ pub(crate) fn define() -> SettingGroup {
         false,
     );
 
+    settings.add_bool(
+        "enable_hot_cold_splitting",
+        "Emit cold blocks into a separate part of the generated code.",
+        r#"
+            Cold blocks are normally placed at the end of the function body.
+            With this option, they are instead emitted as a separate cold part
+            of the compiled code, so that an embedder can place the cold parts
+            of all of its functions away from their hot parts for better
+            instruction-cache density.
+
+            Jumps between the two parts are not resolved by Cranelift: they
+            are reported as relocations which the embedder must apply once it
+            has placed both parts. Embedders that don't do this must leave this
+            option disabled, which is the default.
+        "#,
+        false,
+    );
+
+    settings.add_bool(
+        "enable_shrink_wrapping",
+        "Set up the stack frame only on the paths of a function that need it.",
+        r#"
+            Normally the prologue, which sets up the frame and saves clobbered
+            callee-saved registers, is placed at the start of the function. With
+            this option, on backends that support it, the prologue is instead
+            placed at the start of the block which dominates all blocks that
+            need the frame, so that early-exit paths which don't need it can
+            return without setting it up.
+
+            The frame is only ever torn down by the returns, not at a block
+            that post-dominates the blocks that need it. So the prologue can
+            only be placed at a block whose dominated blocks are left just by
+            returning; if every such block other than the entry block reaches
+            the fast path again, e.g. a slow path which rejoins it, the prologue
+            stays at the start of the function.
+
+            This has no effect when frame pointers are preserved, because the
+            frame pointer chain would then be missing this function on the
+            early-exit paths, nor when hot-cold splitting is enabled.
+        "#,
+        false,
+    );
+
     // Stack probing options.
 
     settings.add_bool(
@@ -14,6 +14,7 @@ use crate::dce::do_dce;
 use crate::dominator_tree::DominatorTree;
 use crate::egraph::EgraphPass;
 use crate::flowgraph::ControlFlowGraph;
+use crate::inline::{do_inline, Inline, InlineBudget, InlinedCall};
 use crate::ir::Function;
 use crate::isa::TargetIsa;
 use crate::legalizer::simple_legalize;
@@ -264,6 +265,27 @@ impl Context {
         Ok(())
     }
 
+    /// Inline direct calls in the function, with callee bodies provided by
+    /// `inliner` and within `budget`.
+    ///
+    /// Cranelift can't find callee bodies by itself, so this isn't part of
+    /// `optimize`; embedders call it before compiling the function. Returns the
+    /// calls that were inlined.
+    pub fn inline<'a, FOI: Into<FlagsOrIsa<'a>>>(
+        &mut self,
+        fisa: FOI,
+        inliner: &mut dyn Inline,
+        budget: InlineBudget,
+    ) -> CodegenResult<Vec<InlinedCall>> {
+        // Inlining changes the CFG.
+        self.domtree.clear();
+        self.loop_analysis.clear();
+
+        let inlined = do_inline(&mut self.func, inliner, budget);
+        self.verify_if(fisa)?;
+        Ok(inlined)
+    }
+
     /// Perform constant-phi removal on the function.
     pub fn remove_constant_phis<'a, FOI: Into<FlagsOrIsa<'a>>>(
         &mut self,
@@ -362,7 +384,16 @@ impl Context {
         );
         pass.run();
         log::debug!("egraph stats: {:?}", pass.stats);
+        let bounds_checks_removed = pass.stats.bounds_checks_removed;
         trace!("After egraph optimization:\n{}", self.func.display());
+
+        // Removing bounds checks turns branches to trap blocks into jumps,
+        // which leaves the trap blocks unreachable.
+        if bounds_checks_removed > 0 {
+            self.compute_cfg();
+            self.compute_domtree();
+            eliminate_unreachable_code(&mut self.func, &mut self.cfg, &self.domtree);
+        }
         Ok(())
     }
 }
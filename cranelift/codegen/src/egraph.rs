@@ -4,6 +4,7 @@ use crate::alias_analysis::{AliasAnalysis, LastStores};
 use crate::ctxhash::{CtxEq, CtxHash, CtxHashMap};
 use crate::cursor::{Cursor, CursorPosition, FuncCursor};
 use crate::dominator_tree::DominatorTree;
+use crate::egraph::bounds_checks::BoundsChecks;
 use crate::egraph::domtree::DomTreeWithChildren;
 use crate::egraph::elaborate::Elaborator;
 use crate::fx::FxHashSet;
@@ -21,6 +22,7 @@ use cranelift_entity::packed_option::ReservedValue;
 use cranelift_entity::SecondaryMap;
 use std::hash::Hasher;
 
+mod bounds_checks;
 mod cost;
 mod domtree;
 mod elaborate;
@@ -454,6 +456,10 @@ impl<'a> EgraphPass<'a> {
         // instructions) be differentiated only on the type.
         let mut effectful_gvn_map: ScopedHashMap<(Type, InstructionData), Value> =
             ScopedHashMap::new();
+        // Bounds checks known to pass, for removing redundant
+        // checks. Scoped like the effectful GVN map, as a check only
+        // tells us something about the blocks it dominates.
+        let mut bounds_checks = BoundsChecks::new(cursor.func);
 
         // In domtree preorder, visit blocks. (TODO: factor out an
         // iterator from this and elaborator.)
@@ -472,6 +478,7 @@ impl<'a> EgraphPass<'a> {
                     block_stack
                         .extend(self.domtree_children.children(block).map(StackEntry::Visit));
                     effectful_gvn_map.increment_depth();
+                    bounds_checks.enter_block(block);
 
                     trace!("Processing block {}", block);
                     cursor.set_position(CursorPosition::Before(block));
@@ -505,6 +512,14 @@ impl<'a> EgraphPass<'a> {
                             new_value
                         });
 
+                        // Remove bounds checks which an earlier check
+                        // has made redundant. This may turn an overflow
+                        // check into a pure add, which is then
+                        // optimized as such below.
+                        if bounds_checks.optimize_inst(cursor.func, inst) {
+                            self.stats.bounds_checks_removed += 1;
+                        }
+
                         // Build a context for optimization, with borrows of
                         // state. We can't invoke a method on `self` because
                         // we've borrowed `self.func` mutably (as
@@ -543,6 +558,7 @@ impl<'a> EgraphPass<'a> {
                 }
                 StackEntry::Pop => {
                     effectful_gvn_map.decrement_depth();
+                    bounds_checks.leave_block();
                 }
             }
         }
@@ -649,6 +665,7 @@ pub(crate) struct Stats {
     pub(crate) pure_inst_deduped: u64,
     pub(crate) skeleton_inst: u64,
     pub(crate) alias_analysis_removed: u64,
+    pub(crate) bounds_checks_removed: u64,
     pub(crate) new_inst: u64,
     pub(crate) union: u64,
     pub(crate) subsume: u64,
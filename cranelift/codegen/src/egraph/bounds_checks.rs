@@ -0,0 +1,516 @@
+//! Redundant bounds-check elimination.
+//!
+//! Bounds checks on memory accesses, such as those emitted for wasm heaps,
+//! take the form of a branch to a block which just traps (conditional traps
+//! are legalized into this form before the egraph pass runs):
+//!
+//! ```text
+//!     v2 = uadd_overflow_trap v0, v1, heap_oob  ; v1 = 8
+//!     v3 = icmp ugt v2, bound
+//!     brif v3, trap_block, continue_block
+//! ```
+//!
+//! Past such a check, in `continue_block` and every block it dominates, we
+//! know that `v0 + 8 <= bound`. A later check that `v0 + k <= bound` with
+//! `k <= 8` can't fail, so its branch is replaced with a jump, and its
+//! overflow check (if any) becomes a plain `iadd`.
+//!
+//! This runs as part of the egraph pass, where the operands of each check
+//! have already been through GVN, so checks are recognized as checking the
+//! same index and bound even when their computations were duplicated.
+//!
+//! Bounds which can change, such as that of a dynamic heap, are loaded from
+//! memory by each check. Alias analysis doesn't merge these loads, as it
+//! treats the trapping instructions in between as writes to memory, so
+//! loads of a bound are instead recognized as loading the same value when
+//! they read the same location and nothing can have written to it in between.
+
+use crate::fx::{FxHashMap, FxHashSet};
+use crate::inst_predicates::visit_block_succs;
+use crate::ir::condcodes::{CondCode, IntCC};
+use crate::ir::immediates::Offset32;
+use crate::ir::{
+    Block, DataFlowGraph, Function, Inst, InstBuilder, InstructionData, MemFlags, Opcode, Type,
+    Value, ValueDef,
+};
+use crate::packed_option::PackedOption;
+use crate::trace;
+use alloc::vec::Vec;
+use cranelift_entity::SecondaryMap;
+use smallvec::{smallvec, SmallVec};
+
+/// The right-hand side of a known `index + offset <= bound` relation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Bound {
+    Value(Value),
+    Load(LoadKey),
+    Const(u64),
+}
+
+/// Identifies the value loaded by a load: loads with the same key load the
+/// same value, even if their results are different SSA values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct LoadKey {
+    address: Value,
+    offset: Offset32,
+    ty: Type,
+    /// The last instruction before the load which may have written to the
+    /// loaded memory.
+    last_write: PackedOption<Inst>,
+}
+
+/// For each category of memory, the last instruction which may have written
+/// to it, as in alias analysis. Unlike there, instructions which can trap
+/// but don't otherwise access memory don't count as writes: they don't
+/// change the values loaded after them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct LastWrites {
+    heap: PackedOption<Inst>,
+    table: PackedOption<Inst>,
+    vmctx: PackedOption<Inst>,
+    other: PackedOption<Inst>,
+}
+
+impl LastWrites {
+    fn get(&self, flags: MemFlags) -> PackedOption<Inst> {
+        if flags.heap() {
+            self.heap
+        } else if flags.table() {
+            self.table
+        } else if flags.vmctx() {
+            self.vmctx
+        } else {
+            self.other
+        }
+    }
+
+    fn update(&mut self, func: &Function, inst: Inst) {
+        let opcode = func.dfg.insts[inst].opcode();
+        let writes_all = opcode.is_call()
+            || matches!(
+                opcode,
+                Opcode::AtomicRmw
+                    | Opcode::AtomicCas
+                    | Opcode::AtomicLoad
+                    | Opcode::AtomicStore
+                    | Opcode::Fence
+            );
+        match func.dfg.insts[inst].memflags() {
+            Some(flags) if opcode.can_store() && !writes_all => {
+                if flags.heap() {
+                    self.heap = inst.into();
+                } else if flags.table() {
+                    self.table = inst.into();
+                } else if flags.vmctx() {
+                    self.vmctx = inst.into();
+                } else {
+                    self.other = inst.into();
+                }
+            }
+            _ if opcode.can_store() || writes_all => {
+                *self = LastWrites {
+                    heap: inst.into(),
+                    table: inst.into(),
+                    vmctx: inst.into(),
+                    other: inst.into(),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn meet_from(&mut self, other: &LastWrites, loc: Inst) {
+        let meet = |a: PackedOption<Inst>, b: PackedOption<Inst>| {
+            if a == b {
+                a
+            } else {
+                loc.into()
+            }
+        };
+        self.heap = meet(self.heap, other.heap);
+        self.table = meet(self.table, other.table);
+        self.vmctx = meet(self.vmctx, other.vmctx);
+        self.other = meet(self.other, other.other);
+    }
+}
+
+/// A known relation `index + offset <= bound`, where the addition doesn't
+/// overflow.
+#[derive(Clone, Copy, Debug)]
+struct Fact {
+    index: Value,
+    offset: u64,
+    bound: Bound,
+}
+
+impl Fact {
+    /// Does `index + offset <= bound` imply `index + other_offset <=
+    /// other_bound`?
+    fn implies(&self, other_offset: u64, other_bound: Bound) -> bool {
+        match (self.bound, other_bound) {
+            (a, b) if a == b => other_offset <= self.offset,
+            // `index <= c1 - offset`, so `index + other_offset <= c1 - offset
+            // + other_offset`, which must be at most `c2`.
+            (Bound::Const(c1), Bound::Const(c2)) => {
+                u128::from(c1) + u128::from(other_offset)
+                    <= u128::from(c2) + u128::from(self.offset)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Known bounds facts, scoped to the domtree traversal of the egraph pass.
+pub(crate) struct BoundsChecks {
+    /// The number of predecessors of each block.
+    preds: SecondaryMap<Block, u32>,
+    /// Facts established by a check in a block's only predecessor, which hold
+    /// in that block once we get to it.
+    pending: FxHashMap<Block, SmallVec<[Fact; 2]>>,
+    /// The facts that hold at the current point, by index.
+    facts: FxHashMap<Value, SmallVec<[Fact; 2]>>,
+    /// The indices facts were pushed for, in order, to undo them.
+    log: Vec<Value>,
+    /// The length of `log` when each enclosing block was entered.
+    scopes: Vec<usize>,
+    /// The last writes to memory at the start of each block.
+    block_writes: FxHashMap<Block, LastWrites>,
+    /// The last writes to memory at the current point.
+    writes: LastWrites,
+    /// The keys of the loads seen so far, by result.
+    loads: FxHashMap<Value, LoadKey>,
+}
+
+impl BoundsChecks {
+    pub(crate) fn new(func: &Function) -> Self {
+        let mut preds = SecondaryMap::with_capacity(func.dfg.num_blocks());
+        for block in func.layout.blocks() {
+            if let Some(inst) = func.layout.last_inst(block) {
+                for dest in func.dfg.insts[inst].branch_destination(&func.dfg.jump_tables) {
+                    preds[dest.block(&func.dfg.value_lists)] += 1;
+                }
+            }
+        }
+        Self {
+            preds,
+            pending: FxHashMap::default(),
+            facts: FxHashMap::default(),
+            log: vec![],
+            scopes: vec![],
+            block_writes: block_writes(func),
+            writes: LastWrites::default(),
+            loads: FxHashMap::default(),
+        }
+    }
+
+    /// Enter `block` in the domtree traversal.
+    pub(crate) fn enter_block(&mut self, block: Block) {
+        self.scopes.push(self.log.len());
+        self.writes = self.block_writes.get(&block).copied().unwrap_or_default();
+        if let Some(facts) = self.pending.remove(&block) {
+            for fact in facts {
+                trace!(" -> in {}: {:?}", block, fact);
+                self.facts.entry(fact.index).or_default().push(fact);
+                self.log.push(fact.index);
+            }
+        }
+    }
+
+    /// Leave the most recently entered block in the domtree traversal.
+    pub(crate) fn leave_block(&mut self) {
+        let len = self.scopes.pop().unwrap();
+        for index in self.log.drain(len..) {
+            self.facts.get_mut(&index).unwrap().pop();
+        }
+    }
+
+    /// Remove checks from `inst` that are made redundant by an earlier check,
+    /// returning whether any were removed.
+    ///
+    /// The arguments of `inst` must already have been rewritten to their
+    /// optimized values, and the instructions of a block must be passed in
+    /// order.
+    pub(crate) fn optimize_inst(&mut self, func: &mut Function, inst: Inst) -> bool {
+        let removed = self.remove_checks(func, inst);
+        if let InstructionData::Load {
+            opcode: Opcode::Load,
+            arg,
+            flags,
+            offset,
+        } = func.dfg.insts[inst]
+        {
+            let result = func.dfg.first_result(inst);
+            let key = LoadKey {
+                address: arg,
+                offset,
+                ty: func.dfg.value_type(result),
+                last_write: self.writes.get(flags),
+            };
+            self.loads.insert(result, key);
+        }
+        self.writes.update(func, inst);
+        removed
+    }
+
+    fn remove_checks(&mut self, func: &mut Function, inst: Inst) -> bool {
+        match func.dfg.insts[inst] {
+            InstructionData::IntAddTrap {
+                opcode: Opcode::UaddOverflowTrap,
+                args: [x, y],
+                ..
+            } => {
+                // `x + k` can't overflow if a fact says that `x + k` or more
+                // is within some bound.
+                let safe = |index, offset| {
+                    self.facts
+                        .get(&index)
+                        .map_or(false, |facts| facts.iter().any(|f| offset <= f.offset))
+                };
+                let safe = const_value(&func.dfg, y).map_or(false, |k| safe(x, k))
+                    || const_value(&func.dfg, x).map_or(false, |k| safe(y, k));
+                if safe {
+                    trace!(" -> overflow check {} is redundant", inst);
+                    func.dfg.replace(inst).iadd(x, y);
+                }
+                safe
+            }
+
+            InstructionData::Brif {
+                arg,
+                blocks: [then_call, else_call],
+                ..
+            } => {
+                let pool = &func.dfg.value_lists;
+                let then_block = then_call.block(pool);
+                let else_block = else_call.block(pool);
+                let (cont_call, holds) = match (
+                    is_trap_block(func, then_block) && then_call.args_slice(pool).is_empty(),
+                    is_trap_block(func, else_block) && else_call.args_slice(pool).is_empty(),
+                ) {
+                    (true, false) => (else_call, false),
+                    (false, true) => (then_call, true),
+                    _ => return false,
+                };
+                let Some((lhs, extra, bound)) = comparison(&func.dfg, &self.loads, arg, holds)
+                else {
+                    return false;
+                };
+
+                let redundant = self.is_known(&func.dfg, lhs, extra, bound);
+                let cont_block = cont_call.block(pool);
+                if redundant {
+                    trace!(" -> bounds check {} is redundant", inst);
+                    let args: SmallVec<[Value; 4]> = cont_call.args_slice(pool).into();
+                    func.dfg.replace(inst).jump(cont_block, &args);
+                } else if self.preds[cont_block] == 1 {
+                    let facts = facts_from(&func.dfg, lhs, extra, bound);
+                    self.pending.insert(cont_block, facts);
+                }
+                redundant
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Is `lhs + extra <= bound` implied by the current facts?
+    fn is_known(&self, dfg: &DataFlowGraph, lhs: Value, extra: u64, bound: Bound) -> bool {
+        let known = |index: Value, offset: u64| {
+            self.facts.get(&index).map_or(false, |facts| {
+                facts.iter().any(|f| f.implies(offset, bound))
+            })
+        };
+        if known(lhs, extra) {
+            return true;
+        }
+
+        // Look through additions of constants to `lhs`. Whether or not they
+        // can overflow, a fact about the same index with a larger offset
+        // means that they don't.
+        defs(dfg, lhs)
+            .into_iter()
+            .any(|inst| match dfg.insts[inst] {
+                InstructionData::IntAddTrap {
+                    opcode: Opcode::UaddOverflowTrap,
+                    args: [x, y],
+                    ..
+                }
+                | InstructionData::Binary {
+                    opcode: Opcode::Iadd,
+                    args: [x, y],
+                } => {
+                    let known_plus =
+                        |index, k: u64| k.checked_add(extra).map_or(false, |o| known(index, o));
+                    const_value(dfg, y).map_or(false, |k| known_plus(x, k))
+                        || const_value(dfg, x).map_or(false, |k| known_plus(y, k))
+                }
+                _ => false,
+            })
+    }
+}
+
+/// The facts that follow from `lhs + extra <= bound`.
+fn facts_from(dfg: &DataFlowGraph, lhs: Value, extra: u64, bound: Bound) -> SmallVec<[Fact; 2]> {
+    let mut facts = smallvec![Fact {
+        index: lhs,
+        offset: extra,
+        bound,
+    }];
+
+    // `uadd_overflow_trap` doesn't wrap, so when `lhs` is `index + k` we know
+    // about `index` too. The same isn't true of `iadd`.
+    for inst in defs(dfg, lhs) {
+        if let InstructionData::IntAddTrap {
+            opcode: Opcode::UaddOverflowTrap,
+            args: [x, y],
+            ..
+        } = dfg.insts[inst]
+        {
+            let (index, k) = match (const_value(dfg, x), const_value(dfg, y)) {
+                (_, Some(k)) => (x, k),
+                (Some(k), _) => (y, k),
+                _ => continue,
+            };
+            if let Some(offset) = k.checked_add(extra) {
+                facts.push(Fact {
+                    index,
+                    offset,
+                    bound,
+                });
+            }
+        }
+    }
+    facts
+}
+
+/// Computes the last writes to memory at the start of each block of `func`.
+fn block_writes(func: &Function) -> FxHashMap<Block, LastWrites> {
+    let mut block_writes = FxHashMap::default();
+    let Some(entry) = func.layout.entry_block() else {
+        return block_writes;
+    };
+    block_writes.insert(entry, LastWrites::default());
+    let mut queue = vec![entry];
+    let mut queue_set = FxHashSet::default();
+    queue_set.insert(entry);
+    while let Some(block) = queue.pop() {
+        queue_set.remove(&block);
+        let mut writes = block_writes[&block];
+        for inst in func.layout.block_insts(block) {
+            writes.update(func, inst);
+        }
+        visit_block_succs(func, block, |_inst, succ, _from_table| {
+            let updated = match block_writes.get_mut(&succ) {
+                Some(succ_writes) => {
+                    let old = *succ_writes;
+                    let first_inst = func.layout.first_inst(succ).unwrap();
+                    succ_writes.meet_from(&writes, first_inst);
+                    *succ_writes != old
+                }
+                None => {
+                    block_writes.insert(succ, writes);
+                    true
+                }
+            };
+            if updated && queue_set.insert(succ) {
+                queue.push(succ);
+            }
+        });
+    }
+    block_writes
+}
+
+/// Decomposes the condition `condition` of a branch into `lhs + extra <=
+/// bound`, given that `holds` is its value on the branch's edge that doesn't
+/// trap.
+fn comparison(
+    dfg: &DataFlowGraph,
+    loads: &FxHashMap<Value, LoadKey>,
+    condition: Value,
+    holds: bool,
+) -> Option<(Value, u64, Bound)> {
+    defs(dfg, condition).into_iter().find_map(|inst| {
+        let (cc, x, y) = match dfg.insts[inst] {
+            InstructionData::IntCompare {
+                opcode: Opcode::Icmp,
+                cond,
+                args: [x, y],
+            } => (cond, x, bound(dfg, loads, y)),
+            InstructionData::IntCompareImm {
+                opcode: Opcode::IcmpImm,
+                cond,
+                arg,
+                imm,
+            } => {
+                let imm = mask(dfg.value_type(arg).bits(), imm.bits() as u64);
+                (cond, arg, Bound::Const(imm))
+            }
+            _ => return None,
+        };
+        // Constants are only tracked up to 64 bits.
+        if dfg.value_type(x).bits() > 64 {
+            return None;
+        }
+        let cc = if holds { cc } else { cc.inverse() };
+        match cc {
+            IntCC::UnsignedLessThanOrEqual => Some((x, 0, y)),
+            IntCC::UnsignedLessThan => Some((x, 1, y)),
+            _ => None,
+        }
+    })
+}
+
+fn bound(dfg: &DataFlowGraph, loads: &FxHashMap<Value, LoadKey>, value: Value) -> Bound {
+    match (const_value(dfg, value), loads.get(&value)) {
+        (Some(c), _) => Bound::Const(c),
+        (None, Some(&key)) => Bound::Load(key),
+        (None, None) => Bound::Value(value),
+    }
+}
+
+/// The value of `value` as an unsigned constant, if it is one.
+fn const_value(dfg: &DataFlowGraph, value: Value) -> Option<u64> {
+    defs(dfg, value)
+        .into_iter()
+        .find_map(|inst| match dfg.insts[inst] {
+            InstructionData::UnaryImm {
+                opcode: Opcode::Iconst,
+                imm,
+            } => Some(mask(dfg.value_type(value).bits(), imm.bits() as u64)),
+            _ => None,
+        })
+}
+
+fn mask(bits: u32, value: u64) -> u64 {
+    if bits >= 64 {
+        value
+    } else {
+        value & ((1 << bits) - 1)
+    }
+}
+
+/// The instructions defining `value` and the other members of its eclass.
+fn defs(dfg: &DataFlowGraph, value: Value) -> SmallVec<[Inst; 4]> {
+    let mut insts = SmallVec::new();
+    let mut stack: SmallVec<[Value; 4]> = smallvec![value];
+    while let Some(value) = stack.pop() {
+        match dfg.value_def(value) {
+            ValueDef::Result(inst, 0) => insts.push(inst),
+            ValueDef::Union(x, y) => {
+                stack.push(x);
+                stack.push(y);
+            }
+            _ => {}
+        }
+    }
+    insts
+}
+
+/// Does `block` do nothing but trap?
+fn is_trap_block(func: &Function, block: Block) -> bool {
+    func.dfg.block_params(block).is_empty()
+        && func
+            .layout
+            .first_inst(block)
+            .map_or(false, |inst| func.dfg.insts[inst].opcode() == Opcode::Trap)
+}
@@ -0,0 +1,632 @@
+//! Function inlining.
+//!
+//! Cranelift compiles one function at a time and knows nothing about the
+//! bodies of the functions it calls, so inlining is driven by the embedder:
+//! [`Context::inline`](crate::Context::inline) asks an [`Inline`]
+//! implementation for the body of each direct callee, and splices the bodies it
+//! is given into the caller within an [`InlineBudget`].
+//!
+//! Only the calls present in the caller when the pass starts are considered;
+//! calls in inlined bodies are left alone, so inlining recursive functions
+//! terminates. An embedder which wants deeper inlining can hand out callee
+//! bodies which have already had their own calls inlined.
+//!
+//! Inlined instructions keep the source locations of the callee. Instructions
+//! without one take the location of the call. For embedders whose source
+//! locations are unique across functions, such as offsets into a wasm module,
+//! this means code can still be attributed to the function it came from; the
+//! [`InlinedCall`]s returned by the pass record where each callee was inlined.
+
+use crate::dominator_tree::DominatorTree;
+use crate::entity::SecondaryMap;
+use crate::flowgraph::ControlFlowGraph;
+use crate::ir::{
+    self, AbiParam, ArgumentPurpose, Block, BlockCall, Function, GlobalValueData, Inst,
+    InstructionData, InstructionMapper, Opcode, Value, ValueList,
+};
+use crate::packed_option::PackedOption;
+use crate::timing;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use smallvec::SmallVec;
+
+/// What to do with a call, as decided by an [`Inline`] implementation.
+pub enum InlineCommand<'a> {
+    /// Leave the call as it is.
+    KeepCall,
+
+    /// Replace the call with a copy of this callee body.
+    Inline(Cow<'a, Function>),
+}
+
+/// The embedder's side of inlining: a source of callee bodies.
+pub trait Inline {
+    /// Decide what to do with `inst` in `caller`, which is a direct call of
+    /// `callee`.
+    ///
+    /// To inline the call, return the callee's body. It must have the
+    /// signature `callee` is declared with in `caller`. The external names and
+    /// symbols the body refers to are interpreted as they would be in the
+    /// caller, so they must mean the same thing in both functions.
+    fn inline(&mut self, caller: &Function, inst: Inst, callee: ir::FuncRef) -> InlineCommand<'_>;
+}
+
+/// Limits on how much code inlining may add to a function, counted in CLIF
+/// instructions.
+#[derive(Clone, Copy, Debug)]
+pub struct InlineBudget {
+    /// The largest callee that will be inlined.
+    pub max_callee_insts: usize,
+
+    /// The most instructions that inlining may add to the caller in total.
+    pub max_total_insts: usize,
+}
+
+/// A call which was replaced by the body of its callee.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlinedCall {
+    /// The name of the callee, as declared in the caller.
+    pub callee: ir::ExternalName,
+
+    /// The source location of the call in the caller.
+    pub srcloc: ir::SourceLoc,
+}
+
+/// Inline the direct calls in `func` for which `inliner` provides a body and
+/// which fit within `budget`.
+pub fn do_inline(
+    func: &mut Function,
+    inliner: &mut dyn Inline,
+    budget: InlineBudget,
+) -> Vec<InlinedCall> {
+    let _tt = timing::inline();
+
+    let calls: Vec<Inst> = func
+        .layout
+        .blocks()
+        .flat_map(|block| func.layout.block_insts(block))
+        .filter(|&inst| func.dfg.insts[inst].opcode() == Opcode::Call)
+        .collect();
+
+    let mut remaining = budget.max_total_insts;
+    let mut inlined = Vec::new();
+    for call in calls {
+        let func_ref = match func.dfg.insts[call] {
+            InstructionData::Call { func_ref, .. } => func_ref,
+            _ => unreachable!(),
+        };
+        let callee = match inliner.inline(func, call, func_ref) {
+            InlineCommand::KeepCall => continue,
+            InlineCommand::Inline(callee) => callee,
+        };
+        let size = callee
+            .layout
+            .blocks()
+            .map(|block| callee.layout.block_insts(block).count())
+            .sum::<usize>();
+        if size > budget.max_callee_insts || size > remaining {
+            continue;
+        }
+        if !can_inline(func, call, &callee) {
+            log::debug!(
+                "not inlining {}: unsupported callee",
+                func.dfg.display_inst(call)
+            );
+            continue;
+        }
+
+        let record = InlinedCall {
+            callee: func.dfg.ext_funcs[func_ref].name.clone(),
+            srcloc: func.srcloc(call),
+        };
+        Inliner::new(func, &callee).inline(call);
+        remaining -= size;
+        inlined.push(record);
+    }
+    inlined
+}
+
+/// Can `callee`'s body replace `call` in `func`?
+fn can_inline(func: &Function, call: Inst, callee: &Function) -> bool {
+    let sig = &func.dfg.signatures[func.dfg.call_signature(call).unwrap()];
+    let same_abi = |a: &[AbiParam], b: &[AbiParam]| {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b)
+                .all(|(a, b)| a.value_type == b.value_type && a.purpose == b.purpose)
+    };
+    if !same_abi(&sig.params, &callee.signature.params)
+        || !same_abi(&sig.returns, &callee.signature.returns)
+    {
+        return false;
+    }
+
+    if callee.layout.entry_block().is_none() || !callee.dynamic_stack_slots.is_empty() {
+        return false;
+    }
+
+    // Tail calls would have to return from the caller, and the frame and
+    // return address would be the caller's rather than the callee's.
+    let unsupported = callee
+        .layout
+        .blocks()
+        .flat_map(|block| callee.layout.block_insts(block))
+        .any(|inst| {
+            matches!(
+                callee.dfg.insts[inst].opcode(),
+                Opcode::ReturnCall
+                    | Opcode::ReturnCallIndirect
+                    | Opcode::GetFramePointer
+                    | Opcode::GetStackPointer
+                    | Opcode::GetReturnAddress
+            )
+        });
+    if unsupported {
+        return false;
+    }
+
+    // The callee's `vmctx` global value can only become the caller's if the
+    // call passes the caller's `vmctx` along.
+    let uses_vmctx = callee
+        .global_values
+        .values()
+        .any(|gv| matches!(gv, GlobalValueData::VMContext));
+    if uses_vmctx {
+        let Some(index) = callee
+            .signature
+            .special_param_index(ArgumentPurpose::VMContext)
+        else {
+            return false;
+        };
+        let Some(vmctx) = func.special_param(ArgumentPurpose::VMContext) else {
+            return false;
+        };
+        let arg = func.dfg.inst_args(call)[index];
+        if func.dfg.resolve_aliases(arg) != func.dfg.resolve_aliases(vmctx) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Copies a callee's body into a caller, translating each of the callee's
+/// entities into the caller as it is first used.
+struct Inliner<'a> {
+    func: &'a mut Function,
+    callee: &'a Function,
+    values: SecondaryMap<Value, PackedOption<Value>>,
+    blocks: SecondaryMap<Block, PackedOption<Block>>,
+    jump_tables: SecondaryMap<ir::JumpTable, PackedOption<ir::JumpTable>>,
+    stack_slots: SecondaryMap<ir::StackSlot, PackedOption<ir::StackSlot>>,
+    global_values: SecondaryMap<ir::GlobalValue, PackedOption<ir::GlobalValue>>,
+    sig_refs: SecondaryMap<ir::SigRef, PackedOption<ir::SigRef>>,
+    func_refs: SecondaryMap<ir::FuncRef, PackedOption<ir::FuncRef>>,
+    tables: SecondaryMap<ir::Table, PackedOption<ir::Table>>,
+    constants: SecondaryMap<ir::Constant, PackedOption<ir::Constant>>,
+    immediates: SecondaryMap<ir::Immediate, PackedOption<ir::Immediate>>,
+}
+
+impl<'a> Inliner<'a> {
+    fn new(func: &'a mut Function, callee: &'a Function) -> Self {
+        Self {
+            func,
+            callee,
+            values: SecondaryMap::new(),
+            blocks: SecondaryMap::new(),
+            jump_tables: SecondaryMap::new(),
+            stack_slots: SecondaryMap::new(),
+            global_values: SecondaryMap::new(),
+            sig_refs: SecondaryMap::new(),
+            func_refs: SecondaryMap::new(),
+            tables: SecondaryMap::new(),
+            constants: SecondaryMap::new(),
+            immediates: SecondaryMap::new(),
+        }
+    }
+
+    /// Replace `call` with the callee's body.
+    ///
+    /// The call's block is split at the call: the first half jumps to the
+    /// callee's entry block with the call's arguments, and the callee's
+    /// returns jump to the second half, whose parameters are the call's
+    /// results.
+    fn inline(mut self, call: Inst) {
+        let callee = self.callee;
+        let call_block = self.func.layout.inst_block(call).unwrap();
+        let call_srcloc = self.func.srcloc(call);
+        let args: SmallVec<[Value; 8]> = self.func.dfg.inst_args(call).into();
+        let results: SmallVec<[Value; 8]> = self.func.dfg.inst_results(call).into();
+
+        let return_block = self.func.dfg.make_block();
+        self.func.layout.split_block(return_block, call);
+        self.func.layout.remove_inst(call);
+        self.func.dfg.clear_results(call);
+        for result in results {
+            self.func.dfg.attach_block_param(return_block, result);
+        }
+
+        // Visit the callee in reverse postorder, so that every value is
+        // defined before it's used. Unreachable blocks are dropped.
+        let cfg = ControlFlowGraph::with_function(callee);
+        let domtree = DominatorTree::with_function(callee, &cfg);
+        let order: Vec<Block> = domtree.cfg_postorder().iter().rev().copied().collect();
+        for &block in &order {
+            let new_block = self.func.dfg.make_block();
+            for (&param, ty) in callee
+                .dfg
+                .block_params(block)
+                .iter()
+                .zip(callee.dfg.block_param_types(block))
+            {
+                let new_param = self.func.dfg.append_block_param(new_block, ty);
+                self.values[param] = new_param.into();
+            }
+            self.func.layout.insert_block(new_block, return_block);
+            self.blocks[block] = new_block.into();
+        }
+
+        let entry = self.map_block(callee.layout.entry_block().unwrap());
+        let destination = self.func.dfg.block_call(entry, &args);
+        let jump = self.func.dfg.make_inst(InstructionData::Jump {
+            opcode: Opcode::Jump,
+            destination,
+        });
+        self.func.layout.append_inst(jump, call_block);
+        self.func.set_srcloc(jump, call_srcloc);
+
+        for &block in &order {
+            let new_block = self.map_block(block);
+            for inst in callee.layout.block_insts(block) {
+                let new_inst = if callee.dfg.insts[inst].opcode() == Opcode::Return {
+                    let rets: SmallVec<[Value; 8]> = callee
+                        .dfg
+                        .inst_args(inst)
+                        .iter()
+                        .map(|&v| self.map_value(v))
+                        .collect();
+                    let destination = self.func.dfg.block_call(return_block, &rets);
+                    self.func.dfg.make_inst(InstructionData::Jump {
+                        opcode: Opcode::Jump,
+                        destination,
+                    })
+                } else {
+                    let data = callee.dfg.insts[inst].map(&mut self);
+                    let new_inst = self.func.dfg.make_inst(data);
+                    self.func
+                        .dfg
+                        .make_inst_results(new_inst, callee.dfg.ctrl_typevar(inst));
+                    for (&old, &new) in callee
+                        .dfg
+                        .inst_results(inst)
+                        .iter()
+                        .zip(self.func.dfg.inst_results(new_inst))
+                    {
+                        self.values[old] = new.into();
+                    }
+                    new_inst
+                };
+                self.func.layout.append_inst(new_inst, new_block);
+
+                let srcloc = callee.srcloc(inst);
+                let srcloc = if srcloc.is_default() {
+                    call_srcloc
+                } else {
+                    srcloc
+                };
+                if !srcloc.is_default() {
+                    self.func.set_srcloc(new_inst, srcloc);
+                }
+            }
+        }
+    }
+
+    fn map_block(&self, block: Block) -> Block {
+        self.blocks[block]
+            .expand()
+            .expect("branch to a block that isn't reachable in the callee")
+    }
+
+    fn map_name(&mut self, name: &ir::ExternalName) -> ir::ExternalName {
+        match name {
+            ir::ExternalName::User(name) => {
+                let name = self.callee.params.user_named_funcs()[*name].clone();
+                ir::ExternalName::User(self.func.declare_imported_user_function(name))
+            }
+            other => other.clone(),
+        }
+    }
+}
+
+impl InstructionMapper for Inliner<'_> {
+    fn map_value(&mut self, value: Value) -> Value {
+        let value = self.callee.dfg.resolve_aliases(value);
+        self.values[value]
+            .expand()
+            .expect("callee value used before it is defined")
+    }
+
+    fn map_value_list(&mut self, value_list: ValueList) -> ValueList {
+        let callee = self.callee;
+        let values: SmallVec<[Value; 8]> = value_list
+            .as_slice(&callee.dfg.value_lists)
+            .iter()
+            .map(|&v| self.map_value(v))
+            .collect();
+        ValueList::from_slice(&values, &mut self.func.dfg.value_lists)
+    }
+
+    fn map_block_call(&mut self, block_call: BlockCall) -> BlockCall {
+        let callee = self.callee;
+        let pool = &callee.dfg.value_lists;
+        let block = self.map_block(block_call.block(pool));
+        let args: SmallVec<[Value; 8]> = block_call
+            .args_slice(pool)
+            .iter()
+            .map(|&v| self.map_value(v))
+            .collect();
+        self.func.dfg.block_call(block, &args)
+    }
+
+    fn map_jump_table(&mut self, jump_table: ir::JumpTable) -> ir::JumpTable {
+        if let Some(jt) = self.jump_tables[jump_table].expand() {
+            return jt;
+        }
+        let callee = self.callee;
+        let data = &callee.dfg.jump_tables[jump_table];
+        let default = self.map_block_call(data.default_block());
+        let table: Vec<BlockCall> = data
+            .as_slice()
+            .iter()
+            .map(|&bc| self.map_block_call(bc))
+            .collect();
+        let jt = self
+            .func
+            .create_jump_table(ir::JumpTableData::new(default, &table));
+        self.jump_tables[jump_table] = jt.into();
+        jt
+    }
+
+    fn map_stack_slot(&mut self, stack_slot: ir::StackSlot) -> ir::StackSlot {
+        if let Some(ss) = self.stack_slots[stack_slot].expand() {
+            return ss;
+        }
+        let data = self.callee.sized_stack_slots[stack_slot].clone();
+        let ss = self.func.create_sized_stack_slot(data);
+        self.stack_slots[stack_slot] = ss.into();
+        ss
+    }
+
+    fn map_dynamic_stack_slot(
+        &mut self,
+        _dynamic_stack_slot: ir::DynamicStackSlot,
+    ) -> ir::DynamicStackSlot {
+        unreachable!("callees with dynamic stack slots are not inlined")
+    }
+
+    fn map_global_value(&mut self, global_value: ir::GlobalValue) -> ir::GlobalValue {
+        if let Some(gv) = self.global_values[global_value].expand() {
+            return gv;
+        }
+        let data = match self.callee.global_values[global_value].clone() {
+            // `can_inline` made sure that the callee's vmctx is the caller's.
+            GlobalValueData::VMContext => {
+                let existing = self
+                    .func
+                    .global_values
+                    .iter()
+                    .find(|(_, data)| matches!(data, GlobalValueData::VMContext))
+                    .map(|(gv, _)| gv);
+                let gv = match existing {
+                    Some(gv) => gv,
+                    None => self.func.create_global_value(GlobalValueData::VMContext),
+                };
+                self.global_values[global_value] = gv.into();
+                return gv;
+            }
+            GlobalValueData::Load {
+                base,
+                offset,
+                global_type,
+                readonly,
+            } => GlobalValueData::Load {
+                base: self.map_global_value(base),
+                offset,
+                global_type,
+                readonly,
+            },
+            GlobalValueData::IAddImm {
+                base,
+                offset,
+                global_type,
+            } => GlobalValueData::IAddImm {
+                base: self.map_global_value(base),
+                offset,
+                global_type,
+            },
+            GlobalValueData::Symbol {
+                name,
+                offset,
+                colocated,
+                tls,
+            } => GlobalValueData::Symbol {
+                name: self.map_name(&name),
+                offset,
+                colocated,
+                tls,
+            },
+            data @ GlobalValueData::DynScaleTargetConst { .. } => data,
+        };
+        let gv = self.func.create_global_value(data);
+        self.global_values[global_value] = gv.into();
+        gv
+    }
+
+    fn map_sig_ref(&mut self, sig_ref: ir::SigRef) -> ir::SigRef {
+        if let Some(sig) = self.sig_refs[sig_ref].expand() {
+            return sig;
+        }
+        let sig = self
+            .func
+            .import_signature(self.callee.dfg.signatures[sig_ref].clone());
+        self.sig_refs[sig_ref] = sig.into();
+        sig
+    }
+
+    fn map_func_ref(&mut self, func_ref: ir::FuncRef) -> ir::FuncRef {
+        if let Some(f) = self.func_refs[func_ref].expand() {
+            return f;
+        }
+        let callee = self.callee;
+        let data = &callee.dfg.ext_funcs[func_ref];
+        let data = ir::ExtFuncData {
+            name: self.map_name(&data.name),
+            signature: self.map_sig_ref(data.signature),
+            colocated: data.colocated,
+        };
+        let f = self.func.import_function(data);
+        self.func_refs[func_ref] = f.into();
+        f
+    }
+
+    fn map_table(&mut self, table: ir::Table) -> ir::Table {
+        if let Some(t) = self.tables[table].expand() {
+            return t;
+        }
+        let callee = self.callee;
+        let data = &callee.tables[table];
+        let data = ir::TableData {
+            base_gv: self.map_global_value(data.base_gv),
+            bound_gv: self.map_global_value(data.bound_gv),
+            ..data.clone()
+        };
+        let t = self.func.create_table(data);
+        self.tables[table] = t.into();
+        t
+    }
+
+    fn map_constant(&mut self, constant: ir::Constant) -> ir::Constant {
+        if let Some(c) = self.constants[constant].expand() {
+            return c;
+        }
+        let data = self.callee.dfg.constants.get(constant).clone();
+        let c = self.func.dfg.constants.insert(data);
+        self.constants[constant] = c.into();
+        c
+    }
+
+    fn map_immediate(&mut self, immediate: ir::Immediate) -> ir::Immediate {
+        if let Some(i) = self.immediates[immediate].expand() {
+            return i;
+        }
+        let data = self.callee.dfg.immediates[immediate].clone();
+        let i = self.func.dfg.immediates.push(data);
+        self.immediates[immediate] = i.into();
+        i
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::{types::I32, InstBuilder, Signature, UserExternalName, UserFuncName};
+    use crate::isa::CallConv;
+    use crate::settings;
+    use crate::verifier::verify_function;
+
+    fn signature() -> Signature {
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.params.push(AbiParam::new(I32));
+        sig.params.push(AbiParam::new(I32));
+        sig.returns.push(AbiParam::new(I32));
+        sig
+    }
+
+    // fn callee(a, b) { if a { return a + b } else { return 7 } }
+    fn callee() -> Function {
+        let mut func = Function::with_name_signature(UserFuncName::testcase("callee"), signature());
+        let entry = func.dfg.make_block();
+        let a = func.dfg.append_block_param(entry, I32);
+        let b = func.dfg.append_block_param(entry, I32);
+        let then = func.dfg.make_block();
+        let otherwise = func.dfg.make_block();
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(entry);
+        pos.ins().brif(a, then, &[], otherwise, &[]);
+        pos.insert_block(then);
+        let sum = pos.ins().iadd(a, b);
+        pos.ins().return_(&[sum]);
+        pos.insert_block(otherwise);
+        let seven = pos.ins().iconst(I32, 7);
+        pos.ins().return_(&[seven]);
+        func
+    }
+
+    // fn caller(x, y) { return callee(x, y) * 2 }
+    fn caller() -> Function {
+        let mut func = Function::with_name_signature(UserFuncName::testcase("caller"), signature());
+        let sig = func.import_signature(signature());
+        let name = func.declare_imported_user_function(UserExternalName::new(0, 1));
+        let callee = func.import_function(ir::ExtFuncData {
+            name: ir::ExternalName::User(name),
+            signature: sig,
+            colocated: true,
+        });
+        let entry = func.dfg.make_block();
+        let x = func.dfg.append_block_param(entry, I32);
+        let y = func.dfg.append_block_param(entry, I32);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(entry);
+        let call = pos.ins().call(callee, &[x, y]);
+        let result = pos.func.dfg.first_result(call);
+        let doubled = pos.ins().imul_imm(result, 2);
+        pos.ins().return_(&[doubled]);
+        func
+    }
+
+    struct Bodies(Function);
+
+    impl Inline for Bodies {
+        fn inline(&mut self, _: &Function, _: Inst, _: ir::FuncRef) -> InlineCommand<'_> {
+            InlineCommand::Inline(Cow::Borrowed(&self.0))
+        }
+    }
+
+    fn has_calls(func: &Function) -> bool {
+        func.layout
+            .blocks()
+            .flat_map(|block| func.layout.block_insts(block))
+            .any(|inst| func.dfg.insts[inst].opcode().is_call())
+    }
+
+    #[test]
+    fn inline_call() {
+        let mut func = caller();
+        let budget = InlineBudget {
+            max_callee_insts: 10,
+            max_total_insts: 10,
+        };
+        let inlined = do_inline(&mut func, &mut Bodies(callee()), budget);
+        assert_eq!(inlined.len(), 1);
+        assert!(!has_calls(&func), "call was not inlined:\n{}", func);
+
+        let flags = settings::Flags::new(settings::builder());
+        verify_function(&func, &flags).unwrap_or_else(|e| panic!("{}\n{}", e, func));
+        assert_eq!(func.layout.blocks().count(), 5, "{}", func);
+    }
+
+    #[test]
+    fn over_budget() {
+        let mut func = caller();
+        let budget = InlineBudget {
+            max_callee_insts: 3,
+            max_total_insts: 10,
+        };
+        let inlined = do_inline(&mut func, &mut Bodies(callee()), budget);
+        assert!(inlined.is_empty());
+        assert!(has_calls(&func));
+    }
+}
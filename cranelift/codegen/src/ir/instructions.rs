@@ -158,6 +158,54 @@ impl<'a> Display for DisplayBlockCall<'a> {
     }
 }
 
+/// A translation of the operands and entity references of an instruction, used
+/// by [`InstructionData::map`].
+///
+/// This is how instructions are copied between functions, where every entity
+/// an instruction refers to has to be translated into (or recreated in) the
+/// destination function.
+pub trait InstructionMapper {
+    /// Map a value operand.
+    fn map_value(&mut self, value: Value) -> Value;
+
+    /// Map a list of value operands, returning a list in the destination
+    /// function's value list pool.
+    fn map_value_list(&mut self, value_list: ValueList) -> ValueList;
+
+    /// Map a branch target and its arguments.
+    fn map_block_call(&mut self, block_call: BlockCall) -> BlockCall;
+
+    /// Map a jump table.
+    fn map_jump_table(&mut self, jump_table: ir::JumpTable) -> ir::JumpTable;
+
+    /// Map a sized stack slot.
+    fn map_stack_slot(&mut self, stack_slot: StackSlot) -> StackSlot;
+
+    /// Map a dynamic stack slot.
+    fn map_dynamic_stack_slot(
+        &mut self,
+        dynamic_stack_slot: ir::DynamicStackSlot,
+    ) -> ir::DynamicStackSlot;
+
+    /// Map a global value.
+    fn map_global_value(&mut self, global_value: ir::GlobalValue) -> ir::GlobalValue;
+
+    /// Map a signature reference.
+    fn map_sig_ref(&mut self, sig_ref: SigRef) -> SigRef;
+
+    /// Map an external function reference.
+    fn map_func_ref(&mut self, func_ref: FuncRef) -> FuncRef;
+
+    /// Map a table.
+    fn map_table(&mut self, table: ir::Table) -> ir::Table;
+
+    /// Map a constant from the constant pool.
+    fn map_constant(&mut self, constant: ir::Constant) -> ir::Constant;
+
+    /// Map an immediate, such as a shuffle mask.
+    fn map_immediate(&mut self, immediate: ir::Immediate) -> ir::Immediate;
+}
+
 // Include code generated by `cranelift-codegen/meta/src/gen_inst.rs`. This file contains:
 //
 // - The `pub enum InstructionFormat` enum with all the instruction formats.
@@ -639,6 +639,22 @@ impl ABIMachineSpec for AArch64MachineDeps {
         insts
     }
 
+    fn supports_shrink_wrapping(_call_conv: isa::CallConv) -> bool {
+        true
+    }
+
+    fn gen_frame_state_unwind(in_frame: bool) -> Inst {
+        let inst = if in_frame {
+            UnwindInst::ReenterFrame
+        } else {
+            UnwindInst::LeaveFrame {
+                // Nothing is pushed by the call.
+                offset_upward_to_caller_sp: 0,
+            }
+        };
+        Inst::Unwind { inst }
+    }
+
     fn gen_probestack(_insts: &mut SmallInstVec<Self::I>, _: u32) {
         // TODO: implement if we ever require stack probes on an AArch64 host
         // (unlikely unless Lucet is ported)
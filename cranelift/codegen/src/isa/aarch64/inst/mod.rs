@@ -3042,4 +3042,11 @@ impl MachInstLabelUse for LabelUse {
             _ => None,
         }
     }
+
+    fn to_reloc(self) -> Option<(CodeOffset, Reloc, Addend)> {
+        match self {
+            LabelUse::Branch26 => Some((0, Reloc::Arm64Call, 0)),
+            _ => None,
+        }
+    }
 }
@@ -2075,6 +2075,13 @@ impl MachInstLabelUse for LabelUse {
             _ => None,
         }
     }
+
+    fn to_reloc(self) -> Option<(CodeOffset, Reloc, Addend)> {
+        match self {
+            Self::PCRel32 => Some((0, Reloc::RiscvCall, 0)),
+            _ => None,
+        }
+    }
 }
 
 impl LabelUse {
@@ -3423,4 +3423,13 @@ impl MachInstLabelUse for LabelUse {
             _ => None,
         }
     }
+
+    fn to_reloc(self) -> Option<(CodeOffset, Reloc, Addend)> {
+        match self {
+            // The offset of a RIL-format branch is in the instruction's last
+            // four bytes, relative to its start.
+            LabelUse::BranchRIL => Some((2, Reloc::S390xPCRel32Dbl, 2)),
+            _ => None,
+        }
+    }
 }
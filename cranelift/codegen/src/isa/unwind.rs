@@ -127,6 +127,11 @@ pub enum UnwindInfo {
 /// unwind UnwindInst::SaveReg { reg: R13, clobber_offset: 8 }
 /// ...
 /// ```
+///
+/// When the prologue is shrink-wrapped, code which runs without the frame may
+/// also be emitted after the prologue. Such code is bracketed by `LeaveFrame`
+/// and `ReenterFrame`, which describe the transitions in emission order rather
+/// than actions taken by the code.
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub enum UnwindInst {
@@ -179,4 +184,17 @@ pub enum UnwindInst {
         /// Whether return addresses (hold in LR) contain a pointer-authentication code.
         return_addresses: bool,
     },
+    /// The code from this point on runs without the frame, as at the start of
+    /// the function. This only happens when the prologue is shrink-wrapped,
+    /// i.e. placed at the start of a later block, in code emitted after the
+    /// prologue which that block doesn't dominate. The current location of SP
+    /// is such that `offset_upward_to_caller_sp` is the distance to
+    /// SP-at-callsite. The frame is back in place at the next `ReenterFrame`.
+    LeaveFrame {
+        /// The offset from the current SP to the SP at caller's callsite.
+        offset_upward_to_caller_sp: u32,
+    },
+    /// The code from this point on runs with the frame again, as it was before
+    /// the last `LeaveFrame`.
+    ReenterFrame,
 }
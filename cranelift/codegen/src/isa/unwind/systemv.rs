@@ -170,6 +170,7 @@ pub(crate) fn create_unwind_info_from_insts<MR: RegisterMapper<Reg>>(
 
     let mut cfa_offset = 0;
     let mut clobber_offset_to_cfa = 0;
+    let mut saved_regs = vec![];
     for &(instruction_offset, ref inst) in insts {
         match inst {
             &UnwindInst::PushFrameRegs {
@@ -242,6 +243,7 @@ pub(crate) fn create_unwind_info_from_insts<MR: RegisterMapper<Reg>>(
                     .map_err(|e| CodegenError::RegisterMappingError(e))?;
                 let off = (clobber_offset as i32) - (clobber_offset_to_cfa as i32);
                 instructions.push((instruction_offset, CallFrameInstruction::Offset(reg, off)));
+                saved_regs.push(reg);
             }
             &UnwindInst::Aarch64SetPointerAuth { return_addresses } => {
                 instructions.push((
@@ -249,6 +251,25 @@ pub(crate) fn create_unwind_info_from_insts<MR: RegisterMapper<Reg>>(
                     CallFrameInstruction::Aarch64SetPointerAuth { return_addresses },
                 ));
             }
+            &UnwindInst::LeaveFrame {
+                offset_upward_to_caller_sp,
+            } => {
+                // Remember the state within the frame for the next
+                // `ReenterFrame`, then describe the state at function entry:
+                // CFA in terms of SP, and no registers saved.
+                instructions.push((instruction_offset, CallFrameInstruction::RememberState));
+                instructions.push((
+                    instruction_offset,
+                    CallFrameInstruction::Cfa(mr.sp(), offset_upward_to_caller_sp as i32),
+                ));
+                let frame_regs = mr.fp().into_iter().chain(mr.lr());
+                for reg in frame_regs.chain(saved_regs.iter().copied()) {
+                    instructions.push((instruction_offset, CallFrameInstruction::SameValue(reg)));
+                }
+            }
+            &UnwindInst::ReenterFrame => {
+                instructions.push((instruction_offset, CallFrameInstruction::RestoreState));
+            }
         }
     }
 
@@ -312,6 +312,13 @@ pub(crate) fn create_unwind_info_from_insts<MR: RegisterMapper<crate::machinst::
             &UnwindInst::Aarch64SetPointerAuth { .. } => {
                 unreachable!("no aarch64 on x64");
             }
+            &UnwindInst::LeaveFrame { .. } | &UnwindInst::ReenterFrame => {
+                // Windows unwind information only describes the prologue, which
+                // must be at the start of the function.
+                return Err(CodegenError::Unsupported(
+                    "shrink-wrapped prologues with Windows x64 unwind info".into(),
+                ));
+            }
         }
         max_unwind_offset = instruction_offset;
     }
@@ -480,6 +480,24 @@ impl ABIMachineSpec for X64ABIMachineSpec {
         insts
     }
 
+    fn supports_shrink_wrapping(call_conv: isa::CallConv) -> bool {
+        // Windows unwind info can only describe a prologue at the start of
+        // the function.
+        !call_conv.extends_windows_fastcall()
+    }
+
+    fn gen_frame_state_unwind(in_frame: bool) -> Inst {
+        let inst = if in_frame {
+            UnwindInst::ReenterFrame
+        } else {
+            UnwindInst::LeaveFrame {
+                // The return address.
+                offset_upward_to_caller_sp: 8,
+            }
+        };
+        Inst::Unwind { inst }
+    }
+
     fn gen_probestack(insts: &mut SmallInstVec<Self::I>, frame_size: u32) {
         insts.push(Inst::imm(
             OperandSize::Size32,
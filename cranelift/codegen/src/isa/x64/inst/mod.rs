@@ -2756,4 +2756,11 @@ impl MachInstLabelUse for LabelUse {
             _ => None,
         }
     }
+
+    fn to_reloc(self) -> Option<(CodeOffset, Reloc, Addend)> {
+        match self {
+            LabelUse::JmpRel32 => Some((0, Reloc::X86CallPCRel4, -4)),
+            LabelUse::PCRel32 => None,
+        }
+    }
 }
@@ -101,7 +101,7 @@ mod tests {
         types, AbiParam, Function, InstBuilder, Signature, StackSlotData, StackSlotKind,
     };
     use crate::isa::{lookup, CallConv};
-    use crate::settings::{builder, Flags};
+    use crate::settings::{builder, Configurable, Flags};
     use crate::Context;
     use gimli::write::Address;
     use std::str::FromStr;
@@ -199,4 +199,67 @@ mod tests {
 
         func
     }
+
+    #[test]
+    fn test_shrink_wrapped_func() {
+        let mut flags = builder();
+        flags.enable("enable_shrink_wrapping").unwrap();
+        let isa = lookup(triple!("x86_64"))
+            .expect("expect x86 ISA")
+            .finish(Flags::new(flags))
+            .expect("expect backend creation to succeed");
+
+        let mut context = Context::for_function(create_shrink_wrapped_function(CallConv::SystemV));
+
+        let code = context
+            .compile(&*isa, &mut Default::default())
+            .expect("expected compilation");
+
+        let fde = match code
+            .create_unwind_info(isa.as_ref())
+            .expect("can create unwind info")
+        {
+            Some(crate::isa::unwind::UnwindInfo::SystemV(info)) => {
+                info.to_fde(Address::Constant(1234))
+            }
+            _ => panic!("expected unwind information"),
+        };
+
+        // The frameless return is emitted after the prologue, so the state
+        // within the frame is remembered and the state at entry described.
+        let fde = format!("{:?}", fde);
+        assert!(fde.contains("CfaRegister(Register(6))"), "{}", fde);
+        assert!(fde.contains("RememberState"), "{}", fde);
+        assert!(fde.contains("Cfa(Register(7), 8)"), "{}", fde);
+        assert!(fde.contains("SameValue(Register(6))"), "{}", fde);
+    }
+
+    fn create_shrink_wrapped_function(call_conv: CallConv) -> Function {
+        let mut sig = Signature::new(call_conv);
+        sig.params.push(AbiParam::new(types::I32));
+        let mut func = Function::with_name_signature(Default::default(), sig);
+        let ss0 = func
+            .sized_stack_slots
+            .push(StackSlotData::new(StackSlotKind::ExplicitSlot, 4));
+
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let block1 = func.dfg.make_block();
+        let block2 = func.dfg.make_block();
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        pos.ins().brif(v0, block1, &[], block2, &[]);
+
+        pos.insert_block(block1);
+        pos.ins().stack_store(v0, ss0, 0);
+        pos.ins().return_(&[]);
+
+        // The block which doesn't need the frame is emitted last.
+        pos.insert_block(block2);
+        pos.ins().return_(&[]);
+        func.layout.set_cold(block2);
+
+        func
+    }
 }
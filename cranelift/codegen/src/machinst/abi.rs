@@ -103,7 +103,7 @@
 
 use crate::binemit::StackMap;
 use crate::entity::{PrimaryMap, SecondaryMap};
-use crate::fx::FxHashMap;
+use crate::fx::{FxHashMap, FxHashSet};
 use crate::ir::types::*;
 use crate::ir::{ArgumentExtension, ArgumentPurpose, DynamicStackSlot, Signature, StackSlot};
 use crate::isa::TargetIsa;
@@ -528,6 +528,21 @@ pub trait ABIMachineSpec {
     /// Generate the usual frame-restore sequence for this architecture.
     fn gen_epilogue_frame_restore(flags: &settings::Flags) -> SmallInstVec<Self::I>;
 
+    /// Whether the prologue of functions with the given calling convention can
+    /// be shrink-wrapped: placed at the start of a block other than the entry
+    /// block, after the part generated by `gen_prologue_start`. Backends which
+    /// return true must implement `gen_frame_state_unwind`.
+    fn supports_shrink_wrapping(_call_conv: isa::CallConv) -> bool {
+        false
+    }
+
+    /// Generate the unwind pseudo-instruction for the start of code which runs
+    /// without the frame set up by a shrink-wrapped prologue (`in_frame` is
+    /// false), or which runs with it again (`in_frame` is true).
+    fn gen_frame_state_unwind(_in_frame: bool) -> Self::I {
+        unreachable!("shrink-wrapping is not supported by this backend")
+    }
+
     /// Generate a probestack call.
     fn gen_probestack(insts: &mut SmallInstVec<Self::I>, frame_size: u32);
 
@@ -1055,6 +1070,9 @@ pub struct Callee<M: ABIMachineSpec> {
     probestack_min_frame: Option<u32>,
     /// Whether it is necessary to generate the usual frame-setup sequence.
     setup_frame: bool,
+    /// The IR blocks with instructions that need the frame, when the prologue
+    /// may be shrink-wrapped.
+    frame_blocks: FxHashSet<ir::Block>,
 
     _mach: PhantomData<M>,
 }
@@ -1106,18 +1124,36 @@ impl<M: ABIMachineSpec> Callee<M> {
             call_conv
         );
 
+        // Slots which are never live at the same time share their space, when
+        // optimizing.
+        let shared_stackslots = if flags.opt_level() != settings::OptLevel::None {
+            stack_coloring::shared_stack_slots(f)
+        } else {
+            SecondaryMap::new()
+        };
+        let mut shared_sizes: SecondaryMap<StackSlot, u32> = SecondaryMap::new();
+        for (stackslot, data) in f.sized_stack_slots.iter() {
+            let leader = shared_stackslots[stackslot].expand().unwrap_or(stackslot);
+            shared_sizes[leader] = shared_sizes[leader].max(data.size);
+        }
+
         // Compute sized stackslot locations and total stackslot size.
         let mut sized_stack_offset: u32 = 0;
-        let mut sized_stackslots = PrimaryMap::new();
-        for (stackslot, data) in f.sized_stack_slots.iter() {
+        let mut sized_stackslots: PrimaryMap<StackSlot, u32> = PrimaryMap::new();
+        for (stackslot, _) in f.sized_stack_slots.iter() {
+            debug_assert_eq!(stackslot.as_u32() as usize, sized_stackslots.len());
+            if let Some(leader) = shared_stackslots[stackslot].expand() {
+                let off = sized_stackslots[leader];
+                sized_stackslots.push(off);
+                continue;
+            }
             let off = sized_stack_offset;
             sized_stack_offset = sized_stack_offset
-                .checked_add(data.size)
+                .checked_add(shared_sizes[stackslot])
                 .ok_or(CodegenError::ImplLimitExceeded)?;
             let mask = M::word_bytes() - 1;
             sized_stack_offset = checked_round_up(sized_stack_offset, mask)
                 .ok_or(CodegenError::ImplLimitExceeded)?;
-            debug_assert_eq!(stackslot.as_u32() as usize, sized_stackslots.len());
             sized_stackslots.push(off);
         }
 
@@ -1173,6 +1209,12 @@ impl<M: ABIMachineSpec> Callee<M> {
             None
         };
 
+        let frame_blocks = if flags.enable_shrink_wrapping() {
+            shrink_wrap::blocks_needing_frame(f)
+        } else {
+            FxHashSet::default()
+        };
+
         Ok(Self {
             ir_sig: ensure_struct_return_ptr_is_returned(&f.signature),
             sig,
@@ -1195,6 +1237,7 @@ impl<M: ABIMachineSpec> Callee<M> {
             stack_limit,
             probestack_min_frame,
             setup_frame: true,
+            frame_blocks,
             _mach: PhantomData,
         })
     }
@@ -1818,6 +1861,39 @@ impl<M: ABIMachineSpec> Callee<M> {
         self.clobbered = clobbered;
     }
 
+    /// Get the clobbered registers which the prologue saves, post-regalloc.
+    pub fn clobbered_callee_saves(&self) -> Vec<Writable<RealReg>> {
+        M::get_clobbered_callee_saves(
+            self.call_conv,
+            &self.flags,
+            self.signature(),
+            &self.clobbered,
+        )
+    }
+
+    /// Whether the prologue may be shrink-wrapped: placed at the start of a
+    /// block other than the entry block, after the part of it which has to
+    /// come first in the function.
+    pub fn can_shrink_wrap(&self, sigs: &SigSet) -> bool {
+        self.flags.enable_shrink_wrapping()
+            && !self.flags.preserve_frame_pointers()
+            && !self.flags.enable_hot_cold_splitting()
+            && M::supports_shrink_wrapping(self.call_conv)
+            // Stack arguments are loaded relative to FP in the entry block, and
+            // the stack check and probes use temporary registers which are only
+            // known to be free at the start of the function.
+            && self.stack_args_size(sigs) == 0
+            && self.stack_limit.is_none()
+            && self.probestack_min_frame.is_none()
+    }
+
+    /// Whether the given IR block has instructions which need the frame, such
+    /// as calls or stack slot accesses. Only known when the prologue may be
+    /// shrink-wrapped.
+    pub fn block_needs_frame(&self, block: ir::Block) -> bool {
+        self.frame_blocks.contains(&block)
+    }
+
     /// Generate a stack map, given a list of spillslots and the emission state
     /// at a given program point (prior to emission of the safepointing
     /// instruction).
@@ -1857,18 +1933,16 @@ impl<M: ABIMachineSpec> Callee<M> {
     /// other methods (`load_arg`, `store_retval`, and spillslot accesses.)
     /// `self` is mutable so that we can store information in it which will be
     /// useful when creating the epilogue.
-    pub fn gen_prologue(&mut self, sigs: &SigSet) -> SmallInstVec<M::I> {
+    ///
+    /// The prologue is returned in two parts: the instructions which have to
+    /// come first in the function, and the rest, which is placed at the start
+    /// of a later block instead when the prologue is shrink-wrapped.
+    pub fn gen_prologue(&mut self, sigs: &SigSet) -> (SmallInstVec<M::I>, SmallInstVec<M::I>) {
         let bytes = M::word_bytes();
         let total_stacksize = self.stackslots_size + bytes * self.spillslots.unwrap() as u32;
         let mask = M::stack_align(self.call_conv) - 1;
         let total_stacksize = (total_stacksize + mask) & !mask; // 16-align the stack.
-        let clobbered_callee_saves = M::get_clobbered_callee_saves(
-            self.call_conv,
-            &self.flags,
-            self.signature(),
-            &self.clobbered,
-        );
-        let mut insts = smallvec![];
+        let clobbered_callee_saves = self.clobbered_callee_saves();
 
         self.fixed_frame_storage_size += total_stacksize;
         self.setup_frame = self.flags.preserve_frame_pointers()
@@ -1879,16 +1953,14 @@ impl<M: ABIMachineSpec> Callee<M> {
                 self.fixed_frame_storage_size,
             );
 
-        insts.extend(
-            M::gen_prologue_start(
-                self.setup_frame,
-                self.call_conv,
-                &self.flags,
-                &self.isa_flags,
-            )
-            .into_iter(),
+        let start_insts = M::gen_prologue_start(
+            self.setup_frame,
+            self.call_conv,
+            &self.flags,
+            &self.isa_flags,
         );
 
+        let mut insts = smallvec![];
         if self.setup_frame {
             // set up frame
             insts.extend(M::gen_prologue_frame_setup(&self.flags).into_iter());
@@ -1945,7 +2017,7 @@ impl<M: ABIMachineSpec> Callee<M> {
         // on stackframe layout and nominal SP maintenance.
 
         self.total_frame_size = Some(total_stacksize + clobber_size as u32);
-        insts
+        (start_insts, insts)
     }
 
     /// Generate an epilogue, post-regalloc.
@@ -1991,6 +2063,21 @@ impl<M: ABIMachineSpec> Callee<M> {
         insts
     }
 
+    /// Generate the epilogue of a return from code which runs without the
+    /// frame, post-regalloc. This is only the return instruction, as nothing
+    /// has been set up by the shrink-wrapped prologue there.
+    pub fn gen_frameless_epilogue(&self, sigs: &SigSet) -> SmallInstVec<M::I> {
+        // The part of the prologue from `gen_prologue_start` has run, so the
+        // return has to match it as in `gen_epilogue`.
+        smallvec![M::gen_ret(
+            self.setup_frame,
+            &self.isa_flags,
+            self.call_conv,
+            vec![],
+            self.stack_bytes_to_pop(sigs),
+        )]
+    }
+
     /// Returns the full frame size for the given function, after prologue
     /// emission has run. This comprises the spill slots and stack-storage slots
     /// (but not storage for clobbered callee-save registers, arguments pushed
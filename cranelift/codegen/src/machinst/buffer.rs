@@ -246,6 +246,9 @@ enum ForceVeneers {
 enum IsLastIsland {
     Yes,
     No,
+    /// The last island of the hot part of a split function. Any label still
+    /// unknown at this point is in the cold part.
+    EndOfHotPart,
 }
 
 /// A buffer of output to be produced, fixed up, and then emitted to a CodeSink
@@ -336,6 +339,11 @@ pub struct MachBuffer<I: VCodeInst> {
     /// constant may appear in this array multiple times if it was emitted
     /// multiple times.
     used_constants: SmallVec<[(VCodeConstant, CodeOffset); 4]>,
+    /// The offset at which the cold part of the function starts, if it has
+    /// been started with `start_cold_part()`.
+    cold_start: Option<CodeOffset>,
+    /// References between the hot and cold parts of the function.
+    split_relocs: SmallVec<[MachSplitReloc; 4]>,
 }
 
 impl MachBufferFinalized<Stencil> {
@@ -354,6 +362,8 @@ impl MachBufferFinalized<Stencil> {
             stack_maps: self.stack_maps,
             unwind_info: self.unwind_info,
             alignment: self.alignment,
+            cold_start: self.cold_start,
+            split_relocs: self.split_relocs,
         }
     }
 }
@@ -381,6 +391,11 @@ pub struct MachBufferFinalized<T: CompilePhase> {
     pub unwind_info: SmallVec<[(CodeOffset, UnwindInst); 8]>,
     /// The requireed alignment of this buffer
     pub alignment: u32,
+    /// The offset at which the cold part of the code starts, if the function
+    /// was split. See `hot_data()` and `cold_data()`.
+    pub(crate) cold_start: Option<CodeOffset>,
+    /// References between the hot and cold parts of the code.
+    pub(crate) split_relocs: SmallVec<[MachSplitReloc; 4]>,
 }
 
 const UNKNOWN_LABEL_OFFSET: CodeOffset = 0xffff_ffff;
@@ -459,6 +474,8 @@ impl<I: VCodeInst> MachBuffer<I> {
             labels_at_tail_off: 0,
             constants: Default::default(),
             used_constants: Default::default(),
+            cold_start: None,
+            split_relocs: SmallVec::new(),
         }
     }
 
@@ -1293,7 +1310,9 @@ impl<I: VCodeInst> MachBuffer<I> {
         }
 
         let last_island_fixups = match last_island {
-            IsLastIsland::Yes => mem::take(&mut self.fixup_records_max_range),
+            IsLastIsland::Yes | IsLastIsland::EndOfHotPart => {
+                mem::take(&mut self.fixup_records_max_range)
+            }
             IsLastIsland::No => smallvec![],
         };
         for fixup in mem::take(&mut self.fixup_records)
@@ -1310,7 +1329,12 @@ impl<I: VCodeInst> MachBuffer<I> {
             let start = offset as usize;
             let end = (offset + kind.patch_size()) as usize;
 
-            if label_offset != UNKNOWN_LABEL_OFFSET {
+            if label_offset != UNKNOWN_LABEL_OFFSET && self.crosses_parts(offset, label_offset) {
+                // References between the hot and cold parts of a split
+                // function can't be patched here, as the distance between
+                // the parts isn't known until they're placed.
+                self.add_split_reloc(label, label_offset, offset, kind);
+            } else if label_offset != UNKNOWN_LABEL_OFFSET {
                 // If the offset of the label for this fixup is known then
                 // we're going to do something here-and-now. We're either going
                 // to patch the original offset because it's an in-bounds jump,
@@ -1381,7 +1405,14 @@ impl<I: VCodeInst> MachBuffer<I> {
                 //    relatively rare and the cost of "upgrading" all
                 //    forward label refs that cross an island should
                 //    be relatively low.
-                if !kind.supports_veneer() {
+                //
+                // At the end of the hot part of a split function, the label
+                // is in the cold part. Veneers are emitted, still in the hot
+                // part, until the label-use can be turned into a relocation
+                // once the label is bound.
+                let to_cold_part =
+                    last_island == IsLastIsland::EndOfHotPart && kind.to_reloc().is_some();
+                if !kind.supports_veneer() || to_cold_part {
                     self.fixup_records_max_range.push(MachLabelFixup {
                         label,
                         offset,
@@ -1452,6 +1483,84 @@ impl<I: VCodeInst> MachBuffer<I> {
         }
     }
 
+    /// Ends the hot part of the function and starts its cold part, so that
+    /// everything emitted from now on is in the cold part.
+    ///
+    /// All pending constants, traps and veneers are emitted at the end of the
+    /// hot part first, and labels used in both parts are resolved as follows:
+    /// references within a part are patched as usual, but references between
+    /// the parts are reported as relocations in the finalized buffer, for the
+    /// embedder to apply once it has placed the two parts.
+    pub fn start_cold_part(&mut self, ctrl_plane: &mut ControlPlane) {
+        assert!(self.cold_start.is_none());
+
+        self.optimize_branches(ctrl_plane);
+        while !self.pending_constants.is_empty()
+            || !self.pending_traps.is_empty()
+            || !self.fixup_records.is_empty()
+        {
+            self.emit_island_maybe_forced(ForceVeneers::No, IsLastIsland::EndOfHotPart, ctrl_plane);
+        }
+
+        // Align the cold part to the largest alignment the function could
+        // require, so that placing it at the same alignment as the function
+        // keeps constants in it aligned.
+        let align = self
+            .constants
+            .values()
+            .map(|c| c.align)
+            .fold(I::function_alignment().minimum, CodeOffset::max);
+        self.align_to(align);
+
+        trace!("MachBuffer: cold part starts at {}", self.cur_offset());
+        self.cold_start = Some(self.cur_offset());
+    }
+
+    /// Are `a` and `b` in different parts of a split function?
+    fn crosses_parts(&self, a: CodeOffset, b: CodeOffset) -> bool {
+        match self.cold_start {
+            Some(cold_start) => (a >= cold_start) != (b >= cold_start),
+            None => false,
+        }
+    }
+
+    /// Records a use of `label`, at `label_offset`, from `offset` in the other
+    /// part of a split function.
+    fn add_split_reloc(
+        &mut self,
+        label: MachLabel,
+        label_offset: CodeOffset,
+        offset: CodeOffset,
+        kind: I::LabelUse,
+    ) {
+        match kind.to_reloc() {
+            Some((delta, reloc, addend)) => {
+                trace!(
+                    "split reloc at offset {} to {} as {:?}",
+                    offset,
+                    label_offset,
+                    reloc
+                );
+                self.split_relocs.push(MachSplitReloc {
+                    offset: offset + delta,
+                    kind: reloc,
+                    addend,
+                    target: label_offset,
+                });
+            }
+            None => {
+                // The veneer will be emitted at the current offset, which
+                // needs to be in the same part as the label-use.
+                assert!(
+                    kind.supports_veneer() && !self.crosses_parts(offset, self.cur_offset()),
+                    "reference between hot and cold parts with unsupported {:?}",
+                    kind,
+                );
+                self.emit_veneer(label, offset, kind);
+            }
+        }
+    }
+
     fn finish_emission_maybe_forcing_veneers(
         &mut self,
         force_veneers: ForceVeneers,
@@ -1502,6 +1611,8 @@ impl<I: VCodeInst> MachBuffer<I> {
             stack_maps: self.stack_maps,
             unwind_info: self.unwind_info,
             alignment,
+            cold_start: self.cold_start,
+            split_relocs: self.split_relocs,
         }
     }
 
@@ -1665,6 +1776,31 @@ impl<T: CompilePhase> MachBufferFinalized<T> {
         &self.relocs[..]
     }
 
+    /// Get the offset at which the cold part of this code starts, if the
+    /// function was split into hot and cold parts.
+    pub fn cold_start(&self) -> Option<CodeOffset> {
+        self.cold_start
+    }
+
+    /// Get the hot part of the code bytes: all of them, if the function wasn't
+    /// split.
+    pub fn hot_data(&self) -> &[u8] {
+        let end = self.cold_start.map_or(self.data.len(), |c| c as usize);
+        &self.data[..end]
+    }
+
+    /// Get the cold part of the code bytes, if any.
+    pub fn cold_data(&self) -> &[u8] {
+        let start = self.cold_start.map_or(self.data.len(), |c| c as usize);
+        &self.data[start..]
+    }
+
+    /// Get the list of references between the hot and cold parts of this
+    /// code, which must be resolved once the parts are placed.
+    pub fn split_relocs(&self) -> &[MachSplitReloc] {
+        &self.split_relocs[..]
+    }
+
     /// Get the list of trap records for this code.
     pub fn traps(&self) -> &[MachTrap] {
         &self.traps[..]
@@ -1739,6 +1875,24 @@ pub struct MachReloc {
     pub addend: i64,
 }
 
+/// A reference between the hot and cold parts of a split function, resulting
+/// from a compilation.
+///
+/// Like all other offsets in the compiled code, both offsets here are relative
+/// to the start of the hot part, as if the cold part directly followed it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "enable-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MachSplitReloc {
+    /// The offset at which the relocation applies.
+    pub offset: CodeOffset,
+    /// The kind of relocation.
+    pub kind: Reloc,
+    /// The addend to add to the target's address.
+    pub addend: i64,
+    /// The offset of the target, in the other part of the function.
+    pub target: CodeOffset,
+}
+
 /// A trap record resulting from a compilation.
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "enable-serde", derive(serde::Serialize, serde::Deserialize))]
@@ -1835,6 +1989,7 @@ impl MachBranch {
 pub struct MachTextSectionBuilder<I: VCodeInst> {
     buf: MachBuffer<I>,
     next_func: usize,
+    num_funcs: usize,
     force_veneers: ForceVeneers,
 }
 
@@ -1847,6 +2002,7 @@ impl<I: VCodeInst> MachTextSectionBuilder<I> {
         MachTextSectionBuilder {
             buf,
             next_func: 0,
+            num_funcs,
             force_veneers: ForceVeneers::No,
         }
     }
@@ -1896,13 +2052,27 @@ impl<I: VCodeInst> TextSectionBuilder for MachTextSectionBuilder<I> {
         }
     }
 
+    fn new_label(&mut self) -> usize {
+        self.buf.get_label().0 as usize
+    }
+
+    fn bind_label(&mut self, label: usize, offset: u64) {
+        // There are no branches to optimize here, so the label can be bound
+        // directly rather than only at the current offset.
+        assert!(label >= self.num_funcs);
+        let offset = u32::try_from(offset).unwrap();
+        assert!(offset < self.buf.cur_offset());
+        debug_assert_eq!(self.buf.label_offsets[label], UNKNOWN_LABEL_OFFSET);
+        self.buf.label_offsets[label] = offset;
+    }
+
     fn force_veneers(&mut self) {
         self.force_veneers = ForceVeneers::Yes;
     }
 
     fn finish(&mut self, ctrl_plane: &mut ControlPlane) -> Vec<u8> {
         // Double-check all functions were pushed.
-        assert_eq!(self.next_func, self.buf.label_offsets.len());
+        assert_eq!(self.next_func, self.num_funcs);
 
         // Finish up any veneers, if necessary.
         self.buf
@@ -2154,6 +2324,83 @@ mod test {
         assert_eq!(&buf.data[2000000..], &buf2.data[..]);
     }
 
+    #[test]
+    fn test_hot_cold_split() {
+        let info = EmitInfo::new(settings::Flags::new(settings::builder()));
+        let mut buf = MachBuffer::new();
+        let mut state = <Inst as MachInstEmit>::State::default();
+        let constants = Default::default();
+
+        buf.reserve_labels_for_blocks(3);
+
+        buf.bind_label(label(0), state.ctrl_plane_mut());
+        let inst = Inst::CondBr {
+            kind: CondBrKind::NotZero(xreg(0)),
+            taken: target(2),
+            not_taken: target(1),
+        };
+        inst.emit(&[], &mut buf, &info, &mut state);
+
+        buf.bind_label(label(1), state.ctrl_plane_mut());
+        let inst = Inst::Nop4;
+        inst.emit(&[], &mut buf, &info, &mut state);
+        let inst = Inst::Udf {
+            trap_code: TrapCode::Interrupt,
+        };
+        inst.emit(&[], &mut buf, &info, &mut state);
+
+        buf.start_cold_part(state.ctrl_plane_mut());
+
+        buf.bind_label(label(2), state.ctrl_plane_mut());
+        let inst = Inst::Nop4;
+        inst.emit(&[], &mut buf, &info, &mut state);
+        let inst = Inst::Jump { dest: target(1) };
+        inst.emit(&[], &mut buf, &info, &mut state);
+
+        let buf = buf.finish(&constants, state.ctrl_plane_mut());
+
+        // The conditional branch can't reach the cold part, so it goes
+        // through a veneer at the end of the hot part instead.
+        assert_eq!(buf.cold_start(), Some(16));
+        assert_eq!(buf.hot_data().len(), 16);
+        assert_eq!(buf.cold_data().len(), 8);
+
+        let mut buf2 = MachBuffer::new();
+        let mut state = Default::default();
+        let inst = Inst::CondBr {
+            kind: CondBrKind::NotZero(xreg(0)),
+            taken: BranchTarget::ResolvedOffset(12),
+            not_taken: BranchTarget::ResolvedOffset(4),
+        };
+        inst.emit(&[], &mut buf2, &info, &mut state);
+        let buf2 = buf2.finish(&constants, state.ctrl_plane_mut());
+        assert_eq!(&buf.data[..4], &buf2.data[..4]);
+
+        // Both the veneer and the jump back to the hot part are left for the
+        // embedder, and not patched.
+        let mut relocs = buf.split_relocs().to_vec();
+        relocs.sort_by_key(|r| r.offset);
+        assert_eq!(
+            relocs,
+            [
+                MachSplitReloc {
+                    offset: 12,
+                    kind: Reloc::Arm64Call,
+                    addend: 0,
+                    target: 16,
+                },
+                MachSplitReloc {
+                    offset: 20,
+                    kind: Reloc::Arm64Call,
+                    addend: 0,
+                    target: 4,
+                },
+            ]
+        );
+        assert_eq!(&buf.data[12..16], &0x1400_0000u32.to_le_bytes());
+        assert_eq!(&buf.data[20..24], &0x1400_0000u32.to_le_bytes());
+    }
+
     #[test]
     fn test_multiple_redirect() {
         // label0:
@@ -86,6 +86,8 @@ pub mod valueregs;
 pub use reg::*;
 pub use valueregs::*;
 pub mod reg;
+mod shrink_wrap;
+mod stack_coloring;
 
 /// A machine instruction.
 pub trait MachInst: Clone + Debug {
@@ -249,6 +251,16 @@ pub trait MachInstLabelUse: Clone + Copy + Debug + Eq {
     /// This returns `None` if the relocation doesn't have a corresponding
     /// representation for the target architecture.
     fn from_reloc(reloc: Reloc, addend: Addend) -> Option<Self>;
+
+    /// Returns a relocation with the same effect as this label-use, as an
+    /// offset relative to the label-use's offset, the kind and the addend.
+    /// This is the inverse of `from_reloc`.
+    ///
+    /// This is used for references between the hot and cold parts of a
+    /// function, which can't be resolved within the buffer. It returns `None`
+    /// if the label-use has no such relocation, in which case a veneer must be
+    /// used to get one.
+    fn to_reloc(self) -> Option<(CodeOffset, Reloc, Addend)>;
 }
 
 /// Describes a block terminator (not call) in the vcode, when its branches
@@ -387,6 +399,7 @@ impl<T: CompilePhase> CompiledCodeBase<T> {
         let mut buf = String::new();
 
         let relocs = self.buffer.relocs();
+        let split_relocs = self.buffer.split_relocs();
         let traps = self.buffer.traps();
 
         // Normalize the block starts to include an initial block of offset 0.
@@ -403,7 +416,11 @@ impl<T: CompilePhase> CompiledCodeBase<T> {
             .zip(block_starts.iter().skip(1))
             .enumerate()
         {
-            writeln!(buf, "block{}: ; offset 0x{:x}", n, start)?;
+            write!(buf, "block{}: ; offset 0x{:x}", n, start)?;
+            if Some(start) == self.buffer.cold_start() {
+                write!(buf, ", start of cold part")?;
+            }
+            writeln!(buf)?;
 
             let buffer = &self.buffer.data()[start as usize..end as usize];
             let insns = cs.disasm_all(buffer, start as u64).map_err(map_caperr)?;
@@ -433,6 +450,17 @@ impl<T: CompilePhase> CompiledCodeBase<T> {
                     )?;
                 }
 
+                if let Some(reloc) = split_relocs
+                    .iter()
+                    .find(|reloc| contains(reloc.offset as u64))
+                {
+                    write!(
+                        buf,
+                        " ; reloc_split {} 0x{:x} {}",
+                        reloc.kind, reloc.target, reloc.addend,
+                    )?;
+                }
+
                 if let Some(trap) = traps.iter().find(|trap| contains(trap.offset as u64)) {
                     write!(buf, " ; trap: {}", trap.code)?;
                 }
@@ -528,6 +556,19 @@ pub trait TextSectionBuilder {
     /// relocation will be resolved in the final bytes returned by `finish`.
     fn resolve_reloc(&mut self, offset: u64, reloc: Reloc, addend: Addend, target: usize) -> bool;
 
+    /// Creates a new label for a location in the text section which isn't
+    /// necessarily the start of labeled data, such as the targets of
+    /// references between the hot and cold parts of a function.
+    ///
+    /// The returned label can be passed as the `target` of `resolve_reloc`
+    /// both before and after it is bound with `bind_label`.
+    fn new_label(&mut self) -> usize;
+
+    /// Binds `label`, created with `new_label`, to `offset` within the text
+    /// section. The offset must be within data which has already been
+    /// appended.
+    fn bind_label(&mut self, label: usize, offset: u64);
+
     /// A debug-only option which is used to for
     fn force_veneers(&mut self);
 
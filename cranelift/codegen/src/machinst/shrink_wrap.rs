@@ -0,0 +1,205 @@
+//! Shrink-wrapping of the prologue and epilogues.
+//!
+//! The prologue normally sets up the frame and saves clobbered callee-saved
+//! registers at the start of the function, and every return restores them.
+//! When only some paths through a function need the frame, as is common for
+//! functions with an early-exit fast path, the prologue can instead be placed
+//! at the start of a block which dominates all blocks that need the frame.
+//!
+//! The blocks dominated by that block run with the frame, and the others run
+//! without it. The block is chosen so that the frame can be torn down by the
+//! epilogues of the returns as usual: the blocks it dominates can only be
+//! entered through it, and only be left by returning. Returns from the other
+//! blocks don't need an epilogue. The part of the prologue which has to come
+//! first in the function, such as a landing pad, stays at its start.
+
+use crate::fx::FxHashSet;
+use crate::ir::{Block, Function, Opcode};
+use crate::machinst::BlockIndex;
+use alloc::vec::Vec;
+use regalloc2::Function as RegallocFunction;
+
+/// Finds the IR blocks of `f` with instructions that need the frame: calls,
+/// and accesses to stack slots or to the frame and stack pointers. The other
+/// reasons for a block to need the frame are only known after register
+/// allocation.
+pub(crate) fn blocks_needing_frame(f: &Function) -> FxHashSet<Block> {
+    let mut blocks = FxHashSet::default();
+    for block in f.layout.blocks() {
+        let needs_frame = f.layout.block_insts(block).any(|inst| {
+            let opcode = f.dfg.insts[inst].opcode();
+            opcode.is_call()
+                || matches!(
+                    opcode,
+                    Opcode::StackAddr
+                        | Opcode::StackLoad
+                        | Opcode::StackStore
+                        | Opcode::DynamicStackAddr
+                        | Opcode::DynamicStackLoad
+                        | Opcode::DynamicStackStore
+                        | Opcode::GetFramePointer
+                        | Opcode::GetStackPointer
+                        | Opcode::GetReturnAddress
+                )
+        });
+        if needs_frame {
+            blocks.insert(block);
+        }
+    }
+    blocks
+}
+
+/// Where the prologue of a shrink-wrapped function goes.
+pub(crate) struct ShrinkWrap {
+    /// The block at whose start the prologue is placed, or `None` if no block
+    /// needs the frame.
+    pub prologue_block: Option<BlockIndex>,
+    /// Whether each block runs with the frame.
+    pub in_frame: Vec<bool>,
+}
+
+/// Chooses where to place the prologue of `f`, given which of its blocks need
+/// the frame and the order in which its blocks are emitted.
+///
+/// Returns `None` if the prologue has to be placed at the start of the
+/// function as usual.
+pub(crate) fn place_prologue<F: RegallocFunction>(
+    f: &F,
+    needs_frame: &[bool],
+    order: &[BlockIndex],
+) -> Option<ShrinkWrap> {
+    let num_blocks = f.num_blocks();
+
+    // Blocks are identified by their index in reverse postorder below, so that
+    // dominators come before the blocks they dominate.
+    let mut postorder = Vec::with_capacity(num_blocks);
+    let mut visited = vec![false; num_blocks];
+    let mut stack = vec![(f.entry_block(), 0)];
+    visited[f.entry_block().index()] = true;
+    while let Some((block, next)) = stack.pop() {
+        match f.block_succs(block).get(next) {
+            Some(&succ) => {
+                stack.push((block, next + 1));
+                if !visited[succ.index()] {
+                    visited[succ.index()] = true;
+                    stack.push((succ, 0));
+                }
+            }
+            None => postorder.push(block),
+        }
+    }
+    let rpo: Vec<BlockIndex> = postorder.into_iter().rev().collect();
+    let mut rpo_index = vec![usize::MAX; num_blocks];
+    for (i, block) in rpo.iter().enumerate() {
+        rpo_index[block.index()] = i;
+    }
+
+    // Immediate dominators, as in "A Simple, Fast Dominance Algorithm" by
+    // Cooper, Harvey and Kennedy.
+    let intersect = |idom: &[usize], mut a: usize, mut b: usize| {
+        while a != b {
+            while a > b {
+                a = idom[a];
+            }
+            while b > a {
+                b = idom[b];
+            }
+        }
+        a
+    };
+    let mut idom = vec![usize::MAX; rpo.len()];
+    idom[0] = 0;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, &block) in rpo.iter().enumerate().skip(1) {
+            let mut new_idom = usize::MAX;
+            for pred in f.block_preds(block) {
+                let pred = rpo_index[pred.index()];
+                if pred == usize::MAX || idom[pred] == usize::MAX {
+                    continue;
+                }
+                new_idom = if new_idom == usize::MAX {
+                    pred
+                } else {
+                    intersect(&idom, pred, new_idom)
+                };
+            }
+            if idom[i] != new_idom {
+                idom[i] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    // The number of edges by which the blocks dominated by each block can be
+    // left, or its start reached again. For an edge `from -> to`, these are
+    // the dominators of `from` which don't dominate `to`, and `to` itself if
+    // it dominates `from`: the edge is counted on that path up the dominator
+    // tree from `from` by a mark at each end, which are summed over subtrees.
+    let mut exits = vec![0isize; rpo.len()];
+    for (from, &block) in rpo.iter().enumerate() {
+        for succ in f.block_succs(block) {
+            let to = rpo_index[succ.index()];
+            let common = intersect(&idom, from, to);
+            exits[from] += 1;
+            if common != to {
+                exits[common] -= 1;
+            } else if to != 0 {
+                exits[idom[to]] -= 1;
+            }
+        }
+    }
+
+    // The position in the emission order of the first block dominated by
+    // each block.
+    let mut position = vec![0; num_blocks];
+    for (i, block) in order.iter().enumerate() {
+        position[block.index()] = i;
+    }
+    let mut first_position: Vec<usize> = rpo.iter().map(|b| position[b.index()]).collect();
+
+    for i in (1..rpo.len()).rev() {
+        exits[idom[i]] += exits[i];
+        first_position[idom[i]] = first_position[idom[i]].min(first_position[i]);
+    }
+
+    // Start from the nearest common dominator of the blocks that need the
+    // frame, and go up the dominator tree until the blocks dominated by the
+    // candidate can only be entered through it and left by returning. These
+    // also have to be emitted after it, as the prologue is emitted at its
+    // start.
+    let mut prologue_block = match (0..rpo.len())
+        .filter(|&i| needs_frame[rpo[i].index()])
+        .reduce(|a, b| intersect(&idom, a, b))
+    {
+        Some(block) => block,
+        None => {
+            return Some(ShrinkWrap {
+                prologue_block: None,
+                in_frame: vec![false; num_blocks],
+            })
+        }
+    };
+    while prologue_block != 0
+        && (exits[prologue_block] != 0
+            || first_position[prologue_block] != position[rpo[prologue_block].index()])
+    {
+        prologue_block = idom[prologue_block];
+    }
+    if prologue_block == 0 {
+        return None;
+    }
+
+    let mut in_frame = vec![false; num_blocks];
+    let mut dominated = vec![false; rpo.len()];
+    dominated[prologue_block] = true;
+    for (i, &block) in rpo.iter().enumerate().skip(prologue_block) {
+        dominated[i] = dominated[i] || dominated[idom[i]];
+        in_frame[block.index()] = dominated[i];
+    }
+    Some(ShrinkWrap {
+        prologue_block: Some(rpo[prologue_block]),
+        in_frame,
+    })
+}
@@ -0,0 +1,289 @@
+//! Sharing of frame space between sized stack slots.
+//!
+//! Frontends may create many sized stack slots which are each only used for a
+//! short part of the function. Such slots can share the same space in the
+//! frame when they are never live at the same time, which is computed here.
+//!
+//! A slot's contents matter from any point where it may have been stored to,
+//! up to the last point where it may be loaded from. Stores may only cover
+//! part of a slot, so they never end these ranges: a slot is *active* at a
+//! program point if it may have been stored to on some path to the point, and
+//! may be loaded from on some path from it. Two slots interfere if one of them
+//! is stored to while the other one is active, or if both are active at the
+//! start of a block. Slots which don't interfere can share their space.
+//!
+//! Only slots whose address doesn't escape are considered: slots which are
+//! only accessed through `stack_addr` results used directly as the address of
+//! in-bounds `load` and `store` instructions, which is what the legalizer
+//! turns `stack_load` and `stack_store` into. All other slots keep space of
+//! their own.
+
+use crate::entity::{packed_option::PackedOption, SecondaryMap};
+use crate::flowgraph::ControlFlowGraph;
+use crate::fx::{FxHashMap, FxHashSet};
+use crate::ir::{Block, Function, Inst, InstructionData, Opcode, StackSlot, Value};
+use crate::trace;
+use alloc::vec::Vec;
+
+/// An access to a stack slot.
+#[derive(Clone, Copy)]
+enum Access {
+    Load(StackSlot),
+    Store(StackSlot),
+}
+
+/// Computes which sized stack slots of `func` can share their space in the
+/// frame.
+///
+/// Each slot which can share the space of lower-numbered slots is mapped to
+/// the lowest-numbered of them. That slot's space then needs to be as large as
+/// the largest of the slots sharing it.
+pub(crate) fn shared_stack_slots(
+    func: &Function,
+) -> SecondaryMap<StackSlot, PackedOption<StackSlot>> {
+    let mut shared = SecondaryMap::new();
+    if func.sized_stack_slots.len() < 2 {
+        return shared;
+    }
+
+    let accesses = find_accesses(func);
+    if accesses.is_empty() {
+        return shared;
+    }
+    let interference = compute_interference(func, &accesses);
+
+    // Greedily color the slots which are accessed, largest first, so that
+    // smaller slots are packed into the space of larger ones.
+    let mut slots: Vec<StackSlot> = accesses
+        .values()
+        .map(|access| match *access {
+            Access::Load(slot) | Access::Store(slot) => slot,
+        })
+        .collect::<FxHashSet<_>>()
+        .into_iter()
+        .collect();
+    slots.sort_unstable_by_key(|&slot| {
+        (core::cmp::Reverse(func.sized_stack_slots[slot].size), slot)
+    });
+
+    let mut colors: Vec<Vec<StackSlot>> = Vec::new();
+    for slot in slots {
+        let interferes =
+            |other: &StackSlot| interference.contains(&(slot.min(*other), slot.max(*other)));
+        match colors
+            .iter_mut()
+            .find(|color| !color.iter().any(interferes))
+        {
+            Some(color) => color.push(slot),
+            None => colors.push(vec![slot]),
+        }
+    }
+
+    for color in colors {
+        let leader = *color.iter().min().unwrap();
+        for slot in color {
+            if slot != leader {
+                trace!("stack slot {} shares the space of {}", slot, leader);
+                shared[slot] = leader.into();
+            }
+        }
+    }
+    shared
+}
+
+/// Finds the loads from and stores to the sized stack slots of `func` whose
+/// address doesn't escape.
+fn find_accesses(func: &Function) -> FxHashMap<Inst, Access> {
+    // The slot and offset of each `stack_addr` result, and the slots which are
+    // referenced by other instructions.
+    let mut addrs: FxHashMap<Value, (StackSlot, i64)> = FxHashMap::default();
+    let mut escaping: FxHashSet<StackSlot> = FxHashSet::default();
+    for block in func.layout.blocks() {
+        for inst in func.layout.block_insts(block) {
+            match func.dfg.insts[inst] {
+                InstructionData::StackLoad {
+                    opcode: Opcode::StackAddr,
+                    stack_slot,
+                    offset,
+                } => {
+                    let addr = func.dfg.first_result(inst);
+                    addrs.insert(addr, (stack_slot, i64::from(offset)));
+                }
+                InstructionData::StackLoad { stack_slot, .. }
+                | InstructionData::StackStore { stack_slot, .. } => {
+                    escaping.insert(stack_slot);
+                }
+                _ => {}
+            }
+        }
+    }
+    if addrs.is_empty() {
+        return FxHashMap::default();
+    }
+
+    let mut accesses = FxHashMap::default();
+    for block in func.layout.blocks() {
+        for inst in func.layout.block_insts(block) {
+            let access = match func.dfg.insts[inst] {
+                InstructionData::Load {
+                    opcode: Opcode::Load,
+                    arg,
+                    offset,
+                    ..
+                } => Some((arg, offset, func.dfg.first_result(inst), false)),
+                InstructionData::Store {
+                    opcode: Opcode::Store,
+                    args: [data, addr],
+                    offset,
+                    ..
+                } => Some((addr, offset, data, true)),
+                _ => None,
+            };
+
+            for value in func.dfg.inst_values(inst) {
+                let value = func.dfg.resolve_aliases(value);
+                let &(slot, addr_offset) = match addrs.get(&value) {
+                    Some(addr) => addr,
+                    None => continue,
+                };
+                let (offset, accessed, is_store) = match access {
+                    Some((addr, offset, accessed, is_store))
+                        if func.dfg.resolve_aliases(addr) == value
+                            && func.dfg.resolve_aliases(accessed) != value =>
+                    {
+                        (offset, accessed, is_store)
+                    }
+                    _ => {
+                        escaping.insert(slot);
+                        continue;
+                    }
+                };
+
+                let start = addr_offset + i64::from(offset);
+                let end = start + i64::from(func.dfg.value_type(accessed).bytes());
+                if start < 0 || end > i64::from(func.sized_stack_slots[slot].size) {
+                    escaping.insert(slot);
+                } else if is_store {
+                    accesses.insert(inst, Access::Store(slot));
+                } else {
+                    accesses.insert(inst, Access::Load(slot));
+                }
+            }
+        }
+    }
+
+    accesses.retain(|_, access| match *access {
+        Access::Load(slot) | Access::Store(slot) => !escaping.contains(&slot),
+    });
+    accesses
+}
+
+/// Computes the pairs of slots accessed in `accesses` which interfere, with the
+/// lower-numbered slot first.
+fn compute_interference(
+    func: &Function,
+    accesses: &FxHashMap<Inst, Access>,
+) -> FxHashSet<(StackSlot, StackSlot)> {
+    let cfg = ControlFlowGraph::with_function(func);
+    let blocks: Vec<Block> = func.layout.blocks().collect();
+
+    let mut loads: SecondaryMap<Block, FxHashSet<StackSlot>> = SecondaryMap::new();
+    let mut stores: SecondaryMap<Block, FxHashSet<StackSlot>> = SecondaryMap::new();
+    for &block in &blocks {
+        for inst in func.layout.block_insts(block) {
+            match accesses.get(&inst) {
+                Some(Access::Load(slot)) => {
+                    loads[block].insert(*slot);
+                }
+                Some(Access::Store(slot)) => {
+                    stores[block].insert(*slot);
+                }
+                None => {}
+            }
+        }
+    }
+
+    // The slots which may have been stored to at the start of each block.
+    let mut stored_in: SecondaryMap<Block, FxHashSet<StackSlot>> = SecondaryMap::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in &blocks {
+            let mut stored: FxHashSet<StackSlot> = stored_in[block].clone();
+            stored.extend(stores[block].iter().copied());
+            for succ in cfg.succ_iter(block) {
+                for &slot in &stored {
+                    changed |= stored_in[succ].insert(slot);
+                }
+            }
+        }
+    }
+
+    // The slots which may be loaded from after the end of each block.
+    let mut loaded_out: SecondaryMap<Block, FxHashSet<StackSlot>> = SecondaryMap::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in blocks.iter().rev() {
+            let mut loaded = loaded_out[block].clone();
+            loaded.extend(loads[block].iter().copied());
+            for pred in cfg.pred_iter(block) {
+                for &slot in &loaded {
+                    changed |= loaded_out[pred.block].insert(slot);
+                }
+            }
+        }
+    }
+
+    let mut interference = FxHashSet::default();
+    let mut interfere = |a: StackSlot, b: StackSlot| {
+        if a != b {
+            interference.insert((a.min(b), a.max(b)));
+        }
+    };
+    for &block in &blocks {
+        let insts: Vec<Inst> = func.layout.block_insts(block).collect();
+
+        // The index of the last load from each slot within the block.
+        let mut last_load: FxHashMap<StackSlot, usize> = FxHashMap::default();
+        for (i, inst) in insts.iter().enumerate() {
+            if let Some(&Access::Load(slot)) = accesses.get(inst) {
+                last_load.insert(slot, i);
+            }
+        }
+        let loaded_after = |slot: StackSlot, i: usize| {
+            loaded_out[block].contains(&slot) || last_load.get(&slot).map_or(false, |&l| l >= i)
+        };
+
+        let mut active: Vec<StackSlot> = stored_in[block]
+            .iter()
+            .copied()
+            .filter(|&slot| loaded_after(slot, 0))
+            .collect();
+        for (i, &a) in active.iter().enumerate() {
+            for &b in &active[i + 1..] {
+                interfere(a, b);
+            }
+        }
+
+        for (i, inst) in insts.iter().enumerate() {
+            match accesses.get(inst) {
+                Some(&Access::Load(slot)) => {
+                    if !loaded_after(slot, i + 1) {
+                        active.retain(|&s| s != slot);
+                    }
+                }
+                Some(&Access::Store(slot)) => {
+                    for &other in &active {
+                        interfere(slot, other);
+                    }
+                    if loaded_after(slot, i + 1) && !active.contains(&slot) {
+                        active.push(slot);
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+    interference
+}
@@ -736,6 +736,57 @@ impl<I: VCodeInst> VCode<I> {
         clobbered
     }
 
+    /// Computes which blocks need the frame, post-regalloc: blocks with IR
+    /// instructions that need it, calls and other instructions with clobbers,
+    /// and accesses to spillslots or writes to the callee-saved registers which
+    /// the prologue saves, including those by regalloc moves.
+    fn compute_blocks_needing_frame(&self, regalloc: &regalloc2::Output) -> Vec<bool> {
+        let callee_saves: FxHashSet<RealReg> = self
+            .abi
+            .clobbered_callee_saves()
+            .into_iter()
+            .map(|reg| reg.to_reg())
+            .collect();
+        let writes_callee_save = |alloc: Allocation| {
+            alloc
+                .as_reg()
+                .map_or(false, |preg| callee_saves.contains(&RealReg::from(preg)))
+        };
+
+        (0..self.num_blocks())
+            .map(|block| {
+                let block = BlockIndex::new(block);
+                let lowered = &self.block_order.lowered_order()[block.index()];
+                if let Some(ir_block) = lowered.orig_block() {
+                    if self.abi.block_needs_frame(ir_block) {
+                        return true;
+                    }
+                }
+
+                regalloc
+                    .block_insts_and_edits(self, block)
+                    .any(|inst_or_edit| match inst_or_edit {
+                        InstOrEdit::Inst(iix) => {
+                            if self.clobbers.contains_key(&iix) {
+                                return true;
+                            }
+                            let (start, end) = self.operand_ranges[iix.index()];
+                            let operands = &self.operands[start as usize..end as usize];
+                            let allocs = &regalloc.allocs[start as usize..end as usize];
+                            operands.iter().zip(allocs.iter()).any(|(operand, alloc)| {
+                                alloc.is_stack()
+                                    || (operand.kind() != OperandKind::Use
+                                        && writes_callee_save(*alloc))
+                            })
+                        }
+                        InstOrEdit::Edit(Edit::Move { from, to }) => {
+                            from.is_stack() || to.is_stack() || writes_callee_save(*to)
+                        }
+                    })
+            })
+            .collect()
+    }
+
     /// Emit the instructions to a `MachBuffer`, containing fixed-up
     /// code and external reloc/trap/etc. records ready for use. Takes
     /// the regalloc results as well.
@@ -779,7 +830,29 @@ impl<I: VCodeInst> VCode<I> {
                 final_order.push(block);
             }
         }
-        final_order.extend(cold_blocks.clone());
+
+        // When splitting the function, the cold blocks go into a separate
+        // part, except for targets of jump tables: their entries can't refer
+        // to the other part, so those blocks stay at the end of the hot part.
+        let first_cold_block = if flags.enable_hot_cold_splitting() {
+            let jump_table_targets: FxHashSet<BlockIndex> = (0..self.num_blocks())
+                .map(BlockIndex::new)
+                .filter(|&block| {
+                    let (start, end) = self.block_ranges[block.index()];
+                    end > start && self.insts[end.index() - 1].is_term() == MachTerminator::Indirect
+                })
+                .flat_map(|block| self.block_succs(block).iter().copied())
+                .collect();
+            let (hot, cold): (SmallVec<[BlockIndex; 16]>, _) = cold_blocks
+                .into_iter()
+                .partition(|block| jump_table_targets.contains(block));
+            final_order.extend(hot);
+            cold_blocks = cold;
+            cold_blocks.first().copied()
+        } else {
+            None
+        };
+        final_order.extend(cold_blocks);
 
         // Compute/save info we need for the prologue: clobbers and
         // number of spillslots.
@@ -793,10 +866,31 @@ impl<I: VCodeInst> VCode<I> {
         self.abi.set_num_spillslots(regalloc.num_spillslots);
         self.abi.set_clobbered(clobbers);
 
+        // When shrink-wrapping, find the block at whose start the prologue
+        // goes, and which blocks run with the frame. Otherwise the prologue
+        // goes at the start of the entry block.
+        let shrink_wrap = if self.abi.can_shrink_wrap(&self.sigs) {
+            let needs_frame = self.compute_blocks_needing_frame(regalloc);
+            shrink_wrap::place_prologue(&self, &needs_frame, &final_order)
+        } else {
+            None
+        };
+        let prologue_block = match &shrink_wrap {
+            Some(shrink_wrap) => shrink_wrap.prologue_block,
+            None => Some(self.entry),
+        };
+        let in_frame = |block: BlockIndex| match &shrink_wrap {
+            Some(shrink_wrap) => shrink_wrap.in_frame[block.index()],
+            None => true,
+        };
+        if let Some(block) = prologue_block {
+            trace!("prologue placed at block {:?}", block);
+        }
+
         // We need to generate the prologue in order to get the ABI
         // object into the right state first. We'll emit it when we
         // hit the right block below.
-        let prologue_insts = self.abi.gen_prologue(&self.sigs);
+        let (prologue_start_insts, prologue_insts) = self.abi.gen_prologue(&self.sigs);
 
         // Emit blocks.
         let mut cur_srcloc = None;
@@ -833,9 +927,18 @@ impl<I: VCodeInst> VCode<I> {
         };
         let mut total_bb_padding = 0;
 
+        // Whether the code emitted last runs with the frame, to mark the
+        // transitions in the unwind info when shrink-wrapping.
+        let mut cur_in_frame = false;
+
         for (block_order_idx, &block) in final_order.iter().enumerate() {
             trace!("emitting block {:?}", block);
 
+            if Some(block) == first_cold_block {
+                trace!(" -> start of cold part");
+                buffer.start_cold_part(state.ctrl_plane_mut());
+            }
+
             // Call the new block hook for state
             state.on_new_block();
 
@@ -860,14 +963,22 @@ impl<I: VCodeInst> VCode<I> {
                 inst.emit(allocs, buffer, &self.emit_info, state);
             };
 
-            // Is this the first block? Emit the prologue directly if so.
+            // Is this the first block? Emit the prologue directly if so,
+            // or the part of it which has to come first when it's placed
+            // at a later block.
             if block == self.entry {
                 trace!(" -> entry block");
                 buffer.start_srcloc(Default::default());
                 state.pre_sourceloc(Default::default());
-                for inst in &prologue_insts {
+                for inst in &prologue_start_insts {
                     do_emit(&inst, &[], &mut disasm, &mut buffer, &mut state);
                 }
+                if prologue_block == Some(block) {
+                    for inst in &prologue_insts {
+                        do_emit(&inst, &[], &mut disasm, &mut buffer, &mut state);
+                    }
+                    cur_in_frame = true;
+                }
                 buffer.end_srcloc();
             }
 
@@ -902,6 +1013,25 @@ impl<I: VCodeInst> VCode<I> {
                 do_emit(&block_start, &[], &mut disasm, &mut buffer, &mut state);
             }
 
+            // Emit a shrink-wrapped prologue after the label, so that all
+            // branches to this block run it; otherwise mark any change in
+            // whether the frame is in place for the unwind info.
+            if block != self.entry && prologue_block == Some(block) {
+                buffer.start_srcloc(Default::default());
+                state.pre_sourceloc(Default::default());
+                for inst in &prologue_insts {
+                    do_emit(&inst, &[], &mut disasm, &mut buffer, &mut state);
+                }
+                buffer.end_srcloc();
+                cur_in_frame = true;
+            } else if in_frame(block) != cur_in_frame {
+                if flags.unwind_info() {
+                    let unwind = I::ABIMachineSpec::gen_frame_state_unwind(in_frame(block));
+                    do_emit(&unwind, &[], &mut disasm, &mut buffer, &mut state);
+                }
+                cur_in_frame = in_frame(block);
+            }
+
             for inst_or_edit in regalloc.block_insts_and_edits(&self, block) {
                 match inst_or_edit {
                     InstOrEdit::Inst(iix) => {
@@ -976,7 +1106,12 @@ impl<I: VCodeInst> VCode<I> {
                         // (and don't emit the return; the actual
                         // epilogue will contain it).
                         if self.insts[iix.index()].is_term() == MachTerminator::Ret {
-                            for inst in self.abi.gen_epilogue(&self.sigs) {
+                            let epilogue = if in_frame(block) {
+                                self.abi.gen_epilogue(&self.sigs)
+                            } else {
+                                self.abi.gen_frameless_epilogue(&self.sigs)
+                            };
+                            for inst in epilogue {
                                 do_emit(&inst, &[], &mut disasm, &mut buffer, &mut state);
                             }
                         } else {
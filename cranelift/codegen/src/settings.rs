@@ -541,6 +541,8 @@ enable_llvm_abi_extensions = false
 unwind_info = true
 preserve_frame_pointers = false
 machine_code_cfg_info = false
+enable_hot_cold_splitting = false
+enable_shrink_wrapping = false
 enable_probestack = false
 probestack_func_adjusts_sp = false
 enable_jump_tables = true
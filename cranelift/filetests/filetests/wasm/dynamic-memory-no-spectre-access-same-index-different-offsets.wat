@@ -33,7 +33,8 @@
     ;; Within the guard region.
     local.get 0
     i32.load offset=0
-    ;; Also within the guard region, bounds check should GVN with previous.
+    ;; Also within the guard region, bounds check should GVN with previous,
+    ;; and is then removed as redundant.
     local.get 0
     i32.load offset=4
     ;; Outside the guard region, needs additional bounds checks.
@@ -74,10 +75,7 @@
 ;; @0047                               v9 = iadd v8, v5
 ;; @0047                               v10 = load.i32 little heap v9
 ;;                                     v2 -> v10
-;; @004c                               brif.i8 v7, block4, block5
-;;
-;;                                 block4 cold:
-;; @004c                               trap heap_oob
+;; @004c                               jump block5
 ;;
 ;;                                 block5:
 ;;                                     v27 = iconst.i64 4
@@ -123,10 +121,7 @@
 ;; @005d                               v8 = load.i64 notrap aligned v4
 ;; @005d                               v9 = iadd v8, v5
 ;; @005d                               store.i32 little heap v1, v9
-;; @0064                               brif.i8 v7, block4, block5
-;;
-;;                                 block4 cold:
-;; @0064                               trap heap_oob
+;; @0064                               jump block5
 ;;
 ;;                                 block5:
 ;;                                     v24 = iconst.i64 4
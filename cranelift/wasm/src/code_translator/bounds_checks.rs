@@ -8,6 +8,10 @@
 //!
 //! bounds check the memory access and translate it into a native memory access.
 //!
+//! Each access is checked on its own here. Checks made redundant by an earlier
+//! check of the same index against the same bound, covering at least as many
+//! bytes, are removed later on by Cranelift's mid-end.
+//!
 //! !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
 //! !!!                                                                      !!!
 //! !!!    THIS CODE IS VERY SUBTLE, HAS MANY SPECIAL CASES, AND IS ALSO     !!!
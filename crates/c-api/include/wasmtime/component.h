@@ -0,0 +1,610 @@
+/**
+ * \file wasmtime/component.h
+ *
+ * APIs for interacting with WebAssembly components, as defined by the
+ * [component model](https://github.com/WebAssembly/component-model).
+ *
+ * Components are compiled with #wasmtime_component_new and instantiated with a
+ * #wasmtime_component_linker_t which provides the component's imports. Values
+ * flowing in and out of components are represented with
+ * #wasmtime_component_val_t.
+ *
+ * Note that the component model must be enabled in the engine's configuration
+ * through #wasmtime_config_wasm_component_model_set.
+ *
+ * These APIs are only available when the C API is built with the
+ * `component-model` Cargo feature, which isn't enabled by default. With CMake
+ * this is done by setting the `WASMTIME_COMPONENT_MODEL` option.
+ */
+
+#ifndef WASMTIME_COMPONENT_H
+#define WASMTIME_COMPONENT_H
+
+#include <wasm.h>
+#include <wasi.h>
+#include <wasmtime/error.h>
+#include <wasmtime/module.h>
+#include <wasmtime/store.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/**
+ * \typedef wasmtime_component_t
+ * \brief Convenience alias for #wasmtime_component
+ *
+ * \struct wasmtime_component
+ * \brief A compiled WebAssembly component.
+ *
+ * A component is safe to share across threads and can be instantiated many
+ * times within different stores.
+ */
+typedef struct wasmtime_component wasmtime_component_t;
+
+/**
+ * \brief Compiles a WebAssembly component from its binary format.
+ *
+ * \param engine the engine to compile the component into.
+ * \param wasm the binary encoding of the component.
+ * \param wasm_len the length of `wasm`, in bytes.
+ * \param ret on success, filled in with the compiled component.
+ *
+ * \return `NULL` on success, or an error describing why compilation failed.
+ *
+ * This function does not take ownership of any of its arguments, and the
+ * returned component must be deleted with #wasmtime_component_delete.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_new(
+    const wasm_engine_t *engine,
+    const uint8_t *wasm,
+    size_t wasm_len,
+    wasmtime_component_t **ret
+);
+
+/**
+ * \brief Deletes a component.
+ */
+WASM_API_EXTERN void wasmtime_component_delete(wasmtime_component_t *component);
+
+/**
+ * \brief Creates a shallow clone of the specified component, increasing its
+ * internal reference count.
+ */
+WASM_API_EXTERN wasmtime_component_t *wasmtime_component_clone(wasmtime_component_t *component);
+
+/**
+ * \brief Serializes a compiled component into a binary blob.
+ *
+ * The serialized bytes can later be passed to
+ * #wasmtime_component_deserialize. On success the bytes are stored in `ret`,
+ * which the caller must free with #wasm_byte_vec_delete.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_serialize(
+    wasmtime_component_t *component,
+    wasm_byte_vec_t *ret
+);
+
+/**
+ * \brief Builds a component from serialized bytes produced by
+ * #wasmtime_component_serialize.
+ *
+ * This function is not safe to call with arbitrary bytes. The bytes must
+ * have been produced by #wasmtime_component_serialize with a compatible
+ * engine, otherwise the behavior is undefined.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_deserialize(
+    wasm_engine_t *engine,
+    const uint8_t *bytes,
+    size_t bytes_len,
+    wasmtime_component_t **ret
+);
+
+/**
+ * \brief Representation of an instantiated component within a store.
+ *
+ * Like #wasmtime_instance_t this is a plain index into the owning store and
+ * must only be used with that store.
+ */
+typedef struct wasmtime_component_instance {
+  /// Internal identifier of what store this belongs to, never zero.
+  uint64_t store_id;
+  /// Internal index within the store.
+  size_t index;
+} wasmtime_component_instance_t;
+
+/**
+ * \brief Representation of a function exported by a component instance.
+ *
+ * Like #wasmtime_func_t this is a plain index into the owning store and must
+ * only be used with that store.
+ */
+typedef struct wasmtime_component_func {
+  /// Internal identifier of what store this belongs to, never zero.
+  uint64_t store_id;
+  /// Internal index within the store.
+  size_t index;
+} wasmtime_component_func_t;
+
+/// \brief Discriminant used in #wasmtime_component_val_t::kind
+typedef uint8_t wasmtime_component_val_kind_t;
+
+/// \brief Value of #wasmtime_component_val_kind_t meaning that
+/// #wasmtime_component_val_t is a `bool`
+#define WASMTIME_COMPONENT_BOOL 0
+/// \brief A `s8`
+#define WASMTIME_COMPONENT_S8 1
+/// \brief A `u8`
+#define WASMTIME_COMPONENT_U8 2
+/// \brief A `s16`
+#define WASMTIME_COMPONENT_S16 3
+/// \brief A `u16`
+#define WASMTIME_COMPONENT_U16 4
+/// \brief A `s32`
+#define WASMTIME_COMPONENT_S32 5
+/// \brief A `u32`
+#define WASMTIME_COMPONENT_U32 6
+/// \brief A `s64`
+#define WASMTIME_COMPONENT_S64 7
+/// \brief A `u64`
+#define WASMTIME_COMPONENT_U64 8
+/// \brief A `float32`
+#define WASMTIME_COMPONENT_FLOAT32 9
+/// \brief A `float64`
+#define WASMTIME_COMPONENT_FLOAT64 10
+/// \brief A `char`, stored as a unicode scalar value
+#define WASMTIME_COMPONENT_CHAR 11
+/// \brief A `string`, stored as utf-8 bytes
+#define WASMTIME_COMPONENT_STRING 12
+/// \brief A `list<T>`
+#define WASMTIME_COMPONENT_LIST 13
+/// \brief A `record`
+#define WASMTIME_COMPONENT_RECORD 14
+/// \brief A `tuple<...>`
+#define WASMTIME_COMPONENT_TUPLE 15
+/// \brief A `variant`
+#define WASMTIME_COMPONENT_VARIANT 16
+/// \brief An `enum`
+#define WASMTIME_COMPONENT_ENUM 17
+/// \brief A `union`
+#define WASMTIME_COMPONENT_UNION 18
+/// \brief An `option<T>`
+#define WASMTIME_COMPONENT_OPTION 19
+/// \brief A `result<T, E>`
+#define WASMTIME_COMPONENT_RESULT 20
+/// \brief A `flags`
+#define WASMTIME_COMPONENT_FLAGS 21
+
+struct wasmtime_component_val;
+struct wasmtime_component_val_record_field;
+
+/// \brief A vector of #wasmtime_component_val_t, used for lists and tuples.
+typedef struct wasmtime_component_val_vec {
+  /// Number of values.
+  size_t size;
+  /// Pointer to the values.
+  struct wasmtime_component_val *data;
+} wasmtime_component_val_vec_t;
+
+/// \brief A vector of fields, used for records.
+typedef struct wasmtime_component_val_record {
+  /// Number of fields.
+  size_t size;
+  /// Pointer to the fields.
+  struct wasmtime_component_val_record_field *data;
+} wasmtime_component_val_record_t;
+
+/// \brief A vector of flag names which are set, used for flags.
+typedef struct wasmtime_component_val_flags {
+  /// Number of flags set.
+  size_t size;
+  /// Pointer to the names of the flags.
+  wasm_name_t *data;
+} wasmtime_component_val_flags_t;
+
+/// \brief Payload of a `variant` value.
+typedef struct wasmtime_component_val_variant {
+  /// Name of the case of this variant.
+  wasm_name_t discriminant;
+  /// Payload of the case, or `NULL` if the case has no payload. Must be
+  /// allocated with #wasmtime_component_val_new.
+  struct wasmtime_component_val *val;
+} wasmtime_component_val_variant_t;
+
+/// \brief Payload of a `union` value.
+typedef struct wasmtime_component_val_union {
+  /// Index of the case of this union.
+  uint32_t discriminant;
+  /// Payload of the case, never `NULL`. Must be allocated with
+  /// #wasmtime_component_val_new.
+  struct wasmtime_component_val *val;
+} wasmtime_component_val_union_t;
+
+/// \brief Payload of a `result` value.
+typedef struct wasmtime_component_val_result {
+  /// Whether this is the `ok` case or the `err` case.
+  bool is_ok;
+  /// Payload of the case, or `NULL` if the case has no payload. Must be
+  /// allocated with #wasmtime_component_val_new.
+  struct wasmtime_component_val *val;
+} wasmtime_component_val_result_t;
+
+/**
+ * \typedef wasmtime_component_val_union_of_t
+ * \brief Convenience alias for #wasmtime_component_val_union_of
+ *
+ * \union wasmtime_component_val_union_of
+ * \brief Container for the payload of a #wasmtime_component_val_t.
+ */
+typedef union wasmtime_component_val_union_of {
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_BOOL
+  bool boolean;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_S8
+  int8_t s8;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_U8
+  uint8_t u8;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_S16
+  int16_t s16;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_U16
+  uint16_t u16;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_S32
+  int32_t s32;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_U32
+  uint32_t u32;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_S64
+  int64_t s64;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_U64
+  uint64_t u64;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_FLOAT32
+  float float32;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_FLOAT64
+  double float64;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_CHAR
+  uint32_t character;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_STRING
+  wasm_name_t string;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_LIST
+  wasmtime_component_val_vec_t list;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_RECORD
+  wasmtime_component_val_record_t record;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_TUPLE
+  wasmtime_component_val_vec_t tuple;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_VARIANT
+  wasmtime_component_val_variant_t variant;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_ENUM
+  wasm_name_t enumeration;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_UNION
+  wasmtime_component_val_union_t union_;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_OPTION,
+  /// `NULL` for `none`. Must be allocated with #wasmtime_component_val_new.
+  struct wasmtime_component_val *option;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_RESULT
+  wasmtime_component_val_result_t result;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_FLAGS
+  wasmtime_component_val_flags_t flags;
+} wasmtime_component_val_union_of_t;
+
+/**
+ * \typedef wasmtime_component_val_t
+ * \brief Convenience alias for #wasmtime_component_val
+ *
+ * \struct wasmtime_component_val
+ * \brief A value passed to or returned from a component function.
+ *
+ * Values own their contents, such as strings, lists and boxed payloads, and
+ * must be released with #wasmtime_component_val_delete. All memory owned by a
+ * value must be allocated through this API, for example with
+ * #wasm_name_new, #wasmtime_component_val_vec_new or
+ * #wasmtime_component_val_new.
+ *
+ * Resources are not currently supported and produce an error when they are
+ * passed to or returned from a component function.
+ */
+typedef struct wasmtime_component_val {
+  /// Discriminant of which field of `of` is valid.
+  wasmtime_component_val_kind_t kind;
+  /// Payload of this value.
+  wasmtime_component_val_union_of_t of;
+} wasmtime_component_val_t;
+
+/// \brief A named field of a `record` value.
+typedef struct wasmtime_component_val_record_field {
+  /// Name of the field.
+  wasm_name_t name;
+  /// Value of the field.
+  wasmtime_component_val_t val;
+} wasmtime_component_val_record_field_t;
+
+/**
+ * \brief Allocates a new value on the heap, initialized to `false`.
+ *
+ * This is used for the boxed payloads of variants, unions, options and
+ * results. Such payloads are freed along with their containing value. A value
+ * allocated here which is not stored within another value must be freed with
+ * #wasmtime_component_val_free.
+ */
+WASM_API_EXTERN wasmtime_component_val_t *wasmtime_component_val_new(void);
+
+/// \brief Frees a value allocated with #wasmtime_component_val_new.
+WASM_API_EXTERN void wasmtime_component_val_free(wasmtime_component_val_t *val);
+
+/**
+ * \brief Releases the memory owned by `val`, resetting it to `false`.
+ *
+ * This does not free `val` itself.
+ */
+WASM_API_EXTERN void wasmtime_component_val_delete(wasmtime_component_val_t *val);
+
+/// \brief Performs a deep copy of `src` into `dst`.
+WASM_API_EXTERN void wasmtime_component_val_copy(
+    wasmtime_component_val_t *dst,
+    const wasmtime_component_val_t *src
+);
+
+/// \brief Creates a vector of values by copying `ptr[0..size]` into `out`.
+WASM_API_EXTERN void wasmtime_component_val_vec_new(
+    wasmtime_component_val_vec_t *out,
+    size_t size,
+    const wasmtime_component_val_t *ptr
+);
+/// \brief Creates an empty vector of values.
+WASM_API_EXTERN void wasmtime_component_val_vec_new_empty(wasmtime_component_val_vec_t *out);
+/// \brief Creates a vector of `size` values, each initialized to `false`.
+WASM_API_EXTERN void wasmtime_component_val_vec_new_uninitialized(
+    wasmtime_component_val_vec_t *out,
+    size_t size
+);
+/// \brief Performs a deep copy of a vector of values.
+WASM_API_EXTERN void wasmtime_component_val_vec_copy(
+    wasmtime_component_val_vec_t *out,
+    const wasmtime_component_val_vec_t *src
+);
+/// \brief Deletes a vector of values.
+WASM_API_EXTERN void wasmtime_component_val_vec_delete(wasmtime_component_val_vec_t *vec);
+
+/// \brief Creates a record by copying `ptr[0..size]` into `out`.
+WASM_API_EXTERN void wasmtime_component_val_record_new(
+    wasmtime_component_val_record_t *out,
+    size_t size,
+    const wasmtime_component_val_record_field_t *ptr
+);
+/// \brief Creates an empty record.
+WASM_API_EXTERN void wasmtime_component_val_record_new_empty(wasmtime_component_val_record_t *out);
+/// \brief Creates a record of `size` default-initialized fields.
+WASM_API_EXTERN void wasmtime_component_val_record_new_uninitialized(
+    wasmtime_component_val_record_t *out,
+    size_t size
+);
+/// \brief Performs a deep copy of a record.
+WASM_API_EXTERN void wasmtime_component_val_record_copy(
+    wasmtime_component_val_record_t *out,
+    const wasmtime_component_val_record_t *src
+);
+/// \brief Deletes a record.
+WASM_API_EXTERN void wasmtime_component_val_record_delete(wasmtime_component_val_record_t *record);
+
+/// \brief Creates a set of flags by copying `ptr[0..size]` into `out`.
+WASM_API_EXTERN void wasmtime_component_val_flags_new(
+    wasmtime_component_val_flags_t *out,
+    size_t size,
+    const wasm_name_t *ptr
+);
+/// \brief Creates an empty set of flags.
+WASM_API_EXTERN void wasmtime_component_val_flags_new_empty(wasmtime_component_val_flags_t *out);
+/// \brief Creates a set of `size` empty flag names.
+WASM_API_EXTERN void wasmtime_component_val_flags_new_uninitialized(
+    wasmtime_component_val_flags_t *out,
+    size_t size
+);
+/// \brief Performs a deep copy of a set of flags.
+WASM_API_EXTERN void wasmtime_component_val_flags_copy(
+    wasmtime_component_val_flags_t *out,
+    const wasmtime_component_val_flags_t *src
+);
+/// \brief Deletes a set of flags.
+WASM_API_EXTERN void wasmtime_component_val_flags_delete(wasmtime_component_val_flags_t *flags);
+
+/**
+ * \typedef wasmtime_component_linker_t
+ * \brief Convenience alias for #wasmtime_component_linker
+ *
+ * \struct wasmtime_component_linker
+ * \brief Object used to provide the imports of a component and instantiate
+ * it.
+ */
+typedef struct wasmtime_component_linker wasmtime_component_linker_t;
+
+/**
+ * \typedef wasmtime_component_linker_instance_t
+ * \brief Convenience alias for #wasmtime_component_linker_instance
+ *
+ * \struct wasmtime_component_linker_instance
+ * \brief Builder for definitions within an instance of a
+ * #wasmtime_component_linker_t.
+ *
+ * This borrows the linker it was created from, and the linker must not be
+ * used until this is deleted with #wasmtime_component_linker_instance_delete.
+ */
+typedef struct wasmtime_component_linker_instance wasmtime_component_linker_instance_t;
+
+/**
+ * \brief Callback signature for #wasmtime_component_linker_instance_define_func.
+ *
+ * The `args` are owned by the caller and are valid only for the duration of
+ * the call. The `results` are initialized to `false` and must each be filled
+ * in with a value of the function's corresponding result type; ownership of
+ * them is taken by Wasmtime.
+ *
+ * Returning a non-`NULL` error will raise a trap in the calling component.
+ */
+typedef wasmtime_error_t *(*wasmtime_component_func_callback_t)(
+    void *env,
+    wasmtime_context_t *context,
+    const wasmtime_component_val_t *args,
+    size_t nargs,
+    wasmtime_component_val_t *results,
+    size_t nresults
+);
+
+/// \brief Creates a new linker for components in the specified engine.
+WASM_API_EXTERN wasmtime_component_linker_t *wasmtime_component_linker_new(const wasm_engine_t *engine);
+
+/// \brief Deletes a linker.
+WASM_API_EXTERN void wasmtime_component_linker_delete(wasmtime_component_linker_t *linker);
+
+/// \brief Configures whether definitions in this linker may shadow previous
+/// definitions of the same name. Defaults to `false`.
+WASM_API_EXTERN void wasmtime_component_linker_allow_shadowing(
+    wasmtime_component_linker_t *linker,
+    bool allow_shadowing
+);
+
+/**
+ * \brief Returns a builder for the root namespace of `linker`.
+ *
+ * The returned builder must be deleted with
+ * #wasmtime_component_linker_instance_delete before `linker` is used again.
+ */
+WASM_API_EXTERN wasmtime_component_linker_instance_t *wasmtime_component_linker_root(
+    wasmtime_component_linker_t *linker
+);
+
+/// \brief Deletes a linker instance builder.
+WASM_API_EXTERN void wasmtime_component_linker_instance_delete(
+    wasmtime_component_linker_instance_t *instance
+);
+
+/**
+ * \brief Defines a nested instance named `name` within `instance`.
+ *
+ * On success `ret` is filled in with a builder for the new instance. It
+ * borrows `instance`, which must not be used until `ret` is deleted.
+ *
+ * Returns an error if `name` is already defined and shadowing is disallowed,
+ * or if `name` is not valid utf-8.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_linker_instance_add_instance(
+    wasmtime_component_linker_instance_t *instance,
+    const char *name,
+    size_t name_len,
+    wasmtime_component_linker_instance_t **ret
+);
+
+/**
+ * \brief Defines a host function named `name` within `instance`.
+ *
+ * The type of the function is taken from the matching import of `component`,
+ * and an error is returned if `component` has no such function import. When
+ * the function is called `cb` is invoked with `data`. The `finalizer`, if
+ * provided, is invoked on `data` when the linker is deleted.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_linker_instance_define_func(
+    wasmtime_component_linker_instance_t *instance,
+    const wasmtime_component_t *component,
+    const char *name,
+    size_t name_len,
+    wasmtime_component_func_callback_t cb,
+    void *data,
+    void (*finalizer)(void*)
+);
+
+/// \brief Defines a core wasm module named `name` within `instance`.
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_linker_instance_define_module(
+    wasmtime_component_linker_instance_t *instance,
+    const char *name,
+    size_t name_len,
+    const wasmtime_module_t *module
+);
+
+/**
+ * \brief Defines the WASI preview2 interfaces of the `wasi:cli/command` world
+ * in this linker.
+ *
+ * Stores which instantiate components with this linker must have their WASI
+ * configuration set with #wasmtime_context_set_wasi_preview2, otherwise the
+ * process will abort when WASI is used.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_linker_define_wasi(
+    wasmtime_component_linker_t *linker
+);
+
+/**
+ * \brief Instantiates `component` using the definitions in `linker`.
+ *
+ * On success `instance` is filled in with the new instance. Returns an error
+ * if an import is missing or has the wrong type, or if instantiation traps.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_linker_instantiate(
+    const wasmtime_component_linker_t *linker,
+    wasmtime_context_t *context,
+    const wasmtime_component_t *component,
+    wasmtime_component_instance_t *instance
+);
+
+/**
+ * \brief Looks up an exported function of a component instance.
+ *
+ * \param instance the instance to look up the export in.
+ * \param context the store that owns `instance`.
+ * \param instance_name the name of the exported instance containing the
+ *   function, or an empty string for a function exported at the root.
+ * \param instance_name_len the length of `instance_name`, in bytes.
+ * \param name the name of the function.
+ * \param name_len the length of `name`, in bytes.
+ * \param func on success filled in with the function.
+ *
+ * \return `true` if the function was found, `false` otherwise.
+ */
+WASM_API_EXTERN bool wasmtime_component_instance_get_func(
+    const wasmtime_component_instance_t *instance,
+    wasmtime_context_t *context,
+    const char *instance_name,
+    size_t instance_name_len,
+    const char *name,
+    size_t name_len,
+    wasmtime_component_func_t *func
+);
+
+/**
+ * \brief Calls a component function.
+ *
+ * The `params` are borrowed and must match the function's parameter types.
+ * On success `results` is initialized with `nresults` values, which must match
+ * the function's number of results, and which the caller must release with
+ * #wasmtime_component_val_delete.
+ *
+ * Returns an error if the arguments have the wrong types or if the function
+ * traps.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_func_call(
+    const wasmtime_component_func_t *func,
+    wasmtime_context_t *context,
+    const wasmtime_component_val_t *params,
+    size_t nparams,
+    wasmtime_component_val_t *results,
+    size_t nresults
+);
+
+/**
+ * \brief Configures WASI preview2 state for components within the specified
+ * store.
+ *
+ * This function is required if #wasmtime_component_linker_define_wasi is
+ * used. Only inherited stdio or in-memory stdin are supported; file-backed
+ * stdio and preopened sockets return an error. Preopened directories are
+ * given full read and write permissions.
+ *
+ * This function does not take ownership of `context` but it does take
+ * ownership of `wasi`. The caller should no longer use `wasi` after calling
+ * this function (even if an error is returned).
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_context_set_wasi_preview2(
+    wasmtime_context_t *context,
+    wasi_config_t *wasi
+);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif
+
+#endif // WASMTIME_COMPONENT_H
@@ -213,6 +213,16 @@ WASMTIME_CONFIG_PROP(void, wasm_multi_memory, bool)
  */
 WASMTIME_CONFIG_PROP(void, wasm_memory64, bool)
 
+/**
+ * \brief Configures whether the WebAssembly component model proposal is
+ * enabled.
+ *
+ * This is required to compile components with #wasmtime_component_new.
+ *
+ * This setting is `false` by default.
+ */
+WASMTIME_CONFIG_PROP(void, wasm_component_model, bool)
+
 /**
  * \brief Configures how JIT code will be compiled.
  *
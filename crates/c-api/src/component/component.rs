@@ -0,0 +1,52 @@
+use crate::{handle_result, wasm_byte_vec_t, wasm_engine_t, wasmtime_error_t};
+use wasmtime::component::Component;
+
+#[derive(Clone)]
+pub struct wasmtime_component_t {
+    pub(crate) component: Component,
+}
+
+wasmtime_c_api_macros::declare_own!(wasmtime_component_t);
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_new(
+    engine: &wasm_engine_t,
+    wasm: *const u8,
+    len: usize,
+    out: &mut *mut wasmtime_component_t,
+) -> Option<Box<wasmtime_error_t>> {
+    handle_result(
+        Component::from_binary(&engine.engine, crate::slice_from_raw_parts(wasm, len)),
+        |component| {
+            *out = Box::into_raw(Box::new(wasmtime_component_t { component }));
+        },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_clone(
+    component: &wasmtime_component_t,
+) -> Box<wasmtime_component_t> {
+    Box::new(component.clone())
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_serialize(
+    component: &wasmtime_component_t,
+    ret: &mut wasm_byte_vec_t,
+) -> Option<Box<wasmtime_error_t>> {
+    handle_result(component.component.serialize(), |buf| ret.set_buffer(buf))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_deserialize(
+    engine: &wasm_engine_t,
+    bytes: *const u8,
+    len: usize,
+    out: &mut *mut wasmtime_component_t,
+) -> Option<Box<wasmtime_error_t>> {
+    let bytes = crate::slice_from_raw_parts(bytes, len);
+    handle_result(Component::deserialize(&engine.engine, bytes), |component| {
+        *out = Box::into_raw(Box::new(wasmtime_component_t { component }));
+    })
+}
@@ -0,0 +1,59 @@
+use super::wasmtime_component_val_t;
+use crate::{handle_result, wasmtime_error_t, CStoreContextMut};
+use anyhow::{bail, Result};
+use std::mem::MaybeUninit;
+use wasmtime::component::{Func, Val};
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_func_call(
+    func: &Func,
+    mut store: CStoreContextMut<'_>,
+    params: *const wasmtime_component_val_t,
+    nparams: usize,
+    results: *mut MaybeUninit<wasmtime_component_val_t>,
+    nresults: usize,
+) -> Option<Box<wasmtime_error_t>> {
+    let params = crate::slice_from_raw_parts(params, nparams);
+    let results = crate::slice_from_raw_parts_mut(results, nresults);
+    handle_result(call(func, &mut store, params, results), |()| ())
+}
+
+fn call(
+    func: &Func,
+    store: &mut CStoreContextMut<'_>,
+    params: &[wasmtime_component_val_t],
+    results: &mut [MaybeUninit<wasmtime_component_val_t>],
+) -> Result<()> {
+    let param_tys = func.params(&*store);
+    let result_tys = func.results(&*store);
+    if params.len() != param_tys.len() {
+        bail!(
+            "expected {} parameters, got {}",
+            param_tys.len(),
+            params.len()
+        );
+    }
+    if results.len() != result_tys.len() {
+        bail!(
+            "expected {} results, got {}",
+            result_tys.len(),
+            results.len()
+        );
+    }
+    let params = params
+        .iter()
+        .zip(param_tys.iter())
+        .map(|(val, ty)| val.to_val(ty))
+        .collect::<Result<Vec<_>>>()?;
+    let mut vals = vec![Val::Bool(false); results.len()];
+    func.call(&mut *store, &params, &mut vals)?;
+    func.post_return(&mut *store)?;
+    let vals = vals
+        .iter()
+        .map(wasmtime_component_val_t::from_val)
+        .collect::<Result<Vec<_>>>()?;
+    for (dst, val) in results.iter_mut().zip(vals) {
+        crate::initialize(dst, val);
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use crate::CStoreContextMut;
+use std::str;
+use wasmtime::component::{Func, Instance};
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_instance_get_func(
+    instance: &Instance,
+    mut store: CStoreContextMut<'_>,
+    instance_name: *const u8,
+    instance_name_len: usize,
+    name: *const u8,
+    name_len: usize,
+    func: &mut Func,
+) -> bool {
+    let instance_name = match str::from_utf8(crate::slice_from_raw_parts(
+        instance_name,
+        instance_name_len,
+    )) {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    let name = match str::from_utf8(crate::slice_from_raw_parts(name, name_len)) {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    let mut exports = instance.exports(&mut store);
+    let mut export = if instance_name.is_empty() {
+        exports.root()
+    } else {
+        match exports.instance(instance_name) {
+            Some(export) => export,
+            None => return false,
+        }
+    };
+    match export.func(name) {
+        Some(f) => {
+            *func = f;
+            true
+        }
+        None => false,
+    }
+}
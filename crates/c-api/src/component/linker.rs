@@ -0,0 +1,177 @@
+use super::{wasmtime_component_t, wasmtime_component_val_t};
+use crate::{
+    bad_utf8, handle_result, wasm_engine_t, wasmtime_error_t, wasmtime_module_t, CStoreContextMut,
+    ForeignData,
+};
+use anyhow::Result;
+use std::ffi::c_void;
+use std::str;
+use wasmtime::component::{Instance, Linker, LinkerInstance, Type, Val};
+
+#[repr(C)]
+pub struct wasmtime_component_linker_t {
+    linker: Linker<crate::StoreData>,
+}
+
+wasmtime_c_api_macros::declare_own!(wasmtime_component_linker_t);
+
+/// A builder for an instance within a `wasmtime_component_linker_t`.
+///
+/// This mutably borrows the linker it was created from, so the linker must not
+/// be used again until this has been deleted.
+#[repr(C)]
+pub struct wasmtime_component_linker_instance_t<'a> {
+    instance: LinkerInstance<'a, crate::StoreData>,
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_linker_instance_delete(
+    _: Box<wasmtime_component_linker_instance_t<'_>>,
+) {
+}
+
+pub type wasmtime_component_func_callback_t = extern "C" fn(
+    *mut c_void,
+    CStoreContextMut<'_>,
+    *const wasmtime_component_val_t,
+    usize,
+    *mut wasmtime_component_val_t,
+    usize,
+) -> Option<Box<wasmtime_error_t>>;
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_linker_new(
+    engine: &wasm_engine_t,
+) -> Box<wasmtime_component_linker_t> {
+    Box::new(wasmtime_component_linker_t {
+        linker: Linker::new(&engine.engine),
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_linker_allow_shadowing(
+    linker: &mut wasmtime_component_linker_t,
+    allow_shadowing: bool,
+) {
+    linker.linker.allow_shadowing(allow_shadowing);
+}
+
+macro_rules! to_str {
+    ($ptr:expr, $len:expr) => {
+        match str::from_utf8(crate::slice_from_raw_parts($ptr, $len)) {
+            Ok(s) => s,
+            Err(_) => return bad_utf8(),
+        }
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_linker_root(
+    linker: &mut wasmtime_component_linker_t,
+) -> Box<wasmtime_component_linker_instance_t<'_>> {
+    Box::new(wasmtime_component_linker_instance_t {
+        instance: linker.linker.root(),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_linker_instance_add_instance<'a>(
+    instance: &'a mut wasmtime_component_linker_instance_t<'_>,
+    name: *const u8,
+    name_len: usize,
+    out: &mut *mut wasmtime_component_linker_instance_t<'a>,
+) -> Option<Box<wasmtime_error_t>> {
+    let name = to_str!(name, name_len);
+    handle_result(instance.instance.instance(name), |instance| {
+        *out = Box::into_raw(Box::new(wasmtime_component_linker_instance_t { instance }));
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_linker_instance_define_func(
+    instance: &mut wasmtime_component_linker_instance_t<'_>,
+    component: &wasmtime_component_t,
+    name: *const u8,
+    name_len: usize,
+    callback: wasmtime_component_func_callback_t,
+    data: *mut c_void,
+    finalizer: Option<extern "C" fn(*mut c_void)>,
+) -> Option<Box<wasmtime_error_t>> {
+    let name = to_str!(name, name_len);
+    let cb = c_callback_to_rust_fn(callback, data, finalizer);
+    handle_result(
+        instance
+            .instance
+            .func_new_with_result_types(&component.component, name, cb),
+        |()| (),
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_linker_instance_define_module(
+    instance: &mut wasmtime_component_linker_instance_t<'_>,
+    name: *const u8,
+    name_len: usize,
+    module: &wasmtime_module_t,
+) -> Option<Box<wasmtime_error_t>> {
+    let name = to_str!(name, name_len);
+    handle_result(instance.instance.module(name, &module.module), |()| ())
+}
+
+#[cfg(feature = "wasi")]
+#[no_mangle]
+pub extern "C" fn wasmtime_component_linker_define_wasi(
+    linker: &mut wasmtime_component_linker_t,
+) -> Option<Box<wasmtime_error_t>> {
+    handle_result(
+        wasmtime_wasi::preview2::command::sync::add_to_linker(&mut linker.linker),
+        |()| (),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_linker_instantiate(
+    linker: &wasmtime_component_linker_t,
+    store: CStoreContextMut<'_>,
+    component: &wasmtime_component_t,
+    instance: &mut Instance,
+) -> Option<Box<wasmtime_error_t>> {
+    let result = linker.linker.instantiate(store, &component.component);
+    handle_result(result, |i| *instance = i)
+}
+
+unsafe fn c_callback_to_rust_fn(
+    callback: wasmtime_component_func_callback_t,
+    data: *mut c_void,
+    finalizer: Option<extern "C" fn(*mut c_void)>,
+) -> impl Fn(CStoreContextMut<'_>, &[Val], &[Type], &mut [Val]) -> Result<()> {
+    let foreign = ForeignData { data, finalizer };
+    move |store, params, result_tys, results| {
+        let _ = &foreign; // move entire foreign into this closure
+
+        let params = params
+            .iter()
+            .map(wasmtime_component_val_t::from_val)
+            .collect::<Result<Vec<_>>>()?;
+        let mut out_results = (0..results.len())
+            .map(|_| wasmtime_component_val_t::default())
+            .collect::<Vec<_>>();
+
+        let out = callback(
+            foreign.data,
+            store,
+            params.as_ptr(),
+            params.len(),
+            out_results.as_mut_ptr(),
+            out_results.len(),
+        );
+        if let Some(err) = out {
+            return Err((*err).into());
+        }
+
+        for ((result, ty), out) in results.iter_mut().zip(result_tys).zip(&out_results) {
+            *result = out.to_val(ty)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+//! Bindings for `wasmtime::component`, the component model embedding API.
+
+mod component;
+mod func;
+mod instance;
+mod linker;
+mod val;
+
+pub use self::component::*;
+pub use self::func::*;
+pub use self::instance::*;
+pub use self::linker::*;
+pub use self::val::*;
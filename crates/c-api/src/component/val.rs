@@ -0,0 +1,320 @@
+use crate::{declare_vecs, wasm_name_t};
+use anyhow::{anyhow, bail, Result};
+use std::mem;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::slice;
+use std::str;
+use wasmtime::component::{Type, Val};
+
+#[repr(C, u8)]
+#[derive(Clone)]
+pub enum wasmtime_component_val_t {
+    Bool(bool),
+    S8(i8),
+    U8(u8),
+    S16(i16),
+    U16(u16),
+    S32(i32),
+    U32(u32),
+    S64(i64),
+    U64(u64),
+    Float32(f32),
+    Float64(f64),
+    Char(u32),
+    String(wasm_name_t),
+    List(wasmtime_component_val_vec_t),
+    Record(wasmtime_component_val_record_t),
+    Tuple(wasmtime_component_val_vec_t),
+    Variant(wasmtime_component_val_variant_t),
+    Enum(wasm_name_t),
+    Union(wasmtime_component_val_union_t),
+    Option(Option<Box<wasmtime_component_val_t>>),
+    Result(wasmtime_component_val_result_t),
+    Flags(wasmtime_component_val_flags_t),
+}
+
+#[repr(C)]
+#[derive(Clone, Default)]
+pub struct wasmtime_component_val_record_field_t {
+    pub name: wasm_name_t,
+    pub val: wasmtime_component_val_t,
+}
+
+#[repr(C)]
+#[derive(Clone)]
+pub struct wasmtime_component_val_variant_t {
+    pub discriminant: wasm_name_t,
+    pub val: Option<Box<wasmtime_component_val_t>>,
+}
+
+#[repr(C)]
+#[derive(Clone)]
+pub struct wasmtime_component_val_union_t {
+    pub discriminant: u32,
+    pub val: Box<wasmtime_component_val_t>,
+}
+
+#[repr(C)]
+#[derive(Clone)]
+pub struct wasmtime_component_val_result_t {
+    pub is_ok: bool,
+    pub val: Option<Box<wasmtime_component_val_t>>,
+}
+
+declare_vecs! {
+    (
+        name: wasmtime_component_val_vec_t,
+        ty: wasmtime_component_val_t,
+        new: wasmtime_component_val_vec_new,
+        empty: wasmtime_component_val_vec_new_empty,
+        uninit: wasmtime_component_val_vec_new_uninitialized,
+        copy: wasmtime_component_val_vec_copy,
+        delete: wasmtime_component_val_vec_delete,
+    )
+    (
+        name: wasmtime_component_val_record_t,
+        ty: wasmtime_component_val_record_field_t,
+        new: wasmtime_component_val_record_new,
+        empty: wasmtime_component_val_record_new_empty,
+        uninit: wasmtime_component_val_record_new_uninitialized,
+        copy: wasmtime_component_val_record_copy,
+        delete: wasmtime_component_val_record_delete,
+    )
+    (
+        name: wasmtime_component_val_flags_t,
+        ty: wasm_name_t,
+        new: wasmtime_component_val_flags_new,
+        empty: wasmtime_component_val_flags_new_empty,
+        uninit: wasmtime_component_val_flags_new_uninitialized,
+        copy: wasmtime_component_val_flags_copy,
+        delete: wasmtime_component_val_flags_delete,
+    )
+}
+
+impl Default for wasmtime_component_val_t {
+    fn default() -> Self {
+        wasmtime_component_val_t::Bool(false)
+    }
+}
+
+fn to_str(name: &wasm_name_t) -> Result<&str> {
+    str::from_utf8(name.as_slice()).map_err(|_| anyhow!("input was not valid utf-8"))
+}
+
+fn from_str(s: &str) -> wasm_name_t {
+    wasm_name_t::from_name(s.to_string())
+}
+
+impl wasmtime_component_val_t {
+    /// Converts a Rust component value into its C representation.
+    pub(crate) fn from_val(val: &Val) -> Result<wasmtime_component_val_t> {
+        use wasmtime_component_val_t as C;
+        Ok(match val {
+            Val::Bool(b) => C::Bool(*b),
+            Val::S8(v) => C::S8(*v),
+            Val::U8(v) => C::U8(*v),
+            Val::S16(v) => C::S16(*v),
+            Val::U16(v) => C::U16(*v),
+            Val::S32(v) => C::S32(*v),
+            Val::U32(v) => C::U32(*v),
+            Val::S64(v) => C::S64(*v),
+            Val::U64(v) => C::U64(*v),
+            Val::Float32(v) => C::Float32(*v),
+            Val::Float64(v) => C::Float64(*v),
+            Val::Char(c) => C::Char(u32::from(*c)),
+            Val::String(s) => C::String(from_str(s)),
+            Val::List(list) => C::List(
+                list.iter()
+                    .map(C::from_val)
+                    .collect::<Result<Vec<_>>>()?
+                    .into(),
+            ),
+            Val::Record(record) => C::Record(
+                record
+                    .fields()
+                    .map(|(name, val)| {
+                        Ok(wasmtime_component_val_record_field_t {
+                            name: from_str(name),
+                            val: C::from_val(val)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?
+                    .into(),
+            ),
+            Val::Tuple(tuple) => C::Tuple(
+                tuple
+                    .values()
+                    .iter()
+                    .map(C::from_val)
+                    .collect::<Result<Vec<_>>>()?
+                    .into(),
+            ),
+            Val::Variant(variant) => C::Variant(wasmtime_component_val_variant_t {
+                discriminant: from_str(variant.discriminant()),
+                val: variant.payload().map(C::from_boxed).transpose()?,
+            }),
+            Val::Enum(e) => C::Enum(from_str(e.discriminant())),
+            Val::Union(u) => C::Union(wasmtime_component_val_union_t {
+                discriminant: u.discriminant(),
+                val: C::from_boxed(u.payload())?,
+            }),
+            Val::Option(o) => C::Option(o.value().map(C::from_boxed).transpose()?),
+            Val::Result(r) => {
+                let (is_ok, val) = match r.value() {
+                    Ok(val) => (true, val),
+                    Err(val) => (false, val),
+                };
+                C::Result(wasmtime_component_val_result_t {
+                    is_ok,
+                    val: val.map(C::from_boxed).transpose()?,
+                })
+            }
+            Val::Flags(flags) => C::Flags(flags.flags().map(from_str).collect::<Vec<_>>().into()),
+            Val::Resource(_) => bail!("resources are not supported in the C API"),
+        })
+    }
+
+    fn from_boxed(val: &Val) -> Result<Box<wasmtime_component_val_t>> {
+        Ok(Box::new(wasmtime_component_val_t::from_val(val)?))
+    }
+
+    /// Converts this C value into a Rust component value of type `ty`,
+    /// returning an error if the two don't match.
+    pub(crate) fn to_val(&self, ty: &Type) -> Result<Val> {
+        use wasmtime_component_val_t as C;
+        Ok(match (self, ty) {
+            (C::Bool(b), Type::Bool) => Val::Bool(*b),
+            (C::S8(v), Type::S8) => Val::S8(*v),
+            (C::U8(v), Type::U8) => Val::U8(*v),
+            (C::S16(v), Type::S16) => Val::S16(*v),
+            (C::U16(v), Type::U16) => Val::U16(*v),
+            (C::S32(v), Type::S32) => Val::S32(*v),
+            (C::U32(v), Type::U32) => Val::U32(*v),
+            (C::S64(v), Type::S64) => Val::S64(*v),
+            (C::U64(v), Type::U64) => Val::U64(*v),
+            (C::Float32(v), Type::Float32) => Val::Float32(*v),
+            (C::Float64(v), Type::Float64) => Val::Float64(*v),
+            (C::Char(c), Type::Char) => {
+                Val::Char(char::from_u32(*c).ok_or_else(|| anyhow!("invalid char {c:#x}"))?)
+            }
+            (C::String(s), Type::String) => Val::String(to_str(s)?.into()),
+            (C::List(vals), Type::List(ty)) => {
+                let elem = ty.ty();
+                ty.new_val(
+                    vals.as_slice()
+                        .iter()
+                        .map(|v| v.to_val(&elem))
+                        .collect::<Result<_>>()?,
+                )?
+            }
+            (C::Record(fields), Type::Record(ty)) => {
+                let fields = fields.as_slice();
+                if fields.len() != ty.fields().len() {
+                    bail!(
+                        "expected {} record fields, got {}",
+                        ty.fields().len(),
+                        fields.len()
+                    );
+                }
+                let vals = ty
+                    .fields()
+                    .zip(fields)
+                    .map(|(field_ty, field)| {
+                        let name = to_str(&field.name)?;
+                        if name != field_ty.name {
+                            bail!("expected record field `{}`, got `{name}`", field_ty.name);
+                        }
+                        Ok((field_ty.name, field.val.to_val(&field_ty.ty)?))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                ty.new_val(vals)?
+            }
+            (C::Tuple(vals), Type::Tuple(ty)) => {
+                let vals = vals.as_slice();
+                if vals.len() != ty.types().len() {
+                    bail!(
+                        "expected {} tuple elements, got {}",
+                        ty.types().len(),
+                        vals.len()
+                    );
+                }
+                ty.new_val(
+                    ty.types()
+                        .zip(vals)
+                        .map(|(ty, v)| v.to_val(&ty))
+                        .collect::<Result<_>>()?,
+                )?
+            }
+            (C::Variant(variant), Type::Variant(ty)) => {
+                let name = to_str(&variant.discriminant)?;
+                let case = ty
+                    .cases()
+                    .find(|case| case.name == name)
+                    .ok_or_else(|| anyhow!("unknown variant case `{name}`"))?;
+                let payload = match (&variant.val, &case.ty) {
+                    (Some(val), Some(ty)) => Some(val.to_val(ty)?),
+                    (None, None) => None,
+                    (Some(_), None) => bail!("variant case `{name}` has no payload"),
+                    (None, Some(_)) => bail!("variant case `{name}` requires a payload"),
+                };
+                ty.new_val(name, payload)?
+            }
+            (C::Enum(name), Type::Enum(ty)) => ty.new_val(to_str(name)?)?,
+            (C::Union(u), Type::Union(ty)) => {
+                let case = ty
+                    .types()
+                    .nth(u.discriminant as usize)
+                    .ok_or_else(|| anyhow!("invalid union discriminant {}", u.discriminant))?;
+                ty.new_val(u.discriminant, u.val.to_val(&case)?)?
+            }
+            (C::Option(val), Type::Option(ty)) => ty.new_val(match val {
+                Some(val) => Some(val.to_val(&ty.ty())?),
+                None => None,
+            })?,
+            (C::Result(r), Type::Result(ty)) => {
+                let payload_ty = if r.is_ok { ty.ok() } else { ty.err() };
+                let payload = match (&r.val, &payload_ty) {
+                    (Some(val), Some(ty)) => Some(val.to_val(ty)?),
+                    (None, None) => None,
+                    (Some(_), None) => bail!("result case has no payload"),
+                    (None, Some(_)) => bail!("result case requires a payload"),
+                };
+                ty.new_val(if r.is_ok { Ok(payload) } else { Err(payload) })?
+            }
+            (C::Flags(names), Type::Flags(ty)) => {
+                let names = names
+                    .as_slice()
+                    .iter()
+                    .map(to_str)
+                    .collect::<Result<Vec<_>>>()?;
+                ty.new_val(&names)?
+            }
+            (_, Type::Own(_) | Type::Borrow(_)) => {
+                bail!("resources are not supported in the C API")
+            }
+            (_, ty) => bail!("value does not match expected type {ty:?}"),
+        })
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_val_new() -> Box<wasmtime_component_val_t> {
+    Box::new(wasmtime_component_val_t::default())
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_val_free(_val: Box<wasmtime_component_val_t>) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_val_delete(val: &mut wasmtime_component_val_t) {
+    *val = wasmtime_component_val_t::default();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_val_copy(
+    dst: &mut MaybeUninit<wasmtime_component_val_t>,
+    src: &wasmtime_component_val_t,
+) {
+    crate::initialize(dst, src.clone());
+}
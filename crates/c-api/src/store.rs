@@ -73,6 +73,13 @@ pub struct StoreData {
     #[cfg(feature = "wasi")]
     pub(crate) wasi: Option<wasmtime_wasi::WasiCtx>,
 
+    /// WASI context and resource table used by components linked with
+    /// `wasmtime_component_linker_define_wasi`.
+    #[cfg(all(feature = "wasi", feature = "component-model"))]
+    pub(crate) wasi_preview2: Option<wasmtime_wasi::preview2::WasiCtx>,
+    #[cfg(all(feature = "wasi", feature = "component-model"))]
+    pub(crate) wasi_preview2_table: wasmtime_wasi::preview2::Table,
+
     /// Temporary storage for usage during a wasm->host call to store values
     /// in a slice we pass to the C API.
     pub hostcall_val_storage: Vec<wasmtime_val_t>,
@@ -98,6 +105,10 @@ pub extern "C" fn wasmtime_store_new(
                 foreign: ForeignData { data, finalizer },
                 #[cfg(feature = "wasi")]
                 wasi: None,
+                #[cfg(all(feature = "wasi", feature = "component-model"))]
+                wasi_preview2: None,
+                #[cfg(all(feature = "wasi", feature = "component-model"))]
+                wasi_preview2_table: wasmtime_wasi::preview2::Table::new(),
                 hostcall_val_storage: Vec::new(),
                 wasm_val_storage: Vec::new(),
                 store_limits: StoreLimits::default(),
@@ -203,6 +214,44 @@ pub extern "C" fn wasmtime_context_set_wasi(
     })
 }
 
+#[cfg(all(feature = "wasi", feature = "component-model"))]
+#[no_mangle]
+pub extern "C" fn wasmtime_context_set_wasi_preview2(
+    mut context: CStoreContextMut<'_>,
+    wasi: Box<crate::wasi_config_t>,
+) -> Option<Box<wasmtime_error_t>> {
+    let data = context.data_mut();
+    crate::handle_result(
+        wasi.into_preview2_ctx(&mut data.wasi_preview2_table),
+        |wasi| {
+            data.wasi_preview2 = Some(wasi);
+        },
+    )
+}
+
+#[cfg(all(feature = "wasi", feature = "component-model"))]
+impl wasmtime_wasi::preview2::WasiView for StoreData {
+    fn table(&self) -> &wasmtime_wasi::preview2::Table {
+        &self.wasi_preview2_table
+    }
+
+    fn table_mut(&mut self) -> &mut wasmtime_wasi::preview2::Table {
+        &mut self.wasi_preview2_table
+    }
+
+    fn ctx(&self) -> &wasmtime_wasi::preview2::WasiCtx {
+        self.wasi_preview2.as_ref().expect(
+            "failed to use WASI in a component; did you set a WASI configuration in the store?",
+        )
+    }
+
+    fn ctx_mut(&mut self) -> &mut wasmtime_wasi::preview2::WasiCtx {
+        self.wasi_preview2.as_mut().expect(
+            "failed to use WASI in a component; did you set a WASI configuration in the store?",
+        )
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn wasmtime_context_gc(mut context: CStoreContextMut<'_>) {
     context.gc();
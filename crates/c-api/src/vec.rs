@@ -10,6 +10,12 @@ use std::slice;
 
 pub type wasm_name_t = wasm_byte_vec_t;
 
+impl Default for wasm_byte_vec_t {
+    fn default() -> Self {
+        Vec::new().into()
+    }
+}
+
 impl wasm_name_t {
     pub(crate) fn from_name(name: String) -> wasm_name_t {
         name.into_bytes().into()
@@ -139,6 +145,8 @@ macro_rules! declare_vecs {
     )*};
 }
 
+pub(crate) use declare_vecs;
+
 declare_vecs! {
     (
         name: wasm_byte_vec_t,
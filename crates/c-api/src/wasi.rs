@@ -2,6 +2,8 @@
 
 use crate::wasm_byte_vec_t;
 use anyhow::Result;
+#[cfg(feature = "component-model")]
+use anyhow::{anyhow, bail};
 use cap_std::ambient_authority;
 use std::collections::HashMap;
 use std::ffi::CStr;
@@ -10,6 +12,8 @@ use std::os::raw::{c_char, c_int};
 use std::path::{Path, PathBuf};
 use std::slice;
 use wasi_common::pipe::ReadPipe;
+#[cfg(feature = "component-model")]
+use wasmtime_wasi::preview2;
 use wasmtime_wasi::{
     sync::{Dir, TcpListener, WasiCtxBuilder},
     WasiCtx,
@@ -138,6 +142,73 @@ impl wasi_config_t {
         }
         Ok(builder.build())
     }
+
+    /// Same as `into_wasi_ctx` but creates a context suitable for components
+    /// using WASI preview2, inserting its resources into `table`.
+    #[cfg(feature = "component-model")]
+    pub fn into_preview2_ctx(self, table: &mut preview2::Table) -> Result<preview2::WasiCtx> {
+        let mut builder = preview2::WasiCtxBuilder::new();
+        if self.inherit_args {
+            builder.args(&std::env::args().collect::<Vec<_>>());
+        } else {
+            for arg in self.args {
+                builder.arg(String::from_utf8(arg)?);
+            }
+        }
+        if self.inherit_env {
+            builder.envs(&std::env::vars().collect::<Vec<_>>());
+        } else {
+            for (k, v) in self.env {
+                builder.env(String::from_utf8(k)?, String::from_utf8(v)?);
+            }
+        }
+        match self.stdin {
+            WasiConfigReadPipe::None => {}
+            WasiConfigReadPipe::Inherit => {
+                builder.inherit_stdin();
+            }
+            WasiConfigReadPipe::File(_) => {
+                bail!("file-backed stdin is not supported for components")
+            }
+            WasiConfigReadPipe::Bytes(binary) => {
+                builder.stdin(preview2::pipe::MemoryInputPipe::new(binary.into()));
+            }
+        };
+        match self.stdout {
+            WasiConfigWritePipe::None => {}
+            WasiConfigWritePipe::Inherit => {
+                builder.inherit_stdout();
+            }
+            WasiConfigWritePipe::File(_) => {
+                bail!("file-backed stdout is not supported for components")
+            }
+        };
+        match self.stderr {
+            WasiConfigWritePipe::None => {}
+            WasiConfigWritePipe::Inherit => {
+                builder.inherit_stderr();
+            }
+            WasiConfigWritePipe::File(_) => {
+                bail!("file-backed stderr is not supported for components")
+            }
+        };
+        for (dir, path) in self.preopen_dirs {
+            let path = path
+                .to_str()
+                .ok_or_else(|| anyhow!("preopen path is not valid utf-8"))?
+                .to_owned();
+            builder.preopened_dir(
+                dir,
+                preview2::DirPerms::all(),
+                preview2::FilePerms::all(),
+                path,
+            );
+        }
+        if !self.preopen_sockets.is_empty() {
+            bail!("preopened sockets are not supported for components");
+        }
+        builder.build(table)
+    }
 }
 
 #[no_mangle]
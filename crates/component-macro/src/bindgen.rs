@@ -1,10 +1,11 @@
 use proc_macro2::{Span, TokenStream};
-use std::collections::HashMap;
+use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use syn::parse::{Error, Parse, ParseStream, Result};
 use syn::punctuated::Punctuated;
-use syn::{braced, token, Ident, Token};
-use wasmtime_wit_bindgen::{Opts, Ownership, TrappableError};
+use syn::{braced, bracketed, token, Ident, Token};
+use wasmtime_wit_bindgen::{AsyncConfig, AsyncFunctions, Opts, Ownership, TrappableError};
 use wit_parser::{PackageId, Resolve, UnresolvedPackage, WorldId};
 
 pub struct Config {
@@ -15,7 +16,7 @@ pub struct Config {
 }
 
 pub fn expand(input: &Config) -> Result<TokenStream> {
-    if !cfg!(feature = "async") && input.opts.async_ {
+    if !cfg!(feature = "async") && input.opts.async_.maybe_async() {
         return Err(Error::new(
             Span::call_site(),
             "cannot enable async bindings unless `async` crate feature is active",
@@ -100,6 +101,12 @@ impl Parse for Config {
                         opts.only_interfaces = true;
                     }
                     Opt::With(val) => opts.with.extend(val),
+                    Opt::AdditionalDerives(paths) => {
+                        opts.additional_derive_attributes = paths
+                            .into_iter()
+                            .map(|p| p.into_token_stream().to_string())
+                            .collect()
+                    }
                 }
             }
         } else {
@@ -171,6 +178,9 @@ mod kw {
     syn::custom_keyword!(ownership);
     syn::custom_keyword!(interfaces);
     syn::custom_keyword!(with);
+    syn::custom_keyword!(additional_derives);
+    syn::custom_keyword!(only_imports);
+    syn::custom_keyword!(except_imports);
 }
 
 enum Opt {
@@ -178,11 +188,12 @@ enum Opt {
     Path(syn::LitStr),
     Inline(syn::LitStr),
     Tracing(bool),
-    Async(bool),
+    Async(AsyncConfig),
     TrappableErrorType(Vec<TrappableError>),
     Ownership(Ownership),
     Interfaces(syn::LitStr),
     With(HashMap<String, String>),
+    AdditionalDerives(Vec<syn::Path>),
 }
 
 impl Parse for Opt {
@@ -207,7 +218,7 @@ impl Parse for Opt {
         } else if l.peek(Token![async]) {
             input.parse::<Token![async]>()?;
             input.parse::<Token![:]>()?;
-            Ok(Opt::Async(input.parse::<syn::LitBool>()?.value))
+            Ok(Opt::Async(parse_async_config(input)?))
         } else if l.peek(kw::ownership) {
             input.parse::<kw::ownership>()?;
             input.parse::<Token![:]>()?;
@@ -266,12 +277,62 @@ impl Parse for Opt {
             let fields: Punctuated<(String, String), Token![,]> =
                 contents.parse_terminated(with_field_parse, Token![,])?;
             Ok(Opt::With(HashMap::from_iter(fields.into_iter())))
+        } else if l.peek(kw::additional_derives) {
+            input.parse::<kw::additional_derives>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbracket = bracketed!(contents in input);
+            let paths: Punctuated<syn::Path, Token![,]> =
+                contents.parse_terminated(syn::Path::parse, Token![,])?;
+            Ok(Opt::AdditionalDerives(paths.into_iter().collect()))
         } else {
             Err(l.error())
         }
     }
 }
 
+fn parse_async_config(input: ParseStream<'_>) -> Result<AsyncConfig> {
+    if input.peek(syn::LitBool) {
+        return Ok(if input.parse::<syn::LitBool>()?.value {
+            AsyncConfig::all()
+        } else {
+            AsyncConfig::default()
+        });
+    }
+
+    let contents;
+    let _lbrace = braced!(contents in input);
+    let l = contents.lookahead1();
+    let only = if l.peek(kw::only_imports) {
+        contents.parse::<kw::only_imports>()?;
+        true
+    } else if l.peek(kw::except_imports) {
+        contents.parse::<kw::except_imports>()?;
+        false
+    } else {
+        return Err(l.error());
+    };
+    contents.parse::<Token![:]>()?;
+    let list;
+    let _lbracket = bracketed!(list in contents);
+    let names: Punctuated<syn::LitStr, Token![,]> =
+        list.parse_terminated(Parse::parse, Token![,])?;
+    let names = names.iter().map(|s| s.value()).collect::<HashSet<_>>();
+    if contents.peek(Token![,]) {
+        contents.parse::<Token![,]>()?;
+    }
+    if !contents.is_empty() {
+        return Err(contents.error("expected a single set of async imports"));
+    }
+    Ok(AsyncConfig {
+        imports: if only {
+            AsyncFunctions::Only(names)
+        } else {
+            AsyncFunctions::AllExcept(names)
+        },
+    })
+}
+
 fn trappable_error_field_parse(input: ParseStream<'_>) -> Result<TrappableError> {
     // Accept a Rust identifier or a string literal. This is required
     // because not all wit identifiers are Rust identifiers, so we can
@@ -10,6 +10,14 @@ macro_rules! gentest {
                     async: true,
                 });
             }
+            mod async_selective {
+                wasmtime::component::bindgen!({
+                    path: $path,
+                    async: {
+                        except_imports: ["foo"],
+                    },
+                });
+            }
             mod tracing {
                 wasmtime::component::bindgen!({
                     path: $path,
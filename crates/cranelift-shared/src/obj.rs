@@ -13,9 +13,9 @@
 //! function body, the imported wasm function do not. The trampolines symbol
 //! names have format "_trampoline_N", where N is `SignatureIndex`.
 
-use crate::{CompiledFuncEnv, CompiledFunction, RelocationTarget};
+use crate::{CompiledFuncEnv, CompiledFunction, Relocation, RelocationTarget};
 use anyhow::Result;
-use cranelift_codegen::binemit::Reloc;
+use cranelift_codegen::binemit::{Addend, Reloc};
 use cranelift_codegen::ir::LibCall;
 use cranelift_codegen::isa::unwind::{systemv, UnwindInfo};
 use cranelift_codegen::TextSectionBuilder;
@@ -26,6 +26,7 @@ use object::write::{Object, SectionId, StandardSegment, Symbol, SymbolId, Symbol
 use object::{Architecture, SectionKind, SymbolFlags, SymbolKind, SymbolScope};
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::mem;
 use std::ops::Range;
 use wasmtime_environ::{Compiler, FuncIndex};
 
@@ -61,9 +62,31 @@ pub struct ModuleTextBuilder<'a> {
     /// builds without SIMD on x86_64 right now.
     libcall_symbols: HashMap<LibCall, SymbolId>,
 
+    /// Cold parts of the functions appended so far, which are placed after
+    /// all hot parts by `append_cold_parts`.
+    cold_parts: Vec<Option<ColdPart<'a>>>,
+
     ctrl_plane: ControlPlane,
 }
 
+/// The cold part of a function split into hot and cold parts, waiting to be
+/// appended to the text section.
+struct ColdPart<'a> {
+    body: &'a [u8],
+    alignment: u32,
+    /// The offset of the cold part within the function's compiled code.
+    cold_start: u32,
+    /// Relocations within the cold part, with offsets relative to its start
+    /// and the targets of calls already resolved.
+    relocs: Vec<(Relocation, Option<usize>)>,
+    /// Labels for references from the hot part, to bind at the given offset
+    /// within the cold part once it's placed.
+    labels: Vec<(usize, u32)>,
+    /// References to the hot part, with offsets relative to the start of the
+    /// cold part, against labels already bound in the hot part.
+    to_hot: Vec<(u32, Reloc, Addend, usize)>,
+}
+
 impl<'a> ModuleTextBuilder<'a> {
     /// Creates a new builder for the text section of an executable.
     ///
@@ -91,6 +114,7 @@ impl<'a> ModuleTextBuilder<'a> {
             unwind_info: Default::default(),
             text,
             libcall_symbols: HashMap::default(),
+            cold_parts: Vec::new(),
             ctrl_plane: ControlPlane::default(),
         }
     }
@@ -104,14 +128,17 @@ impl<'a> ModuleTextBuilder<'a> {
     /// the target will be defined by the `n`th call to `append_func`.
     ///
     /// Returns the symbol associated with the function as well as the range
-    /// that the function resides within the text section.
+    /// that the function resides within the text section. If the function was
+    /// split into hot and cold parts, this is only the range of its hot part:
+    /// its cold part is placed later on by `append_cold_parts`.
     pub fn append_func(
         &mut self,
         name: &str,
         compiled_func: &'a CompiledFunction<impl CompiledFuncEnv>,
         resolve_reloc_target: impl Fn(FuncIndex) -> usize,
     ) -> (SymbolId, Range<u64>) {
-        let body = compiled_func.buffer.data();
+        let buffer = &compiled_func.buffer;
+        let body = buffer.hot_data();
         let alignment = compiled_func.alignment;
         let body_len = body.len() as u64;
         let off = self
@@ -133,84 +160,167 @@ impl<'a> ModuleTextBuilder<'a> {
             self.unwind_info.push(off, body_len, info);
         }
 
+        let mut cold_part = buffer.cold_start().map(|cold_start| ColdPart {
+            body: buffer.cold_data(),
+            alignment,
+            cold_start,
+            relocs: Vec::new(),
+            labels: Vec::new(),
+            to_hot: Vec::new(),
+        });
+
         for r in compiled_func.relocations() {
-            match r.reloc_target {
-                // Relocations against user-defined functions means that this is
-                // a relocation against a module-local function, typically a
-                // call between functions. The `text` field is given priority to
-                // resolve this relocation before we actually emit an object
-                // file, but if it can't handle it then we pass through the
-                // relocation.
-                RelocationTarget::UserFunc(index) => {
-                    let target = resolve_reloc_target(index);
-                    if self
-                        .text
-                        .resolve_reloc(off + u64::from(r.offset), r.reloc, r.addend, target)
-                    {
-                        continue;
-                    }
-
-                    // At this time it's expected that all relocations are
-                    // handled by `text.resolve_reloc`, and anything that isn't
-                    // handled is a bug in `text.resolve_reloc` or something
-                    // transitively there. If truly necessary, though, then this
-                    // loop could also be updated to forward the relocation to
-                    // the final object file as well.
-                    panic!(
-                        "unresolved relocation could not be processed against \
-                         {index:?}: {r:?}"
-                    );
+            let target = match r.reloc_target {
+                RelocationTarget::UserFunc(index) => Some(resolve_reloc_target(index)),
+                RelocationTarget::LibCall(_) => None,
+            };
+            match &mut cold_part {
+                Some(cold) if r.offset >= cold.cold_start => {
+                    let offset = r.offset - cold.cold_start;
+                    cold.relocs.push((Relocation { offset, ..r }, target));
                 }
+                _ => self.append_reloc(off, &r, target),
+            }
+        }
 
-                // Relocations against libcalls are not common at this time and
-                // are only used in non-default configurations that disable wasm
-                // SIMD, disable SSE features, and for wasm modules that still
-                // use floating point operations.
-                //
-                // Currently these relocations are all expected to be absolute
-                // 8-byte relocations so that's asserted here and then encoded
-                // directly into the object as a normal object relocation. This
-                // is processed at module load time to resolve the relocations.
-                RelocationTarget::LibCall(call) => {
-                    let symbol = *self.libcall_symbols.entry(call).or_insert_with(|| {
-                        self.obj.add_symbol(Symbol {
-                            name: libcall_name(call).as_bytes().to_vec(),
-                            value: 0,
-                            size: 0,
-                            kind: SymbolKind::Text,
-                            scope: SymbolScope::Linkage,
-                            weak: false,
-                            section: SymbolSection::Undefined,
-                            flags: SymbolFlags::None,
-                        })
-                    });
-                    let (encoding, kind, size) = match r.reloc {
-                        Reloc::Abs8 => (
-                            object::RelocationEncoding::Generic,
-                            object::RelocationKind::Absolute,
-                            8,
-                        ),
-                        other => unimplemented!("unimplemented relocation kind {other:?}"),
-                    };
-                    self.obj
-                        .add_relocation(
-                            self.text_section,
-                            object::write::Relocation {
-                                symbol,
-                                size,
-                                kind,
-                                encoding,
-                                offset: off + u64::from(r.offset),
-                                addend: r.addend,
-                            },
-                        )
-                        .unwrap();
+        // References between the hot and cold parts of the function go
+        // through labels of the text section, bound in the hot part now and
+        // in the cold part when it's placed.
+        for r in buffer.split_relocs() {
+            let cold = cold_part.as_mut().unwrap();
+            let label = self.text.new_label();
+            if r.target >= cold.cold_start {
+                let offset = off + u64::from(r.offset);
+                if !self.text.resolve_reloc(offset, r.kind, r.addend, label) {
+                    panic!("unresolved reference to the cold part: {r:?}");
                 }
-            };
+                cold.labels.push((label, r.target - cold.cold_start));
+            } else {
+                self.text.bind_label(label, off + u64::from(r.target));
+                let offset = r.offset - cold.cold_start;
+                cold.to_hot.push((offset, r.kind, r.addend, label));
+            }
         }
+        self.cold_parts.push(cold_part);
+
         (symbol_id, off..off + body_len)
     }
 
+    /// Appends the cold parts of all functions appended so far with
+    /// `append_func`, after all of their hot parts.
+    ///
+    /// Returns the range of the cold part of each function within the text
+    /// section, in the order the functions were appended, or `None` for
+    /// functions which weren't split.
+    pub fn append_cold_parts(&mut self) -> Vec<Option<Range<u64>>> {
+        let mut ranges = Vec::new();
+        for cold in mem::take(&mut self.cold_parts) {
+            let cold = match cold {
+                Some(cold) => cold,
+                None => {
+                    ranges.push(None);
+                    continue;
+                }
+            };
+            let off = self
+                .text
+                .append(false, cold.body, cold.alignment, &mut self.ctrl_plane);
+            for (label, offset) in cold.labels {
+                self.text.bind_label(label, off + u64::from(offset));
+            }
+            for (offset, kind, addend, label) in cold.to_hot {
+                let offset = off + u64::from(offset);
+                if !self.text.resolve_reloc(offset, kind, addend, label) {
+                    panic!("unresolved reference to the hot part: {kind:?}");
+                }
+            }
+            for (r, target) in cold.relocs {
+                self.append_reloc(off, &r, target);
+            }
+            ranges.push(Some(off..off + cold.body.len() as u64));
+        }
+        ranges
+    }
+
+    /// Handles the relocation `r` of code placed at `off` in the text
+    /// section, where `target` is the resolved index of the function it
+    /// refers to, if any.
+    fn append_reloc(&mut self, off: u64, r: &Relocation, target: Option<usize>) {
+        match r.reloc_target {
+            // Relocations against user-defined functions means that this is
+            // a relocation against a module-local function, typically a
+            // call between functions. The `text` field is given priority to
+            // resolve this relocation before we actually emit an object
+            // file, but if it can't handle it then we pass through the
+            // relocation.
+            RelocationTarget::UserFunc(index) => {
+                let target = target.unwrap();
+                if self
+                    .text
+                    .resolve_reloc(off + u64::from(r.offset), r.reloc, r.addend, target)
+                {
+                    return;
+                }
+
+                // At this time it's expected that all relocations are
+                // handled by `text.resolve_reloc`, and anything that isn't
+                // handled is a bug in `text.resolve_reloc` or something
+                // transitively there. If truly necessary, though, then this
+                // function could also be updated to forward the relocation
+                // to the final object file as well.
+                panic!(
+                    "unresolved relocation could not be processed against \
+                     {index:?}: {r:?}"
+                );
+            }
+
+            // Relocations against libcalls are not common at this time and
+            // are only used in non-default configurations that disable wasm
+            // SIMD, disable SSE features, and for wasm modules that still
+            // use floating point operations.
+            //
+            // Currently these relocations are all expected to be absolute
+            // 8-byte relocations so that's asserted here and then encoded
+            // directly into the object as a normal object relocation. This
+            // is processed at module load time to resolve the relocations.
+            RelocationTarget::LibCall(call) => {
+                let symbol = *self.libcall_symbols.entry(call).or_insert_with(|| {
+                    self.obj.add_symbol(Symbol {
+                        name: libcall_name(call).as_bytes().to_vec(),
+                        value: 0,
+                        size: 0,
+                        kind: SymbolKind::Text,
+                        scope: SymbolScope::Linkage,
+                        weak: false,
+                        section: SymbolSection::Undefined,
+                        flags: SymbolFlags::None,
+                    })
+                });
+                let (encoding, kind, size) = match r.reloc {
+                    Reloc::Abs8 => (
+                        object::RelocationEncoding::Generic,
+                        object::RelocationKind::Absolute,
+                        8,
+                    ),
+                    other => unimplemented!("unimplemented relocation kind {other:?}"),
+                };
+                self.obj
+                    .add_relocation(
+                        self.text_section,
+                        object::write::Relocation {
+                            symbol,
+                            size,
+                            kind,
+                            encoding,
+                            offset: off + u64::from(r.offset),
+                            addend: r.addend,
+                        },
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
     /// Forces "veneers" to be used for inter-function calls in the text
     /// section which means that in-bounds optimized addresses are never used.
     ///
@@ -238,6 +348,11 @@ impl<'a> ModuleTextBuilder<'a> {
     /// Note that this will also write out the unwind information sections if
     /// necessary.
     pub fn finish(mut self) {
+        // Place any cold parts which haven't been placed yet.
+        if self.cold_parts.iter().any(|c| c.is_some()) {
+            self.append_cold_parts();
+        }
+
         // Finish up the text section now that we're done adding functions.
         let text = self.text.finish(&mut self.ctrl_plane);
         self.obj
@@ -3,6 +3,7 @@ use crate::func_environ::FuncEnvironment;
 use crate::{array_call_signature, native_call_signature, DEBUG_ASSERT_TRAP_CODE};
 use crate::{builder::LinkOptions, value_type, wasm_call_signature};
 use anyhow::{Context as _, Result};
+use cranelift_codegen::inline::{Inline, InlineBudget, InlineCommand, InlinedCall};
 use cranelift_codegen::ir::{
     self, InstBuilder, MemFlags, UserExternalName, UserExternalNameRef, UserFuncName, Value,
 };
@@ -19,6 +20,7 @@ use cranelift_wasm::{
 use object::write::{Object, StandardSegment, SymbolId};
 use object::{RelocationEncoding, RelocationKind, SectionKind};
 use std::any::Any;
+use std::borrow::Cow;
 use std::cmp;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
@@ -26,12 +28,15 @@ use std::convert::TryFrom;
 use std::mem;
 use std::path;
 use std::sync::{Arc, Mutex};
-use wasmparser::{FuncValidatorAllocations, FunctionBody};
+use wasmparser::{
+    FuncToValidate, FuncValidatorAllocations, FunctionBody, ValidatorResources, WasmModuleResources,
+};
 use wasmtime_cranelift_shared::{CompiledFunction, ModuleTextBuilder};
 use wasmtime_environ::{
-    AddressMapSection, CacheStore, CompileError, FlagValue, FunctionBodyData, FunctionLoc,
-    ModuleTranslation, ModuleTypes, PtrSize, StackMapInformation, TrapEncodingBuilder, Tunables,
-    VMOffsets, WasmFunctionInfo,
+    AddressMapSection, CacheStore, CompileError, FilePos, FlagValue, FunctionBodyData, FunctionLoc,
+    InlinedFunction, InstructionAddressMap, ModuleTranslation, ModuleTypes, PtrSize,
+    StackMapInformation, TrapEncodingBuilder, TrapInformation, Tunables, VMOffsets,
+    WasmFunctionInfo,
 };
 
 #[cfg(feature = "component-model")]
@@ -211,6 +216,24 @@ impl wasmtime_environ::Compiler for Compiler {
             &mut func_env,
         )?;
 
+        // Native debuginfo describes the locals of this function only, so
+        // inlining is skipped when it's requested.
+        let mut inlined = Vec::new();
+        if self.tunables.inlining && !self.tunables.generate_native_debuginfo {
+            let mut inliner = WasmInliner {
+                compiler: self,
+                translation,
+                types,
+                resources: validator.resources(),
+                translator: FuncTranslator::new(),
+                bodies: HashMap::new(),
+            };
+            let calls = context
+                .inline(isa, &mut inliner, INLINE_BUDGET)
+                .map_err(|error| CompileError::Codegen(pretty_error(&context.func, error)))?;
+            inlined = inliner.describe(&context.func, &calls);
+        }
+
         if let Some(path) = &self.clif_dir {
             use std::io::Write;
 
@@ -222,7 +245,8 @@ impl wasmtime_environ::Compiler for Compiler {
             write!(output, "{}", context.func.display()).unwrap();
         }
 
-        let (info, func) = compiler.finish_with_info(Some((&body, &self.tunables)))?;
+        let (mut info, func) = compiler.finish_with_info(Some((&body, &self.tunables)))?;
+        info.inlined = inlined.into();
 
         let timing = cranelift_codegen::timing::take_current();
         log::debug!("{:?} translated in {:?}", func_index, timing.total());
@@ -454,22 +478,72 @@ impl wasmtime_environ::Compiler for Compiler {
         let mut traps = TrapEncodingBuilder::default();
 
         let mut ret = Vec::with_capacity(funcs.len());
+        let mut compiled_funcs = Vec::with_capacity(funcs.len());
         for (i, (sym, func)) in funcs.iter().enumerate() {
             let func = func
                 .downcast_ref::<CompiledFunction<CompiledFuncEnv>>()
                 .unwrap();
             let (sym, range) = builder.append_func(&sym, func, |idx| resolve_reloc(i, idx));
+            let cold_start = func.buffer.cold_start().unwrap_or(u32::MAX);
             if self.tunables.generate_address_map {
                 let addr = func.address_map();
-                addrs.push(range.clone(), &addr.instructions);
+                let hot = addr
+                    .instructions
+                    .iter()
+                    .take_while(|i| i.code_offset < cold_start)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                addrs.push(range.clone(), &hot);
             }
-            traps.push(range.clone(), &func.traps().collect::<Vec<_>>());
+            let hot = func
+                .traps()
+                .filter(|t| t.code_offset < cold_start)
+                .collect::<Vec<_>>();
+            traps.push(range.clone(), &hot);
             builder.append_padding(self.linkopts.padding_between_functions);
             let info = FunctionLoc {
                 start: u32::try_from(range.start).unwrap(),
                 length: u32::try_from(range.end - range.start).unwrap(),
+                cold_start: 0,
+                cold_length: 0,
             };
             ret.push((sym, info));
+            compiled_funcs.push(func);
+        }
+
+        // The cold parts of split functions are placed after all hot parts,
+        // with their traps and address maps relative to the start of the cold
+        // part.
+        let cold_ranges = builder.append_cold_parts();
+        for ((func, range), (_, info)) in compiled_funcs.iter().zip(cold_ranges).zip(&mut ret) {
+            let (cold_start, range) = match (func.buffer.cold_start(), range) {
+                (Some(cold_start), Some(range)) => (cold_start, range),
+                _ => continue,
+            };
+            if self.tunables.generate_address_map {
+                let addr = func.address_map();
+                let cold = addr
+                    .instructions
+                    .iter()
+                    .filter(|i| i.code_offset >= cold_start)
+                    .map(|i| InstructionAddressMap {
+                        srcloc: i.srcloc,
+                        code_offset: i.code_offset - cold_start,
+                    })
+                    .collect::<Vec<_>>();
+                addrs.push(range.clone(), &cold);
+            }
+            let cold = func
+                .traps()
+                .filter(|t| t.code_offset >= cold_start)
+                .map(|t| TrapInformation {
+                    code_offset: t.code_offset - cold_start,
+                    ..t
+                })
+                .collect::<Vec<_>>();
+            traps.push(range.clone(), &cold);
+            info.cold_start = u32::try_from(range.start).unwrap();
+            info.cold_length = u32::try_from(range.end - range.start).unwrap();
         }
 
         builder.finish();
@@ -501,10 +575,14 @@ impl wasmtime_environ::Compiler for Compiler {
         let wasm_to_array = FunctionLoc {
             start: u32::try_from(wasm_to_array.start).unwrap(),
             length: u32::try_from(wasm_to_array.end - wasm_to_array.start).unwrap(),
+            cold_start: 0,
+            cold_length: 0,
         };
         let native_to_array = FunctionLoc {
             start: u32::try_from(native_to_array.start).unwrap(),
             length: u32::try_from(native_to_array.end - native_to_array.start).unwrap(),
+            cold_start: 0,
+            cold_length: 0,
         };
 
         builder.finish();
@@ -1074,12 +1152,134 @@ impl FunctionCompiler<'_> {
             WasmFunctionInfo {
                 start_srcloc: compiled_function.metadata().address_map.start_srcloc,
                 stack_maps: stack_maps.into(),
+                inlined: Box::new([]),
             },
             compiled_function,
         ))
     }
 }
 
+/// How much inlining may grow a wasm function, in CLIF instructions.
+///
+/// Only small callees are worth inlining: the call overhead saved by inlining a
+/// large function is lost in its body, while every copy of it adds code size.
+const INLINE_BUDGET: InlineBudget = InlineBudget {
+    max_callee_insts: 50,
+    max_total_insts: 1000,
+};
+
+/// Provides Cranelift's inliner with the bodies of functions defined in the
+/// same module, translating them from wasm as they are asked for.
+struct WasmInliner<'a, 'data> {
+    compiler: &'a Compiler,
+    translation: &'a ModuleTranslation<'data>,
+    types: &'a ModuleTypes,
+    resources: &'a ValidatorResources,
+    translator: FuncTranslator,
+    bodies: HashMap<FuncIndex, Option<ir::Function>>,
+}
+
+impl WasmInliner<'_, '_> {
+    /// The wasm function called through `callee` in `func`, if any.
+    fn callee_index(&self, func: &ir::Function, callee: ir::FuncRef) -> Option<FuncIndex> {
+        let ir::ExternalName::User(name) = func.dfg.ext_funcs[callee].name else {
+            return None;
+        };
+        let UserExternalName { namespace, index } = func.params.user_named_funcs()[name];
+        if namespace != 0 {
+            return None;
+        }
+        Some(FuncIndex::from_u32(index))
+    }
+
+    /// Translates the body of the defined function `index`, or returns `None`
+    /// if it's not available or doesn't validate.
+    fn translate(&mut self, index: FuncIndex) -> Option<ir::Function> {
+        let module = &self.translation.module;
+        let def_index = module.defined_func_index(index)?;
+        let body = self.translation.inlinable_function_bodies.get(def_index)?;
+
+        // Don't bother translating bodies which are certainly over budget.
+        // Wasm instructions take at least a byte each, and many translate to
+        // no CLIF at all, so this is a generous bound.
+        if body.get_binary_reader().bytes_remaining() > 8 * INLINE_BUDGET.max_callee_insts {
+            return None;
+        }
+        let ty = self.resources.type_index_of_function(index.as_u32())?;
+
+        let isa = &*self.compiler.isa;
+        let tunables = &self.compiler.tunables;
+        let sig = &self.types[module.functions[index].signature];
+        let mut func = ir::Function::with_name_signature(
+            UserFuncName::User(UserExternalName {
+                namespace: 0,
+                index: index.as_u32(),
+            }),
+            wasm_call_signature(isa, sig, tunables),
+        );
+        let mut validator = FuncToValidate::new(
+            index.as_u32(),
+            ty,
+            self.resources,
+            &self.translation.wasm_features,
+        )
+        .into_validator(Default::default());
+        let mut func_env = FuncEnvironment::new(isa, self.translation, self.types, tunables);
+        self.translator
+            .translate_body(&mut validator, body.clone(), &mut func, &mut func_env)
+            .ok()?;
+        Some(func)
+    }
+
+    /// Describes the `calls` which were inlined into `func` so that frames for
+    /// the inlined functions can be reconstructed in backtraces.
+    fn describe(&self, func: &ir::Function, calls: &[InlinedCall]) -> Vec<InlinedFunction> {
+        calls
+            .iter()
+            .filter_map(|call| {
+                let index = match &call.callee {
+                    ir::ExternalName::User(name) => {
+                        FuncIndex::from_u32(func.params.user_named_funcs()[*name].index)
+                    }
+                    _ => return None,
+                };
+                let def_index = self.translation.module.defined_func_index(index)?;
+                let body =
+                    self.translation.inlinable_function_bodies[def_index].get_binary_reader();
+                let start = body.original_position() as u32;
+                let end = start + body.bytes_remaining() as u32;
+                Some(InlinedFunction {
+                    func_index: index,
+                    start_srcloc: FilePos::new(start),
+                    end_srcloc: FilePos::new(end),
+                    call_site: FilePos::new(call.srcloc.bits()),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Inline for WasmInliner<'_, '_> {
+    fn inline(
+        &mut self,
+        caller: &ir::Function,
+        _inst: ir::Inst,
+        callee: ir::FuncRef,
+    ) -> InlineCommand<'_> {
+        let Some(index) = self.callee_index(caller, callee) else {
+            return InlineCommand::KeepCall;
+        };
+        if !self.bodies.contains_key(&index) {
+            let body = self.translate(index);
+            self.bodies.insert(index, body);
+        }
+        match &self.bodies[&index] {
+            Some(body) => InlineCommand::Inline(Cow::Borrowed(body)),
+            None => InlineCommand::KeepCall,
+        }
+    }
+}
+
 fn mach_stack_maps_to_stack_maps(mach_stack_maps: &[MachStackMap]) -> Vec<StackMapInformation> {
     // This is converting from Cranelift's representation of a stack map to
     // Wasmtime's representation. They happen to align today but that may
@@ -25,6 +25,25 @@ use thiserror::Error;
 pub struct WasmFunctionInfo {
     pub start_srcloc: FilePos,
     pub stack_maps: Box<[StackMapInformation]>,
+    pub inlined: Box<[InlinedFunction]>,
+}
+
+/// A function whose body was inlined into the function being described by a
+/// `WasmFunctionInfo`.
+///
+/// Instructions copied from the inlined function keep their original offsets
+/// in the wasm file, so code whose offset lies within `start_srcloc` and
+/// `end_srcloc` belongs to the inlined function rather than to its caller.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InlinedFunction {
+    /// The function that was inlined.
+    pub func_index: FuncIndex,
+    /// The start of the inlined function's body in the wasm file.
+    pub start_srcloc: FilePos,
+    /// The end of the inlined function's body in the wasm file.
+    pub end_srcloc: FilePos,
+    /// The location of the call that was replaced by the inlined body.
+    pub call_site: FilePos,
 }
 
 /// Description of where a function is located in the text section of a
@@ -35,7 +54,16 @@ pub struct FunctionLoc {
     /// function starts.
     pub start: u32,
     /// The byte length of this function's function body.
+    ///
+    /// If the function was split into hot and cold parts, this is only the
+    /// length of its hot part.
     pub length: u32,
+    /// The byte offset from the start of the text section where the cold part
+    /// of this function starts, if it was split into hot and cold parts.
+    pub cold_start: u32,
+    /// The byte length of the cold part of this function, or 0 if it wasn't
+    /// split into hot and cold parts.
+    pub cold_length: u32,
 }
 
 /// The offset within a function of a GC safepoint, and its associated stack
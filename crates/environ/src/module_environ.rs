@@ -17,7 +17,7 @@ use std::sync::Arc;
 use wasmparser::{
     types::Types, CustomSectionReader, DataKind, ElementItems, ElementKind, Encoding, ExternalKind,
     FuncToValidate, FunctionBody, NameSectionReader, Naming, Operator, Parser, Payload,
-    StructuralType, TypeRef, Validator, ValidatorResources,
+    StructuralType, TypeRef, Validator, ValidatorResources, WasmFeatures,
 };
 
 /// Object containing the standalone environment information.
@@ -51,6 +51,14 @@ pub struct ModuleTranslation<'data> {
     /// References to the function bodies.
     pub function_body_inputs: PrimaryMap<DefinedFuncIndex, FunctionBodyData<'data>>,
 
+    /// Copies of the function bodies, kept so that they can be translated
+    /// again to inline them into their callers. This is only filled in when
+    /// `Tunables::inlining` is enabled.
+    pub inlinable_function_bodies: PrimaryMap<DefinedFuncIndex, FunctionBody<'data>>,
+
+    /// The WebAssembly features that the function bodies are validated with.
+    pub wasm_features: WasmFeatures,
+
     /// A list of type signatures which are considered exported from this
     /// module, or those that can possibly be called. This list is sorted, and
     /// trampolines for each of these signatures are required.
@@ -541,6 +549,7 @@ impl<'a, 'data> ModuleEnvironment<'a, 'data> {
                 self.validator.code_section_start(count, &range)?;
                 let cnt = usize::try_from(count).unwrap();
                 self.result.function_body_inputs.reserve_exact(cnt);
+                self.result.wasm_features = *self.validator.features();
                 self.result.debuginfo.wasm_file.code_section_offset = range.start as u64;
             }
 
@@ -569,6 +578,9 @@ impl<'a, 'data> ModuleEnvironment<'a, 'data> {
                         });
                 }
                 body.allow_memarg64(self.validator.features().memory64);
+                if self.tunables.inlining {
+                    self.result.inlinable_function_bodies.push(body.clone());
+                }
                 self.result
                     .function_body_inputs
                     .push(FunctionBodyData { validator, body });
@@ -52,6 +52,10 @@ pub struct Tunables {
 
     /// Whether or not Wasm functions can be tail-called or not.
     pub tail_callable: bool,
+
+    /// Whether or not small functions are inlined into their callers within
+    /// the same module.
+    pub inlining: bool,
 }
 
 impl Default for Tunables {
@@ -110,6 +114,7 @@ impl Default for Tunables {
             debug_adapter_modules: false,
             relaxed_simd_deterministic: false,
             tail_callable: false,
+            inlining: false,
         }
     }
 }
@@ -423,6 +423,9 @@ impl<'a> ObjectBuilder<'a> {
 pub struct CompiledModule {
     module: Arc<Module>,
     funcs: PrimaryMap<DefinedFuncIndex, CompiledFunctionInfo>,
+    /// Defined functions which were split into hot and cold parts, in the
+    /// order their cold parts are placed in the text section.
+    split_funcs: Vec<DefinedFuncIndex>,
     wasm_to_native_trampolines: Vec<(SignatureIndex, FunctionLoc)>,
     meta: Metadata,
     code_memory: Arc<CodeMemory>,
@@ -455,9 +458,16 @@ impl CompiledModule {
         profiler: &dyn ProfilingAgent,
         id_allocator: &CompiledModuleIdAllocator,
     ) -> Result<Self> {
+        let split_funcs = info
+            .funcs
+            .iter()
+            .filter(|(_, f)| f.wasm_func_loc.cold_length > 0)
+            .map(|(i, _)| i)
+            .collect();
         let mut ret = Self {
             module: Arc::new(info.module),
             funcs: info.funcs,
+            split_funcs,
             wasm_to_native_trampolines: info.wasm_to_native_trampolines,
             dbg_jit_registration: None,
             code_memory,
@@ -631,15 +641,34 @@ impl CompiledModule {
             }
         };
 
-        let CompiledFunctionInfo { wasm_func_loc, .. } = self.funcs.get(index)?;
-        let start = wasm_func_loc.start;
-        let end = wasm_func_loc.start + wasm_func_loc.length;
+        if let Some(CompiledFunctionInfo { wasm_func_loc, .. }) = self.funcs.get(index) {
+            let start = wasm_func_loc.start;
+            let end = wasm_func_loc.start + wasm_func_loc.length;
 
-        if text_offset < start || end < text_offset {
-            return None;
+            if start <= text_offset && text_offset <= end {
+                return Some((index, text_offset - wasm_func_loc.start));
+            }
         }
 
-        Some((index, text_offset - wasm_func_loc.start))
+        self.split_func_by_text_offset(text_offset)
+    }
+
+    /// Lookups a defined function by a program counter value within the cold
+    /// part of the function.
+    ///
+    /// The relative address returned is within the function as a whole, in
+    /// which the cold part directly follows the hot part.
+    fn split_func_by_text_offset(&self, text_offset: u32) -> Option<(DefinedFuncIndex, u32)> {
+        let i = self.split_funcs.partition_point(|i| {
+            let loc = &self.funcs[*i].wasm_func_loc;
+            loc.cold_start + loc.cold_length <= text_offset
+        });
+        let index = *self.split_funcs.get(i)?;
+        let loc = &self.funcs[index].wasm_func_loc;
+        if text_offset < loc.cold_start {
+            return None;
+        }
+        Some((index, loc.length + (text_offset - loc.cold_start)))
     }
 
     /// Gets the function location information for a given function index.
@@ -0,0 +1,92 @@
+use command_tests::wasi::filesystem::preopens;
+use command_tests::wasi::filesystem::types::{
+    self, AccessType, DescriptorFlags, ErrorCode, Modes, OpenFlags, PathFlags,
+};
+
+fn main() {
+    let preopens = preopens::get_directories();
+    let (dir, _) = preopens
+        .iter()
+        .find(|(_, path)| path == "/")
+        .expect("expected a preopen for `/`");
+    let dir = *dir;
+
+    let open = |flags| {
+        types::open_at(
+            dir,
+            PathFlags::empty(),
+            "bar.txt",
+            OpenFlags::empty(),
+            flags,
+            Modes::empty(),
+        )
+        .expect("open bar.txt")
+    };
+    let a = open(DescriptorFlags::READ | DescriptorFlags::WRITE);
+    let b = open(DescriptorFlags::READ | DescriptorFlags::WRITE);
+
+    // An exclusive lock excludes every other lock on the file.
+    types::lock_exclusive(a).unwrap();
+    assert!(matches!(
+        types::try_lock_shared(b),
+        Err(ErrorCode::WouldBlock)
+    ));
+    assert!(matches!(
+        types::try_lock_exclusive(b),
+        Err(ErrorCode::WouldBlock)
+    ));
+    types::unlock(a).unwrap();
+
+    // Shared locks coexist, but exclude exclusive ones.
+    types::lock_shared(a).unwrap();
+    types::try_lock_shared(b).unwrap();
+    types::unlock(b).unwrap();
+    assert!(matches!(
+        types::try_lock_exclusive(b),
+        Err(ErrorCode::WouldBlock)
+    ));
+    types::unlock(a).unwrap();
+    types::try_lock_exclusive(b).unwrap();
+    types::unlock(b).unwrap();
+
+    // Unlocking a file which isn't locked is fine.
+    types::unlock(b).unwrap();
+
+    types::drop_descriptor(a);
+    types::drop_descriptor(b);
+
+    types::access_at(dir, PathFlags::empty(), "bar.txt", AccessType::Exists).unwrap();
+    types::access_at(
+        dir,
+        PathFlags::empty(),
+        "bar.txt",
+        AccessType::Access(Modes::READABLE | Modes::WRITABLE),
+    )
+    .unwrap();
+    types::access_at(dir, PathFlags::empty(), "sub", AccessType::Exists).unwrap();
+    assert!(matches!(
+        types::access_at(dir, PathFlags::empty(), "missing.txt", AccessType::Exists),
+        Err(ErrorCode::NoEntry)
+    ));
+
+    // Directories can't be made executable.
+    assert!(matches!(
+        types::change_directory_permissions_at(
+            dir,
+            PathFlags::empty(),
+            "sub",
+            Modes::READABLE | Modes::EXECUTABLE,
+        ),
+        Err(ErrorCode::Invalid)
+    ));
+    types::change_directory_permissions_at(
+        dir,
+        PathFlags::empty(),
+        "sub",
+        Modes::READABLE | Modes::WRITABLE,
+    )
+    .unwrap();
+
+    // Make bar.txt read-only; the host checks that this took effect.
+    types::change_file_permissions_at(dir, PathFlags::empty(), "bar.txt", Modes::READABLE).unwrap();
+}
@@ -0,0 +1,9 @@
+use command_tests::wasi::logging::handler::{log, Level};
+
+fn main() {
+    log(Level::Trace, "logging", "tracing");
+    log(Level::Debug, "logging", "debugging");
+    log(Level::Info, "logging", "informing");
+    log(Level::Warn, "logging::warnings", "warning");
+    log(Level::Error, "", "erring");
+}
@@ -0,0 +1,101 @@
+use command_tests::wasi::filesystem::preopens;
+use command_tests::wasi::filesystem::types::{
+    self, DescriptorFlags, ErrorCode, Modes, OpenFlags, PathFlags,
+};
+
+fn main() {
+    let preopens = preopens::get_directories();
+    let (dir, _) = preopens
+        .iter()
+        .find(|(_, path)| path == "/")
+        .expect("expected a preopen for `/`");
+    let dir = *dir;
+
+    let open = |dir, path: &str, oflags, flags| {
+        types::open_at(dir, PathFlags::empty(), path, oflags, flags, Modes::empty())
+    };
+
+    // `**/.git` is hidden, wherever it is.
+    assert!(matches!(
+        open(dir, ".git/HEAD", OpenFlags::empty(), DescriptorFlags::READ),
+        Err(ErrorCode::NoEntry)
+    ));
+    assert!(matches!(
+        types::stat_at(dir, PathFlags::empty(), "src/.git"),
+        Err(ErrorCode::NoEntry)
+    ));
+    let src = open(dir, "src", OpenFlags::DIRECTORY, DescriptorFlags::READ).unwrap();
+    assert!(matches!(
+        types::stat_at(src, PathFlags::empty(), ".git"),
+        Err(ErrorCode::NoEntry)
+    ));
+    assert!(matches!(
+        types::stat_at(src, PathFlags::empty(), "../.git"),
+        Err(ErrorCode::NoEntry)
+    ));
+    types::drop_descriptor(src);
+
+    let entries = types::read_directory(dir).unwrap();
+    let mut names = Vec::new();
+    while let Some(entry) = types::read_directory_entry(entries).unwrap() {
+        names.push(entry.name);
+    }
+    types::drop_directory_entry_stream(entries);
+    names.sort();
+    assert_eq!(names, ["config", "src"]);
+
+    // `config` can be read, but not modified.
+    let file = open(
+        dir,
+        "config/app.toml",
+        OpenFlags::empty(),
+        DescriptorFlags::READ,
+    )
+    .unwrap();
+    let (contents, _) = types::read(file, 100, 0).unwrap();
+    assert_eq!(contents, b"debug = false");
+    types::drop_descriptor(file);
+    assert!(matches!(
+        open(
+            dir,
+            "config/app.toml",
+            OpenFlags::empty(),
+            DescriptorFlags::READ | DescriptorFlags::WRITE
+        ),
+        Err(ErrorCode::ReadOnly)
+    ));
+    assert!(matches!(
+        open(
+            dir,
+            "config/new.toml",
+            OpenFlags::CREATE,
+            DescriptorFlags::READ
+        ),
+        Err(ErrorCode::ReadOnly)
+    ));
+    assert!(matches!(
+        types::unlink_file_at(dir, "config/app.toml"),
+        Err(ErrorCode::ReadOnly)
+    ));
+    assert!(matches!(
+        types::rename_at(dir, "config/app.toml", dir, "src/app.toml"),
+        Err(ErrorCode::ReadOnly)
+    ));
+
+    // Links can't be used to get around the rules.
+    assert!(matches!(
+        types::symlink_at(dir, "../config", "src/config"),
+        Err(ErrorCode::NotPermitted)
+    ));
+
+    // Everything else is unaffected.
+    let file = open(
+        dir,
+        "src/main.rs",
+        OpenFlags::CREATE,
+        DescriptorFlags::READ | DescriptorFlags::WRITE,
+    )
+    .unwrap();
+    types::write(file, b"fn main() {}", 0).unwrap();
+    types::drop_descriptor(file);
+}
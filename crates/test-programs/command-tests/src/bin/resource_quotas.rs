@@ -0,0 +1,78 @@
+use command_tests::wasi::cli_base::stdout;
+use command_tests::wasi::filesystem::preopens;
+use command_tests::wasi::filesystem::types::{
+    self, DescriptorFlags, ErrorCode, Modes, OpenFlags, PathFlags,
+};
+use command_tests::wasi::io::streams;
+
+fn main() {
+    let preopens = preopens::get_directories();
+    let (dir, _) = preopens
+        .iter()
+        .find(|(_, path)| path == "/")
+        .expect("expected a preopen for `/`");
+    let dir = *dir;
+
+    let open = |path: &str, oflags| {
+        types::open_at(
+            dir,
+            PathFlags::empty(),
+            path,
+            oflags,
+            DescriptorFlags::READ | DescriptorFlags::WRITE,
+            Modes::READABLE | Modes::WRITABLE,
+        )
+    };
+
+    // The host allows 10 bytes to be written to files in total.
+    let a = open("a.txt", OpenFlags::CREATE).unwrap();
+    // Writing past the end of the file also writes the zeros before it.
+    assert_eq!(types::write(a, b"1234", 0).unwrap(), 4);
+    assert_eq!(types::write(a, b"abcd", 8).unwrap(), 2);
+    assert!(matches!(types::write(a, b"x", 10), Err(ErrorCode::Quota)));
+    // Growing the file would write zeros to it, but setting the size it
+    // already has doesn't.
+    assert!(matches!(types::set_size(a, 100), Err(ErrorCode::Quota)));
+    types::set_size(a, 10).unwrap();
+    let out = types::append_via_stream(a).unwrap();
+    assert!(streams::write(out, b"x").is_err());
+    streams::drop_output_stream(out);
+
+    // The host allows two files to be created. Opening an existing file with
+    // `create` doesn't count against that.
+    let a2 = open("a.txt", OpenFlags::CREATE).unwrap();
+    types::drop_descriptor(a2);
+    types::create_directory_at(dir, "d").unwrap();
+    assert!(matches!(
+        open("b.txt", OpenFlags::CREATE),
+        Err(ErrorCode::Quota)
+    ));
+    assert!(matches!(
+        open("b.txt", OpenFlags::empty()),
+        Err(ErrorCode::NoEntry)
+    ));
+
+    // The host limits the number of table entries, so opening files fails
+    // eventually, and succeeds again once some are closed.
+    let mut fds = Vec::new();
+    loop {
+        match open("a.txt", OpenFlags::empty()) {
+            Ok(fd) => fds.push(fd),
+            Err(ErrorCode::Quota) => break,
+            Err(e) => panic!("unexpected error: {e:?}"),
+        }
+        assert!(fds.len() < 100, "table quota not enforced");
+    }
+    for fd in fds {
+        types::drop_descriptor(fd);
+    }
+    let a3 = open("a.txt", OpenFlags::empty()).unwrap();
+    types::drop_descriptor(a3);
+    types::drop_descriptor(a);
+
+    // The host allows 5 bytes to be written to stdout and stderr.
+    let stdout = stdout::get_stdout();
+    let (n, _) = streams::write(stdout, b"hello world").unwrap();
+    assert_eq!(n, 5);
+    assert!(streams::write(stdout, b"!").is_err());
+}
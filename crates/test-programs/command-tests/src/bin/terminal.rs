@@ -0,0 +1,19 @@
+use command_tests::wasmtime::wasi::terminal_input::{drop_terminal_input, set_raw_mode};
+use command_tests::wasmtime::wasi::terminal_output::{drop_terminal_output, get_window_size};
+use command_tests::wasmtime::wasi::{terminal_stderr, terminal_stdin, terminal_stdout};
+
+fn main() {
+    let stdin = terminal_stdin::get_terminal_stdin().expect("stdin should be a terminal");
+    set_raw_mode(stdin, true).unwrap();
+    set_raw_mode(stdin, false).unwrap();
+    // Left in raw mode for the host to see.
+    set_raw_mode(stdin, true).unwrap();
+    drop_terminal_input(stdin);
+
+    let stdout = terminal_stdout::get_terminal_stdout().expect("stdout should be a terminal");
+    let size = get_window_size(stdout).expect("window size should be known");
+    assert_eq!((size.columns, size.rows), (80, 24));
+    drop_terminal_output(stdout);
+
+    assert!(terminal_stderr::get_terminal_stderr().is_none());
+}
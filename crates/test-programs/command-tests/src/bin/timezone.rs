@@ -0,0 +1,28 @@
+use command_tests::wasi::clocks::timezone::{display, drop_timezone, utc_offset};
+use command_tests::wasi::clocks::wall_clock::Datetime;
+
+fn main() {
+    // Guests make up their own handles, which all refer to the same timezone.
+    let tz = 0;
+
+    let winter = Datetime {
+        seconds: 1673740800,
+        nanoseconds: 0,
+    };
+    let display_winter = display(tz, winter);
+    assert_eq!(display_winter.name, "CET");
+    assert_eq!(display_winter.utc_offset, 3600);
+    assert!(!display_winter.in_daylight_saving_time);
+
+    let summer = Datetime {
+        seconds: 1686787200,
+        nanoseconds: 0,
+    };
+    let display_summer = display(tz, summer);
+    assert_eq!(display_summer.name, "CEST");
+    assert_eq!(display_summer.utc_offset, 7200);
+    assert!(display_summer.in_daylight_saving_time);
+    assert_eq!(utc_offset(tz, summer), 7200);
+
+    drop_timezone(tz);
+}
@@ -1,15 +1,20 @@
 use anyhow::Result;
 use cap_std::{ambient_authority, fs::Dir, time::Duration};
-use std::{io::Write, sync::Mutex};
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
 use wasmtime::{
     component::{Component, Linker},
     Config, Engine, Store,
 };
 use wasmtime_wasi::preview2::{
+    bindings::clocks::timezone::TimezoneDisplay,
+    bindings::logging::handler::Level,
     command::{add_to_linker, Command},
-    pipe::MemoryInputPipe,
-    DirPerms, FilePerms, HostMonotonicClock, HostWallClock, Table, WasiCtx, WasiCtxBuilder,
-    WasiView,
+    pipe::{MemoryInputPipe, MemoryOutputPipe},
+    DirPerms, FilePerms, HostLogger, HostMonotonicClock, HostTimezone, HostWallClock, PathAcl,
+    Table, WasiCtx, WasiCtxBuilder, WasiView,
 };
 
 lazy_static::lazy_static! {
@@ -169,6 +174,44 @@ async fn time() -> Result<()> {
         .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))
 }
 
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn timezone() -> Result<()> {
+    // Central European Time, with daylight saving time from 2023-03-26 to
+    // 2023-10-29.
+    struct FakeTimezone;
+
+    impl HostTimezone for FakeTimezone {
+        fn display(&self, datetime: Duration) -> TimezoneDisplay {
+            if (1679792400..1698541200).contains(&datetime.as_secs()) {
+                TimezoneDisplay {
+                    utc_offset: 7200,
+                    name: "CEST".to_string(),
+                    in_daylight_saving_time: true,
+                }
+            } else {
+                TimezoneDisplay {
+                    utc_offset: 3600,
+                    name: "CET".to_string(),
+                    in_daylight_saving_time: false,
+                }
+            }
+        }
+    }
+
+    let mut table = Table::new();
+    let wasi = WasiCtxBuilder::new()
+        .timezone(FakeTimezone)
+        .build(&mut table)?;
+
+    let (mut store, command) =
+        instantiate(get_component("timezone"), CommandCtx { table, wasi }).await?;
+
+    command
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))
+}
+
 #[test_log::test(tokio::test(flavor = "multi_thread"))]
 async fn stdin() -> Result<()> {
     let mut table = Table::new();
@@ -444,6 +487,205 @@ async fn read_only() -> Result<()> {
         .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))
 }
 
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn file_lock_access() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    std::fs::File::create(dir.path().join("bar.txt"))?.write_all(b"One, two! One, two!")?;
+    std::fs::create_dir(dir.path().join("sub"))?;
+
+    let mut table = Table::new();
+    let open_dir = Dir::open_ambient_dir(dir.path(), ambient_authority())?;
+    let wasi = WasiCtxBuilder::new()
+        .preopened_dir(open_dir, DirPerms::all(), FilePerms::all(), "/")
+        .build(&mut table)?;
+
+    let (mut store, command) = instantiate(
+        get_component("file_lock_access"),
+        CommandCtx { table, wasi },
+    )
+    .await?;
+
+    command
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))?;
+
+    let bar = dir.path().join("bar.txt");
+    let mut perms = std::fs::metadata(&bar)?.permissions();
+    assert!(perms.readonly());
+    // Let the tempdir clean up after itself.
+    perms.set_readonly(false);
+    std::fs::set_permissions(&bar, perms)?;
+    Ok(())
+}
+
+#[derive(Clone, Default)]
+struct CapturingLogger(Arc<Mutex<Vec<String>>>);
+
+impl HostLogger for CapturingLogger {
+    fn log(&self, level: Level, context: &str, message: &str) {
+        let level = match level {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        };
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{level} [{context}] {message}"));
+    }
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn logging() -> Result<()> {
+    let logger = CapturingLogger::default();
+
+    let mut table = Table::new();
+    let wasi = WasiCtxBuilder::new()
+        .logger(logger.clone())
+        .log_level(Some(Level::Info))
+        .build(&mut table)?;
+
+    let (mut store, command) =
+        instantiate(get_component("logging"), CommandCtx { table, wasi }).await?;
+
+    command
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))?;
+
+    assert_eq!(
+        *logger.0.lock().unwrap(),
+        [
+            "info [logging] informing",
+            "warn [logging::warnings] warning",
+            "error [] erring",
+        ]
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn resource_quotas() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let stdout = MemoryOutputPipe::new();
+
+    let mut table = Table::new();
+    let open_dir = Dir::open_ambient_dir(dir.path(), ambient_authority())?;
+    let wasi = WasiCtxBuilder::new()
+        .stdout(stdout.clone())
+        .preopened_dir(open_dir, DirPerms::all(), FilePerms::all(), "/")
+        .max_table_entries(16)
+        .max_bytes_written(10)
+        .max_files_created(2)
+        .max_stdio_bytes(5)
+        .build(&mut table)?;
+
+    let (mut store, command) =
+        instantiate(get_component("resource_quotas"), CommandCtx { table, wasi }).await?;
+
+    command
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))?;
+
+    assert_eq!(std::fs::read(dir.path().join("a.txt"))?, b"1234\0\0\0\0ab");
+    assert!(dir.path().join("d").is_dir());
+    assert!(!dir.path().join("b.txt").exists());
+    assert_eq!(stdout.contents().as_ref(), b"hello");
+    Ok(())
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn path_acl() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    std::fs::create_dir_all(dir.path().join(".git"))?;
+    std::fs::write(dir.path().join(".git/HEAD"), "ref: refs/heads/main")?;
+    std::fs::create_dir_all(dir.path().join("src/.git"))?;
+    std::fs::create_dir(dir.path().join("config"))?;
+    std::fs::write(dir.path().join("config/app.toml"), "debug = false")?;
+
+    let mut acl = PathAcl::new();
+    acl.hide("**/.git")?.read_only("config")?;
+
+    let mut table = Table::new();
+    let open_dir = Dir::open_ambient_dir(dir.path(), ambient_authority())?;
+    let wasi = WasiCtxBuilder::new()
+        .preopened_dir_with_acl(open_dir, DirPerms::all(), FilePerms::all(), acl, "/")
+        .build(&mut table)?;
+
+    let (mut store, command) =
+        instantiate(get_component("path_acl"), CommandCtx { table, wasi }).await?;
+
+    command
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))?;
+
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("config/app.toml"))?,
+        "debug = false"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("src/main.rs"))?,
+        "fn main() {}"
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn terminal() -> Result<()> {
+    use rustix::pty::{grantpt, openpt, ptsname, unlockpt, OpenptFlags};
+    use rustix::termios::{tcgetattr, tcsetwinsize, LocalModes, Winsize};
+    use wasmtime_wasi::preview2::TtyTerminal;
+
+    let master = openpt(OpenptFlags::RDWR | OpenptFlags::NOCTTY)?;
+    grantpt(&master)?;
+    unlockpt(&master)?;
+    let path = ptsname(&master, Vec::new())?;
+    let tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path.to_str()?)?;
+    tcsetwinsize(
+        &master,
+        Winsize {
+            ws_row: 24,
+            ws_col: 80,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        },
+    )?;
+    let cooked = LocalModes::ICANON | LocalModes::ECHO;
+    assert!(tcgetattr(&tty)?.local_modes.contains(cooked));
+
+    let mut table = Table::new();
+    let wasi = WasiCtxBuilder::new()
+        .stdin_terminal(TtyTerminal::new(tty.try_clone()?)?)
+        .stdout_terminal(TtyTerminal::new(tty.try_clone()?)?)
+        .build(&mut table)?;
+
+    let (mut store, command) =
+        instantiate(get_component("terminal"), CommandCtx { table, wasi }).await?;
+
+    command
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))?;
+
+    // The guest left the terminal in raw mode, which is undone once the
+    // terminal is dropped along with the store.
+    assert!(!tcgetattr(&tty)?.local_modes.intersects(cooked));
+    drop(store);
+    assert!(tcgetattr(&tty)?.local_modes.contains(cooked));
+    Ok(())
+}
+
 #[test_log::test(tokio::test(flavor = "multi_thread"))]
 async fn stream_pollable_lifetimes() -> Result<()> {
     // Test program has two modes, dispatching based on argument.
@@ -1,7 +1,7 @@
 #![cfg(feature = "test_programs")]
 use anyhow::Result;
 use tempfile::TempDir;
-use wasmtime::{Config, Engine, Linker, Store};
+use wasmtime::{Config, Engine, Linker, Module, Store};
 use wasmtime_wasi::preview2::{
     pipe::MemoryOutputPipe,
     preview1::{add_to_linker, WasiPreview1Adapter, WasiPreview1View},
@@ -28,6 +28,34 @@ pub fn prepare_workspace(exe_name: &str) -> Result<TempDir> {
     Ok(tempdir)
 }
 
+struct Ctx {
+    wasi: WasiCtx,
+    table: Table,
+    adapter: WasiPreview1Adapter,
+}
+impl WasiView for Ctx {
+    fn ctx(&self) -> &WasiCtx {
+        &self.wasi
+    }
+    fn ctx_mut(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+    fn table(&self) -> &Table {
+        &self.table
+    }
+    fn table_mut(&mut self) -> &mut Table {
+        &mut self.table
+    }
+}
+impl WasiPreview1View for Ctx {
+    fn adapter(&self) -> &WasiPreview1Adapter {
+        &self.adapter
+    }
+    fn adapter_mut(&mut self) -> &mut WasiPreview1Adapter {
+        &mut self.adapter
+    }
+}
+
 async fn run(name: &str, inherit_stdio: bool) -> Result<()> {
     let workspace = prepare_workspace(name)?;
     let stdout = MemoryOutputPipe::new();
@@ -56,34 +84,6 @@ async fn run(name: &str, inherit_stdio: bool) -> Result<()> {
 
         let mut table = Table::new();
         let wasi = builder.build(&mut table)?;
-        struct Ctx {
-            wasi: WasiCtx,
-            table: Table,
-            adapter: WasiPreview1Adapter,
-        }
-        impl WasiView for Ctx {
-            fn ctx(&self) -> &WasiCtx {
-                &self.wasi
-            }
-            fn ctx_mut(&mut self) -> &mut WasiCtx {
-                &mut self.wasi
-            }
-            fn table(&self) -> &Table {
-                &self.table
-            }
-            fn table_mut(&mut self) -> &mut Table {
-                &mut self.table
-            }
-        }
-        impl WasiPreview1View for Ctx {
-            fn adapter(&self) -> &WasiPreview1Adapter {
-                &self.adapter
-            }
-            fn adapter_mut(&mut self) -> &mut WasiPreview1Adapter {
-                &mut self.adapter
-            }
-        }
-
         let adapter = WasiPreview1Adapter::new();
         let ctx = Ctx {
             wasi,
@@ -116,6 +116,89 @@ async fn run(name: &str, inherit_stdio: bool) -> Result<()> {
     Ok(())
 }
 
+// Echoes what a client sends on a socket accepted from the listener
+// preopened as descriptor 3.
+const SOCK_ECHO: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "sock_accept"
+    (func $sock_accept (param i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "sock_recv"
+    (func $sock_recv (param i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "sock_send"
+    (func $sock_send (param i32 i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "sock_shutdown"
+    (func $sock_shutdown (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_close"
+    (func $fd_close (param i32) (result i32)))
+  (memory (export "memory") 1)
+
+  (func $check (param $errno i32)
+    (if (local.get $errno) (then unreachable)))
+
+  (func (export "_start")
+    (local $fd i32)
+
+    ;; The listener can't be received from: that's `notsock`.
+    (if (i32.ne
+          (call $sock_recv (i32.const 3) (i32.const 0) (i32.const 1) (i32.const 0)
+            (i32.const 104) (i32.const 108))
+          (i32.const 57))
+      (then unreachable))
+
+    (call $check (call $sock_accept (i32.const 3) (i32.const 0) (i32.const 100)))
+    (local.set $fd (i32.load (i32.const 100)))
+
+    ;; Receive up to 64 bytes with `recv_waitall`, which returns early once
+    ;; the client shuts down its side of the connection.
+    (i32.store (i32.const 0) (i32.const 16))
+    (i32.store (i32.const 4) (i32.const 64))
+    (call $check (call $sock_recv (local.get $fd) (i32.const 0) (i32.const 1) (i32.const 2)
+      (i32.const 104) (i32.const 108)))
+
+    ;; Send back what was received, and shut down the connection.
+    (i32.store (i32.const 4) (i32.load (i32.const 104)))
+    (call $check (call $sock_send (local.get $fd) (i32.const 0) (i32.const 1) (i32.const 0)
+      (i32.const 112)))
+    (call $check (call $sock_shutdown (local.get $fd) (i32.const 2)))
+    (call $check (call $fd_close (local.get $fd))))
+)
+"#;
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn sock_echo() -> Result<()> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let client = std::thread::spawn(move || -> std::io::Result<Vec<u8>> {
+        use std::io::{Read, Write};
+        let mut stream = std::net::TcpStream::connect(addr)?;
+        stream.write_all(b"hello")?;
+        stream.shutdown(std::net::Shutdown::Write)?;
+        let mut echo = Vec::new();
+        stream.read_to_end(&mut echo)?;
+        Ok(echo)
+    });
+
+    let mut linker = Linker::new(&ENGINE);
+    add_to_linker(&mut linker)?;
+    let mut table = Table::new();
+    let wasi = WasiCtxBuilder::new()
+        .preopened_tcp_listener(cap_std::net::TcpListener::from_std(listener))
+        .build(&mut table)?;
+    let ctx = Ctx {
+        wasi,
+        table,
+        adapter: WasiPreview1Adapter::new(),
+    };
+    let mut store = Store::new(&ENGINE, ctx);
+    let module = Module::new(&ENGINE, SOCK_ECHO)?;
+    let instance = linker.instantiate_async(&mut store, &module).await?;
+    let start = instance.get_typed_func::<(), ()>(&mut store, "_start")?;
+    start.call_async(&mut store, ()).await?;
+
+    assert_eq!(client.join().unwrap()?, b"hello");
+    Ok(())
+}
+
 // Below here is mechanical: there should be one test for every binary in
 // wasi-tests. The only differences should be should_panic annotations for
 // tests which fail.
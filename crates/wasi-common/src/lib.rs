@@ -44,7 +44,9 @@
 //! `cap_std::time::Instant`.  * Randomness: we re-use the `cap_rand::RngCore`
 //! trait to represent a randomness source. A trivial `Deterministic` impl is
 //! provided.  * Scheduling: The `WasiSched` trait abstracts over the
-//! `sched_yield` and `poll_oneoff` functions.
+//! `sched_yield` and `poll_oneoff` functions. The `virtual_time` module
+//! provides clocks and a scheduler driven by a virtual clock, for tests which
+//! shouldn't wait on real time.
 //!
 //! Users can provide implementations of each of these interfaces to the
 //! `WasiCtx::builder(...)` function. The
@@ -61,6 +63,7 @@ pub mod sched;
 pub mod snapshots;
 mod string_array;
 pub mod table;
+pub mod virtual_time;
 
 pub use cap_rand::RngCore;
 pub use clocks::{SystemTimeSpec, WasiClocks, WasiMonotonicClock, WasiSystemClock};
@@ -19,6 +19,8 @@ wiggle::from_witx!({
     errors: { errno => trappable Error },
     async: *,
     wasmtime: false,
+    // Sizes are as wide as pointers, so they're 64 bits for guests with a 64-bit memory.
+    pointer_sized: [size],
 });
 
 use types::Error;
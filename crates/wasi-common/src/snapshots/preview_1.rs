@@ -32,6 +32,8 @@ wiggle::from_witx!({
     // tedious, and there is no cost to having a sync function be async in this case.
     async: *,
     wasmtime: false,
+    // Sizes are as wide as pointers, so they're 64 bits for guests with a 64-bit memory.
+    pointer_sized: [size],
 });
 
 impl wiggle::GuestErrorType for types::Errno {
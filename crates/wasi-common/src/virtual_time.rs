@@ -0,0 +1,277 @@
+//! Clocks and a scheduler driven by virtual time rather than the host's
+//! clocks.
+//!
+//! A [`VirtualClock`] only moves forward when it is told to: either by the
+//! embedder calling [`VirtualClock::advance`], or by a [`VirtualSched`] which
+//! is asked to wait for something. Instead of blocking, the scheduler jumps
+//! the clock forward to the earliest deadline it was given, so sleeps and
+//! clock subscriptions complete immediately and in timestamp order. This makes
+//! guests which lean on timers deterministic and fast to run in tests:
+//!
+//! ```
+//! use wasi_common::virtual_time::VirtualClock;
+//! use wasi_common::{table::Table, WasiCtx};
+//!
+//! let clock = VirtualClock::new();
+//! let ctx = WasiCtx::new(
+//!     Box::new(wasi_common::random::Deterministic::new(vec![42])),
+//!     clock.clocks(),
+//!     Box::new(clock.sched()),
+//!     Table::new(),
+//! );
+//! # drop(ctx);
+//! ```
+
+use crate::clocks::{WasiClocks, WasiMonotonicClock, WasiSystemClock};
+use crate::sched::subscription::{RwEventFlags, Subscription};
+use crate::sched::{Poll, WasiSched};
+use crate::{Error, ErrorExt};
+use cap_std::time::{Duration, Instant, SystemTime};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A clock whose time only advances when asked to.
+///
+/// Clones share the same time, so a clone kept by the embedder observes (and
+/// can advance) the time seen by the guest. The clock serves as both the
+/// system and the monotonic clock; both start at creation and advance
+/// together.
+#[derive(Clone)]
+pub struct VirtualClock {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    // Nanoseconds elapsed since the clock was created.
+    elapsed: AtomicU64,
+    // What `Instant` and `SystemTime` correspond to zero elapsed time. The
+    // instant anchor is arbitrary, as guests only see monotonic time relative
+    // to when their context was created.
+    instant_base: Instant,
+    system_base: SystemTime,
+}
+
+impl VirtualClock {
+    /// Creates a clock whose system time starts at the Unix epoch.
+    pub fn new() -> Self {
+        Self::with_system_time(SystemTime::from_std(std::time::UNIX_EPOCH))
+    }
+
+    /// Creates a clock whose system time starts at `start`.
+    pub fn with_system_time(start: SystemTime) -> Self {
+        VirtualClock {
+            inner: Arc::new(Inner {
+                elapsed: AtomicU64::new(0),
+                instant_base: Instant::from_std(std::time::Instant::now()),
+                system_base: start,
+            }),
+        }
+    }
+
+    /// The amount of virtual time which has passed since the clock was
+    /// created.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.inner.elapsed.load(Ordering::SeqCst))
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        let nanos = duration_to_nanos(duration);
+        let _ = self
+            .inner
+            .elapsed
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                Some(n.saturating_add(nanos))
+            });
+    }
+
+    /// Moves the clock forward to `deadline`, if it isn't already past it.
+    fn advance_to(&self, deadline: Instant) {
+        if let Some(d) = deadline.checked_duration_since(self.inner.instant_base) {
+            self.inner
+                .elapsed
+                .fetch_max(duration_to_nanos(d), Ordering::SeqCst);
+        }
+    }
+
+    /// Returns a `WasiClocks` whose system and monotonic clocks both read
+    /// this clock.
+    pub fn clocks(&self) -> WasiClocks {
+        WasiClocks::new()
+            .with_system(self.clone())
+            .with_monotonic(self.clone())
+    }
+
+    /// Returns a scheduler which advances this clock instead of waiting.
+    pub fn sched(&self) -> VirtualSched {
+        VirtualSched::new(self.clone())
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn duration_to_nanos(d: Duration) -> u64 {
+    u64::try_from(d.as_nanos()).unwrap_or(u64::MAX)
+}
+
+impl WasiSystemClock for VirtualClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+    fn now(&self, _precision: Duration) -> SystemTime {
+        self.inner.system_base + self.elapsed()
+    }
+}
+
+impl WasiMonotonicClock for VirtualClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+    fn now(&self, _precision: Duration) -> Instant {
+        self.inner.instant_base + self.elapsed()
+    }
+}
+
+/// A `WasiSched` which never blocks, and instead advances a [`VirtualClock`].
+///
+/// There is nothing to wait on in virtual time, so file subscriptions are
+/// always considered ready: reads report however many bytes the file says are
+/// ready, and writes complete immediately. When no file is subscribed to,
+/// the clock is advanced to the earliest clock deadline, which completes that
+/// subscription and any others with the same deadline. Clock subscriptions
+/// must be made against the same `VirtualClock`.
+pub struct VirtualSched {
+    clock: VirtualClock,
+}
+
+impl VirtualSched {
+    pub fn new(clock: VirtualClock) -> Self {
+        VirtualSched { clock }
+    }
+}
+
+#[wiggle::async_trait]
+impl WasiSched for VirtualSched {
+    async fn poll_oneoff<'a>(&self, poll: &mut Poll<'a>) -> Result<(), Error> {
+        let mut ready = false;
+        for s in poll.rw_subscriptions() {
+            match s {
+                Subscription::Read(sub) => match sub.file.num_ready_bytes() {
+                    Ok(n) => sub.complete(n, RwEventFlags::empty()),
+                    Err(e) => sub.error(e),
+                },
+                Subscription::Write(sub) => sub.complete(0, RwEventFlags::empty()),
+                Subscription::MonotonicClock { .. } => unreachable!(),
+            }
+            ready = true;
+        }
+        if ready {
+            return Ok(());
+        }
+        if let Some(t) = poll.earliest_clock_deadline() {
+            self.clock.advance_to(t.deadline);
+            if t.result().is_none() {
+                return Err(Error::invalid_argument()
+                    .context("clock subscription is not on the scheduler's virtual clock"));
+            }
+        }
+        Ok(())
+    }
+    async fn sched_yield(&self) -> Result<(), Error> {
+        Ok(())
+    }
+    async fn sleep(&self, duration: Duration) -> Result<(), Error> {
+        self.clock.advance(duration);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sched::SubscriptionResult;
+
+    #[test]
+    fn sleep_advances() {
+        let clock = VirtualClock::new();
+        let sched = clock.sched();
+        let start = WasiMonotonicClock::now(&clock, Duration::from_nanos(1));
+        wiggle::run_in_dummy_executor(sched.sleep(Duration::from_secs(3600)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(clock.elapsed(), Duration::from_secs(3600));
+        assert_eq!(
+            WasiMonotonicClock::now(&clock, Duration::from_nanos(1)).duration_since(start),
+            Duration::from_secs(3600)
+        );
+        assert_eq!(
+            WasiSystemClock::now(&clock, Duration::from_nanos(1)),
+            SystemTime::from_std(std::time::UNIX_EPOCH) + Duration::from_secs(3600)
+        );
+    }
+
+    #[test]
+    fn deadlines_in_order() {
+        let clock = VirtualClock::new();
+        let sched = clock.sched();
+        let start = WasiMonotonicClock::now(&clock, Duration::from_nanos(1));
+        let precision = Duration::from_nanos(1);
+
+        let mut poll = Poll::new();
+        poll.subscribe_monotonic_clock(
+            &clock,
+            start + Duration::from_secs(20),
+            precision,
+            2.into(),
+        );
+        poll.subscribe_monotonic_clock(
+            &clock,
+            start + Duration::from_secs(10),
+            precision,
+            1.into(),
+        );
+        poll.subscribe_monotonic_clock(
+            &clock,
+            start + Duration::from_secs(10),
+            precision,
+            3.into(),
+        );
+        wiggle::run_in_dummy_executor(sched.poll_oneoff(&mut poll))
+            .unwrap()
+            .unwrap();
+        assert_eq!(clock.elapsed(), Duration::from_secs(10));
+        let results = poll.results();
+        assert_eq!(results.len(), 2);
+        for (result, ud) in results {
+            assert!(matches!(result, SubscriptionResult::MonotonicClock(Ok(()))));
+            assert!(ud == 1.into() || ud == 3.into());
+        }
+
+        // A deadline already in the past doesn't move the clock backwards.
+        let mut poll = Poll::new();
+        poll.subscribe_monotonic_clock(&clock, start + Duration::from_secs(5), precision, 4.into());
+        wiggle::run_in_dummy_executor(sched.poll_oneoff(&mut poll))
+            .unwrap()
+            .unwrap();
+        assert_eq!(clock.elapsed(), Duration::from_secs(10));
+        assert_eq!(poll.results().len(), 1);
+    }
+
+    #[test]
+    fn other_clocks_are_rejected() {
+        let clock = VirtualClock::new();
+        let other = VirtualClock::new();
+        let sched = clock.sched();
+        let deadline =
+            WasiMonotonicClock::now(&other, Duration::from_nanos(1)) + Duration::from_secs(1);
+
+        let mut poll = Poll::new();
+        poll.subscribe_monotonic_clock(&other, deadline, Duration::from_nanos(1), 1.into());
+        let result = wiggle::run_in_dummy_executor(sched.poll_oneoff(&mut poll)).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -1,33 +1,141 @@
 //! Define the Rust interface a backend must implement in order to be used by
 //! this crate. the `Box<dyn ...>` types returned by these interfaces allow
 //! implementations to maintain backend-specific state between calls.
+//!
+//! Backends only see host memory: the guest's graph builders and tensors are
+//! copied out of (or borrowed from) the guest before being passed along.
 
-use crate::witx::types::{ExecutionTarget, GraphBuilderArray, Tensor};
+use std::path::Path;
 use thiserror::Error;
 use wiggle::GuestError;
 
 /// A [Backend] contains the necessary state to load [BackendGraph]s.
-pub(crate) trait Backend: Send + Sync {
+pub trait Backend: Send + Sync {
     fn name(&self) -> &str;
     fn load(
         &mut self,
-        builders: &GraphBuilderArray<'_>,
+        builders: &[&[u8]],
         target: ExecutionTarget,
     ) -> Result<Box<dyn BackendGraph>, BackendError>;
+
+    /// Load a graph which the host preloads from `path`, rather than one
+    /// passed in by the guest. By default `path` is a file holding the only
+    /// graph builder.
+    fn load_from_path(
+        &mut self,
+        path: &Path,
+        target: ExecutionTarget,
+    ) -> Result<Box<dyn BackendGraph>, BackendError> {
+        let builder = std::fs::read(path).map_err(anyhow::Error::from)?;
+        self.load(&[&builder], target)
+    }
 }
 
 /// A [BackendGraph] can create [BackendExecutionContext]s; this is the backing
-/// implementation for a [crate::witx::types::Graph].
-pub(crate) trait BackendGraph: Send + Sync {
-    fn init_execution_context(&mut self) -> Result<Box<dyn BackendExecutionContext>, BackendError>;
+/// implementation for a wasi-nn `graph`. A graph may be shared by many guests,
+/// each with their own execution contexts.
+pub trait BackendGraph: Send + Sync {
+    fn init_execution_context(&self) -> Result<Box<dyn BackendExecutionContext>, BackendError>;
 }
 
 /// A [BackendExecutionContext] performs the actual inference; this is the
-/// backing implementation for a [crate::witx::types::GraphExecutionContext].
-pub(crate) trait BackendExecutionContext: Send + Sync {
+/// backing implementation for a wasi-nn `graph-execution-context`.
+pub trait BackendExecutionContext: Send + Sync {
     fn set_input(&mut self, index: u32, tensor: &Tensor<'_>) -> Result<(), BackendError>;
     fn compute(&mut self) -> Result<(), BackendError>;
     fn get_output(&mut self, index: u32, destination: &mut [u8]) -> Result<u32, BackendError>;
+
+    /// Get an output as a tensor the host owns, for the component-model
+    /// interface. By default the output is copied out with
+    /// [BackendExecutionContext::get_output] and described as a flat `u8`
+    /// tensor, since that's all a backend reports there.
+    fn get_output_tensor(&mut self, index: u32) -> Result<OwnedTensor, BackendError> {
+        let mut data = vec![0; 4096];
+        loop {
+            match self.get_output(index, &mut data) {
+                Ok(size) => {
+                    data.truncate(size as usize);
+                    return Ok(OwnedTensor {
+                        dimensions: vec![size],
+                        type_: TensorType::U8,
+                        data,
+                    });
+                }
+                Err(BackendError::NotEnoughMemory(size)) if size > data.len() => {
+                    data.resize(size, 0)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// The format of a graph, which decides the [Backend] that loads it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GraphEncoding {
+    Openvino,
+    Onnx,
+    Tensorflow,
+    Pytorch,
+    Tensorflowlite,
+}
+
+impl std::str::FromStr for GraphEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "openvino" => GraphEncoding::Openvino,
+            "onnx" => GraphEncoding::Onnx,
+            "tensorflow" => GraphEncoding::Tensorflow,
+            "pytorch" => GraphEncoding::Pytorch,
+            "tensorflowlite" => GraphEncoding::Tensorflowlite,
+            _ => anyhow::bail!("unknown graph encoding: {s}"),
+        })
+    }
+}
+
+/// Where a graph should be executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExecutionTarget {
+    Cpu,
+    Gpu,
+    Tpu,
+}
+
+/// The type of the elements in a [Tensor].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TensorType {
+    F16,
+    F32,
+    U8,
+    I32,
+}
+
+/// An input tensor, with its elements stored as little-endian bytes.
+#[derive(Clone, Copy, Debug)]
+pub struct Tensor<'a> {
+    pub dimensions: &'a [u32],
+    pub type_: TensorType,
+    pub data: &'a [u8],
+}
+
+/// A tensor which owns its dimensions and data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedTensor {
+    pub dimensions: Vec<u32>,
+    pub type_: TensorType,
+    pub data: Vec<u8>,
+}
+
+impl OwnedTensor {
+    pub fn as_tensor(&self) -> Tensor<'_> {
+        Tensor {
+            dimensions: &self.dimensions,
+            type_: self.type_,
+            data: &self.data,
+        }
+    }
 }
 
 /// Errors returned by a backend; [BackendError::BackendAccess] is a catch-all
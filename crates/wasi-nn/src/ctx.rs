@@ -1,36 +1,105 @@
 //! Implements the base structure (i.e. [WasiNnCtx]) that will provide the
 //! implementation of the wasi-nn API.
-use crate::api::{Backend, BackendError, BackendExecutionContext, BackendGraph};
+use crate::api::{
+    Backend, BackendError, BackendExecutionContext, BackendGraph, ExecutionTarget, GraphEncoding,
+};
 use crate::openvino::OpenvinoBackend;
 use crate::r#impl::UsageError;
-use crate::witx::types::{Graph, GraphEncoding, GraphExecutionContext};
+use crate::registry::GraphRegistry;
+use crate::witx::types::{Graph, GraphExecutionContext};
+use anyhow::anyhow;
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::path::Path;
+use std::sync::Arc;
 use thiserror::Error;
 use wiggle::GuestError;
 
 /// Capture the state necessary for calling into the backend ML libraries.
 pub struct WasiNnCtx {
-    pub(crate) backends: HashMap<u8, Box<dyn Backend>>,
-    pub(crate) graphs: Table<Graph, Box<dyn BackendGraph>>,
+    pub(crate) backends: HashMap<GraphEncoding, Box<dyn Backend>>,
+    pub(crate) registry: Arc<GraphRegistry>,
+    pub(crate) graphs: Table<Graph, Arc<dyn BackendGraph>>,
     pub(crate) executions: Table<GraphExecutionContext, Box<dyn BackendExecutionContext>>,
 }
 
 impl WasiNnCtx {
-    /// Make a new context from the default state.
+    /// Make a new context from the default state, in which OpenVINO is the
+    /// only backend.
     pub fn new() -> WasiNnResult<Self> {
-        let mut backends = HashMap::new();
-        backends.insert(
-            // This is necessary because Wiggle's variant types do not derive
-            // `Hash` and `Eq`.
-            GraphEncoding::Openvino.into(),
-            Box::new(OpenvinoBackend::default()) as Box<dyn Backend>,
-        );
-        Ok(Self {
-            backends,
+        Ok(WasiNnCtxBuilder::new()
+            .backend(GraphEncoding::Openvino, OpenvinoBackend::default())
+            .build())
+    }
+
+    /// Start building a context with no backends.
+    pub fn builder() -> WasiNnCtxBuilder {
+        WasiNnCtxBuilder::new()
+    }
+}
+
+/// Build a [WasiNnCtx] from the backends the embedder chooses.
+#[derive(Default)]
+pub struct WasiNnCtxBuilder {
+    backends: HashMap<GraphEncoding, Box<dyn Backend>>,
+    registry: Arc<GraphRegistry>,
+}
+
+impl WasiNnCtxBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `backend` to load graphs with the given `encoding`, replacing any
+    /// backend previously registered for it.
+    pub fn backend(
+        &mut self,
+        encoding: GraphEncoding,
+        backend: impl Backend + 'static,
+    ) -> &mut Self {
+        self.backends.insert(encoding, Box::new(backend));
+        self
+    }
+
+    /// Use `registry` for the graphs guests can load by name. Sharing one
+    /// registry between contexts shares its graphs, rather than loading them
+    /// for each context.
+    pub fn graphs(&mut self, registry: Arc<GraphRegistry>) -> &mut Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Load the graph at `path` with the backend registered for `encoding`,
+    /// for guests to load by `name`.
+    ///
+    /// The graph is loaded to execute on the CPU; use
+    /// [`GraphRegistry::preload`] with a shared registry for other targets.
+    pub fn preload(
+        &mut self,
+        name: impl Into<String>,
+        encoding: GraphEncoding,
+        path: impl AsRef<Path>,
+    ) -> Result<&mut Self, BackendError> {
+        let backend = self
+            .backends
+            .get_mut(&encoding)
+            .ok_or_else(|| anyhow!("no backend is registered for {encoding:?}"))?;
+        Arc::make_mut(&mut self.registry).preload(
+            name,
+            backend.as_mut(),
+            path,
+            ExecutionTarget::Cpu,
+        )?;
+        Ok(self)
+    }
+
+    pub fn build(&mut self) -> WasiNnCtx {
+        WasiNnCtx {
+            backends: std::mem::take(&mut self.backends),
+            registry: std::mem::take(&mut self.registry),
             graphs: Table::default(),
             executions: Table::default(),
-        })
+        }
     }
 }
 
@@ -91,4 +160,51 @@ mod test {
     fn instantiate() {
         WasiNnCtx::new().unwrap();
     }
+
+    #[test]
+    fn register_backends() {
+        let ctx = WasiNnCtx::builder()
+            .backend(GraphEncoding::Onnx, crate::dense::DenseBackend)
+            .backend(GraphEncoding::Openvino, OpenvinoBackend::default())
+            .build();
+        assert_eq!(ctx.backends.len(), 2);
+        assert_eq!(ctx.backends[&GraphEncoding::Onnx].name(), "dense");
+    }
+
+    #[test]
+    fn preload() {
+        // A single 1x1 layer which doubles its input.
+        let graph = [1u32, 1, 1, 0]
+            .iter()
+            .flat_map(|n| n.to_le_bytes())
+            .chain(2.0f32.to_le_bytes())
+            .chain(0.0f32.to_le_bytes())
+            .collect::<Vec<_>>();
+        let path = std::env::temp_dir().join(format!("wasi-nn-preload-{}", std::process::id()));
+        std::fs::write(&path, graph).unwrap();
+
+        let mut builder = WasiNnCtx::builder();
+        builder
+            .backend(GraphEncoding::Onnx, crate::dense::DenseBackend)
+            .preload("double", GraphEncoding::Onnx, &path)
+            .unwrap();
+        assert!(builder
+            .preload("double", GraphEncoding::Openvino, &path)
+            .is_err());
+        let mut ctx = builder.build();
+        std::fs::remove_file(&path).unwrap();
+
+        let first = ctx.load_by_name("double").unwrap();
+        let second = ctx.load_by_name("double").unwrap();
+        assert_ne!(first, second);
+        assert!(ctx.load_by_name("triple").is_err());
+
+        // Another context sharing the registry shares the loaded graph.
+        let mut other = WasiNnCtx::builder().graphs(ctx.registry.clone()).build();
+        let third = other.load_by_name("double").unwrap();
+        let graph = |ctx: &mut WasiNnCtx, graph| {
+            Arc::as_ptr(ctx.graphs.get_mut(graph).unwrap()) as *const ()
+        };
+        assert_eq!(graph(&mut ctx, first), graph(&mut other, third));
+    }
 }
@@ -0,0 +1,359 @@
+//! A pure-Rust reference backend for small, fully-connected networks, which
+//! allows wasi-nn to be used and tested without any ML library installed.
+//!
+//! A graph is loaded from a single builder holding a sequence of layers, with
+//! every number stored little-endian:
+//!
+//! ```text
+//! layers: u32
+//! repeated `layers` times:
+//!     inputs: u32
+//!     outputs: u32
+//!     activation: u32 (0 = identity, 1 = ReLU, 2 = sigmoid, 3 = softmax)
+//!     weights: [f32; inputs * outputs] (row-major, one row per output)
+//!     biases: [f32; outputs]
+//! ```
+//!
+//! Graphs take a single `f32` input tensor, which is flattened, and produce a
+//! single `f32` output tensor.
+
+use crate::api::{
+    Backend, BackendError, BackendExecutionContext, BackendGraph, ExecutionTarget, OwnedTensor,
+    Tensor, TensorType,
+};
+use anyhow::{anyhow, bail};
+use std::sync::Arc;
+
+/// A [Backend] for simple dense networks; see the [module
+/// documentation](self) for the format it loads.
+///
+/// wasi-nn has no graph encoding for this format, so embedders register it
+/// under whichever [`GraphEncoding`](crate::GraphEncoding) their guests use
+/// for it.
+#[derive(Default)]
+pub struct DenseBackend;
+
+impl Backend for DenseBackend {
+    fn name(&self) -> &str {
+        "dense"
+    }
+
+    fn load(
+        &mut self,
+        builders: &[&[u8]],
+        target: ExecutionTarget,
+    ) -> Result<Box<dyn BackendGraph>, BackendError> {
+        if builders.len() != 1 {
+            return Err(BackendError::InvalidNumberOfBuilders(
+                1,
+                builders.len() as u32,
+            ));
+        }
+        if target != ExecutionTarget::Cpu {
+            return Err(anyhow!("the dense backend can only execute on the CPU").into());
+        }
+        let layers = parse(builders[0])?;
+        Ok(Box::new(DenseGraph(Arc::new(layers))))
+    }
+}
+
+/// A fully-connected layer.
+struct Layer {
+    inputs: usize,
+    outputs: usize,
+    activation: Activation,
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+}
+
+#[derive(Clone, Copy)]
+enum Activation {
+    Identity,
+    Relu,
+    Sigmoid,
+    Softmax,
+}
+
+impl Layer {
+    fn apply(&self, input: &[f32]) -> Vec<f32> {
+        let mut output = self
+            .weights
+            .chunks_exact(self.inputs)
+            .zip(&self.biases)
+            .map(|(row, bias)| row.iter().zip(input).map(|(w, x)| w * x).sum::<f32>() + bias)
+            .collect::<Vec<_>>();
+        match self.activation {
+            Activation::Identity => {}
+            Activation::Relu => output.iter_mut().for_each(|x| *x = x.max(0.0)),
+            Activation::Sigmoid => output
+                .iter_mut()
+                .for_each(|x| *x = 1.0 / (1.0 + (-*x).exp())),
+            Activation::Softmax => {
+                let max = output.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                output.iter_mut().for_each(|x| *x = (*x - max).exp());
+                let sum = output.iter().sum::<f32>();
+                output.iter_mut().for_each(|x| *x /= sum);
+            }
+        }
+        output
+    }
+}
+
+fn parse(mut bytes: &[u8]) -> anyhow::Result<Vec<Layer>> {
+    fn read_u32(bytes: &mut &[u8]) -> anyhow::Result<u32> {
+        if bytes.len() < 4 {
+            bail!("unexpected end of the dense graph");
+        }
+        let (n, rest) = bytes.split_at(4);
+        *bytes = rest;
+        Ok(u32::from_le_bytes(n.try_into().unwrap()))
+    }
+    fn read_f32s(bytes: &mut &[u8], len: usize) -> anyhow::Result<Vec<f32>> {
+        let size = len
+            .checked_mul(4)
+            .filter(|size| *size <= bytes.len())
+            .ok_or_else(|| anyhow!("unexpected end of the dense graph"))?;
+        let (floats, rest) = bytes.split_at(size);
+        *bytes = rest;
+        Ok(floats
+            .chunks_exact(4)
+            .map(|f| f32::from_le_bytes(f.try_into().unwrap()))
+            .collect())
+    }
+
+    let mut layers = Vec::<Layer>::new();
+    for _ in 0..read_u32(&mut bytes)? {
+        let inputs = read_u32(&mut bytes)? as usize;
+        let outputs = read_u32(&mut bytes)? as usize;
+        if inputs == 0 || outputs == 0 {
+            bail!("dense layers must have at least one input and output");
+        }
+        if let Some(previous) = layers.last() {
+            if previous.outputs != inputs {
+                bail!(
+                    "a layer with {inputs} inputs can't follow one with {} outputs",
+                    previous.outputs
+                );
+            }
+        }
+        let activation = match read_u32(&mut bytes)? {
+            0 => Activation::Identity,
+            1 => Activation::Relu,
+            2 => Activation::Sigmoid,
+            3 => Activation::Softmax,
+            n => bail!("unknown activation function: {n}"),
+        };
+        let weights = inputs
+            .checked_mul(outputs)
+            .ok_or_else(|| anyhow!("dense layer is too large"))?;
+        let weights = read_f32s(&mut bytes, weights)?;
+        let biases = read_f32s(&mut bytes, outputs)?;
+        layers.push(Layer {
+            inputs,
+            outputs,
+            activation,
+            weights,
+            biases,
+        });
+    }
+    if layers.is_empty() {
+        bail!("dense graphs must have at least one layer");
+    }
+    if !bytes.is_empty() {
+        bail!("unexpected trailing bytes after the dense graph");
+    }
+    Ok(layers)
+}
+
+struct DenseGraph(Arc<Vec<Layer>>);
+
+impl BackendGraph for DenseGraph {
+    fn init_execution_context(&self) -> Result<Box<dyn BackendExecutionContext>, BackendError> {
+        Ok(Box::new(DenseExecutionContext {
+            layers: self.0.clone(),
+            input: None,
+            output: None,
+        }))
+    }
+}
+
+struct DenseExecutionContext {
+    layers: Arc<Vec<Layer>>,
+    input: Option<Vec<f32>>,
+    output: Option<Vec<f32>>,
+}
+
+impl BackendExecutionContext for DenseExecutionContext {
+    fn set_input(&mut self, index: u32, tensor: &Tensor<'_>) -> Result<(), BackendError> {
+        if index != 0 {
+            return Err(anyhow!("dense graphs have a single input, not {index}").into());
+        }
+        if tensor.type_ != TensorType::F32 {
+            return Err(anyhow!("dense graphs take f32 tensors, not {:?}", tensor.type_).into());
+        }
+        let expected = self.layers[0].inputs;
+        let len = tensor
+            .dimensions
+            .iter()
+            .try_fold(1usize, |len, d| len.checked_mul(*d as usize));
+        if len != Some(expected) || tensor.data.len() != expected * 4 {
+            return Err(anyhow!(
+                "the input tensor {:?} doesn't have the {expected} elements the graph expects",
+                tensor.dimensions
+            )
+            .into());
+        }
+        self.input = Some(
+            tensor
+                .data
+                .chunks_exact(4)
+                .map(|f| f32::from_le_bytes(f.try_into().unwrap()))
+                .collect(),
+        );
+        Ok(())
+    }
+
+    fn compute(&mut self) -> Result<(), BackendError> {
+        let input = self
+            .input
+            .as_ref()
+            .ok_or_else(|| anyhow!("the input tensor hasn't been set"))?;
+        let output = self
+            .layers
+            .iter()
+            .fold(input.clone(), |values, layer| layer.apply(&values));
+        self.output = Some(output);
+        Ok(())
+    }
+
+    fn get_output(&mut self, index: u32, destination: &mut [u8]) -> Result<u32, BackendError> {
+        let output = self.output(index)?;
+        let size = output.len() * 4;
+        if size > destination.len() {
+            return Err(BackendError::NotEnoughMemory(size));
+        }
+        for (dst, x) in destination.chunks_exact_mut(4).zip(output) {
+            dst.copy_from_slice(&x.to_le_bytes());
+        }
+        Ok(size as u32)
+    }
+
+    fn get_output_tensor(&mut self, index: u32) -> Result<OwnedTensor, BackendError> {
+        let output = self.output(index)?;
+        Ok(OwnedTensor {
+            dimensions: vec![output.len() as u32],
+            type_: TensorType::F32,
+            data: output.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        })
+    }
+}
+
+impl DenseExecutionContext {
+    fn output(&self, index: u32) -> Result<&[f32], BackendError> {
+        if index != 0 {
+            return Err(anyhow!("dense graphs have a single output, not {index}").into());
+        }
+        Ok(self
+            .output
+            .as_deref()
+            .ok_or_else(|| anyhow!("the graph hasn't been computed"))?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn graph(layers: &[(u32, u32, u32, &[f32], &[f32])]) -> Vec<u8> {
+        let mut bytes = (layers.len() as u32).to_le_bytes().to_vec();
+        for (inputs, outputs, activation, weights, biases) in layers {
+            bytes.extend_from_slice(&inputs.to_le_bytes());
+            bytes.extend_from_slice(&outputs.to_le_bytes());
+            bytes.extend_from_slice(&activation.to_le_bytes());
+            for x in weights.iter().chain(biases.iter()) {
+                bytes.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    fn run(graph: &[u8], input: &[f32]) -> Result<Vec<f32>, BackendError> {
+        let graph = DenseBackend.load(&[graph], ExecutionTarget::Cpu)?;
+        let mut context = graph.init_execution_context()?;
+        let data = input
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        context.set_input(
+            0,
+            &Tensor {
+                dimensions: &[1, input.len() as u32],
+                type_: TensorType::F32,
+                data: &data,
+            },
+        )?;
+        context.compute()?;
+        let mut output = vec![0; 64];
+        let size = context.get_output(0, &mut output)? as usize;
+        Ok(output[..size]
+            .chunks_exact(4)
+            .map(|f| f32::from_le_bytes(f.try_into().unwrap()))
+            .collect())
+    }
+
+    #[test]
+    fn two_layers() {
+        let graph = graph(&[
+            (2, 2, 1, &[1.0, -1.0, 2.0, 0.5], &[0.0, -1.0]),
+            (2, 1, 0, &[3.0, 1.0], &[0.5]),
+        ]);
+        // relu([1 - 2, 2 + 1 - 1]) = [0, 2], then 3 * 0 + 2 + 0.5 = 2.5
+        assert_eq!(run(&graph, &[1.0, 2.0]).unwrap(), [2.5]);
+    }
+
+    #[test]
+    fn softmax() {
+        let graph = graph(&[(1, 2, 3, &[1.0, 1.0], &[0.0, 0.0])]);
+        assert_eq!(run(&graph, &[3.0]).unwrap(), [0.5, 0.5]);
+    }
+
+    #[test]
+    fn output_tensor() {
+        let graph = graph(&[(1, 2, 0, &[1.0, -1.0], &[0.0, 0.0])]);
+        let graph = DenseBackend.load(&[&graph], ExecutionTarget::Cpu).unwrap();
+        let mut context = graph.init_execution_context().unwrap();
+        assert!(context.get_output_tensor(0).is_err());
+        let input = OwnedTensor {
+            dimensions: vec![1],
+            type_: TensorType::F32,
+            data: 2.0f32.to_le_bytes().to_vec(),
+        };
+        context.set_input(0, &input.as_tensor()).unwrap();
+        context.compute().unwrap();
+        let output = context.get_output_tensor(0).unwrap();
+        assert_eq!(output.dimensions, [2]);
+        assert_eq!(output.type_, TensorType::F32);
+        assert_eq!(
+            output.data,
+            [2.0f32, -2.0]
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<_>>()
+        );
+        assert!(context.get_output_tensor(1).is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(DenseBackend.load(&[&[]], ExecutionTarget::Cpu).is_err());
+        let mismatched = graph(&[
+            (1, 2, 0, &[1.0, 1.0], &[0.0, 0.0]),
+            (3, 1, 0, &[1.0, 1.0, 1.0], &[0.0]),
+        ]);
+        assert!(DenseBackend
+            .load(&[&mismatched], ExecutionTarget::Cpu)
+            .is_err());
+        let graph = graph(&[(2, 1, 0, &[1.0, 1.0], &[0.0])]);
+        assert!(run(&graph, &[1.0]).is_err());
+    }
+}
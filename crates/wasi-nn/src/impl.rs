@@ -1,4 +1,5 @@
 //! Implements the wasi-nn API.
+use crate::api;
 use crate::ctx::WasiNnResult as Result;
 use crate::witx::types::{
     ExecutionTarget, Graph, GraphBuilderArray, GraphEncoding, GraphExecutionContext, Tensor,
@@ -6,13 +7,15 @@ use crate::witx::types::{
 use crate::witx::wasi_ephemeral_nn::WasiEphemeralNn;
 use crate::WasiNnCtx;
 use thiserror::Error;
-use wiggle::GuestPtr;
+use wiggle::{GuestError, GuestPtr};
+
+const SHARED_MEMORY_UNSUPPORTED: &str = "cannot use with shared memories; see https://github.com/bytecodealliance/wasmtime/issues/5235 (TODO)";
 
 #[derive(Debug, Error)]
 pub enum UsageError {
     #[error("Invalid context; has the load function been called?")]
     InvalidContext,
-    #[error("Only OpenVINO's IR is currently supported, passed encoding: {0:?}")]
+    #[error("No backend is registered for the passed encoding: {0:?}")]
     InvalidEncoding(GraphEncoding),
     #[error("OpenVINO expects only two buffers (i.e. [ir, weights]), passed: {0}")]
     InvalidNumberOfBuilders(u32),
@@ -22,6 +25,20 @@ pub enum UsageError {
     InvalidExecutionContextHandle,
     #[error("Not enough memory to copy tensor data of size: {0}")]
     NotEnoughMemory(u32),
+    #[error("No graph was preloaded with the name: {0}")]
+    NotFound(String),
+}
+
+impl WasiNnCtx {
+    /// Get a handle to the graph the host preloaded as `name`. This isn't part
+    /// of the witx definition of wasi-nn, so it's linked in by hand.
+    pub(crate) fn load_by_name(&mut self, name: &str) -> Result<Graph> {
+        let graph = match self.registry.get(name) {
+            Some(graph) => graph.clone(),
+            None => return Err(UsageError::NotFound(name.to_string()).into()),
+        };
+        Ok(self.graphs.insert(graph))
+    }
 }
 
 impl<'a> WasiEphemeralNn for WasiNnCtx {
@@ -31,13 +48,24 @@ impl<'a> WasiEphemeralNn for WasiNnCtx {
         encoding: GraphEncoding,
         target: ExecutionTarget,
     ) -> Result<Graph> {
-        let encoding_id: u8 = encoding.into();
-        let graph = if let Some(backend) = self.backends.get_mut(&encoding_id) {
-            backend.load(builders, target)?
+        let backend_encoding: api::GraphEncoding = encoding.into();
+        let graph = if let Some(backend) = self.backends.get_mut(&backend_encoding) {
+            // Borrow each of the guest's buffers for the backend to read.
+            let builders = builders
+                .iter()
+                .map(|builder| {
+                    Ok(builder?
+                        .read()?
+                        .as_slice()?
+                        .expect(SHARED_MEMORY_UNSUPPORTED))
+                })
+                .collect::<std::result::Result<Vec<_>, GuestError>>()?;
+            let builders = builders.iter().map(|b| &**b).collect::<Vec<_>>();
+            backend.load(&builders, target.into())?
         } else {
             return Err(UsageError::InvalidEncoding(encoding).into());
         };
-        let graph_id = self.graphs.insert(graph);
+        let graph_id = self.graphs.insert(graph.into());
         Ok(graph_id)
     }
 
@@ -59,7 +87,17 @@ impl<'a> WasiEphemeralNn for WasiNnCtx {
         tensor: &Tensor<'b>,
     ) -> Result<()> {
         if let Some(exec_context) = self.executions.get_mut(exec_context_id) {
-            Ok(exec_context.set_input(index, tensor)?)
+            let dimensions = tensor
+                .dimensions
+                .as_slice()?
+                .expect(SHARED_MEMORY_UNSUPPORTED);
+            let data = tensor.data.as_slice()?.expect(SHARED_MEMORY_UNSUPPORTED);
+            let tensor = api::Tensor {
+                dimensions: &dimensions,
+                type_: tensor.type_.into(),
+                data: &data,
+            };
+            Ok(exec_context.set_input(index, &tensor)?)
         } else {
             Err(UsageError::InvalidGraphHandle.into())
         }
@@ -84,7 +122,7 @@ impl<'a> WasiEphemeralNn for WasiNnCtx {
             let mut destination = out_buffer
                 .as_array(out_buffer_max_size)
                 .as_slice_mut()?
-                .expect("cannot use with shared memories; see https://github.com/bytecodealliance/wasmtime/issues/5235 (TODO)");
+                .expect(SHARED_MEMORY_UNSUPPORTED);
             Ok(exec_context.get_output(index, &mut destination)?)
         } else {
             Err(UsageError::InvalidGraphHandle.into())
@@ -1,8 +1,21 @@
 mod api;
 mod ctx;
+mod dense;
 mod r#impl;
 mod openvino;
+mod registry;
+#[cfg(feature = "component-model")]
+mod wit;
 mod witx;
 
-pub use ctx::WasiNnCtx;
-pub use witx::wasi_ephemeral_nn::add_to_linker;
+pub use api::{
+    Backend, BackendError, BackendExecutionContext, BackendGraph, ExecutionTarget, GraphEncoding,
+    OwnedTensor, Tensor, TensorType,
+};
+pub use ctx::{WasiNnCtx, WasiNnCtxBuilder};
+pub use dense::DenseBackend;
+pub use openvino::OpenvinoBackend;
+pub use registry::GraphRegistry;
+#[cfg(feature = "component-model")]
+pub use wit::{add_to_component_linker, WasiNnView};
+pub use witx::add_to_linker;
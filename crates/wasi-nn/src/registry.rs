@@ -0,0 +1,54 @@
+//! Implements the registry of graphs which the host loads ahead of time, so
+//! that guests can refer to them by name rather than passing their bytes in.
+
+use crate::api::{Backend, BackendError, BackendGraph, ExecutionTarget};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Graphs preloaded by the host, which guests get handles to with
+/// `load_by_name`.
+///
+/// A registry can be shared by many [WasiNnCtx](crate::WasiNnCtx)s, in which
+/// case each graph is only loaded once for all of them.
+#[derive(Clone, Default)]
+pub struct GraphRegistry {
+    graphs: HashMap<String, Arc<dyn BackendGraph>>,
+}
+
+impl GraphRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the graph at `path` with `backend` and register it as `name`,
+    /// replacing any graph previously registered with that name.
+    pub fn preload(
+        &mut self,
+        name: impl Into<String>,
+        backend: &mut dyn Backend,
+        path: impl AsRef<Path>,
+        target: ExecutionTarget,
+    ) -> Result<&mut Self, BackendError> {
+        let graph = backend.load_from_path(path.as_ref(), target)?;
+        Ok(self.insert(name, graph))
+    }
+
+    /// Register an already-loaded graph as `name`.
+    pub fn insert(&mut self, name: impl Into<String>, graph: Box<dyn BackendGraph>) -> &mut Self {
+        self.graphs.insert(name.into(), Arc::from(graph));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn BackendGraph>> {
+        self.graphs.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.graphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.graphs.is_empty()
+    }
+}
@@ -0,0 +1,372 @@
+//! Implements the component-model version of wasi-nn, from `wit/wasi-nn.wit`.
+//!
+//! Graphs, execution contexts and tensors are resources in the same preview2
+//! [Table] as the rest of WASI, so wasi-nn can be linked into a component
+//! alongside `wasmtime_wasi::preview2::command::add_to_linker`.
+use crate::api::{self, BackendError, BackendExecutionContext, BackendGraph, OwnedTensor};
+use crate::ctx::WasiNnCtx;
+use std::sync::Arc;
+use wasmtime_wasi::preview2::Table;
+
+wasmtime::component::bindgen!({
+    path: "wit",
+    world: "ml",
+});
+
+use self::wasi::nn::{errors, graph, inference, tensor};
+
+/// The state wasi-nn needs from a component's store: the resource table it
+/// shares with the rest of WASI, and the context holding its backends.
+pub trait WasiNnView: Send {
+    fn table(&self) -> &Table;
+    fn table_mut(&mut self) -> &mut Table;
+    fn ctx(&self) -> &WasiNnCtx;
+    fn ctx_mut(&mut self) -> &mut WasiNnCtx;
+}
+
+/// Add the wasi-nn interfaces to a component linker.
+pub fn add_to_component_linker<T: WasiNnView>(
+    linker: &mut wasmtime::component::Linker<T>,
+) -> anyhow::Result<()> {
+    errors::add_to_linker(linker, |t| t)?;
+    tensor::add_to_linker(linker, |t| t)?;
+    graph::add_to_linker(linker, |t| t)?;
+    inference::add_to_linker(linker, |t| t)?;
+    Ok(())
+}
+
+/// The table entry for a `graph` handle.
+struct GraphEntry(Arc<dyn BackendGraph>);
+
+/// The table entry for a `graph-execution-context` handle.
+struct ExecutionContextEntry(Box<dyn BackendExecutionContext>);
+
+/// The table entry for a `tensor` handle. Tensors are shared with the
+/// execution contexts they're passed to, rather than copied.
+struct TensorEntry(Arc<OwnedTensor>);
+
+impl<T: WasiNnView> errors::Host for T {}
+
+impl<T: WasiNnView> tensor::Host for T {
+    fn new_tensor(
+        &mut self,
+        dimensions: tensor::TensorDimensions,
+        ty: tensor::TensorType,
+        data: tensor::TensorData,
+    ) -> anyhow::Result<tensor::Tensor> {
+        let tensor = OwnedTensor {
+            dimensions,
+            type_: ty.into(),
+            data,
+        };
+        Ok(self
+            .table_mut()
+            .push(Box::new(TensorEntry(Arc::new(tensor))))?)
+    }
+
+    fn dimensions(&mut self, this: tensor::Tensor) -> anyhow::Result<tensor::TensorDimensions> {
+        let TensorEntry(tensor) = self.table().get(this)?;
+        Ok(tensor.dimensions.clone())
+    }
+
+    fn ty(&mut self, this: tensor::Tensor) -> anyhow::Result<tensor::TensorType> {
+        let TensorEntry(tensor) = self.table().get(this)?;
+        Ok(tensor.type_.into())
+    }
+
+    fn data(&mut self, this: tensor::Tensor) -> anyhow::Result<tensor::TensorData> {
+        let TensorEntry(tensor) = self.table().get(this)?;
+        Ok(tensor.data.clone())
+    }
+
+    fn drop_tensor(&mut self, this: tensor::Tensor) -> anyhow::Result<()> {
+        self.table_mut().delete::<TensorEntry>(this)?;
+        Ok(())
+    }
+}
+
+impl<T: WasiNnView> graph::Host for T {
+    fn load(
+        &mut self,
+        builders: Vec<graph::GraphBuilder>,
+        encoding: graph::GraphEncoding,
+        target: graph::ExecutionTarget,
+    ) -> anyhow::Result<Result<graph::Graph, errors::Error>> {
+        let encoding = api::GraphEncoding::from(encoding);
+        let backend = match self.ctx_mut().backends.get_mut(&encoding) {
+            Some(backend) => backend,
+            None => return Ok(Err(errors::Error::InvalidEncoding)),
+        };
+        let builders = builders.iter().map(|b| b.as_slice()).collect::<Vec<_>>();
+        let graph = match backend.load(&builders, target.into()) {
+            Ok(graph) => graph,
+            Err(e) => return Ok(Err(e.into())),
+        };
+        Ok(Ok(self
+            .table_mut()
+            .push(Box::new(GraphEntry(graph.into())))?))
+    }
+
+    fn load_by_name(
+        &mut self,
+        name: String,
+    ) -> anyhow::Result<Result<graph::Graph, errors::Error>> {
+        let graph = match self.ctx().registry.get(&name) {
+            Some(graph) => graph.clone(),
+            None => return Ok(Err(errors::Error::NotFound)),
+        };
+        Ok(Ok(self.table_mut().push(Box::new(GraphEntry(graph)))?))
+    }
+
+    fn init_execution_context(
+        &mut self,
+        this: graph::Graph,
+    ) -> anyhow::Result<Result<inference::GraphExecutionContext, errors::Error>> {
+        let GraphEntry(graph) = self.table().get(this)?;
+        let context = match graph.init_execution_context() {
+            Ok(context) => context,
+            Err(e) => return Ok(Err(e.into())),
+        };
+        Ok(Ok(self
+            .table_mut()
+            .push(Box::new(ExecutionContextEntry(context)))?))
+    }
+
+    fn drop_graph(&mut self, this: graph::Graph) -> anyhow::Result<()> {
+        self.table_mut().delete::<GraphEntry>(this)?;
+        Ok(())
+    }
+}
+
+impl<T: WasiNnView> inference::Host for T {
+    fn set_input(
+        &mut self,
+        this: inference::GraphExecutionContext,
+        index: u32,
+        tensor: tensor::Tensor,
+    ) -> anyhow::Result<Result<(), errors::Error>> {
+        let TensorEntry(input) = self.table().get(tensor)?;
+        let input = input.clone();
+        let ExecutionContextEntry(context) = self.table_mut().get_mut(this)?;
+        Ok(context
+            .set_input(index, &input.as_tensor())
+            .map_err(Into::into))
+    }
+
+    fn compute(
+        &mut self,
+        this: inference::GraphExecutionContext,
+    ) -> anyhow::Result<Result<(), errors::Error>> {
+        let ExecutionContextEntry(context) = self.table_mut().get_mut(this)?;
+        Ok(context.compute().map_err(Into::into))
+    }
+
+    fn get_output(
+        &mut self,
+        this: inference::GraphExecutionContext,
+        index: u32,
+    ) -> anyhow::Result<Result<tensor::Tensor, errors::Error>> {
+        let ExecutionContextEntry(context) = self.table_mut().get_mut(this)?;
+        let output = match context.get_output_tensor(index) {
+            Ok(output) => output,
+            Err(e) => return Ok(Err(e.into())),
+        };
+        Ok(Ok(self
+            .table_mut()
+            .push(Box::new(TensorEntry(Arc::new(output))))?))
+    }
+
+    fn drop_graph_execution_context(
+        &mut self,
+        this: inference::GraphExecutionContext,
+    ) -> anyhow::Result<()> {
+        self.table_mut().delete::<ExecutionContextEntry>(this)?;
+        Ok(())
+    }
+}
+
+impl From<BackendError> for errors::Error {
+    fn from(e: BackendError) -> Self {
+        match e {
+            BackendError::InvalidNumberOfBuilders(..) => errors::Error::InvalidArgument,
+            BackendError::BackendAccess(_)
+            | BackendError::GuestAccess(_)
+            | BackendError::NotEnoughMemory(_) => errors::Error::RuntimeError,
+        }
+    }
+}
+
+impl From<graph::GraphEncoding> for api::GraphEncoding {
+    fn from(value: graph::GraphEncoding) -> Self {
+        match value {
+            graph::GraphEncoding::Openvino => api::GraphEncoding::Openvino,
+            graph::GraphEncoding::Onnx => api::GraphEncoding::Onnx,
+            graph::GraphEncoding::Tensorflow => api::GraphEncoding::Tensorflow,
+            graph::GraphEncoding::Pytorch => api::GraphEncoding::Pytorch,
+            graph::GraphEncoding::Tensorflowlite => api::GraphEncoding::Tensorflowlite,
+        }
+    }
+}
+
+impl From<graph::ExecutionTarget> for api::ExecutionTarget {
+    fn from(value: graph::ExecutionTarget) -> Self {
+        match value {
+            graph::ExecutionTarget::Cpu => api::ExecutionTarget::Cpu,
+            graph::ExecutionTarget::Gpu => api::ExecutionTarget::Gpu,
+            graph::ExecutionTarget::Tpu => api::ExecutionTarget::Tpu,
+        }
+    }
+}
+
+impl From<tensor::TensorType> for api::TensorType {
+    fn from(value: tensor::TensorType) -> Self {
+        match value {
+            tensor::TensorType::Fp16 => api::TensorType::F16,
+            tensor::TensorType::Fp32 => api::TensorType::F32,
+            tensor::TensorType::U8 => api::TensorType::U8,
+            tensor::TensorType::I32 => api::TensorType::I32,
+        }
+    }
+}
+
+impl From<api::TensorType> for tensor::TensorType {
+    fn from(value: api::TensorType) -> Self {
+        match value {
+            api::TensorType::F16 => tensor::TensorType::Fp16,
+            api::TensorType::F32 => tensor::TensorType::Fp32,
+            api::TensorType::U8 => tensor::TensorType::U8,
+            api::TensorType::I32 => tensor::TensorType::I32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::graph::Host as _;
+    use super::inference::Host as _;
+    use super::tensor::Host as _;
+    use super::*;
+    use crate::api::GraphEncoding;
+    use crate::DenseBackend;
+    use wasmtime_wasi::preview2::{self, WasiCtxBuilder};
+
+    struct Host {
+        table: Table,
+        wasi: preview2::WasiCtx,
+        wasi_nn: WasiNnCtx,
+    }
+
+    impl Host {
+        fn new() -> Self {
+            let mut table = Table::new();
+            let wasi = WasiCtxBuilder::new().build(&mut table).unwrap();
+            let wasi_nn = WasiNnCtx::builder()
+                .backend(GraphEncoding::Onnx, DenseBackend)
+                .build();
+            Host {
+                table,
+                wasi,
+                wasi_nn,
+            }
+        }
+    }
+
+    impl preview2::WasiView for Host {
+        fn table(&self) -> &Table {
+            &self.table
+        }
+        fn table_mut(&mut self) -> &mut Table {
+            &mut self.table
+        }
+        fn ctx(&self) -> &preview2::WasiCtx {
+            &self.wasi
+        }
+        fn ctx_mut(&mut self) -> &mut preview2::WasiCtx {
+            &mut self.wasi
+        }
+    }
+
+    impl WasiNnView for Host {
+        fn table(&self) -> &Table {
+            &self.table
+        }
+        fn table_mut(&mut self) -> &mut Table {
+            &mut self.table
+        }
+        fn ctx(&self) -> &WasiNnCtx {
+            &self.wasi_nn
+        }
+        fn ctx_mut(&mut self) -> &mut WasiNnCtx {
+            &mut self.wasi_nn
+        }
+    }
+
+    #[test]
+    fn link_with_preview2() {
+        let engine = wasmtime::Engine::default();
+        let mut linker = wasmtime::component::Linker::<Host>::new(&engine);
+        preview2::command::sync::add_to_linker(&mut linker).unwrap();
+        add_to_component_linker(&mut linker).unwrap();
+    }
+
+    #[test]
+    fn infer() {
+        let mut host = Host::new();
+
+        // A single 1x1 layer which doubles its input.
+        let builder = [1u32, 1, 1, 0]
+            .iter()
+            .flat_map(|n| n.to_le_bytes())
+            .chain(2.0f32.to_le_bytes())
+            .chain(0.0f32.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            host.load(
+                vec![builder.clone()],
+                graph::GraphEncoding::Openvino,
+                graph::ExecutionTarget::Cpu
+            )
+            .unwrap(),
+            Err(errors::Error::InvalidEncoding)
+        );
+        assert_eq!(
+            host.load_by_name("double".to_string()).unwrap(),
+            Err(errors::Error::NotFound)
+        );
+        let graph = host
+            .load(
+                vec![builder],
+                graph::GraphEncoding::Onnx,
+                graph::ExecutionTarget::Cpu,
+            )
+            .unwrap()
+            .unwrap();
+        let context = host.init_execution_context(graph).unwrap().unwrap();
+        host.drop_graph(graph).unwrap();
+
+        let input = host
+            .new_tensor(
+                vec![1],
+                tensor::TensorType::Fp32,
+                3.0f32.to_le_bytes().to_vec(),
+            )
+            .unwrap();
+        host.set_input(context, 0, input).unwrap().unwrap();
+        host.compute(context).unwrap().unwrap();
+        assert_eq!(
+            host.get_output(context, 1).unwrap(),
+            Err(errors::Error::RuntimeError)
+        );
+        let output = host.get_output(context, 0).unwrap().unwrap();
+        assert_eq!(host.dimensions(output).unwrap(), [1]);
+        assert_eq!(host.ty(output).unwrap(), tensor::TensorType::Fp32);
+        assert_eq!(host.data(output).unwrap(), 6.0f32.to_le_bytes());
+
+        // Handles belong to the preview2 table, so they're checked by type.
+        assert!(host.set_input(context, 0, context).is_err());
+        host.drop_tensor(input).unwrap();
+        host.drop_tensor(output).unwrap();
+        host.drop_graph_execution_context(context).unwrap();
+        assert!(host.compute(context).is_err());
+    }
+}
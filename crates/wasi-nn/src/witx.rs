@@ -1,6 +1,8 @@
 //! Contains the macro-generated implementation of wasi-nn from the its witx definition file.
+use crate::api;
 use crate::ctx::WasiNnCtx;
 use crate::ctx::WasiNnError;
+use crate::r#impl::UsageError;
 use anyhow::Result;
 
 // Generate the traits and types of wasi-nn in several Rust modules (e.g. `types`).
@@ -9,16 +11,69 @@ wiggle::from_witx!({
     errors: { nn_errno => WasiNnError }
 });
 
-use types::NnErrno;
+use types::{NnErrno, UserErrorConversion};
+
+/// Add the wasi-nn functions to the linker, including `load_by_name`, which
+/// gets a handle to a graph preloaded by the host.
+pub fn add_to_linker<T>(
+    linker: &mut wiggle::wasmtime_crate::Linker<T>,
+    get_cx: impl Fn(&mut T) -> &mut WasiNnCtx + Send + Sync + Copy + 'static,
+) -> Result<()> {
+    wasi_ephemeral_nn::add_to_linker(linker, get_cx)?;
+    linker.func_wrap(
+        "wasi_ephemeral_nn",
+        "load_by_name",
+        move |mut caller: wiggle::wasmtime_crate::Caller<'_, T>,
+              name: i32,
+              name_len: i32,
+              graph: i32|
+              -> Result<i32> {
+            let export = caller.get_export("memory");
+            let (mem, ctx) = match &export {
+                Some(wiggle::wasmtime_crate::Extern::Memory(m)) => {
+                    let (mem, ctx) = m.data_and_store_mut(&mut caller);
+                    let ctx = get_cx(ctx);
+                    (wiggle::wasmtime::WasmtimeGuestMemory::new(mem), ctx)
+                }
+                Some(wiggle::wasmtime_crate::Extern::SharedMemory(m)) => {
+                    let ctx = get_cx(caller.data_mut());
+                    (wiggle::wasmtime::WasmtimeGuestMemory::shared(m.data()), ctx)
+                }
+                _ => anyhow::bail!("missing required memory export"),
+            };
+            let name =
+                wiggle::GuestPtr::<str>::new(&mem, (u64::from(name as u32), name_len as u32));
+            let graph = wiggle::GuestPtr::new(&mem, u64::from(graph as u32));
+            let errno = match load_by_name(ctx, &name, &graph) {
+                Ok(()) => NnErrno::Success,
+                Err(e) => ctx.nn_errno_from_wasi_nn_error(e)?,
+            };
+            Ok(errno as i32)
+        },
+    )?;
+    Ok(())
+}
+
+fn load_by_name(
+    ctx: &mut WasiNnCtx,
+    name: &wiggle::GuestPtr<'_, str>,
+    graph: &wiggle::GuestPtr<'_, types::Graph>,
+) -> Result<(), WasiNnError> {
+    let graph_id = ctx.load_by_name(&name.as_cow()?)?;
+    graph.write(graph_id)?;
+    Ok(())
+}
 
 impl<'a> types::UserErrorConversion for WasiNnCtx {
     fn nn_errno_from_wasi_nn_error(&mut self, e: WasiNnError) -> Result<NnErrno> {
         eprintln!("Host error: {:?}", e);
-        match e {
-            WasiNnError::BackendError(_) => unimplemented!(),
-            WasiNnError::GuestError(_) => unimplemented!(),
-            WasiNnError::UsageError(_) => unimplemented!(),
-        }
+        Ok(match e {
+            WasiNnError::BackendError(_) => NnErrno::RuntimeError,
+            WasiNnError::GuestError(_) => NnErrno::InvalidArgument,
+            WasiNnError::UsageError(UsageError::InvalidEncoding(_)) => NnErrno::InvalidEncoding,
+            WasiNnError::UsageError(UsageError::NotEnoughMemory(_)) => NnErrno::MissingMemory,
+            WasiNnError::UsageError(_) => NnErrno::InvalidArgument,
+        })
     }
 }
 
@@ -28,3 +83,36 @@ impl wiggle::GuestErrorType for NnErrno {
         Self::Success
     }
 }
+
+impl From<types::GraphEncoding> for api::GraphEncoding {
+    fn from(encoding: types::GraphEncoding) -> Self {
+        match encoding {
+            types::GraphEncoding::Openvino => api::GraphEncoding::Openvino,
+            types::GraphEncoding::Onnx => api::GraphEncoding::Onnx,
+            types::GraphEncoding::Tensorflow => api::GraphEncoding::Tensorflow,
+            types::GraphEncoding::Pytorch => api::GraphEncoding::Pytorch,
+            types::GraphEncoding::Tensorflowlite => api::GraphEncoding::Tensorflowlite,
+        }
+    }
+}
+
+impl From<types::ExecutionTarget> for api::ExecutionTarget {
+    fn from(target: types::ExecutionTarget) -> Self {
+        match target {
+            types::ExecutionTarget::Cpu => api::ExecutionTarget::Cpu,
+            types::ExecutionTarget::Gpu => api::ExecutionTarget::Gpu,
+            types::ExecutionTarget::Tpu => api::ExecutionTarget::Tpu,
+        }
+    }
+}
+
+impl From<types::TensorType> for api::TensorType {
+    fn from(type_: types::TensorType) -> Self {
+        match type_ {
+            types::TensorType::F16 => api::TensorType::F16,
+            types::TensorType::F32 => api::TensorType::F32,
+            types::TensorType::U8 => api::TensorType::U8,
+            types::TensorType::I32 => api::TensorType::I32,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Load a graph preloaded by the host from a wasm guest.
+
+use anyhow::Result;
+use wasmtime::{Engine, Linker, Module, Store};
+use wasmtime_wasi_nn::{DenseBackend, GraphEncoding, WasiNnCtx};
+
+const GUEST: &str = r#"
+(module
+  (import "wasi_ephemeral_nn" "load_by_name"
+    (func $load_by_name (param i32 i32 i32) (result i32)))
+  (import "wasi_ephemeral_nn" "init_execution_context"
+    (func $init_execution_context (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "double")
+  (data (i32.const 8) "triple")
+
+  ;; Loads the graph named by `name` and `len`, storing its handle at 64.
+  (func (export "load") (param $name i32) (param $len i32) (result i32)
+    (call $load_by_name (local.get $name) (local.get $len) (i32.const 64))
+  )
+  ;; Creates an execution context for the graph stored at 64.
+  (func (export "init") (result i32)
+    (call $init_execution_context (i32.load (i32.const 64)) (i32.const 68))
+  )
+)
+"#;
+
+#[test]
+fn load_by_name_from_guest() -> Result<()> {
+    // A single 1x1 layer which doubles its input.
+    let graph = [1u32, 1, 1, 0]
+        .iter()
+        .flat_map(|n| n.to_le_bytes())
+        .chain(2.0f32.to_le_bytes())
+        .chain(0.0f32.to_le_bytes())
+        .collect::<Vec<_>>();
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("double");
+    std::fs::write(&path, graph)?;
+
+    let mut builder = WasiNnCtx::builder();
+    builder.backend(GraphEncoding::Onnx, DenseBackend).preload(
+        "double",
+        GraphEncoding::Onnx,
+        &path,
+    )?;
+    let ctx = builder.build();
+
+    let engine = Engine::default();
+    let mut linker = Linker::<WasiNnCtx>::new(&engine);
+    wasmtime_wasi_nn::add_to_linker(&mut linker, |ctx| ctx)?;
+    let module = Module::new(&engine, GUEST)?;
+    let mut store = Store::new(&engine, ctx);
+    let instance = linker.instantiate(&mut store, &module)?;
+    let load = instance.get_typed_func::<(i32, i32), i32>(&mut store, "load")?;
+    let init = instance.get_typed_func::<(), i32>(&mut store, "init")?;
+
+    // A successful call returns an errno of zero, and the handle works like
+    // one returned by `load`.
+    assert_eq!(load.call(&mut store, (0, 6))?, 0);
+    assert_eq!(init.call(&mut store, ())?, 0);
+
+    // Names which weren't preloaded are an error for the guest, not a trap.
+    assert_ne!(load.call(&mut store, (8, 6))?, 0);
+    Ok(())
+}
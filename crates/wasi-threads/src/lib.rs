@@ -3,25 +3,177 @@
 //! [`wasi-threads`]: https://github.com/WebAssembly/wasi-threads
 
 use anyhow::{anyhow, Result};
-use rand::Rng;
 use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use wasmtime::{Caller, ExternType, InstancePre, Linker, Module, SharedMemory, Store, ValType};
-use wasmtime_wasi::maybe_exit_on_error;
+use wasmtime::{
+    Caller, ExternType, InstancePre, Linker, Module, SharedMemory, Store, UpdateDeadline, ValType,
+};
 
 // This name is a function export designated by the wasi-threads specification:
 // https://github.com/WebAssembly/wasi-threads/#detailed-design-discussion
 const WASI_ENTRY_POINT: &str = "wasi_thread_start";
 
+// The largest thread ID allowed by the wasi-threads specification; the upper
+// bits are reserved.
+const MAX_THREAD_ID: i32 = 0x1FFF_FFFF;
+
 pub struct WasiThreadsCtx<T> {
     instance_pre: Arc<InstancePre<T>>,
+    max_threads: usize,
+    group: Arc<ThreadGroup>,
+    #[cfg(feature = "async")]
+    runtime: Option<tokio::runtime::Handle>,
+}
+
+/// The state shared by all of the threads spawned from one
+/// [`WasiThreadsCtx`].
+struct ThreadGroup {
+    next_thread_id: AtomicI32,
+    running: AtomicUsize,
+    terminated: AtomicBool,
+    error: Mutex<Option<anyhow::Error>>,
+    terminated_cond: Condvar,
+}
+
+impl ThreadGroup {
+    fn is_terminated(&self) -> bool {
+        self.terminated.load(Ordering::SeqCst)
+    }
+
+    /// Marks the group as terminated, keeping only the first error.
+    ///
+    /// The engine's epoch is deliberately left alone, as it is shared with
+    /// stores outside of this group: running threads notice the flag at the
+    /// next epoch change made by the embedder.
+    fn terminate(&self, error: anyhow::Error) {
+        let mut slot = self.error.lock().unwrap();
+        if !self.terminated.swap(true, Ordering::SeqCst) {
+            *slot = Some(error);
+        }
+        self.terminated_cond.notify_all();
+    }
+
+    /// Records how a spawned thread ended, where `Err` means it panicked.
+    fn thread_exited(&self, wasi_thread_id: i32, result: Result<Result<()>, ()>) {
+        match result {
+            Ok(Ok(())) => log::trace!("exiting thread id = {} normally", wasi_thread_id),
+            Ok(Err(e)) => {
+                log::trace!("exiting thread id = {} due to error", wasi_thread_id);
+                self.terminate(e);
+            }
+            Err(()) => self.terminate(anyhow!("wasi-thread-{} panicked", wasi_thread_id)),
+        }
+    }
+}
+
+/// Decrements the running thread count when a spawned thread ends, however it
+/// ends.
+struct RunningGuard(Arc<ThreadGroup>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.running.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl<T: Clone + Send + 'static> WasiThreadsCtx<T> {
     pub fn new(module: Module, linker: Arc<Linker<T>>) -> Result<Self> {
         let instance_pre = Arc::new(linker.instantiate_pre(&module)?);
-        Ok(Self { instance_pre })
+        Ok(Self {
+            instance_pre,
+            max_threads: usize::MAX,
+            group: Arc::new(ThreadGroup {
+                next_thread_id: AtomicI32::new(1),
+                running: AtomicUsize::new(0),
+                terminated: AtomicBool::new(false),
+                error: Mutex::new(None),
+                terminated_cond: Condvar::new(),
+            }),
+            #[cfg(feature = "async")]
+            runtime: None,
+        })
+    }
+
+    /// Like [`new`](Self::new), but for engines configured with
+    /// [`Config::async_support`]: threads are spawned as tasks on `runtime`
+    /// and run with `call_async` rather than on their own OS threads.
+    ///
+    /// Spawned threads yield to the executor whenever the engine's epoch
+    /// changes, so the engine should be configured with
+    /// [`Config::epoch_interruption`] and its epoch incremented periodically.
+    ///
+    /// A thread blocked in `memory.atomic.wait` can't yield, and blocks the
+    /// worker thread running it until it is woken up. `runtime` must
+    /// therefore be a multi-threaded runtime, with more worker threads than
+    /// the guest is expected to have waiting at once; an error is returned
+    /// for a current-thread runtime.
+    ///
+    /// [`Config::async_support`]: wasmtime::Config::async_support
+    /// [`Config::epoch_interruption`]: wasmtime::Config::epoch_interruption
+    #[cfg(feature = "async")]
+    pub fn new_async(
+        module: Module,
+        linker: Arc<Linker<T>>,
+        runtime: tokio::runtime::Handle,
+    ) -> Result<Self> {
+        if let tokio::runtime::RuntimeFlavor::CurrentThread = runtime.runtime_flavor() {
+            return Err(anyhow!(
+                "wasi-threads requires a multi-threaded runtime to run threads as tasks"
+            ));
+        }
+        let mut ctx = Self::new(module, linker)?;
+        ctx.runtime = Some(runtime);
+        Ok(ctx)
+    }
+
+    /// Limits the number of spawned threads which may be running at once.
+    /// Beyond this, `thread-spawn` fails until a thread exits. There is no
+    /// limit by default.
+    pub fn max_threads(mut self, max_threads: usize) -> Self {
+        self.max_threads = max_threads;
+        self
+    }
+
+    /// Returns whether a thread has trapped or exited, ending execution for
+    /// the whole group.
+    pub fn is_terminated(&self) -> bool {
+        self.group.is_terminated()
+    }
+
+    /// Ends execution for all of the spawned threads as if one of them had
+    /// trapped with `error`. This has no effect if the group was already
+    /// terminated.
+    ///
+    /// Threads which are running wasm only notice this if the engine was
+    /// configured with [`Config::epoch_interruption`], the next time its
+    /// epoch is incremented; this doesn't increment it, as that would affect
+    /// every other store using the engine too. Threads blocked in
+    /// `memory.atomic.wait` only notice once they wake up.
+    ///
+    /// [`Config::epoch_interruption`]: wasmtime::Config::epoch_interruption
+    pub fn terminate(&self, error: anyhow::Error) {
+        self.group.terminate(error);
+    }
+
+    /// Returns the error which terminated the group, if it was terminated,
+    /// taking it so that later calls return `None`.
+    ///
+    /// A call to `proc_exit` in a spawned thread is reported as an
+    /// [`I32Exit`](wasmtime_wasi::I32Exit) error.
+    pub fn take_error(&self) -> Option<anyhow::Error> {
+        self.group.error.lock().unwrap().take()
+    }
+
+    /// Blocks until the group is terminated, then returns its error as
+    /// [`take_error`](Self::take_error) does.
+    pub fn wait_for_termination(&self) -> Option<anyhow::Error> {
+        let mut error = self.group.error.lock().unwrap();
+        while !self.group.is_terminated() {
+            error = self.group.terminated_cond.wait(error).unwrap();
+        }
+        error.take()
     }
 
     pub fn spawn(&self, host: T, thread_start_arg: i32) -> Result<i32> {
@@ -44,61 +196,144 @@ impl<T: Clone + Send + 'static> WasiThreadsCtx<T> {
             log::error!("the exported entry point function has an incorrect signature: expected `(i32, i32) -> ()`");
             return Ok(-1);
         }
+        if self.group.is_terminated() {
+            log::error!("cannot spawn a thread after a sibling thread has terminated execution");
+            return Ok(-1);
+        }
+
+        // Reserve a slot for the thread, giving it back if it can't be used.
+        let running = self.group.running.fetch_add(1, Ordering::SeqCst);
+        let guard = RunningGuard(self.group.clone());
+        if running >= self.max_threads {
+            log::error!(
+                "cannot spawn more than {} threads at once",
+                self.max_threads
+            );
+            return Ok(-1);
+        }
+        let wasi_thread_id = match next_thread_id(&self.group) {
+            Some(id) => id,
+            None => {
+                log::error!("ran out of wasi-threads thread IDs");
+                return Ok(-1);
+            }
+        };
+
+        log::trace!(
+            "spawned thread id = {}; calling start function `{}` with: {}",
+            wasi_thread_id,
+            WASI_ENTRY_POINT,
+            thread_start_arg
+        );
+        #[cfg(feature = "async")]
+        if let Some(runtime) = &self.runtime {
+            self.spawn_task(runtime, host, wasi_thread_id, thread_start_arg, guard);
+            return Ok(wasi_thread_id);
+        }
 
         // Start a Rust thread running a new instance of the current module.
-        let wasi_thread_id = random_thread_id();
+        let group = self.group.clone();
         let builder = thread::Builder::new().name(format!("wasi-thread-{}", wasi_thread_id));
         builder.spawn(move || {
+            let _guard = guard;
+
             // Catch any panic failures in host code; e.g., if a WASI module
             // were to crash, we want all threads to exit, not just this one.
-            let result = catch_unwind(AssertUnwindSafe(|| {
+            let result = catch_unwind(AssertUnwindSafe(|| -> Result<()> {
                 // Each new instance is created in its own store.
                 let mut store = Store::new(&instance_pre.module().engine(), host);
-                let instance = instance_pre.instantiate(&mut store).unwrap();
-                let thread_entry_point = instance
-                    .get_typed_func::<(i32, i32), ()>(&mut store, WASI_ENTRY_POINT)
-                    .unwrap();
+                trap_on_termination(&mut store, group.clone(), || UpdateDeadline::Continue(1));
+                let instance = instance_pre.instantiate(&mut store)?;
+                let thread_entry_point =
+                    instance.get_typed_func::<(i32, i32), ()>(&mut store, WASI_ENTRY_POINT)?;
 
                 // Start the thread's entry point. Any traps or calls to
                 // `proc_exit`, by specification, should end execution for all
-                // threads. This code uses `process::exit` to do so, which is
-                // what the user expects from the CLI but probably not in a
-                // Wasmtime embedding.
-                log::trace!(
-                    "spawned thread id = {}; calling start function `{}` with: {}",
-                    wasi_thread_id,
-                    WASI_ENTRY_POINT,
-                    thread_start_arg
-                );
-                match thread_entry_point.call(&mut store, (wasi_thread_id, thread_start_arg)) {
-                    Ok(_) => log::trace!("exiting thread id = {} normally", wasi_thread_id),
-                    Err(e) => {
-                        log::trace!("exiting thread id = {} due to error", wasi_thread_id);
-                        let e = maybe_exit_on_error(e);
-                        eprintln!("Error: {:?}", e);
-                        std::process::exit(1);
-                    }
-                }
+                // threads, which is left to the embedder once the error is
+                // reported.
+                thread_entry_point.call(&mut store, (wasi_thread_id, thread_start_arg))
             }));
 
-            if let Err(e) = result {
-                eprintln!("wasi-thread-{} panicked: {:?}", wasi_thread_id, e);
-                std::process::exit(1);
-            }
+            group.thread_exited(wasi_thread_id, result.map_err(drop));
         })?;
 
         Ok(wasi_thread_id)
     }
+
+    /// Runs a new instance of the current module as a task on `runtime`, the
+    /// async counterpart of spawning an OS thread in [`spawn`](Self::spawn).
+    #[cfg(feature = "async")]
+    fn spawn_task(
+        &self,
+        runtime: &tokio::runtime::Handle,
+        host: T,
+        wasi_thread_id: i32,
+        thread_start_arg: i32,
+        guard: RunningGuard,
+    ) {
+        let instance_pre = self.instance_pre.clone();
+        let group = self.group.clone();
+        let task = runtime.spawn(async move {
+            // Each new instance is created in its own store, which yields
+            // back to the executor whenever the epoch changes so that a
+            // spinning thread can't starve the other tasks.
+            let mut store = Store::new(instance_pre.module().engine(), host);
+            trap_on_termination(&mut store, group, || UpdateDeadline::Yield(1));
+            let instance = instance_pre.instantiate_async(&mut store).await?;
+            let thread_entry_point =
+                instance.get_typed_func::<(i32, i32), ()>(&mut store, WASI_ENTRY_POINT)?;
+            thread_entry_point
+                .call_async(&mut store, (wasi_thread_id, thread_start_arg))
+                .await
+        });
+
+        // Like the OS threads, a panicking task ends execution for all of
+        // them, which is only seen once the task is joined.
+        let group = self.group.clone();
+        runtime.spawn(async move {
+            let _guard = guard;
+            let result = task.await;
+            group.thread_exited(wasi_thread_id, result.map_err(drop));
+        });
+    }
+}
+
+/// Configures `store` to trap at the first epoch change after its group is
+/// terminated, and otherwise to update its deadline with `update`.
+fn trap_on_termination<T>(
+    store: &mut Store<T>,
+    group: Arc<ThreadGroup>,
+    update: fn() -> UpdateDeadline,
+) {
+    store.epoch_deadline_callback(move |_| {
+        if group.is_terminated() {
+            Err(anyhow!(
+                "wasi-threads: execution terminated by a sibling thread"
+            ))
+        } else {
+            Ok(update())
+        }
+    });
+    store.set_epoch_deadline(1);
 }
 
 /// Helper for generating valid WASI thread IDs (TID).
 ///
 /// Callers of `wasi_thread_spawn` expect a TID >=0 to indicate a successful
 /// spawning of the thread whereas a negative return value indicates an
-/// failure to spawn.
-fn random_thread_id() -> i32 {
-    let tid: u32 = rand::thread_rng().gen();
-    (tid >> 1) as i32
+/// failure to spawn. IDs are handed out in order and never reused, so `None`
+/// is returned once they run out.
+fn next_thread_id(group: &ThreadGroup) -> Option<i32> {
+    group
+        .next_thread_id
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |id| {
+            if id <= MAX_THREAD_ID {
+                Some(id + 1)
+            } else {
+                None
+            }
+        })
+        .ok()
 }
 
 /// Manually add the WASI `thread_spawn` function to the linker.
@@ -120,10 +355,7 @@ pub fn add_to_linker<T: Clone + Send + 'static>(
             let host = caller.data().clone();
             let ctx = get_cx(caller.data_mut());
             match ctx.spawn(host, start_arg) {
-                Ok(thread_id) => {
-                    assert!(thread_id >= 0, "thread_id = {}", thread_id);
-                    thread_id
-                }
+                Ok(thread_id) => thread_id,
                 Err(e) => {
                     log::error!("failed to spawn thread: {}", e);
                     -1
@@ -0,0 +1,115 @@
+//! Run wasi-threads as tasks on an async executor.
+
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wasmtime::{Config, Engine, Linker, Module, Store};
+use wasmtime_wasi_threads::WasiThreadsCtx;
+
+const THREADS: &str = r#"
+(module
+  (import "" "memory" (memory 1 1 shared))
+  (import "wasi" "thread-spawn" (func $spawn (param i32) (result i32)))
+  (func (export "_start")
+    ;; One thread spins forever while three others bump a counter.
+    (drop (call $spawn (i32.const 0)))
+    (drop (call $spawn (i32.const 1)))
+    (drop (call $spawn (i32.const 1)))
+    (drop (call $spawn (i32.const 1)))
+  )
+  (func (export "wasi_thread_start") (param $tid i32) (param $spin i32)
+    (if (i32.eqz (local.get $spin))
+      (then (loop $forever (br $forever))))
+    (drop (i32.atomic.rmw.add (i32.const 128) (i32.const 1)))
+  )
+  (export "memory" (memory 0))
+)
+"#;
+
+#[derive(Clone)]
+struct Host {
+    threads: Option<Arc<WasiThreadsCtx<Host>>>,
+    // Every store holds a clone of this, so its count shows how many of
+    // them are still alive.
+    stores: Arc<()>,
+}
+
+fn engine() -> Result<Engine> {
+    let mut config = Config::new();
+    config.async_support(true);
+    config.wasm_threads(true);
+    config.epoch_interruption(true);
+    Engine::new(&config)
+}
+
+// The threads all run on the one worker thread, so the counting threads only
+// make progress if the spinning thread yields.
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn spinning_thread_does_not_starve_executor() -> Result<()> {
+    let engine = engine()?;
+    let module = Module::new(&engine, THREADS)?;
+
+    let ticker = engine.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_millis(1));
+        ticker.increment_epoch();
+    });
+
+    let mut linker = Linker::new(&engine);
+    let mut store = Store::new(
+        &engine,
+        Host {
+            threads: None,
+            stores: Arc::new(()),
+        },
+    );
+    store.epoch_deadline_async_yield_and_update(1);
+    wasmtime_wasi_threads::add_to_linker(&mut linker, &store, &module, |host| {
+        host.threads.as_ref().unwrap()
+    })?;
+    let threads = Arc::new(WasiThreadsCtx::new_async(
+        module.clone(),
+        Arc::new(linker.clone()),
+        tokio::runtime::Handle::current(),
+    )?);
+    store.data_mut().threads = Some(threads.clone());
+
+    let instance = linker.instantiate_async(&mut store, &module).await?;
+    instance
+        .get_typed_func::<(), ()>(&mut store, "_start")?
+        .call_async(&mut store, ())
+        .await?;
+
+    let memory = instance.get_shared_memory(&mut store, "memory").unwrap();
+    let counter = unsafe { &*(memory.data()[128..].as_ptr() as *const AtomicU32) };
+    while counter.load(Ordering::SeqCst) < 3 {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    // Terminating the group stops the spinning thread, dropping its store.
+    let stores = store.data().stores.clone();
+    threads.terminate(anyhow!("done"));
+    assert!(threads.is_terminated());
+    let start = Instant::now();
+    while Arc::strong_count(&stores) > 2 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "thread still running"
+        );
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    assert_eq!(threads.take_error().unwrap().to_string(), "done");
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn current_thread_runtime_is_rejected() -> Result<()> {
+    let engine = engine()?;
+    let module = Module::new(&engine, THREADS)?;
+    let linker = Linker::<Host>::new(&engine);
+    let result =
+        WasiThreadsCtx::new_async(module, Arc::new(linker), tokio::runtime::Handle::current());
+    assert!(result.is_err());
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Run wasi-threads on OS threads, as the CLI does.
+
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wasmtime::{Config, Engine, Instance, Linker, Module, Store};
+use wasmtime_wasi_threads::WasiThreadsCtx;
+
+const THREADS: &str = r#"
+(module
+  (import "" "memory" (memory 1 1 shared))
+  (import "wasi" "thread-spawn" (func $spawn (param i32) (result i32)))
+  (func (export "spawn") (result i32)
+    (call $spawn (i32.const 0))
+  )
+  ;; Threads spin until the host stores a non-zero value at address 0.
+  (func (export "wasi_thread_start") (param $tid i32) (param $arg i32)
+    (loop $wait
+      (br_if $wait (i32.eqz (i32.atomic.load (i32.const 0)))))
+  )
+  (export "memory" (memory 0))
+)
+"#;
+
+#[derive(Clone)]
+struct Host {
+    threads: Option<Arc<WasiThreadsCtx<Host>>>,
+    // Every store holds a clone of this, so its count shows how many of
+    // them are still alive.
+    stores: Arc<()>,
+}
+
+fn instantiate(
+    engine: &Engine,
+    configure: impl FnOnce(WasiThreadsCtx<Host>) -> WasiThreadsCtx<Host>,
+) -> Result<(Store<Host>, Instance, Arc<WasiThreadsCtx<Host>>)> {
+    let module = Module::new(engine, THREADS)?;
+    let mut linker = Linker::new(engine);
+    let mut store = Store::new(
+        engine,
+        Host {
+            threads: None,
+            stores: Arc::new(()),
+        },
+    );
+    wasmtime_wasi_threads::add_to_linker(&mut linker, &store, &module, |host| {
+        host.threads.as_ref().unwrap()
+    })?;
+    let threads = Arc::new(configure(WasiThreadsCtx::new(
+        module.clone(),
+        Arc::new(linker.clone()),
+    )?));
+    store.data_mut().threads = Some(threads.clone());
+    let instance = linker.instantiate(&mut store, &module)?;
+    Ok((store, instance, threads))
+}
+
+fn release_threads(store: &mut Store<Host>, instance: &Instance) {
+    let memory = instance.get_shared_memory(&mut *store, "memory").unwrap();
+    let flag = unsafe { &*(memory.data().as_ptr() as *const AtomicU32) };
+    flag.store(1, Ordering::SeqCst);
+}
+
+fn wait_until(mut done: impl FnMut() -> bool) {
+    let start = Instant::now();
+    while !done() {
+        assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn max_threads_and_sequential_ids() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_threads(true);
+    let engine = Engine::new(&config)?;
+    let (mut store, instance, threads) = instantiate(&engine, |ctx| ctx.max_threads(2))?;
+    let spawn = instance.get_typed_func::<(), i32>(&mut store, "spawn")?;
+
+    // Thread IDs are handed out in order, and a failed spawn doesn't use one.
+    assert_eq!(spawn.call(&mut store, ())?, 1);
+    assert_eq!(spawn.call(&mut store, ())?, 2);
+    assert_eq!(spawn.call(&mut store, ())?, -1);
+
+    // Once the running threads exit, spawning succeeds again without reusing
+    // their IDs.
+    release_threads(&mut store, &instance);
+    let stores = store.data().stores.clone();
+    wait_until(|| Arc::strong_count(&stores) == 2);
+    assert_eq!(spawn.call(&mut store, ())?, 3);
+
+    wait_until(|| Arc::strong_count(&stores) == 2);
+    assert!(!threads.is_terminated());
+    assert!(threads.take_error().is_none());
+    Ok(())
+}
+
+#[test]
+fn embedder_terminates_spinning_thread() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_threads(true);
+    config.epoch_interruption(true);
+    let engine = Engine::new(&config)?;
+
+    let ticker = engine.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_millis(1));
+        ticker.increment_epoch();
+    });
+
+    let (mut store, instance, threads) = instantiate(&engine, |ctx| ctx)?;
+    store.epoch_deadline_callback(|_| Ok(wasmtime::UpdateDeadline::Continue(1)));
+    let spawn = instance.get_typed_func::<(), i32>(&mut store, "spawn")?;
+    assert_eq!(spawn.call(&mut store, ())?, 1);
+
+    // The spawned thread never exits on its own; terminating the group stops
+    // it while the embedder, and this process, carry on.
+    threads.terminate(anyhow!("done"));
+    let stores = store.data().stores.clone();
+    wait_until(|| Arc::strong_count(&stores) == 2);
+    assert_eq!(threads.wait_for_termination().unwrap().to_string(), "done");
+
+    // Nothing can be spawned afterwards, but the main thread keeps working.
+    assert_eq!(spawn.call(&mut store, ())?, -1);
+    Ok(())
+}
@@ -45,6 +45,8 @@ macro_rules! define_wasi {
 
 use wasmtime::Linker;
 
+pub use wiggle::PointerWidth;
+
 pub fn add_to_linker<T, U>(
     linker: &mut Linker<T>,
     get_cx: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
@@ -54,8 +56,24 @@ pub fn add_to_linker<T, U>(
             + wasi_common::snapshots::preview_1::wasi_snapshot_preview1::WasiSnapshotPreview1,
         $($bounds)*
 {
-    snapshots::preview_1::add_wasi_snapshot_preview1_to_linker(linker, get_cx)?;
-    snapshots::preview_0::add_wasi_unstable_to_linker(linker, get_cx)?;
+    add_to_linker_with_pointer_width(linker, PointerWidth::Bits32, get_cx)
+}
+
+/// Like [`add_to_linker`], but defines the WASI functions to take pointers of
+/// the given `width`. Modules with a 64-bit memory need
+/// [`PointerWidth::Bits64`].
+pub fn add_to_linker_with_pointer_width<T, U>(
+    linker: &mut Linker<T>,
+    width: PointerWidth,
+    get_cx: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
+) -> anyhow::Result<()>
+    where U: Send
+            + wasi_common::snapshots::preview_0::wasi_unstable::WasiUnstable
+            + wasi_common::snapshots::preview_1::wasi_snapshot_preview1::WasiSnapshotPreview1,
+        $($bounds)*
+{
+    snapshots::preview_1::add_wasi_snapshot_preview1_to_linker_with_pointer_width(linker, width, get_cx)?;
+    snapshots::preview_0::add_wasi_unstable_to_linker_with_pointer_width(linker, width, get_cx)?;
     Ok(())
 }
 
@@ -68,6 +86,7 @@ pub mod snapshots {
             // the `WASI_ROOT` env variable, which is set in wasi-common's `build.rs`.
             witx: ["$WASI_ROOT/phases/snapshot/witx/wasi_snapshot_preview1.witx"],
             errors: { errno => trappable Error },
+            pointer_sized: [size],
             $async_mode: *
         });
     }
@@ -79,6 +98,7 @@ pub mod snapshots {
             // the `WASI_ROOT` env variable, which is set in wasi-common's `build.rs`.
             witx: ["$WASI_ROOT/phases/old/snapshot_0/witx/wasi_unstable.witx"],
             errors: { errno => trappable Error },
+            pointer_sized: [size],
             $async_mode: *
         });
     }
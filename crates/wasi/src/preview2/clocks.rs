@@ -1,4 +1,7 @@
 pub mod host;
+#[cfg(unix)]
+mod tzif;
+use crate::preview2::bindings::clocks::timezone::TimezoneDisplay;
 use cap_std::time::Duration;
 
 pub trait HostWallClock: Send + Sync {
@@ -10,3 +13,38 @@ pub trait HostMonotonicClock: Send + Sync {
     fn resolution(&self) -> u64;
     fn now(&self) -> u64;
 }
+
+/// The timezone exposed to guests through `wasi:clocks/timezone`.
+///
+/// Times are given as durations since the Unix epoch, like
+/// [`HostWallClock::now`].
+pub trait HostTimezone: Send + Sync {
+    /// Returns the information needed to display `datetime` in this timezone.
+    fn display(&self, datetime: Duration) -> TimezoneDisplay;
+
+    /// Returns the number of seconds east of UTC of this timezone at
+    /// `datetime`.
+    fn utc_offset(&self, datetime: Duration) -> i32 {
+        self.display(datetime).utc_offset
+    }
+}
+
+/// A timezone which is always UTC, with no daylight saving time.
+///
+/// This is the default timezone of a [`WasiCtxBuilder`](crate::preview2::WasiCtxBuilder),
+/// as it doesn't reveal anything about the host.
+pub struct UtcTimezone;
+
+impl HostTimezone for UtcTimezone {
+    fn display(&self, _datetime: Duration) -> TimezoneDisplay {
+        TimezoneDisplay {
+            utc_offset: 0,
+            name: "UTC".to_string(),
+            in_daylight_saving_time: false,
+        }
+    }
+
+    fn utc_offset(&self, _datetime: Duration) -> i32 {
+        0
+    }
+}
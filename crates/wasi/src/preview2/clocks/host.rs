@@ -1,4 +1,5 @@
-use super::{HostMonotonicClock, HostWallClock};
+use super::{HostMonotonicClock, HostTimezone, HostWallClock, UtcTimezone};
+use crate::preview2::bindings::clocks::timezone::TimezoneDisplay;
 use cap_std::time::{Duration, Instant, SystemClock};
 use cap_std::{ambient_authority, AmbientAuthority};
 use cap_time_ext::{MonotonicClockExt, SystemClockExt};
@@ -71,3 +72,50 @@ pub fn monotonic_clock() -> Box<dyn HostMonotonicClock + Send + Sync> {
 pub fn wall_clock() -> Box<dyn HostWallClock + Send + Sync> {
     Box::new(WallClock::new(ambient_authority()))
 }
+
+/// The timezone of the host, as configured by the `TZ` environment variable
+/// or `/etc/localtime`.
+///
+/// The host timezone database is only consulted on Unix platforms; elsewhere,
+/// or if the timezone can't be determined, this behaves like
+/// [`UtcTimezone`].
+pub struct SystemTimezone {
+    #[cfg(unix)]
+    tz: Option<super::tzif::TimeZone>,
+}
+
+impl SystemTimezone {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(unix)]
+            tz: super::tzif::TimeZone::local(),
+        }
+    }
+}
+
+impl Default for SystemTimezone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HostTimezone for SystemTimezone {
+    fn display(&self, datetime: Duration) -> TimezoneDisplay {
+        #[cfg(unix)]
+        if let Some(tz) = &self.tz {
+            let time = i64::try_from(datetime.as_secs()).unwrap_or(i64::MAX);
+            if let Some(ty) = tz.find(time) {
+                return TimezoneDisplay {
+                    utc_offset: ty.utc_offset,
+                    name: ty.name,
+                    in_daylight_saving_time: ty.is_dst,
+                };
+            }
+        }
+        UtcTimezone.display(datetime)
+    }
+}
+
+pub fn timezone() -> Box<dyn HostTimezone + Send + Sync> {
+    Box::new(SystemTimezone::new())
+}
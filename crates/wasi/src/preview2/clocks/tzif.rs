@@ -0,0 +1,594 @@
+//! A minimal reader for the system timezone database.
+//!
+//! This understands the TZif files described in [RFC 8536], as found in
+//! `/usr/share/zoneinfo` and `/etc/localtime`, along with the POSIX `TZ`
+//! strings used in their footers and in the `TZ` environment variable.
+//!
+//! [RFC 8536]: https://www.rfc-editor.org/rfc/rfc8536
+
+use std::path::Path;
+
+/// The local time rules of a single timezone.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TimeZone {
+    /// Transition times, in seconds since the Unix epoch, in ascending order.
+    transitions: Vec<i64>,
+    /// The index into `types` which takes effect at each transition.
+    transition_types: Vec<usize>,
+    types: Vec<LocalTimeType>,
+    /// Rule for times after the last transition.
+    footer: Option<PosixTz>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LocalTimeType {
+    /// Seconds east of UTC.
+    pub utc_offset: i32,
+    pub is_dst: bool,
+    pub name: String,
+}
+
+impl TimeZone {
+    /// Loads the timezone configured for the host, following the `TZ`
+    /// environment variable if it is set and `/etc/localtime` otherwise.
+    pub fn local() -> Option<TimeZone> {
+        match std::env::var("TZ") {
+            Ok(tz) if !tz.is_empty() => TimeZone::named(tz.strip_prefix(':').unwrap_or(&tz)),
+            _ => TimeZone::from_file(Path::new("/etc/localtime")),
+        }
+    }
+
+    /// Looks up a timezone either by its name in the timezone database, such
+    /// as `Europe/Paris`, by an absolute path to a TZif file, or as a POSIX
+    /// `TZ` string, such as `EST5EDT,M3.2.0,M11.1.0`.
+    fn named(name: &str) -> Option<TimeZone> {
+        let path = Path::new(name);
+        if path.is_absolute() {
+            return TimeZone::from_file(path);
+        }
+        let in_database = !name.split('/').any(|c| c.is_empty() || c == "..");
+        if in_database {
+            for dir in ["/usr/share/zoneinfo", "/usr/lib/zoneinfo", "/etc/zoneinfo"] {
+                if let Some(tz) = TimeZone::from_file(&Path::new(dir).join(path)) {
+                    return Some(tz);
+                }
+            }
+        }
+        TimeZone::from_posix(name)
+    }
+
+    fn from_file(path: &Path) -> Option<TimeZone> {
+        TimeZone::parse(&std::fs::read(path).ok()?)
+    }
+
+    /// Creates a timezone which is described entirely by a POSIX `TZ` string.
+    pub fn from_posix(s: &str) -> Option<TimeZone> {
+        Some(TimeZone {
+            transitions: Vec::new(),
+            transition_types: Vec::new(),
+            types: Vec::new(),
+            footer: Some(PosixTz::parse(s)?),
+        })
+    }
+
+    /// Parses the contents of a TZif file.
+    pub fn parse(bytes: &[u8]) -> Option<TimeZone> {
+        let mut reader = Reader(bytes);
+        let header = Header::parse(&mut reader)?;
+        if header.version == 0 {
+            return header.parse_body(&mut reader, 4);
+        }
+
+        // Version 2 and above files repeat the data with 64-bit transition
+        // times, followed by a footer with a POSIX `TZ` string.
+        header.skip_body(&mut reader, 4)?;
+        let header = Header::parse(&mut reader)?;
+        let mut tz = header.parse_body(&mut reader, 8)?;
+        let footer = reader.0.strip_prefix(b"\n")?;
+        let end = footer.iter().position(|b| *b == b'\n')?;
+        let footer = std::str::from_utf8(&footer[..end]).ok()?;
+        if !footer.is_empty() {
+            tz.footer = PosixTz::parse(footer);
+        }
+        Some(tz)
+    }
+
+    /// Returns the local time type in effect at `time`, in seconds since the
+    /// Unix epoch.
+    pub fn find(&self, time: i64) -> Option<LocalTimeType> {
+        let idx = self.transitions.partition_point(|t| *t <= time);
+        if idx == self.transitions.len() {
+            if let Some(footer) = &self.footer {
+                return Some(footer.find(time));
+            }
+        }
+        match idx.checked_sub(1) {
+            Some(idx) => self.types.get(self.transition_types[idx]).cloned(),
+            None => self.types.first().cloned(),
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (ret, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(ret)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn time(&mut self, size: usize) -> Option<i64> {
+        match size {
+            4 => self.i32().map(i64::from),
+            _ => Some(i64::from_be_bytes(self.take(8)?.try_into().unwrap())),
+        }
+    }
+}
+
+struct Header {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl Header {
+    fn parse(reader: &mut Reader<'_>) -> Option<Header> {
+        if reader.take(4)? != b"TZif" {
+            return None;
+        }
+        let version = match reader.u8()? {
+            0 => 0,
+            v @ b'2'..=b'9' => v - b'0',
+            _ => return None,
+        };
+        reader.take(15)?;
+        let mut count = || usize::try_from(reader.u32()?).ok();
+        Some(Header {
+            version,
+            isutcnt: count()?,
+            isstdcnt: count()?,
+            leapcnt: count()?,
+            timecnt: count()?,
+            typecnt: count()?,
+            charcnt: count()?,
+        })
+    }
+
+    fn body_len(&self, time_size: usize) -> Option<usize> {
+        let len = self.timecnt.checked_mul(time_size + 1)?
+            + self.typecnt.checked_mul(6)?
+            + self.charcnt
+            + self.leapcnt.checked_mul(time_size + 4)?
+            + self.isstdcnt
+            + self.isutcnt;
+        Some(len)
+    }
+
+    fn skip_body(&self, reader: &mut Reader<'_>, time_size: usize) -> Option<()> {
+        reader.take(self.body_len(time_size)?)?;
+        Some(())
+    }
+
+    fn parse_body(&self, reader: &mut Reader<'_>, time_size: usize) -> Option<TimeZone> {
+        let transitions = (0..self.timecnt)
+            .map(|_| reader.time(time_size))
+            .collect::<Option<Vec<_>>>()?;
+        let transition_types = (0..self.timecnt)
+            .map(|_| reader.u8().map(usize::from))
+            .collect::<Option<Vec<_>>>()?;
+        let types = (0..self.typecnt)
+            .map(|_| Some((reader.i32()?, reader.u8()? != 0, usize::from(reader.u8()?))))
+            .collect::<Option<Vec<_>>>()?;
+        let names = reader.take(self.charcnt)?;
+        reader.take(self.leapcnt.checked_mul(time_size + 4)? + self.isstdcnt + self.isutcnt)?;
+
+        let types = types
+            .into_iter()
+            .map(|(utc_offset, is_dst, name)| {
+                let name = names.get(name..)?;
+                let end = name.iter().position(|b| *b == 0)?;
+                Some(LocalTimeType {
+                    utc_offset,
+                    is_dst,
+                    name: String::from_utf8(name[..end].to_vec()).ok()?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if transition_types.iter().any(|t| *t >= types.len()) {
+            return None;
+        }
+        Some(TimeZone {
+            transitions,
+            transition_types,
+            types,
+            footer: None,
+        })
+    }
+}
+
+/// A timezone described by a POSIX `TZ` string, such as
+/// `CET-1CEST,M3.5.0,M10.5.0/3`.
+#[derive(Debug, Clone, PartialEq)]
+struct PosixTz {
+    std: LocalTimeType,
+    dst: Option<(LocalTimeType, Rule, Rule)>,
+}
+
+/// The date and local time at which daylight saving time starts or ends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rule {
+    day: RuleDay,
+    /// Seconds after local midnight.
+    time: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RuleDay {
+    /// `Jn`: the day of the year from 1 to 365, never counting February 29.
+    Julian(u16),
+    /// `n`: the zero-based day of the year from 0 to 365.
+    Zero(u16),
+    /// `Mm.w.d`: day `d` (0 is Sunday) of week `w` of month `m`, where week 5
+    /// means the last such day in the month.
+    MonthWeekDay(u8, u8, u8),
+}
+
+impl PosixTz {
+    fn parse(s: &str) -> Option<PosixTz> {
+        let mut p = Parser(s.as_bytes());
+        let std_name = p.name()?;
+        let std_offset = -p.offset()?;
+        let std = LocalTimeType {
+            utc_offset: i32::try_from(std_offset).ok()?,
+            is_dst: false,
+            name: std_name,
+        };
+        if p.0.is_empty() {
+            return Some(PosixTz { std, dst: None });
+        }
+
+        let dst_name = p.name()?;
+        let dst_offset = match p.0.first() {
+            Some(b',') | None => std_offset + 3600,
+            Some(_) => -p.offset()?,
+        };
+        let dst = LocalTimeType {
+            utc_offset: i32::try_from(dst_offset).ok()?,
+            is_dst: true,
+            name: dst_name,
+        };
+        // Without explicit rules, POSIX leaves the default up to the
+        // implementation; use the current United States rules like most
+        // other implementations do.
+        let (start, end) = if p.0.is_empty() {
+            (
+                Rule {
+                    day: RuleDay::MonthWeekDay(3, 2, 0),
+                    time: 7200,
+                },
+                Rule {
+                    day: RuleDay::MonthWeekDay(11, 1, 0),
+                    time: 7200,
+                },
+            )
+        } else {
+            p.expect(b',')?;
+            let start = p.rule()?;
+            p.expect(b',')?;
+            let end = p.rule()?;
+            (start, end)
+        };
+        if !p.0.is_empty() {
+            return None;
+        }
+        Some(PosixTz {
+            std,
+            dst: Some((dst, start, end)),
+        })
+    }
+
+    fn find(&self, time: i64) -> LocalTimeType {
+        let (dst, start, end) = match &self.dst {
+            Some(dst) => dst,
+            None => return self.std.clone(),
+        };
+        let year = civil_from_days(time.div_euclid(86400)).0;
+        // The start of daylight saving time is given in standard time, and
+        // the end in daylight saving time.
+        let start = start.unix_time(year) - i64::from(self.std.utc_offset);
+        let end = end.unix_time(year) - i64::from(dst.utc_offset);
+        let in_dst = if start <= end {
+            start <= time && time < end
+        } else {
+            // Southern hemisphere: daylight saving time spans the new year.
+            !(end <= time && time < start)
+        };
+        if in_dst {
+            dst.clone()
+        } else {
+            self.std.clone()
+        }
+    }
+}
+
+impl Rule {
+    /// Returns the local time of this rule in `year` as if it were UTC, in
+    /// seconds since the Unix epoch.
+    fn unix_time(&self, year: i64) -> i64 {
+        let jan1 = days_from_civil(year, 1, 1);
+        let day = match self.day {
+            RuleDay::Julian(n) => {
+                let n = i64::from(n) - 1;
+                jan1 + n + i64::from(is_leap_year(year) && n >= 59)
+            }
+            RuleDay::Zero(n) => jan1 + i64::from(n),
+            RuleDay::MonthWeekDay(month, week, weekday) => {
+                let first = days_from_civil(year, month.into(), 1);
+                // 1970-01-01 was a Thursday.
+                let first_weekday = (first + 4).rem_euclid(7);
+                let mut day = first
+                    + (i64::from(weekday) - first_weekday).rem_euclid(7)
+                    + 7 * (i64::from(week) - 1);
+                let next_month = match month {
+                    12 => days_from_civil(year + 1, 1, 1),
+                    _ => days_from_civil(year, i64::from(month) + 1, 1),
+                };
+                while day >= next_month {
+                    day -= 7;
+                }
+                day
+            }
+        };
+        day * 86400 + self.time
+    }
+}
+
+struct Parser<'a>(&'a [u8]);
+
+impl<'a> Parser<'a> {
+    fn expect(&mut self, b: u8) -> Option<()> {
+        self.0 = self.0.strip_prefix(&[b])?;
+        Some(())
+    }
+
+    fn take_while(&mut self, f: impl Fn(u8) -> bool) -> &'a [u8] {
+        let len = self.0.iter().position(|b| !f(*b)).unwrap_or(self.0.len());
+        let (ret, rest) = self.0.split_at(len);
+        self.0 = rest;
+        ret
+    }
+
+    fn name(&mut self) -> Option<String> {
+        let name = if self.expect(b'<').is_some() {
+            let name = self.take_while(|b| b != b'>');
+            self.expect(b'>')?;
+            name
+        } else {
+            self.take_while(|b| b.is_ascii_alphabetic())
+        };
+        if name.len() < 3 {
+            return None;
+        }
+        String::from_utf8(name.to_vec()).ok()
+    }
+
+    /// Parses a number of at most three digits, which is all that the fields
+    /// of a TZ string ever need.
+    fn number(&mut self) -> Option<i64> {
+        let digits = self.take_while(|b| b.is_ascii_digit());
+        if digits.is_empty() || digits.len() > 3 {
+            return None;
+        }
+        digits.iter().try_fold(0i64, |n, d| {
+            n.checked_mul(10)?.checked_add(i64::from(d - b'0'))
+        })
+    }
+
+    /// Parses `[+-]hh[:mm[:ss]]` into seconds.
+    fn offset(&mut self) -> Option<i64> {
+        let sign = if self.expect(b'-').is_some() {
+            -1
+        } else {
+            let _ = self.expect(b'+');
+            1
+        };
+        // RFC 8536 extends the hours to 167 for the times of rules.
+        let hours = self.number().filter(|h| *h <= 167)?;
+        let mut secs = hours.checked_mul(3600)?;
+        if self.expect(b':').is_some() {
+            let mins = self.number().filter(|m| *m <= 59)?;
+            secs = secs.checked_add(mins.checked_mul(60)?)?;
+            if self.expect(b':').is_some() {
+                secs = secs.checked_add(self.number().filter(|s| *s <= 59)?)?;
+            }
+        }
+        secs.checked_mul(sign)
+    }
+
+    fn rule(&mut self) -> Option<Rule> {
+        let day = if self.expect(b'J').is_some() {
+            match u16::try_from(self.number()?).ok()? {
+                n @ 1..=365 => RuleDay::Julian(n),
+                _ => return None,
+            }
+        } else if self.expect(b'M').is_some() {
+            let month = u8::try_from(self.number()?).ok()?;
+            self.expect(b'.')?;
+            let week = u8::try_from(self.number()?).ok()?;
+            self.expect(b'.')?;
+            let weekday = u8::try_from(self.number()?).ok()?;
+            if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+                return None;
+            }
+            RuleDay::MonthWeekDay(month, week, weekday)
+        } else {
+            match u16::try_from(self.number()?).ok()? {
+                n @ 0..=365 => RuleDay::Zero(n),
+                _ => return None,
+            }
+        };
+        let time = if self.expect(b'/').is_some() {
+            self.offset()?
+        } else {
+            7200
+        };
+        Some(Rule { day, time })
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Returns the number of days since the Unix epoch of the given date in the
+/// proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The inverse of `days_from_civil`, returning `(year, month, day)`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unix_time(year: i64, month: i64, day: i64, hour: i64, min: i64) -> i64 {
+        days_from_civil(year, month, day) * 86400 + hour * 3600 + min * 60
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        for days in [-1, 0, 59, 365, 11016, 11017, 19000] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn posix_northern() {
+        let tz = TimeZone::from_posix("EST5EDT,M3.2.0,M11.1.0").unwrap();
+        let find = |t| {
+            let t = tz.find(t).unwrap();
+            (t.name, t.utc_offset, t.is_dst)
+        };
+        // 2023-03-12 at 2am local, 7am UTC, is the start of DST.
+        assert_eq!(
+            find(unix_time(2023, 3, 12, 6, 59)),
+            ("EST".into(), -18000, false)
+        );
+        assert_eq!(
+            find(unix_time(2023, 3, 12, 7, 0)),
+            ("EDT".into(), -14400, true)
+        );
+        // 2023-11-05 at 2am local daylight time, 6am UTC, is the end of DST.
+        assert_eq!(
+            find(unix_time(2023, 11, 5, 5, 59)),
+            ("EDT".into(), -14400, true)
+        );
+        assert_eq!(
+            find(unix_time(2023, 11, 5, 6, 0)),
+            ("EST".into(), -18000, false)
+        );
+    }
+
+    #[test]
+    fn posix_southern() {
+        let tz = TimeZone::from_posix("<+1030>-10:30<+11>-11,M10.1.0,M4.1.0").unwrap();
+        let january = tz.find(unix_time(2023, 1, 15, 0, 0)).unwrap();
+        assert_eq!((january.name.as_str(), january.utc_offset), ("+11", 39600));
+        let june = tz.find(unix_time(2023, 6, 15, 0, 0)).unwrap();
+        assert_eq!((june.name.as_str(), june.utc_offset), ("+1030", 37800));
+    }
+
+    #[test]
+    fn posix_invalid() {
+        for s in [
+            "",
+            "A1",
+            "EST",
+            "EST5EDT,M13.1.0,M11.1.0",
+            "EST5EDT,J0,J1",
+            "EST99999999999999999999",
+            "EST5EDT,M3.2.0/2562047788015215,M11.1.0",
+            "EST168",
+            "EST5:60",
+        ] {
+            assert!(TimeZone::from_posix(s).is_none(), "{s}");
+        }
+    }
+
+    #[test]
+    fn tzif_v2() {
+        fn header(out: &mut Vec<u8>, version: u8, timecnt: u32, typecnt: u32, charcnt: u32) {
+            out.extend_from_slice(b"TZif");
+            out.push(version);
+            out.extend_from_slice(&[0; 15]);
+            for count in [0, 0, 0, timecnt, typecnt, charcnt] {
+                out.extend_from_slice(&u32::to_be_bytes(count));
+            }
+        }
+
+        let mut file = Vec::new();
+        // An empty version 1 body, which readers of version 2 skip.
+        header(&mut file, b'2', 0, 1, 4);
+        file.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        file.extend_from_slice(b"UTC\0");
+        // One transition at 1000 from LMT to CET, then the footer rules.
+        header(&mut file, b'2', 1, 2, 8);
+        file.extend_from_slice(&1000i64.to_be_bytes());
+        file.push(1);
+        file.extend_from_slice(&561i32.to_be_bytes());
+        file.extend_from_slice(&[0, 0]);
+        file.extend_from_slice(&3600i32.to_be_bytes());
+        file.extend_from_slice(&[0, 4]);
+        file.extend_from_slice(b"LMT\0CET\0");
+        file.extend_from_slice(b"\nCET-1CEST,M3.5.0,M10.5.0/3\n");
+
+        let tz = TimeZone::parse(&file).unwrap();
+        assert_eq!(tz.find(0).unwrap().name, "LMT");
+        assert_eq!(tz.find(1000).unwrap().name, "CET");
+        let summer = tz.find(unix_time(2023, 7, 1, 0, 0)).unwrap();
+        assert_eq!((summer.name.as_str(), summer.utc_offset), ("CEST", 7200));
+        assert!(summer.is_dst);
+    }
+}
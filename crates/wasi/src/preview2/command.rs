@@ -39,6 +39,12 @@ pub fn add_to_linker<T: WasiView>(l: &mut wasmtime::component::Linker<T>) -> any
     crate::preview2::bindings::cli_base::stdin::add_to_linker(l, |t| t)?;
     crate::preview2::bindings::cli_base::stdout::add_to_linker(l, |t| t)?;
     crate::preview2::bindings::cli_base::stderr::add_to_linker(l, |t| t)?;
+    crate::preview2::bindings::terminal::terminal_input::add_to_linker(l, |t| t)?;
+    crate::preview2::bindings::terminal::terminal_output::add_to_linker(l, |t| t)?;
+    crate::preview2::bindings::terminal::terminal_stdin::add_to_linker(l, |t| t)?;
+    crate::preview2::bindings::terminal::terminal_stdout::add_to_linker(l, |t| t)?;
+    crate::preview2::bindings::terminal::terminal_stderr::add_to_linker(l, |t| t)?;
+    crate::preview2::bindings::logging::handler::add_to_linker(l, |t| t)?;
     Ok(())
 }
 
@@ -86,6 +92,12 @@ pub mod sync {
         crate::preview2::bindings::cli_base::stdin::add_to_linker(l, |t| t)?;
         crate::preview2::bindings::cli_base::stdout::add_to_linker(l, |t| t)?;
         crate::preview2::bindings::cli_base::stderr::add_to_linker(l, |t| t)?;
+        crate::preview2::bindings::terminal::terminal_input::add_to_linker(l, |t| t)?;
+        crate::preview2::bindings::terminal::terminal_output::add_to_linker(l, |t| t)?;
+        crate::preview2::bindings::terminal::terminal_stdin::add_to_linker(l, |t| t)?;
+        crate::preview2::bindings::terminal::terminal_stdout::add_to_linker(l, |t| t)?;
+        crate::preview2::bindings::terminal::terminal_stderr::add_to_linker(l, |t| t)?;
+        crate::preview2::bindings::logging::handler::add_to_linker(l, |t| t)?;
         Ok(())
     }
 }
@@ -1,27 +1,45 @@
-use super::clocks::host::{monotonic_clock, wall_clock};
+use super::clocks::host::{monotonic_clock, timezone, wall_clock};
 use crate::preview2::{
-    clocks::{self, HostMonotonicClock, HostWallClock},
-    filesystem::{Dir, TableFsExt},
-    pipe, random, stdio,
+    bindings::logging::handler::Level,
+    clocks::{self, HostMonotonicClock, HostTimezone, HostWallClock, UtcTimezone},
+    filesystem::{AclDir, Dir, HostDir, OsDir, PathAcl, TableFsExt},
+    logging::{ForwardingLogger, HostLogger},
+    pipe,
+    quota::{LimitedOutputStream, Quota},
+    random, stdio,
     stream::{HostInputStream, HostOutputStream, TableStreamExt},
+    tcp::{HostTcpListener, TableTcpExt},
+    terminal::{HostTerminal, TtyTerminal},
     DirPerms, FilePerms, Table,
 };
 use cap_rand::{Rng, RngCore, SeedableRng};
 use std::mem;
+use std::sync::Arc;
 
 pub struct WasiCtxBuilder {
     stdin: Box<dyn HostInputStream>,
     stdout: Box<dyn HostOutputStream>,
     stderr: Box<dyn HostOutputStream>,
+    stdin_terminal: Option<Arc<dyn HostTerminal>>,
+    stdout_terminal: Option<Arc<dyn HostTerminal>>,
+    stderr_terminal: Option<Arc<dyn HostTerminal>>,
     env: Vec<(String, String)>,
     args: Vec<String>,
     preopens: Vec<(Dir, String)>,
+    preopened_sockets: Vec<cap_std::net::TcpListener>,
 
     random: Box<dyn RngCore + Send + Sync>,
     insecure_random: Box<dyn RngCore + Send + Sync>,
     insecure_random_seed: u128,
     wall_clock: Box<dyn HostWallClock + Send + Sync>,
     monotonic_clock: Box<dyn HostMonotonicClock + Send + Sync>,
+    timezone: Box<dyn HostTimezone + Send + Sync>,
+    logger: Box<dyn HostLogger>,
+    log_level: Option<Level>,
+    max_table_entries: Option<usize>,
+    max_bytes_written: Option<u64>,
+    max_files_created: Option<u64>,
+    max_stdio_bytes: Option<u64>,
     built: bool,
 }
 
@@ -36,6 +54,9 @@ impl WasiCtxBuilder {
     /// * no arguments
     /// * no preopens
     /// * clocks use the host implementation of wall/monotonic clocks
+    /// * the timezone is UTC
+    /// * guest log messages of every level are forwarded to the `log` crate
+    /// * there are no quotas on the resources the guest uses
     /// * RNGs are all initialized with random state and suitable generator
     ///   quality to satisfy the requirements of WASI APIs.
     ///
@@ -59,43 +80,93 @@ impl WasiCtxBuilder {
             stdin: Box::new(pipe::ClosedInputStream),
             stdout: Box::new(pipe::SinkOutputStream),
             stderr: Box::new(pipe::SinkOutputStream),
+            stdin_terminal: None,
+            stdout_terminal: None,
+            stderr_terminal: None,
             env: Vec::new(),
             args: Vec::new(),
             preopens: Vec::new(),
+            preopened_sockets: Vec::new(),
             random: random::thread_rng(),
             insecure_random,
             insecure_random_seed,
             wall_clock: wall_clock(),
             monotonic_clock: monotonic_clock(),
+            timezone: Box::new(UtcTimezone),
+            logger: Box::new(ForwardingLogger),
+            log_level: Some(Level::Trace),
+            max_table_entries: None,
+            max_bytes_written: None,
+            max_files_created: None,
+            max_stdio_bytes: None,
             built: false,
         }
     }
 
+    /// Sets the guest's stdin, which isn't connected to a terminal unless
+    /// [`stdin_terminal`](WasiCtxBuilder::stdin_terminal) is used afterwards.
     pub fn stdin(&mut self, stdin: impl HostInputStream + 'static) -> &mut Self {
         self.stdin = Box::new(stdin);
+        self.stdin_terminal = None;
         self
     }
 
+    /// Sets the guest's stdout, which isn't connected to a terminal unless
+    /// [`stdout_terminal`](WasiCtxBuilder::stdout_terminal) is used afterwards.
     pub fn stdout(&mut self, stdout: impl HostOutputStream + 'static) -> &mut Self {
         self.stdout = Box::new(stdout);
+        self.stdout_terminal = None;
         self
     }
 
+    /// Sets the guest's stderr, which isn't connected to a terminal unless
+    /// [`stderr_terminal`](WasiCtxBuilder::stderr_terminal) is used afterwards.
     pub fn stderr(&mut self, stderr: impl HostOutputStream + 'static) -> &mut Self {
         self.stderr = Box::new(stderr);
+        self.stderr_terminal = None;
         self
     }
 
+    /// Sets the terminal which stdin is connected to, which the guest can
+    /// put into raw mode.
+    pub fn stdin_terminal(&mut self, terminal: impl HostTerminal + 'static) -> &mut Self {
+        self.stdin_terminal = Some(Arc::new(terminal));
+        self
+    }
+
+    /// Sets the terminal which stdout is connected to, which the guest can
+    /// query the window size of.
+    pub fn stdout_terminal(&mut self, terminal: impl HostTerminal + 'static) -> &mut Self {
+        self.stdout_terminal = Some(Arc::new(terminal));
+        self
+    }
+
+    /// Sets the terminal which stderr is connected to, which the guest can
+    /// query the window size of.
+    pub fn stderr_terminal(&mut self, terminal: impl HostTerminal + 'static) -> &mut Self {
+        self.stderr_terminal = Some(Arc::new(terminal));
+        self
+    }
+
+    /// Inherits the host's stdin, along with its terminal if it has one.
     pub fn inherit_stdin(&mut self) -> &mut Self {
-        self.stdin(stdio::stdin())
+        self.stdin(stdio::stdin());
+        self.stdin_terminal = TtyTerminal::stdin().map(|t| Arc::new(t) as Arc<dyn HostTerminal>);
+        self
     }
 
+    /// Inherits the host's stdout, along with its terminal if it has one.
     pub fn inherit_stdout(&mut self) -> &mut Self {
-        self.stdout(stdio::stdout())
+        self.stdout(stdio::stdout());
+        self.stdout_terminal = TtyTerminal::stdout().map(|t| Arc::new(t) as Arc<dyn HostTerminal>);
+        self
     }
 
+    /// Inherits the host's stderr, along with its terminal if it has one.
     pub fn inherit_stderr(&mut self) -> &mut Self {
-        self.stderr(stdio::stderr())
+        self.stderr(stdio::stderr());
+        self.stderr_terminal = TtyTerminal::stderr().map(|t| Arc::new(t) as Arc<dyn HostTerminal>);
+        self
     }
 
     pub fn inherit_stdio(&mut self) -> &mut Self {
@@ -133,8 +204,46 @@ impl WasiCtxBuilder {
         file_perms: FilePerms,
         path: impl AsRef<str>,
     ) -> &mut Self {
-        self.preopens
-            .push((Dir::new(dir, perms, file_perms), path.as_ref().to_owned()));
+        self.preopened_host_dir(OsDir::new(dir), perms, file_perms, path)
+    }
+
+    /// Like [`preopened_dir`](WasiCtxBuilder::preopened_dir), but also
+    /// enforces the path-level rules in `acl`, such as hiding `**/.git`.
+    pub fn preopened_dir_with_acl(
+        &mut self,
+        dir: cap_std::fs::Dir,
+        perms: DirPerms,
+        file_perms: FilePerms,
+        acl: PathAcl,
+        path: impl AsRef<str>,
+    ) -> &mut Self {
+        self.preopened_host_dir(AclDir::new(OsDir::new(dir), acl), perms, file_perms, path)
+    }
+
+    /// Preopens a listening TCP socket. Guests using preview1 see it as a
+    /// descriptor numbered after stdio and before any preopened directories,
+    /// on which they can call `sock_accept`.
+    ///
+    /// If `listener` is in non-blocking mode, `sock_accept` fails with
+    /// `EAGAIN` when no connection is pending.
+    pub fn preopened_tcp_listener(&mut self, listener: cap_std::net::TcpListener) -> &mut Self {
+        self.preopened_sockets.push(listener);
+        self
+    }
+
+    /// Preopens a directory implemented by the embedder, such as a
+    /// [`MemoryDir`](crate::preview2::MemoryDir), at `path` in the guest.
+    pub fn preopened_host_dir(
+        &mut self,
+        dir: impl HostDir,
+        perms: DirPerms,
+        file_perms: FilePerms,
+        path: impl AsRef<str>,
+    ) -> &mut Self {
+        self.preopens.push((
+            Dir::new(Box::new(dir), perms, file_perms),
+            path.as_ref().to_owned(),
+        ));
         self
     }
 
@@ -178,6 +287,69 @@ impl WasiCtxBuilder {
         self
     }
 
+    /// Configures the timezone reported by `wasi:clocks/timezone`.
+    pub fn timezone(&mut self, timezone: impl clocks::HostTimezone + 'static) -> &mut Self {
+        self.timezone = Box::new(timezone);
+        self
+    }
+
+    /// Reports the host's timezone to the guest, rather than UTC.
+    pub fn inherit_timezone(&mut self) -> &mut Self {
+        self.timezone = timezone();
+        self
+    }
+
+    /// Configures where messages from `wasi:logging/handler` go. By default
+    /// they're forwarded to the `log` crate, see [`ForwardingLogger`].
+    pub fn logger(&mut self, logger: impl HostLogger + 'static) -> &mut Self {
+        self.logger = Box::new(logger);
+        self
+    }
+
+    /// Drops guest log messages less severe than `level` before they reach
+    /// the logger. `None` drops every message.
+    pub fn log_level(&mut self, level: Option<Level>) -> &mut Self {
+        self.log_level = level;
+        self
+    }
+
+    /// Limits the number of entries in the [`Table`], such as open files
+    /// and streams, which the guest may hold at once.
+    ///
+    /// Operations which would exceed this fail with `error-code::quota`
+    /// where the interface allows for an error, and trap otherwise. The
+    /// limit also covers entries inserted by the host, including those
+    /// inserted by [`build`](WasiCtxBuilder::build).
+    pub fn max_table_entries(&mut self, max: usize) -> &mut Self {
+        self.max_table_entries = Some(max);
+        self
+    }
+
+    /// Limits the total number of bytes the guest may write to files, over
+    /// its lifetime. Writes beyond this fail with `error-code::quota`, or
+    /// with a stream error when writing through an output stream. The zeros
+    /// filling a file which is extended, by `set-size` or by writing past its
+    /// end, count as written too.
+    pub fn max_bytes_written(&mut self, max: u64) -> &mut Self {
+        self.max_bytes_written = Some(max);
+        self
+    }
+
+    /// Limits the total number of files, directories and links the guest may
+    /// create, over its lifetime. Creating more fails with
+    /// `error-code::quota`.
+    pub fn max_files_created(&mut self, max: u64) -> &mut Self {
+        self.max_files_created = Some(max);
+        self
+    }
+
+    /// Limits the total number of bytes the guest may write to stdout and
+    /// stderr combined. Writes beyond this fail with a stream error.
+    pub fn max_stdio_bytes(&mut self, max: u64) -> &mut Self {
+        self.max_stdio_bytes = Some(max);
+        self
+    }
+
     /// Uses the configured context so far to construct the final `WasiCtx`.
     ///
     /// This will insert resources into the provided `table`.
@@ -196,18 +368,44 @@ impl WasiCtxBuilder {
             stdin,
             stdout,
             stderr,
+            stdin_terminal,
+            stdout_terminal,
+            stderr_terminal,
             env,
             args,
             preopens,
+            preopened_sockets,
             random,
             insecure_random,
             insecure_random_seed,
             wall_clock,
             monotonic_clock,
+            timezone,
+            logger,
+            log_level,
+            max_table_entries,
+            max_bytes_written,
+            max_files_created,
+            max_stdio_bytes,
             built: _,
         } = mem::replace(self, Self::new());
         self.built = true;
 
+        if let Some(max) = max_table_entries {
+            table.set_max_entries(max);
+        }
+        let (stdout, stderr) = match max_stdio_bytes {
+            Some(max) => {
+                let quota = Quota::new(Some(max));
+                let stdout: Box<dyn HostOutputStream> =
+                    Box::new(LimitedOutputStream::new(stdout, quota.clone()));
+                let stderr: Box<dyn HostOutputStream> =
+                    Box::new(LimitedOutputStream::new(stderr, quota));
+                (stdout, stderr)
+            }
+            None => (stdout, stderr),
+        };
+
         let stdin = table.push_input_stream(stdin).context("stdin")?;
         let stdout = table.push_output_stream(stdout).context("stdout")?;
         let stderr = table.push_output_stream(stderr).context("stderr")?;
@@ -221,19 +419,36 @@ impl WasiCtxBuilder {
                 Ok((dirfd, path))
             })
             .collect::<anyhow::Result<Vec<_>>>()?;
+        let preopened_sockets = preopened_sockets
+            .into_iter()
+            .map(|listener| {
+                table
+                    .push_tcp_listener(HostTcpListener::new(listener))
+                    .context("preopened socket")
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
         Ok(WasiCtx {
             stdin,
             stdout,
             stderr,
+            stdin_terminal,
+            stdout_terminal,
+            stderr_terminal,
             env,
             args,
             preopens,
+            preopened_sockets,
             random,
             insecure_random,
             insecure_random_seed,
             wall_clock,
             monotonic_clock,
+            timezone,
+            logger,
+            log_level,
+            bytes_written: Quota::new(max_bytes_written),
+            files_created: Quota::new(max_files_created),
         })
     }
 }
@@ -251,10 +466,58 @@ pub struct WasiCtx {
     pub(crate) insecure_random_seed: u128,
     pub(crate) wall_clock: Box<dyn HostWallClock + Send + Sync>,
     pub(crate) monotonic_clock: Box<dyn HostMonotonicClock + Send + Sync>,
+    pub(crate) timezone: Box<dyn HostTimezone + Send + Sync>,
+    pub(crate) logger: Box<dyn HostLogger>,
+    pub(crate) log_level: Option<Level>,
+    pub(crate) bytes_written: Arc<Quota>,
+    pub(crate) files_created: Arc<Quota>,
     pub(crate) env: Vec<(String, String)>,
     pub(crate) args: Vec<String>,
     pub(crate) preopens: Vec<(u32, String)>,
+    pub(crate) preopened_sockets: Vec<u32>,
     pub(crate) stdin: u32,
     pub(crate) stdout: u32,
     pub(crate) stderr: u32,
+    pub(crate) stdin_terminal: Option<Arc<dyn HostTerminal>>,
+    pub(crate) stdout_terminal: Option<Arc<dyn HostTerminal>>,
+    pub(crate) stderr_terminal: Option<Arc<dyn HostTerminal>>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io;
+
+    struct FakeTerminal;
+
+    impl HostTerminal for FakeTerminal {
+        fn set_raw_mode(&self, _enabled: bool) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn window_size(&self) -> Option<(u16, u16)> {
+            Some((80, 24))
+        }
+    }
+
+    #[test]
+    fn replacing_stdio_drops_terminals() {
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .stdin_terminal(FakeTerminal)
+            .stdout_terminal(FakeTerminal)
+            .stderr_terminal(FakeTerminal);
+        builder
+            .stdin(pipe::ClosedInputStream)
+            .stdout(pipe::SinkOutputStream)
+            .stderr(pipe::SinkOutputStream);
+        assert!(builder.stdin_terminal.is_none());
+        assert!(builder.stdout_terminal.is_none());
+        assert!(builder.stderr_terminal.is_none());
+
+        builder
+            .stdout(pipe::SinkOutputStream)
+            .stdout_terminal(FakeTerminal);
+        assert!(builder.stdout_terminal.is_some());
+    }
 }
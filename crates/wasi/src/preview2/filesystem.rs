@@ -1,7 +1,25 @@
+use crate::preview2::bindings::clocks::wall_clock;
+use crate::preview2::bindings::filesystem::types;
+use crate::preview2::quota::Quota;
 use crate::preview2::{StreamState, Table, TableError};
-use bytes::{Bytes, BytesMut};
+use bytes::Bytes;
+use std::any::Any;
 use std::sync::Arc;
 
+mod acl;
+mod memory;
+mod os;
+
+pub use self::acl::{AclDir, PathAccess, PathAcl};
+pub use self::memory::MemoryDir;
+pub(crate) use self::os::OsDir;
+
+/// Result type of the methods of [`HostDir`] and [`HostFile`].
+///
+/// Errors are reported to the guest as a `wasi:filesystem/types.error-code`,
+/// or trap the guest if they were created with `types::Error::trap`.
+pub type FsResult<T> = Result<T, types::Error>;
+
 bitflags::bitflags! {
     #[derive(Copy, Clone, Debug, PartialEq, Eq)]
     pub struct FilePerms: usize {
@@ -10,31 +28,300 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct DirPerms: usize {
+        const READ = 0b1;
+        const MUTATE = 0b10;
+    }
+}
+
+/// The result of successfully opening a path with [`HostDir::open_at`].
+pub enum OpenResult {
+    Dir(Box<dyn HostDir>),
+    File(Box<dyn HostFile>),
+}
+
+/// A directory which can be preopened for a guest with
+/// [`WasiCtxBuilder::preopened_host_dir`](crate::preview2::WasiCtxBuilder::preopened_host_dir).
+///
+/// The `wasi:filesystem/types` host implementation dispatches all operations
+/// on directory descriptors through this trait. Wasmtime provides an
+/// implementation backed by the host filesystem, used by
+/// [`WasiCtxBuilder::preopened_dir`](crate::preview2::WasiCtxBuilder::preopened_dir),
+/// and an in-memory implementation, [`MemoryDir`].
+///
+/// Permission checks based on [`DirPerms`] and [`FilePerms`] happen before
+/// these methods are called, so implementations don't need to repeat them.
+/// Paths are relative to this directory, and implementations are responsible
+/// for not resolving paths to anything outside of it.
+#[async_trait::async_trait]
+pub trait HostDir: Send + Sync + 'static {
+    /// Opens the file or directory at `path`.
+    async fn open_at(
+        &self,
+        path: &str,
+        path_flags: types::PathFlags,
+        oflags: types::OpenFlags,
+        flags: types::DescriptorFlags,
+    ) -> FsResult<OpenResult>;
+
+    /// Creates a new directory at `path`.
+    async fn create_directory_at(&self, path: &str) -> FsResult<()>;
+
+    /// Returns the attributes of this directory.
+    async fn stat(&self) -> FsResult<types::DescriptorStat>;
+
+    /// Returns the attributes of the file or directory at `path`.
+    async fn stat_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+    ) -> FsResult<types::DescriptorStat>;
+
+    /// Returns the entries of this directory, excluding `.` and `..`.
+    async fn read_directory(&self) -> FsResult<Vec<FsResult<types::DirectoryEntry>>>;
+
+    /// Removes the empty directory at `path`.
+    async fn remove_directory_at(&self, path: &str) -> FsResult<()>;
+
+    /// Removes the file or symbolic link at `path`.
+    async fn unlink_file_at(&self, path: &str) -> FsResult<()>;
+
+    /// Renames `old_path` in this directory to `new_path` in `new_dir`.
+    ///
+    /// `new_dir` can be downcast with [`HostDir::as_any`]. Implementations
+    /// should return `ErrorCode::CrossDevice` if it isn't a directory they
+    /// can rename into.
+    async fn rename_at(
+        &self,
+        old_path: &str,
+        new_dir: &dyn HostDir,
+        new_path: &str,
+    ) -> FsResult<()>;
+
+    /// Creates a hard link named `new_path` in `new_dir` to `old_path` in
+    /// this directory.
+    ///
+    /// The default implementation returns `ErrorCode::Unsupported`.
+    async fn link_at(
+        &self,
+        _old_path: &str,
+        _new_dir: &dyn HostDir,
+        _new_path: &str,
+    ) -> FsResult<()> {
+        Err(types::ErrorCode::Unsupported.into())
+    }
+
+    /// Creates a symbolic link at `dest_path` pointing to `src_path`.
+    ///
+    /// The default implementation returns `ErrorCode::Unsupported`.
+    async fn symlink_at(&self, _src_path: &str, _dest_path: &str) -> FsResult<()> {
+        Err(types::ErrorCode::Unsupported.into())
+    }
+
+    /// Reads the contents of the symbolic link at `path`.
+    ///
+    /// The default implementation returns `ErrorCode::Invalid`, as there
+    /// are no symbolic links.
+    async fn readlink_at(&self, _path: &str) -> FsResult<String> {
+        Err(types::ErrorCode::Invalid.into())
+    }
+
+    /// Checks whether the file or directory at `path` exists or is
+    /// accessible with the given modes.
+    async fn access_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        access: types::AccessType,
+    ) -> FsResult<()>;
+
+    /// Changes the permissions of the file at `path`.
+    ///
+    /// The default implementation returns `ErrorCode::Unsupported`.
+    async fn change_file_permissions_at(
+        &self,
+        _path_flags: types::PathFlags,
+        _path: &str,
+        _modes: types::Modes,
+    ) -> FsResult<()> {
+        Err(types::ErrorCode::Unsupported.into())
+    }
+
+    /// Changes the permissions of the directory at `path`. `modes` never
+    /// contains `Modes::EXECUTABLE`.
+    ///
+    /// The default implementation returns `ErrorCode::Unsupported`.
+    async fn change_directory_permissions_at(
+        &self,
+        _path_flags: types::PathFlags,
+        _path: &str,
+        _modes: types::Modes,
+    ) -> FsResult<()> {
+        Err(types::ErrorCode::Unsupported.into())
+    }
+
+    /// Adjusts the timestamps of this directory.
+    async fn set_times(&self, atim: types::NewTimestamp, mtim: types::NewTimestamp)
+        -> FsResult<()>;
+
+    /// Adjusts the timestamps of the file or directory at `path`.
+    async fn set_times_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()>;
+
+    /// Returns a hash of this directory's identity, see
+    /// [`HostDir::metadata_hash_at`].
+    async fn metadata_hash(&self) -> FsResult<types::MetadataHashValue>;
+
+    /// Returns a hash identifying the file or directory at `path`.
+    ///
+    /// Two descriptors are considered the same object by the guest if their
+    /// hashes are equal.
+    async fn metadata_hash_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+    ) -> FsResult<types::MetadataHashValue>;
+
+    /// Returns the synchronization flags of this directory. Read and mutate
+    /// flags are filled in by the caller from [`DirPerms`].
+    async fn get_flags(&self) -> FsResult<types::DescriptorFlags> {
+        Ok(types::DescriptorFlags::empty())
+    }
+
+    /// Synchronizes this directory's data to storage.
+    async fn sync_data(&self) -> FsResult<()> {
+        Ok(())
+    }
+
+    /// Synchronizes this directory's data and metadata to storage.
+    async fn sync(&self) -> FsResult<()> {
+        Ok(())
+    }
+
+    /// Returns `self` as `Any`, used to implement operations across two
+    /// directories such as [`HostDir::rename_at`].
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// A file opened through a [`HostDir`].
+///
+/// The `wasi:filesystem/types` host implementation dispatches all operations
+/// on file descriptors, as well as reads and writes of streams created from
+/// them, through this trait.
+#[async_trait::async_trait]
+pub trait HostFile: Send + Sync + 'static {
+    /// Reads up to `len` bytes starting at `offset`. Returning an empty
+    /// buffer for a non-zero `len` indicates the end of the file.
+    async fn read_at(&self, len: usize, offset: u64) -> FsResult<Bytes>;
+
+    /// Writes `buf` at `offset`, returning the number of bytes written.
+    async fn write_at(&self, buf: Bytes, offset: u64) -> FsResult<usize>;
+
+    /// Writes `buf` at the end of the file, returning the number of bytes
+    /// written.
+    async fn append(&self, buf: Bytes) -> FsResult<usize>;
+
+    /// Truncates or extends the file to `size` bytes.
+    async fn set_size(&self, size: u64) -> FsResult<()>;
+
+    /// Returns the attributes of this file.
+    async fn stat(&self) -> FsResult<types::DescriptorStat>;
+
+    /// Adjusts the timestamps of this file.
+    async fn set_times(&self, atim: types::NewTimestamp, mtim: types::NewTimestamp)
+        -> FsResult<()>;
+
+    /// Returns a hash identifying this file, see
+    /// [`HostDir::metadata_hash_at`].
+    async fn metadata_hash(&self) -> FsResult<types::MetadataHashValue>;
+
+    /// Returns the synchronization flags of this file. Read and write flags
+    /// are filled in by the caller from [`FilePerms`].
+    async fn get_flags(&self) -> FsResult<types::DescriptorFlags> {
+        Ok(types::DescriptorFlags::empty())
+    }
+
+    /// Provides file advisory information. The default implementation
+    /// ignores the advice.
+    async fn advise(&self, _offset: u64, _len: u64, _advice: types::Advice) -> FsResult<()> {
+        Ok(())
+    }
+
+    /// Synchronizes this file's data to storage.
+    async fn sync_data(&self) -> FsResult<()> {
+        Ok(())
+    }
+
+    /// Synchronizes this file's data and metadata to storage.
+    async fn sync(&self) -> FsResult<()> {
+        Ok(())
+    }
+
+    /// Acquires a shared advisory lock on this file, waiting until it's
+    /// available. An exclusive lock held through this file is downgraded.
+    ///
+    /// This and the other locking methods return `ErrorCode::Unsupported`
+    /// by default.
+    async fn lock_shared(&self) -> FsResult<()> {
+        Err(types::ErrorCode::Unsupported.into())
+    }
+
+    /// Acquires an exclusive advisory lock on this file, waiting until it's
+    /// available. A shared lock held through this file is upgraded.
+    async fn lock_exclusive(&self) -> FsResult<()> {
+        Err(types::ErrorCode::Unsupported.into())
+    }
+
+    /// Like [`HostFile::lock_shared`], but returns `ErrorCode::WouldBlock`
+    /// rather than waiting.
+    async fn try_lock_shared(&self) -> FsResult<()> {
+        Err(types::ErrorCode::Unsupported.into())
+    }
+
+    /// Like [`HostFile::lock_exclusive`], but returns
+    /// `ErrorCode::WouldBlock` rather than waiting.
+    async fn try_lock_exclusive(&self) -> FsResult<()> {
+        Err(types::ErrorCode::Unsupported.into())
+    }
+
+    /// Releases any advisory lock held through this file.
+    async fn unlock(&self) -> FsResult<()> {
+        Err(types::ErrorCode::Unsupported.into())
+    }
+}
+
+/// Takes the zeros a write at `offset` fills the gap past the end of `file`
+/// with from `quota`, returning how many that was. Fails with
+/// `ErrorCode::Quota` if there aren't enough left.
+pub(crate) async fn charge_gap(file: &dyn HostFile, offset: u64, quota: &Quota) -> FsResult<u64> {
+    let gap = offset.saturating_sub(file.stat().await?.size);
+    if !quota.take_all(gap) {
+        return Err(types::ErrorCode::Quota.into());
+    }
+    Ok(gap)
+}
+
 pub(crate) struct File {
     /// Wrapped in an Arc because the same underlying file is used for
-    /// implementing the stream types. Also needed for [`block`].
-    pub file: Arc<cap_std::fs::File>,
+    /// implementing the stream types.
+    pub file: Arc<dyn HostFile>,
     pub perms: FilePerms,
 }
 
 impl File {
-    pub fn new(file: cap_std::fs::File, perms: FilePerms) -> Self {
+    pub fn new(file: Box<dyn HostFile>, perms: FilePerms) -> Self {
         Self {
-            file: Arc::new(file),
+            file: file.into(),
             perms,
         }
     }
-
-    /// Spawn a task on tokio's blocking thread for performing blocking
-    /// syscalls on the underlying [`cap_std::fs::File`].
-    pub(crate) async fn spawn_blocking<F, R>(&self, body: F) -> R
-    where
-        F: FnOnce(&cap_std::fs::File) -> R + Send + 'static,
-        R: Send + 'static,
-    {
-        let f = self.file.clone();
-        tokio::task::spawn_blocking(move || body(&f)).await.unwrap()
-    }
 }
 pub(crate) trait TableFsExt {
     fn push_file(&mut self, file: File) -> Result<u32, TableError>;
@@ -76,65 +363,52 @@ impl TableFsExt for Table {
     }
 }
 
-bitflags::bitflags! {
-    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-    pub struct DirPerms: usize {
-        const READ = 0b1;
-        const MUTATE = 0b10;
-    }
-}
-
 pub(crate) struct Dir {
-    pub dir: Arc<cap_std::fs::Dir>,
+    pub dir: Arc<dyn HostDir>,
     pub perms: DirPerms,
     pub file_perms: FilePerms,
 }
 
 impl Dir {
-    pub fn new(dir: cap_std::fs::Dir, perms: DirPerms, file_perms: FilePerms) -> Self {
+    pub fn new(dir: Box<dyn HostDir>, perms: DirPerms, file_perms: FilePerms) -> Self {
         Dir {
-            dir: Arc::new(dir),
+            dir: dir.into(),
             perms,
             file_perms,
         }
     }
+}
 
-    /// Spawn a task on tokio's blocking thread for performing blocking
-    /// syscalls on the underlying [`cap_std::fs::Dir`].
-    pub(crate) async fn spawn_blocking<F, R>(&self, body: F) -> R
-    where
-        F: FnOnce(&cap_std::fs::Dir) -> R + Send + 'static,
-        R: Send + 'static,
-    {
-        let d = self.dir.clone();
-        tokio::task::spawn_blocking(move || body(&d)).await.unwrap()
-    }
+pub(crate) fn systemtime_from(t: wall_clock::Datetime) -> FsResult<std::time::SystemTime> {
+    use std::time::{Duration, SystemTime};
+    SystemTime::UNIX_EPOCH
+        .checked_add(Duration::new(t.seconds, t.nanoseconds))
+        .ok_or_else(|| types::ErrorCode::Overflow.into())
+}
+
+pub(crate) fn datetime_from(t: std::time::SystemTime) -> wall_clock::Datetime {
+    // FIXME make this infallible or handle errors properly
+    wall_clock::Datetime::try_from(cap_std::time::SystemTime::from_std(t)).unwrap()
 }
 
 pub(crate) struct FileInputStream {
-    file: Arc<cap_std::fs::File>,
+    file: Arc<dyn HostFile>,
     position: u64,
 }
 impl FileInputStream {
-    pub fn new(file: Arc<cap_std::fs::File>, position: u64) -> Self {
+    pub fn new(file: Arc<dyn HostFile>, position: u64) -> Self {
         Self { file, position }
     }
 
     pub async fn read(&mut self, size: usize) -> anyhow::Result<(Bytes, StreamState)> {
-        use system_interface::fs::FileIoExt;
-        let f = Arc::clone(&self.file);
-        let p = self.position;
-        let (r, mut buf) = tokio::task::spawn_blocking(move || {
-            let mut buf = BytesMut::zeroed(size);
-            let r = f.read_at(&mut buf, p);
-            (r, buf)
-        })
-        .await
-        .unwrap();
-        let (n, state) = read_result(r)?;
-        buf.truncate(n);
-        self.position += n as u64;
-        Ok((buf.freeze(), state))
+        let buf = self.file.read_at(size, self.position).await?;
+        let state = if buf.is_empty() && size > 0 {
+            StreamState::Closed
+        } else {
+            StreamState::Open
+        };
+        self.position += buf.len() as u64;
+        Ok((buf, state))
     }
 
     pub async fn skip(&mut self, nelem: usize) -> anyhow::Result<(usize, StreamState)> {
@@ -152,27 +426,6 @@ impl FileInputStream {
     }
 }
 
-pub(crate) fn read_result(
-    r: Result<usize, std::io::Error>,
-) -> Result<(usize, StreamState), std::io::Error> {
-    match r {
-        Ok(0) => Ok((0, StreamState::Closed)),
-        Ok(n) => Ok((n, StreamState::Open)),
-        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => Ok((0, StreamState::Open)),
-        Err(e) => Err(e),
-    }
-}
-
-pub(crate) fn write_result(
-    r: Result<usize, std::io::Error>,
-) -> Result<(usize, StreamState), std::io::Error> {
-    match r {
-        Ok(0) => Ok((0, StreamState::Closed)),
-        Ok(n) => Ok((n, StreamState::Open)),
-        Err(e) => Err(e),
-    }
-}
-
 #[derive(Clone, Copy)]
 pub(crate) enum FileOutputMode {
     Position(u64),
@@ -180,37 +433,97 @@ pub(crate) enum FileOutputMode {
 }
 
 pub(crate) struct FileOutputStream {
-    file: Arc<cap_std::fs::File>,
+    file: Arc<dyn HostFile>,
     mode: FileOutputMode,
+    quota: Arc<Quota>,
 }
 impl FileOutputStream {
-    pub fn write_at(file: Arc<cap_std::fs::File>, position: u64) -> Self {
+    pub fn write_at(file: Arc<dyn HostFile>, position: u64, quota: Arc<Quota>) -> Self {
         Self {
             file,
             mode: FileOutputMode::Position(position),
+            quota,
         }
     }
-    pub fn append(file: Arc<cap_std::fs::File>) -> Self {
+    pub fn append(file: Arc<dyn HostFile>, quota: Arc<Quota>) -> Self {
         Self {
             file,
             mode: FileOutputMode::Append,
+            quota,
         }
     }
     /// Write bytes. On success, returns the number of bytes written.
-    pub async fn write(&mut self, buf: Bytes) -> anyhow::Result<(usize, StreamState)> {
-        use system_interface::fs::FileIoExt;
-        let f = Arc::clone(&self.file);
-        let m = self.mode;
-        let r = tokio::task::spawn_blocking(move || match m {
-            FileOutputMode::Position(p) => f.write_at(buf.as_ref(), p),
-            FileOutputMode::Append => f.append(buf.as_ref()),
-        })
-        .await
-        .unwrap();
-        let (n, state) = write_result(r)?;
+    pub async fn write(&mut self, mut buf: Bytes) -> anyhow::Result<(usize, StreamState)> {
+        let gap = match self.mode {
+            FileOutputMode::Position(p) if !buf.is_empty() => {
+                charge_gap(&*self.file, p, &self.quota).await?
+            }
+            _ => 0,
+        };
+        if !self.quota.reserve(&mut buf) {
+            self.quota.give_back(gap);
+            anyhow::bail!("quota of bytes written exceeded");
+        }
+        let len = buf.len();
+        let result = match self.mode {
+            FileOutputMode::Position(p) => self.file.write_at(buf, p).await,
+            FileOutputMode::Append => self.file.append(buf).await,
+        };
+        let written = result.as_ref().map_or(0, |n| *n);
+        self.quota.settle(len, written);
+        if written == 0 {
+            self.quota.give_back(gap);
+        }
+        let n = result?;
+        let state = if n == 0 && len > 0 {
+            StreamState::Closed
+        } else {
+            StreamState::Open
+        };
         if let FileOutputMode::Position(ref mut position) = self.mode {
             *position += n as u64;
         }
         Ok((n, state))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use types::{DescriptorFlags, OpenFlags, PathFlags};
+
+    #[tokio::test]
+    async fn writes_past_the_end_charge_the_gap() {
+        let dir = MemoryDir::new();
+        dir.create_file("a.txt", "1234").unwrap();
+        let flags = DescriptorFlags::READ | DescriptorFlags::WRITE;
+        let file = match dir
+            .open_at("a.txt", PathFlags::empty(), OpenFlags::empty(), flags)
+            .await
+            .unwrap()
+        {
+            OpenResult::File(f) => Arc::from(f),
+            OpenResult::Dir(_) => panic!("expected a file"),
+        };
+
+        // Writing at 8 fills 4 bytes of zeros, leaving 2 bytes of the quota.
+        let quota = Quota::new(Some(6));
+        let mut stream = FileOutputStream::write_at(Arc::clone(&file), 8, quota.clone());
+        let (n, _) = stream.write(Bytes::from_static(b"abcd")).await.unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(dir.read_file("a.txt").unwrap(), b"1234\0\0\0\0ab");
+        assert!(stream.write(Bytes::from_static(b"c")).await.is_err());
+
+        // A gap that doesn't fit in the quota isn't written at all.
+        let quota = Quota::new(Some(3));
+        let mut stream = FileOutputStream::write_at(Arc::clone(&file), 20, quota.clone());
+        assert!(stream.write(Bytes::from_static(b"x")).await.is_err());
+        assert_eq!(file.stat().await.unwrap().size, 10);
+
+        // Overwriting is only charged for the bytes written.
+        let mut stream = FileOutputStream::write_at(file, 0, quota);
+        let (n, _) = stream.write(Bytes::from_static(b"wxyz")).await.unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(dir.read_file("a.txt").unwrap(), b"wxy4\0\0\0\0ab");
+    }
+}
@@ -0,0 +1,483 @@
+//! Path-level access rules for preopened directories.
+
+use super::{FsResult, HostDir, OpenResult};
+use crate::preview2::bindings::filesystem::types::{self, ErrorCode};
+use std::any::Any;
+use std::sync::Arc;
+
+/// What a guest may do with the paths matched by a rule of a [`PathAcl`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathAccess {
+    /// The paths may be read, but not modified. Modifying them fails with
+    /// `error-code::read-only`.
+    ReadOnly,
+    /// The paths appear not to exist.
+    Hidden,
+}
+
+/// Path-level access rules for a preopened directory, applied on top of its
+/// [`DirPerms`](crate::preview2::DirPerms) and
+/// [`FilePerms`](crate::preview2::FilePerms).
+///
+/// Rules are glob patterns, such as `**/.git`, matched against paths
+/// relative to the root of the preopen with `/` as the separator. A rule
+/// which matches a directory also applies to everything inside it. Where
+/// several rules apply, hiding takes precedence over making read-only.
+///
+/// Paths are matched as the guest writes them, after resolving `.` and `..`
+/// lexically. To keep paths from escaping their rules under another name,
+/// guests can't rename, link, or create symbolic links to or from paths
+/// which have rules, or directories whose names a rule depends on, such as
+/// `a` for the rule `a/config`. Symbolic links which already exist in the
+/// preopen are not resolved, and can be used to reach those paths under
+/// another name.
+#[derive(Clone, Debug, Default)]
+pub struct PathAcl {
+    rules: Vec<(glob::Pattern, PathAccess)>,
+}
+
+impl PathAcl {
+    /// Creates a set of rules which allows everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hides the paths matching `pattern` from the guest.
+    pub fn hide(&mut self, pattern: &str) -> anyhow::Result<&mut Self> {
+        self.rule(pattern, PathAccess::Hidden)
+    }
+
+    /// Prevents the guest from modifying the paths matching `pattern`.
+    pub fn read_only(&mut self, pattern: &str) -> anyhow::Result<&mut Self> {
+        self.rule(pattern, PathAccess::ReadOnly)
+    }
+
+    fn rule(&mut self, pattern: &str, access: PathAccess) -> anyhow::Result<&mut Self> {
+        let pattern = glob::Pattern::new(pattern.trim_matches('/'))?;
+        self.rules.push((pattern, access));
+        Ok(self)
+    }
+
+    /// Returns the most restrictive rule which applies to `path`, a
+    /// normalized relative path.
+    pub fn access(&self, path: &str) -> Option<PathAccess> {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        // Check `path` and each of its ancestors, except the root.
+        let ancestors = path
+            .match_indices('/')
+            .map(|(i, _)| &path[..i])
+            .chain((!path.is_empty()).then_some(path));
+        let mut access = None;
+        for ancestor in ancestors {
+            for (pattern, rule) in &self.rules {
+                if pattern.matches_with(ancestor, options) {
+                    access = access.max(Some(*rule));
+                }
+            }
+        }
+        access
+    }
+
+    /// Returns whether a rule could apply to paths inside `path`, a
+    /// normalized relative path, because of the names of `path`'s own
+    /// components. Giving `path` another name could take those paths out of
+    /// the rule's reach.
+    pub fn applies_below(&self, path: &str) -> bool {
+        let components = path
+            .split('/')
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<_>>();
+        self.rules.iter().any(|(pattern, _)| {
+            let pattern = pattern.as_str().split('/').collect::<Vec<_>>();
+            matches_below(&pattern, &components, false)
+        })
+    }
+}
+
+/// Returns whether `pattern` could match a path inside `path`, with at least
+/// one of `path`'s components matched by something other than `**` if
+/// `named` isn't set yet.
+fn matches_below(pattern: &[&str], path: &[&str], named: bool) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        // The rest of the pattern is matched inside `path`.
+        (Some(_), None) => named,
+        // The pattern matches `path` itself or one of its ancestors, which
+        // `PathAcl::access` covers.
+        (None, _) => false,
+        (Some((&"**", rest)), Some((_, path_rest))) => {
+            matches_below(rest, path, named) || matches_below(pattern, path_rest, named)
+        }
+        (Some((component, rest)), Some((name, path_rest))) => {
+            // Be conservative about components which aren't patterns on
+            // their own.
+            let matches = glob::Pattern::new(component).map_or(true, |c| c.matches(name));
+            matches && matches_below(rest, path_rest, true)
+        }
+    }
+}
+
+/// A [`HostDir`] which enforces a [`PathAcl`] on another directory.
+pub struct AclDir {
+    inner: Arc<dyn HostDir>,
+    acl: Arc<PathAcl>,
+    /// The path of this directory relative to the root the rules apply to.
+    prefix: String,
+}
+
+impl AclDir {
+    /// Applies `acl` to `dir`, and every directory opened through it.
+    pub fn new(dir: impl HostDir, acl: PathAcl) -> Self {
+        AclDir {
+            inner: Arc::new(dir),
+            acl: Arc::new(acl),
+            prefix: String::new(),
+        }
+    }
+
+    /// Resolves `path` relative to the root the rules apply to. Returns
+    /// `None` if it's absolute or escapes the root, which the inner
+    /// directory will refuse anyway.
+    fn resolve(&self, path: &str) -> Option<String> {
+        if path.starts_with('/') {
+            return None;
+        }
+        let mut components: Vec<&str> = self.prefix.split('/').filter(|c| !c.is_empty()).collect();
+        for component in path.split('/') {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    components.pop()?;
+                }
+                _ => components.push(component),
+            }
+        }
+        Some(components.join("/"))
+    }
+
+    /// Checks that the guest may access `path`, and may modify it if
+    /// `write` is set. Returns its resolved path.
+    fn check(&self, path: &str, write: bool) -> FsResult<Option<String>> {
+        let resolved = self.resolve(path);
+        match resolved.as_deref().and_then(|p| self.acl.access(p)) {
+            Some(PathAccess::Hidden) => Err(ErrorCode::NoEntry.into()),
+            Some(PathAccess::ReadOnly) if write => Err(ErrorCode::ReadOnly.into()),
+            _ => Ok(resolved),
+        }
+    }
+
+    /// Like [`AclDir::check`] for modifying `path`, and also checks that
+    /// `path` may be given another name, which isn't the case if rules
+    /// apply to paths inside it because of its name.
+    fn check_rename(&self, path: &str) -> FsResult<Option<String>> {
+        let resolved = self.check(path, true)?;
+        match &resolved {
+            Some(p) if self.acl.applies_below(p) => Err(ErrorCode::NotPermitted.into()),
+            _ => Ok(resolved),
+        }
+    }
+
+    /// Checks that the guest may modify this directory itself.
+    fn check_self(&self) -> FsResult<()> {
+        self.check(".", true).map(|_| ())
+    }
+
+    /// Unwraps `dir` if it's an [`AclDir`] too, so that the inner directory
+    /// can downcast it.
+    fn inner_of(dir: &dyn HostDir) -> &dyn HostDir {
+        match dir.as_any().downcast_ref::<AclDir>() {
+            Some(dir) => &*dir.inner,
+            None => dir,
+        }
+    }
+
+    /// Like [`AclDir::check_rename`], for a path in `dir`, which may be
+    /// another [`AclDir`].
+    fn check_rename_in(dir: &dyn HostDir, path: &str) -> FsResult<()> {
+        match dir.as_any().downcast_ref::<AclDir>() {
+            Some(dir) => dir.check_rename(path).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl HostDir for AclDir {
+    async fn open_at(
+        &self,
+        path: &str,
+        path_flags: types::PathFlags,
+        oflags: types::OpenFlags,
+        flags: types::DescriptorFlags,
+    ) -> FsResult<OpenResult> {
+        let write = flags.contains(types::DescriptorFlags::WRITE)
+            || oflags.contains(types::OpenFlags::CREATE)
+            || oflags.contains(types::OpenFlags::TRUNCATE);
+        let resolved = self.check(path, write)?;
+        match self.inner.open_at(path, path_flags, oflags, flags).await? {
+            OpenResult::Dir(dir) => Ok(OpenResult::Dir(Box::new(AclDir {
+                inner: dir.into(),
+                acl: self.acl.clone(),
+                // The inner directory would have refused a path which
+                // escapes the root.
+                prefix: resolved.unwrap_or_default(),
+            }))),
+            file => Ok(file),
+        }
+    }
+
+    async fn create_directory_at(&self, path: &str) -> FsResult<()> {
+        self.check(path, true)?;
+        self.inner.create_directory_at(path).await
+    }
+
+    async fn stat(&self) -> FsResult<types::DescriptorStat> {
+        self.inner.stat().await
+    }
+
+    async fn stat_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+    ) -> FsResult<types::DescriptorStat> {
+        self.check(path, false)?;
+        self.inner.stat_at(path_flags, path).await
+    }
+
+    async fn read_directory(&self) -> FsResult<Vec<FsResult<types::DirectoryEntry>>> {
+        let mut entries = self.inner.read_directory().await?;
+        entries.retain(|entry| match entry {
+            Ok(entry) => self.check(&entry.name, false).is_ok(),
+            Err(_) => true,
+        });
+        Ok(entries)
+    }
+
+    async fn remove_directory_at(&self, path: &str) -> FsResult<()> {
+        self.check(path, true)?;
+        self.inner.remove_directory_at(path).await
+    }
+
+    async fn unlink_file_at(&self, path: &str) -> FsResult<()> {
+        self.check(path, true)?;
+        self.inner.unlink_file_at(path).await
+    }
+
+    async fn rename_at(
+        &self,
+        old_path: &str,
+        new_dir: &dyn HostDir,
+        new_path: &str,
+    ) -> FsResult<()> {
+        self.check_rename(old_path)?;
+        Self::check_rename_in(new_dir, new_path)?;
+        self.inner
+            .rename_at(old_path, Self::inner_of(new_dir), new_path)
+            .await
+    }
+
+    async fn link_at(&self, old_path: &str, new_dir: &dyn HostDir, new_path: &str) -> FsResult<()> {
+        // A hard link to a read-only file could be used to modify it.
+        self.check_rename(old_path)?;
+        Self::check_rename_in(new_dir, new_path)?;
+        self.inner
+            .link_at(old_path, Self::inner_of(new_dir), new_path)
+            .await
+    }
+
+    async fn symlink_at(&self, src_path: &str, dest_path: &str) -> FsResult<()> {
+        let resolved = self.check_rename(dest_path)?;
+        // Don't let the guest create another name for a path with rules,
+        // which is resolved relative to the directory of the link.
+        let link_dir = match resolved.as_deref().and_then(|p| p.rsplit_once('/')) {
+            Some((dir, _)) => dir,
+            None => "",
+        };
+        let target = AclDir {
+            inner: self.inner.clone(),
+            acl: self.acl.clone(),
+            prefix: link_dir.to_string(),
+        };
+        if target.check_rename(src_path).is_err() {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        self.inner.symlink_at(src_path, dest_path).await
+    }
+
+    async fn readlink_at(&self, path: &str) -> FsResult<String> {
+        self.check(path, false)?;
+        self.inner.readlink_at(path).await
+    }
+
+    async fn access_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        access: types::AccessType,
+    ) -> FsResult<()> {
+        let write = match access {
+            types::AccessType::Access(modes) => modes.contains(types::Modes::WRITABLE),
+            types::AccessType::Exists => false,
+        };
+        self.check(path, write)?;
+        self.inner.access_at(path_flags, path, access).await
+    }
+
+    async fn change_file_permissions_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        modes: types::Modes,
+    ) -> FsResult<()> {
+        self.check(path, true)?;
+        self.inner
+            .change_file_permissions_at(path_flags, path, modes)
+            .await
+    }
+
+    async fn change_directory_permissions_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        modes: types::Modes,
+    ) -> FsResult<()> {
+        self.check(path, true)?;
+        self.inner
+            .change_directory_permissions_at(path_flags, path, modes)
+            .await
+    }
+
+    async fn set_times(
+        &self,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        self.check_self()?;
+        self.inner.set_times(atim, mtim).await
+    }
+
+    async fn set_times_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        self.check(path, true)?;
+        self.inner.set_times_at(path_flags, path, atim, mtim).await
+    }
+
+    async fn metadata_hash(&self) -> FsResult<types::MetadataHashValue> {
+        self.inner.metadata_hash().await
+    }
+
+    async fn metadata_hash_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+    ) -> FsResult<types::MetadataHashValue> {
+        self.check(path, false)?;
+        self.inner.metadata_hash_at(path_flags, path).await
+    }
+
+    async fn get_flags(&self) -> FsResult<types::DescriptorFlags> {
+        self.inner.get_flags().await
+    }
+
+    async fn sync_data(&self) -> FsResult<()> {
+        self.inner.sync_data().await
+    }
+
+    async fn sync(&self) -> FsResult<()> {
+        self.inner.sync().await
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AclDir, PathAccess, PathAcl};
+    use crate::preview2::bindings::filesystem::types::ErrorCode;
+    use crate::preview2::filesystem::{HostDir, MemoryDir};
+
+    #[test]
+    fn rules_apply_to_descendants() {
+        let mut acl = PathAcl::new();
+        acl.hide("**/.git").unwrap().read_only("config").unwrap();
+
+        assert_eq!(acl.access(""), None);
+        assert_eq!(acl.access("src/main.rs"), None);
+        assert_eq!(acl.access(".git"), Some(PathAccess::Hidden));
+        assert_eq!(acl.access("a/b/.git/HEAD"), Some(PathAccess::Hidden));
+        assert_eq!(acl.access(".github"), None);
+        assert_eq!(acl.access("config"), Some(PathAccess::ReadOnly));
+        assert_eq!(acl.access("config/app.toml"), Some(PathAccess::ReadOnly));
+        assert_eq!(acl.access("config/.git"), Some(PathAccess::Hidden));
+        assert_eq!(acl.access("src/config"), None);
+    }
+
+    #[test]
+    fn rules_below_paths() {
+        let mut acl = PathAcl::new();
+        acl.hide("**/.git")
+            .unwrap()
+            .read_only("a/config")
+            .unwrap()
+            .hide("x/**/secret")
+            .unwrap()
+            .read_only("*/data")
+            .unwrap();
+
+        assert!(acl.applies_below("a"));
+        assert!(acl.applies_below("x"));
+        assert!(acl.applies_below("x/y/z"));
+        assert!(acl.applies_below("b"));
+        assert!(!acl.applies_below("b/c"));
+        assert!(!acl.applies_below("src/project"));
+        // These are covered by `access` instead.
+        assert!(!acl.applies_below("a/config"));
+        assert!(!acl.applies_below("a/config/app.toml"));
+    }
+
+    fn error_code(err: crate::preview2::bindings::filesystem::types::Error) -> ErrorCode {
+        err.downcast().unwrap()
+    }
+
+    #[tokio::test]
+    async fn no_renaming_around_rules() {
+        let mem = MemoryDir::new();
+        mem.create_file("a/config", "secret").unwrap();
+        mem.create_file("other/file", "").unwrap();
+        let mut acl = PathAcl::new();
+        acl.read_only("a/config").unwrap();
+        let dir = AclDir::new(mem, acl);
+
+        let err = dir.rename_at("a", &dir, "b").await.unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::NotPermitted);
+        let err = dir.rename_at("other", &dir, "a").await.unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::NotPermitted);
+        let err = dir.link_at("a", &dir, "b").await.unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::NotPermitted);
+        dir.rename_at("other", &dir, "moved").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn no_symlinks_around_rules() {
+        let mem = MemoryDir::new();
+        mem.create_file("a/config", "secret").unwrap();
+        mem.create_dir("sub").unwrap();
+        let mut acl = PathAcl::new();
+        acl.read_only("a/config").unwrap();
+        let dir = AclDir::new(mem, acl);
+
+        for (src, dest) in [("a", "l"), ("../a", "sub/l"), ("other", "a")] {
+            let err = dir.symlink_at(src, dest).await.unwrap_err();
+            assert_eq!(error_code(err), ErrorCode::NotPermitted);
+        }
+    }
+}
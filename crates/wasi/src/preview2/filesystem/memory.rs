@@ -0,0 +1,838 @@
+//! An in-memory implementation of [`HostDir`] and [`HostFile`].
+
+use super::{datetime_from, systemtime_from, FsResult, HostDir, HostFile, OpenResult};
+use crate::preview2::bindings::filesystem::types::{self, ErrorCode};
+use bytes::Bytes;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+type DirRef = Arc<Mutex<DirData>>;
+type FileRef = Arc<Mutex<FileData>>;
+
+#[derive(Clone)]
+enum Node {
+    Dir(DirRef),
+    File(FileRef),
+}
+
+struct Times {
+    accessed: SystemTime,
+    modified: SystemTime,
+    changed: SystemTime,
+}
+
+impl Times {
+    fn now() -> Self {
+        let now = SystemTime::now();
+        Times {
+            accessed: now,
+            modified: now,
+            changed: now,
+        }
+    }
+
+    fn set(&mut self, atim: types::NewTimestamp, mtim: types::NewTimestamp) -> FsResult<()> {
+        if let Some(t) = new_timestamp(atim)? {
+            self.accessed = t;
+        }
+        if let Some(t) = new_timestamp(mtim)? {
+            self.modified = t;
+        }
+        self.changed = SystemTime::now();
+        Ok(())
+    }
+
+    fn touch(&mut self) {
+        let now = SystemTime::now();
+        self.modified = now;
+        self.changed = now;
+    }
+}
+
+struct DirData {
+    entries: BTreeMap<String, Node>,
+    times: Times,
+}
+
+impl DirData {
+    fn new() -> DirRef {
+        Arc::new(Mutex::new(DirData {
+            entries: BTreeMap::new(),
+            times: Times::now(),
+        }))
+    }
+
+    fn stat(&self) -> types::DescriptorStat {
+        stat(types::DescriptorType::Directory, 0, &self.times)
+    }
+}
+
+/// The bytes used by the files of a tree, and the most they may use.
+struct Space {
+    used: AtomicU64,
+    max: u64,
+}
+
+impl Space {
+    fn new(max: u64) -> Arc<Space> {
+        Arc::new(Space {
+            used: AtomicU64::new(0),
+            max,
+        })
+    }
+
+    fn reserve(&self, bytes: u64) -> FsResult<()> {
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(bytes).filter(|used| *used <= self.max)
+            })
+            .map_err(|_| ErrorCode::InsufficientSpace)?;
+        Ok(())
+    }
+
+    fn release(&self, bytes: u64) {
+        self.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+struct FileData {
+    contents: Vec<u8>,
+    times: Times,
+    /// The space of the tree the file is in, which its contents count
+    /// against until the file is dropped.
+    space: Arc<Space>,
+}
+
+impl FileData {
+    fn new(contents: Vec<u8>, space: &Arc<Space>) -> FsResult<FileRef> {
+        space.reserve(contents.len() as u64)?;
+        Ok(Arc::new(Mutex::new(FileData {
+            contents,
+            times: Times::now(),
+            space: space.clone(),
+        })))
+    }
+
+    fn stat(&self) -> types::DescriptorStat {
+        stat(
+            types::DescriptorType::RegularFile,
+            self.contents.len() as u64,
+            &self.times,
+        )
+    }
+
+    /// Resizes the contents to `len` bytes, zero-filling any new bytes.
+    ///
+    /// Growing the file is the guest's choice, so exceeding the tree's
+    /// maximum size or failing to allocate is reported to it instead of
+    /// aborting the host.
+    fn resize(&mut self, len: usize) -> FsResult<()> {
+        let old_len = self.contents.len();
+        if len <= old_len {
+            self.contents.truncate(len);
+            self.space.release((old_len - len) as u64);
+            return Ok(());
+        }
+        let additional = len - old_len;
+        self.space.reserve(additional as u64)?;
+        if self.contents.try_reserve(additional).is_err() {
+            self.space.release(additional as u64);
+            return Err(ErrorCode::InsufficientMemory.into());
+        }
+        self.contents.resize(len, 0);
+        Ok(())
+    }
+}
+
+impl Drop for FileData {
+    fn drop(&mut self) {
+        self.space.release(self.contents.len() as u64);
+    }
+}
+
+/// A directory tree which lives entirely in host memory.
+///
+/// A `MemoryDir` can be preopened for a guest with
+/// [`WasiCtxBuilder::preopened_host_dir`](crate::preview2::WasiCtxBuilder::preopened_host_dir).
+/// Clones of a `MemoryDir` share the same underlying tree, so the embedder can
+/// keep a clone around to populate the directory before running the guest
+/// and to inspect what the guest wrote afterwards.
+///
+/// The files of a tree can use as much memory as the host has unless the
+/// tree is created with [`MemoryDir::with_max_size`]. Files which were
+/// removed while they're still open keep using space until they're closed.
+///
+/// Symbolic links and hard links are not supported.
+#[derive(Clone)]
+pub struct MemoryDir {
+    dir: DirRef,
+    space: Arc<Space>,
+}
+
+impl MemoryDir {
+    /// Creates a new, empty directory.
+    pub fn new() -> Self {
+        MemoryDir::with_max_size(u64::MAX)
+    }
+
+    /// Creates a new, empty directory whose files may hold at most
+    /// `max_size` bytes in total. Growing a file beyond that fails with
+    /// `error-code::insufficient-space`.
+    pub fn with_max_size(max_size: u64) -> Self {
+        MemoryDir {
+            dir: DirData::new(),
+            space: Space::new(max_size),
+        }
+    }
+
+    /// Returns a `MemoryDir` for `dir`, a directory in this one's tree.
+    fn sub(&self, dir: DirRef) -> Self {
+        MemoryDir {
+            dir,
+            space: self.space.clone(),
+        }
+    }
+
+    /// Creates a file at `path` with the given `contents`, replacing any file
+    /// which was already there.
+    ///
+    /// Parent directories of `path` are created as needed.
+    pub fn create_file(&self, path: &str, contents: impl Into<Vec<u8>>) -> FsResult<()> {
+        let (parent, name) = self.create_parents(path)?;
+        let name = name.ok_or(ErrorCode::IsDirectory)?;
+        let mut parent = parent.lock().unwrap();
+        if let Some(Node::Dir(_)) = parent.entries.get(name) {
+            return Err(ErrorCode::IsDirectory.into());
+        }
+        let file = FileData::new(contents.into(), &self.space)?;
+        parent.entries.insert(name.to_string(), Node::File(file));
+        parent.times.touch();
+        Ok(())
+    }
+
+    /// Creates a directory at `path`, along with any of its parents which
+    /// don't exist yet.
+    pub fn create_dir(&self, path: &str) -> FsResult<()> {
+        let (parent, name) = self.create_parents(path)?;
+        if let Some(name) = name {
+            let mut parent = parent.lock().unwrap();
+            match parent.entries.get(name) {
+                Some(Node::Dir(_)) => {}
+                Some(Node::File(_)) => return Err(ErrorCode::Exist.into()),
+                None => {
+                    parent
+                        .entries
+                        .insert(name.to_string(), Node::Dir(DirData::new()));
+                    parent.times.touch();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the contents of the file at `path`.
+    pub fn read_file(&self, path: &str) -> FsResult<Vec<u8>> {
+        match lookup(&self.dir, path)? {
+            Node::File(f) => Ok(f.lock().unwrap().contents.clone()),
+            Node::Dir(_) => Err(ErrorCode::IsDirectory.into()),
+        }
+    }
+
+    fn create_parents<'a>(&self, path: &'a str) -> FsResult<(DirRef, Option<&'a str>)> {
+        let (parent, name) = match path.rsplit_once('/') {
+            Some((parent, name)) => {
+                self.create_dir(parent)?;
+                (parent, name)
+            }
+            None => (".", path),
+        };
+        let (parent, _) = resolve(&self.dir, &format!("{parent}/."))?;
+        match name {
+            "" | "." => Ok((parent, None)),
+            ".." => Err(ErrorCode::Invalid.into()),
+            name => Ok((parent, Some(name))),
+        }
+    }
+}
+
+impl Default for MemoryDir {
+    fn default() -> Self {
+        MemoryDir::new()
+    }
+}
+
+/// Resolves all but the last component of `path`, returning the directory it
+/// refers to and the final component.
+///
+/// The final component is `None` if `path` refers to a directory through a
+/// trailing `.`, `..` or `/`. Paths can't escape from `root`.
+fn resolve<'a>(root: &DirRef, path: &'a str) -> FsResult<(DirRef, Option<&'a str>)> {
+    if path.is_empty() {
+        return Err(ErrorCode::NoEntry.into());
+    }
+    if path.starts_with('/') {
+        return Err(ErrorCode::NotPermitted.into());
+    }
+    // A trailing `.` or `/` means that the last named component must itself
+    // be resolved as a directory.
+    let trailing_dir = matches!(path.rsplit('/').next(), Some("" | "."));
+    let mut stack = vec![root.clone()];
+    let mut components = path
+        .split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .peekable();
+    while let Some(component) = components.next() {
+        if component == ".." {
+            if stack.len() == 1 {
+                return Err(ErrorCode::NotPermitted.into());
+            }
+            stack.pop();
+            continue;
+        }
+        if components.peek().is_none() && !trailing_dir {
+            return Ok((stack.pop().unwrap(), Some(component)));
+        }
+        let next = match stack.last().unwrap().lock().unwrap().entries.get(component) {
+            Some(Node::Dir(d)) => d.clone(),
+            Some(Node::File(_)) => return Err(ErrorCode::NotDirectory.into()),
+            None => return Err(ErrorCode::NoEntry.into()),
+        };
+        stack.push(next);
+    }
+    Ok((stack.pop().unwrap(), None))
+}
+
+fn lookup(root: &DirRef, path: &str) -> FsResult<Node> {
+    match resolve(root, path)? {
+        (dir, None) => Ok(Node::Dir(dir)),
+        (dir, Some(name)) => dir
+            .lock()
+            .unwrap()
+            .entries
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ErrorCode::NoEntry.into()),
+    }
+}
+
+/// Returns whether `dir` is `target` or contains it.
+fn contains(dir: &DirRef, target: &DirRef) -> bool {
+    if Arc::ptr_eq(dir, target) {
+        return true;
+    }
+    let children = dir
+        .lock()
+        .unwrap()
+        .entries
+        .values()
+        .filter_map(|node| match node {
+            Node::Dir(d) => Some(d.clone()),
+            Node::File(_) => None,
+        })
+        .collect::<Vec<_>>();
+    children.iter().any(|child| contains(child, target))
+}
+
+/// Moves the files in `node` to the space `to` of another tree, if they fit.
+fn move_space(node: &Node, to: &Arc<Space>) -> FsResult<()> {
+    fn collect(node: &Node, files: &mut Vec<FileRef>) {
+        match node {
+            Node::File(f) => files.push(f.clone()),
+            Node::Dir(d) => {
+                for child in d.lock().unwrap().entries.values() {
+                    collect(child, files);
+                }
+            }
+        }
+    }
+    let mut files = Vec::new();
+    collect(node, &mut files);
+    let mut files = files.iter().map(|f| f.lock().unwrap()).collect::<Vec<_>>();
+    to.reserve(files.iter().map(|f| f.contents.len() as u64).sum())?;
+    for file in files.iter_mut() {
+        let from = std::mem::replace(&mut file.space, to.clone());
+        from.release(file.contents.len() as u64);
+    }
+    Ok(())
+}
+
+fn new_timestamp(t: types::NewTimestamp) -> FsResult<Option<SystemTime>> {
+    match t {
+        types::NewTimestamp::NoChange => Ok(None),
+        types::NewTimestamp::Now => Ok(Some(SystemTime::now())),
+        types::NewTimestamp::Timestamp(t) => Ok(Some(systemtime_from(t)?)),
+    }
+}
+
+fn stat(type_: types::DescriptorType, size: u64, times: &Times) -> types::DescriptorStat {
+    types::DescriptorStat {
+        type_,
+        link_count: 1,
+        size,
+        data_access_timestamp: datetime_from(times.accessed),
+        data_modification_timestamp: datetime_from(times.modified),
+        status_change_timestamp: datetime_from(times.changed),
+    }
+}
+
+fn stat_node(node: &Node) -> types::DescriptorStat {
+    match node {
+        Node::Dir(d) => d.lock().unwrap().stat(),
+        Node::File(f) => f.lock().unwrap().stat(),
+    }
+}
+
+/// Nodes live for as long as they are referenced, so their address is a
+/// unique identity for them.
+fn metadata_hash<T>(node: &Arc<T>) -> types::MetadataHashValue {
+    use std::hash::Hasher;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    hasher.write_usize(Arc::as_ptr(node) as *const u8 as usize);
+    let lower = hasher.finish();
+    // See `calculate_metadata_hash` in the `os` module for this constant.
+    let upper = lower ^ 4614256656552045848u64;
+    types::MetadataHashValue { lower, upper }
+}
+
+fn metadata_hash_node(node: &Node) -> types::MetadataHashValue {
+    match node {
+        Node::Dir(d) => metadata_hash(d),
+        Node::File(f) => metadata_hash(f),
+    }
+}
+
+#[async_trait::async_trait]
+impl HostDir for MemoryDir {
+    async fn open_at(
+        &self,
+        path: &str,
+        _path_flags: types::PathFlags,
+        oflags: types::OpenFlags,
+        flags: types::DescriptorFlags,
+    ) -> FsResult<OpenResult> {
+        use types::{DescriptorFlags, OpenFlags};
+
+        let exclusive = oflags.contains(OpenFlags::CREATE | OpenFlags::EXCLUSIVE);
+        let (parent, name) = resolve(&self.dir, path)?;
+        let name = match name {
+            Some(name) => name,
+            None if exclusive => return Err(ErrorCode::Exist.into()),
+            None => return Ok(OpenResult::Dir(Box::new(self.sub(parent)))),
+        };
+
+        let mut parent = parent.lock().unwrap();
+        let existing = parent.entries.get(name).cloned();
+        match existing {
+            Some(_) if exclusive => Err(ErrorCode::Exist.into()),
+            Some(Node::Dir(_)) if flags.contains(DescriptorFlags::WRITE) => {
+                Err(ErrorCode::IsDirectory.into())
+            }
+            Some(Node::Dir(d)) => Ok(OpenResult::Dir(Box::new(self.sub(d)))),
+            Some(Node::File(_)) if oflags.contains(OpenFlags::DIRECTORY) => {
+                Err(ErrorCode::NotDirectory.into())
+            }
+            Some(Node::File(f)) => {
+                if oflags.contains(OpenFlags::TRUNCATE) {
+                    let mut data = f.lock().unwrap();
+                    data.resize(0)?;
+                    data.times.touch();
+                }
+                Ok(OpenResult::File(Box::new(MemoryFile(f))))
+            }
+            None if oflags.contains(OpenFlags::CREATE) => {
+                let f = FileData::new(Vec::new(), &self.space)?;
+                parent
+                    .entries
+                    .insert(name.to_string(), Node::File(f.clone()));
+                parent.times.touch();
+                Ok(OpenResult::File(Box::new(MemoryFile(f))))
+            }
+            None => Err(ErrorCode::NoEntry.into()),
+        }
+    }
+
+    async fn create_directory_at(&self, path: &str) -> FsResult<()> {
+        let (parent, name) = resolve(&self.dir, path)?;
+        let name = name.ok_or(ErrorCode::Exist)?;
+        let mut parent = parent.lock().unwrap();
+        if parent.entries.contains_key(name) {
+            return Err(ErrorCode::Exist.into());
+        }
+        parent
+            .entries
+            .insert(name.to_string(), Node::Dir(DirData::new()));
+        parent.times.touch();
+        Ok(())
+    }
+
+    async fn stat(&self) -> FsResult<types::DescriptorStat> {
+        Ok(self.dir.lock().unwrap().stat())
+    }
+
+    async fn stat_at(
+        &self,
+        _path_flags: types::PathFlags,
+        path: &str,
+    ) -> FsResult<types::DescriptorStat> {
+        Ok(stat_node(&lookup(&self.dir, path)?))
+    }
+
+    async fn access_at(
+        &self,
+        _path_flags: types::PathFlags,
+        path: &str,
+        access: types::AccessType,
+    ) -> FsResult<()> {
+        lookup(&self.dir, path)?;
+        match access {
+            // Nothing in memory can be executed.
+            types::AccessType::Access(modes) if modes.contains(types::Modes::EXECUTABLE) => {
+                Err(ErrorCode::Access.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn read_directory(&self) -> FsResult<Vec<FsResult<types::DirectoryEntry>>> {
+        Ok(self
+            .dir
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|(name, node)| {
+                let type_ = match node {
+                    Node::Dir(_) => types::DescriptorType::Directory,
+                    Node::File(_) => types::DescriptorType::RegularFile,
+                };
+                Ok(types::DirectoryEntry {
+                    type_,
+                    name: name.clone(),
+                })
+            })
+            .collect())
+    }
+
+    async fn remove_directory_at(&self, path: &str) -> FsResult<()> {
+        let (parent, name) = resolve(&self.dir, path)?;
+        let name = name.ok_or(ErrorCode::Invalid)?;
+        let mut parent = parent.lock().unwrap();
+        match parent.entries.get(name) {
+            Some(Node::Dir(d)) if !d.lock().unwrap().entries.is_empty() => {
+                return Err(ErrorCode::NotEmpty.into())
+            }
+            Some(Node::Dir(_)) => {}
+            Some(Node::File(_)) => return Err(ErrorCode::NotDirectory.into()),
+            None => return Err(ErrorCode::NoEntry.into()),
+        }
+        parent.entries.remove(name);
+        parent.times.touch();
+        Ok(())
+    }
+
+    async fn unlink_file_at(&self, path: &str) -> FsResult<()> {
+        let (parent, name) = resolve(&self.dir, path)?;
+        let name = name.ok_or(ErrorCode::IsDirectory)?;
+        let mut parent = parent.lock().unwrap();
+        match parent.entries.get(name) {
+            Some(Node::File(_)) => {}
+            Some(Node::Dir(_)) => return Err(ErrorCode::IsDirectory.into()),
+            None => return Err(ErrorCode::NoEntry.into()),
+        }
+        parent.entries.remove(name);
+        parent.times.touch();
+        Ok(())
+    }
+
+    async fn rename_at(
+        &self,
+        old_path: &str,
+        new_dir: &dyn HostDir,
+        new_path: &str,
+    ) -> FsResult<()> {
+        let new_dir = match new_dir.as_any().downcast_ref::<MemoryDir>() {
+            Some(dir) => dir,
+            None => return Err(ErrorCode::CrossDevice.into()),
+        };
+        let (old_parent, old_name) = resolve(&self.dir, old_path)?;
+        let old_name = old_name.ok_or(ErrorCode::Invalid)?;
+        let (new_parent, new_name) = resolve(&new_dir.dir, new_path)?;
+        let new_name = new_name.ok_or(ErrorCode::Invalid)?;
+
+        let node = old_parent
+            .lock()
+            .unwrap()
+            .entries
+            .get(old_name)
+            .cloned()
+            .ok_or(ErrorCode::NoEntry)?;
+        if Arc::ptr_eq(&old_parent, &new_parent) && old_name == new_name {
+            return Ok(());
+        }
+        if let Node::Dir(d) = &node {
+            // A directory can't be moved inside of itself.
+            if contains(d, &new_parent) {
+                return Err(ErrorCode::Invalid.into());
+            }
+        }
+        match (&node, new_parent.lock().unwrap().entries.get(new_name)) {
+            (_, None) => {}
+            (Node::File(_), Some(Node::File(_))) => {}
+            (Node::File(_), Some(Node::Dir(_))) => return Err(ErrorCode::IsDirectory.into()),
+            (Node::Dir(_), Some(Node::File(_))) => return Err(ErrorCode::NotDirectory.into()),
+            (Node::Dir(_), Some(Node::Dir(d))) => {
+                if !d.lock().unwrap().entries.is_empty() {
+                    return Err(ErrorCode::NotEmpty.into());
+                }
+            }
+        }
+        if !Arc::ptr_eq(&self.space, &new_dir.space) {
+            move_space(&node, &new_dir.space)?;
+        }
+
+        {
+            let mut old_parent = old_parent.lock().unwrap();
+            old_parent.entries.remove(old_name);
+            old_parent.times.touch();
+        }
+        let mut new_parent = new_parent.lock().unwrap();
+        new_parent.entries.insert(new_name.to_string(), node);
+        new_parent.times.touch();
+        Ok(())
+    }
+
+    async fn set_times(
+        &self,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        self.dir.lock().unwrap().times.set(atim, mtim)
+    }
+
+    async fn set_times_at(
+        &self,
+        _path_flags: types::PathFlags,
+        path: &str,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        match lookup(&self.dir, path)? {
+            Node::Dir(d) => d.lock().unwrap().times.set(atim, mtim),
+            Node::File(f) => f.lock().unwrap().times.set(atim, mtim),
+        }
+    }
+
+    async fn metadata_hash(&self) -> FsResult<types::MetadataHashValue> {
+        Ok(metadata_hash(&self.dir))
+    }
+
+    async fn metadata_hash_at(
+        &self,
+        _path_flags: types::PathFlags,
+        path: &str,
+    ) -> FsResult<types::MetadataHashValue> {
+        Ok(metadata_hash_node(&lookup(&self.dir, path)?))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+struct MemoryFile(FileRef);
+
+#[async_trait::async_trait]
+impl HostFile for MemoryFile {
+    async fn read_at(&self, len: usize, offset: u64) -> FsResult<Bytes> {
+        let mut data = self.0.lock().unwrap();
+        data.times.accessed = SystemTime::now();
+        let start = usize::try_from(offset)
+            .unwrap_or(usize::MAX)
+            .min(data.contents.len());
+        let end = start.saturating_add(len).min(data.contents.len());
+        Ok(Bytes::copy_from_slice(&data.contents[start..end]))
+    }
+
+    async fn write_at(&self, buf: Bytes, offset: u64) -> FsResult<usize> {
+        let mut data = self.0.lock().unwrap();
+        let start = usize::try_from(offset)?;
+        let end = start.checked_add(buf.len()).ok_or(ErrorCode::Overflow)?;
+        if data.contents.len() < end {
+            data.resize(end)?;
+        }
+        data.contents[start..end].copy_from_slice(&buf);
+        data.times.touch();
+        Ok(buf.len())
+    }
+
+    async fn append(&self, buf: Bytes) -> FsResult<usize> {
+        let mut data = self.0.lock().unwrap();
+        let end = data
+            .contents
+            .len()
+            .checked_add(buf.len())
+            .ok_or(ErrorCode::Overflow)?;
+        data.resize(end)?;
+        let start = end - buf.len();
+        data.contents[start..].copy_from_slice(&buf);
+        data.times.touch();
+        Ok(buf.len())
+    }
+
+    async fn set_size(&self, size: u64) -> FsResult<()> {
+        let mut data = self.0.lock().unwrap();
+        data.resize(usize::try_from(size)?)?;
+        data.times.touch();
+        Ok(())
+    }
+
+    async fn stat(&self) -> FsResult<types::DescriptorStat> {
+        Ok(self.0.lock().unwrap().stat())
+    }
+
+    async fn set_times(
+        &self,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        self.0.lock().unwrap().times.set(atim, mtim)
+    }
+
+    async fn metadata_hash(&self) -> FsResult<types::MetadataHashValue> {
+        Ok(metadata_hash(&self.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use types::{DescriptorFlags, OpenFlags, PathFlags};
+
+    async fn open_file(
+        dir: &MemoryDir,
+        path: &str,
+        oflags: OpenFlags,
+    ) -> FsResult<Box<dyn HostFile>> {
+        let flags = DescriptorFlags::READ | DescriptorFlags::WRITE;
+        match dir.open_at(path, PathFlags::empty(), oflags, flags).await? {
+            OpenResult::File(f) => Ok(f),
+            OpenResult::Dir(_) => panic!("expected a file"),
+        }
+    }
+
+    fn error_code(err: types::Error) -> ErrorCode {
+        err.downcast().unwrap()
+    }
+
+    #[tokio::test]
+    async fn read_and_write() {
+        let dir = MemoryDir::new();
+        dir.create_file("a/b.txt", "hello").unwrap();
+
+        let f = open_file(&dir, "a/b.txt", OpenFlags::empty())
+            .await
+            .unwrap();
+        assert_eq!(&f.read_at(100, 1).await.unwrap()[..], b"ello");
+        assert!(f.read_at(100, 5).await.unwrap().is_empty());
+        assert_eq!(f.write_at(Bytes::from_static(b"!"), 7).await.unwrap(), 1);
+        assert_eq!(dir.read_file("a/b.txt").unwrap(), b"hello\0\0!");
+        assert_eq!(f.stat().await.unwrap().size, 8);
+
+        let f = open_file(&dir, "./a/../c.txt", OpenFlags::CREATE)
+            .await
+            .unwrap();
+        f.append(Bytes::from_static(b"new")).await.unwrap();
+        assert_eq!(dir.read_file("c.txt").unwrap(), b"new");
+    }
+
+    #[tokio::test]
+    async fn paths_stay_inside_root() {
+        let dir = MemoryDir::new();
+        dir.create_dir("a").unwrap();
+
+        for path in ["..", "a/../../b", "/a"] {
+            let err = open_file(&dir, path, OpenFlags::CREATE)
+                .await
+                .err()
+                .unwrap();
+            assert_eq!(error_code(err), ErrorCode::NotPermitted);
+        }
+        let err = open_file(&dir, "a/missing", OpenFlags::empty())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error_code(err), ErrorCode::NoEntry);
+    }
+
+    #[tokio::test]
+    async fn remove_and_rename() {
+        let dir = MemoryDir::new();
+        dir.create_file("a/b.txt", "b").unwrap();
+
+        let err = dir.remove_directory_at("a").await.unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::NotEmpty);
+        let err = dir.rename_at("a", &dir, "a/c").await.unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::Invalid);
+
+        let other = MemoryDir::new();
+        dir.rename_at("a/b.txt", &other, "moved.txt").await.unwrap();
+        assert_eq!(other.read_file("moved.txt").unwrap(), b"b");
+        assert_eq!(
+            error_code(dir.read_file("a/b.txt").unwrap_err()),
+            ErrorCode::NoEntry
+        );
+
+        dir.remove_directory_at("a").await.unwrap();
+        assert!(dir.read_directory().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn huge_sizes_are_errors() {
+        let dir = MemoryDir::new();
+        dir.create_file("a.txt", "a").unwrap();
+
+        let f = open_file(&dir, "a.txt", OpenFlags::empty()).await.unwrap();
+        let err = f.set_size(u64::MAX >> 1).await.unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::InsufficientMemory);
+        let err = f
+            .write_at(Bytes::from_static(b"!"), u64::MAX >> 2)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::InsufficientMemory);
+        assert_eq!(dir.read_file("a.txt").unwrap(), b"a");
+    }
+
+    #[tokio::test]
+    async fn max_size() {
+        let dir = MemoryDir::with_max_size(10);
+        dir.create_file("a.txt", "hello").unwrap();
+        let err = dir.create_file("b.txt", "world!").unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::InsufficientSpace);
+
+        let f = open_file(&dir, "a.txt", OpenFlags::empty()).await.unwrap();
+        let err = f.write_at(Bytes::from_static(b"!"), 10).await.unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::InsufficientSpace);
+        let err = f.set_size(11).await.unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::InsufficientSpace);
+        f.set_size(10).await.unwrap();
+        f.set_size(2).await.unwrap();
+        dir.create_file("b.txt", "world!").unwrap();
+
+        // Files count against the tree they're moved to.
+        let other = MemoryDir::with_max_size(4);
+        let err = dir.rename_at("b.txt", &other, "b.txt").await.unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::InsufficientSpace);
+        dir.rename_at("a.txt", &other, "a.txt").await.unwrap();
+        dir.create_file("c.txt", "!!").unwrap();
+
+        // Removed files use space until they're closed.
+        other.unlink_file_at("a.txt").await.unwrap();
+        let err = other.create_file("d.txt", "abcd").unwrap_err();
+        assert_eq!(error_code(err), ErrorCode::InsufficientSpace);
+        drop(f);
+        other.create_file("d.txt", "abcd").unwrap();
+    }
+}
@@ -0,0 +1,851 @@
+//! Implementation of [`HostDir`] and [`HostFile`] for the host filesystem,
+//! using `cap-std`.
+
+use super::{FsResult, HostDir, HostFile, OpenResult};
+use crate::preview2::bindings::clocks::wall_clock;
+use crate::preview2::bindings::filesystem::types::{self, ErrorCode};
+use bytes::{Bytes, BytesMut};
+use std::any::Any;
+use std::sync::Arc;
+
+pub(crate) struct OsDir(Arc<cap_std::fs::Dir>);
+
+impl OsDir {
+    pub fn new(dir: cap_std::fs::Dir) -> Self {
+        OsDir(Arc::new(dir))
+    }
+
+    /// Spawn a task on tokio's blocking thread for performing blocking
+    /// syscalls on the underlying [`cap_std::fs::Dir`].
+    async fn spawn_blocking<F, R>(&self, body: F) -> R
+    where
+        F: FnOnce(&cap_std::fs::Dir) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let d = self.0.clone();
+        tokio::task::spawn_blocking(move || body(&d)).await.unwrap()
+    }
+
+    async fn set_permissions(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        modes: types::Modes,
+        is_dir: bool,
+    ) -> FsResult<()> {
+        let path = path.to_string();
+        let follow = symlink_follow(path_flags);
+        self.spawn_blocking(move |d| -> FsResult<()> {
+            let meta = if follow {
+                d.metadata(&path)?
+            } else {
+                d.symlink_metadata(&path)?
+            };
+            // Permissions of symlinks themselves can't be changed on most
+            // platforms.
+            if meta.file_type().is_symlink() {
+                return Err(ErrorCode::Loop.into());
+            }
+            if meta.is_dir() != is_dir {
+                return Err(if is_dir {
+                    ErrorCode::NotDirectory
+                } else {
+                    ErrorCode::IsDirectory
+                }
+                .into());
+            }
+            let perms = permissions_from(meta.permissions(), modes, is_dir);
+            Ok(d.set_permissions(&path, perms)?)
+        })
+        .await
+    }
+
+    fn downcast(dir: &dyn HostDir) -> FsResult<Arc<cap_std::fs::Dir>> {
+        match dir.as_any().downcast_ref::<OsDir>() {
+            Some(dir) => Ok(dir.0.clone()),
+            None => Err(ErrorCode::CrossDevice.into()),
+        }
+    }
+}
+
+pub(crate) struct OsFile(Arc<cap_std::fs::File>);
+
+impl OsFile {
+    pub fn new(file: cap_std::fs::File) -> Self {
+        OsFile(Arc::new(file))
+    }
+
+    /// Spawn a task on tokio's blocking thread for performing blocking
+    /// syscalls on the underlying [`cap_std::fs::File`].
+    async fn spawn_blocking<F, R>(&self, body: F) -> R
+    where
+        F: FnOnce(&cap_std::fs::File) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let f = self.0.clone();
+        tokio::task::spawn_blocking(move || body(&f)).await.unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+impl HostDir for OsDir {
+    async fn open_at(
+        &self,
+        path: &str,
+        path_flags: types::PathFlags,
+        oflags: types::OpenFlags,
+        flags: types::DescriptorFlags,
+    ) -> FsResult<OpenResult> {
+        use cap_fs_ext::{FollowSymlinks, OpenOptionsFollowExt, OpenOptionsMaybeDirExt};
+        use system_interface::fs::{FdFlags, GetSetFdFlags};
+        use types::{DescriptorFlags, OpenFlags};
+
+        let mut opts = cap_std::fs::OpenOptions::new();
+        opts.maybe_dir(true);
+
+        if oflags.contains(OpenFlags::CREATE | OpenFlags::EXCLUSIVE) {
+            opts.create_new(true);
+            opts.write(true);
+        } else if oflags.contains(OpenFlags::CREATE) {
+            opts.create(true);
+            opts.write(true);
+        }
+        if oflags.contains(OpenFlags::TRUNCATE) {
+            opts.truncate(true);
+        }
+        if flags.contains(DescriptorFlags::READ) {
+            opts.read(true);
+        }
+        if flags.contains(DescriptorFlags::WRITE) {
+            opts.write(true);
+        } else {
+            // If not opened write, open read. This way the OS lets us open
+            // the file, but we can use perms to reject use of the file later.
+            opts.read(true);
+        }
+        if symlink_follow(path_flags) {
+            opts.follow(FollowSymlinks::Yes);
+        } else {
+            opts.follow(FollowSymlinks::No);
+        }
+
+        // Represents each possible outcome from the spawn_blocking operation.
+        // This makes sure we don't have to give spawn_blocking any way to
+        // manipulate the table.
+        enum Opened {
+            Dir(cap_std::fs::Dir),
+            File(cap_std::fs::File),
+            NotDir,
+        }
+
+        let path = path.to_string();
+        let opened = self
+            .spawn_blocking::<_, std::io::Result<Opened>>(move |d| {
+                let mut opened = d.open_with(&path, &opts)?;
+                if opened.metadata()?.is_dir() {
+                    Ok(Opened::Dir(cap_std::fs::Dir::from_std_file(
+                        opened.into_std(),
+                    )))
+                } else if oflags.contains(OpenFlags::DIRECTORY) {
+                    Ok(Opened::NotDir)
+                } else {
+                    // FIXME cap-std needs a nonblocking open option so that files reads and writes
+                    // are nonblocking. Instead we set it after opening here:
+                    let set_fd_flags = opened.new_set_fd_flags(FdFlags::NONBLOCK)?;
+                    opened.set_fd_flags(set_fd_flags)?;
+                    Ok(Opened::File(opened))
+                }
+            })
+            .await?;
+
+        match opened {
+            Opened::Dir(dir) => Ok(OpenResult::Dir(Box::new(OsDir::new(dir)))),
+            Opened::File(file) => Ok(OpenResult::File(Box::new(OsFile::new(file)))),
+            Opened::NotDir => Err(ErrorCode::NotDirectory.into()),
+        }
+    }
+
+    async fn create_directory_at(&self, path: &str) -> FsResult<()> {
+        let path = path.to_string();
+        self.spawn_blocking(move |d| d.create_dir(&path)).await?;
+        Ok(())
+    }
+
+    async fn stat(&self) -> FsResult<types::DescriptorStat> {
+        let meta = self.spawn_blocking(|d| d.dir_metadata()).await?;
+        Ok(descriptorstat_from(meta))
+    }
+
+    async fn stat_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+    ) -> FsResult<types::DescriptorStat> {
+        let path = path.to_string();
+        let meta = if symlink_follow(path_flags) {
+            self.spawn_blocking(move |d| d.metadata(&path)).await?
+        } else {
+            self.spawn_blocking(move |d| d.symlink_metadata(&path))
+                .await?
+        };
+        Ok(descriptorstat_from(meta))
+    }
+
+    async fn read_directory(&self) -> FsResult<Vec<FsResult<types::DirectoryEntry>>> {
+        enum ReaddirError {
+            Io(std::io::Error),
+            IllegalSequence,
+        }
+        impl From<std::io::Error> for ReaddirError {
+            fn from(e: std::io::Error) -> ReaddirError {
+                ReaddirError::Io(e)
+            }
+        }
+
+        let entries = self
+            .spawn_blocking(|d| {
+                // Both `entries` and `metadata` perform syscalls, which is why they are done
+                // within this `block` call, rather than delay calculating the metadata
+                // for entries when they're demanded later in the iterator chain.
+                Ok::<_, std::io::Error>(
+                    d.entries()?
+                        .map(|entry| {
+                            let entry = entry?;
+                            let meta = entry.metadata()?;
+                            let type_ = descriptortype_from(meta.file_type());
+                            let name = entry
+                                .file_name()
+                                .into_string()
+                                .map_err(|_| ReaddirError::IllegalSequence)?;
+                            Ok(types::DirectoryEntry { type_, name })
+                        })
+                        .collect::<Vec<Result<types::DirectoryEntry, ReaddirError>>>(),
+                )
+            })
+            .await?
+            .into_iter();
+
+        // On windows, filter out files like `C:\DumpStack.log.tmp` which we
+        // can't get full metadata for.
+        #[cfg(windows)]
+        let entries = entries.filter(|entry| {
+            use windows_sys::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_SHARING_VIOLATION};
+            if let Err(ReaddirError::Io(err)) = entry {
+                if err.raw_os_error() == Some(ERROR_SHARING_VIOLATION as i32)
+                    || err.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32)
+                {
+                    return false;
+                }
+            }
+            true
+        });
+        Ok(entries
+            .map(|r| match r {
+                Ok(r) => Ok(r),
+                Err(ReaddirError::Io(e)) => Err(types::Error::from(e)),
+                Err(ReaddirError::IllegalSequence) => Err(ErrorCode::IllegalByteSequence.into()),
+            })
+            .collect())
+    }
+
+    async fn remove_directory_at(&self, path: &str) -> FsResult<()> {
+        let path = path.to_string();
+        Ok(self.spawn_blocking(move |d| d.remove_dir(&path)).await?)
+    }
+
+    async fn unlink_file_at(&self, path: &str) -> FsResult<()> {
+        use cap_fs_ext::DirExt;
+
+        let path = path.to_string();
+        Ok(self
+            .spawn_blocking(move |d| d.remove_file_or_symlink(&path))
+            .await?)
+    }
+
+    async fn rename_at(
+        &self,
+        old_path: &str,
+        new_dir: &dyn HostDir,
+        new_path: &str,
+    ) -> FsResult<()> {
+        let new_dir_handle = OsDir::downcast(new_dir)?;
+        let old_path = old_path.to_string();
+        let new_path = new_path.to_string();
+        Ok(self
+            .spawn_blocking(move |d| d.rename(&old_path, &new_dir_handle, &new_path))
+            .await?)
+    }
+
+    async fn link_at(&self, old_path: &str, new_dir: &dyn HostDir, new_path: &str) -> FsResult<()> {
+        let new_dir_handle = OsDir::downcast(new_dir)?;
+        let old_path = old_path.to_string();
+        let new_path = new_path.to_string();
+        Ok(self
+            .spawn_blocking(move |d| d.hard_link(&old_path, &new_dir_handle, &new_path))
+            .await?)
+    }
+
+    async fn symlink_at(&self, src_path: &str, dest_path: &str) -> FsResult<()> {
+        // On windows, Dir.symlink is provided by DirExt
+        #[cfg(windows)]
+        use cap_fs_ext::DirExt;
+
+        let src_path = src_path.to_string();
+        let dest_path = dest_path.to_string();
+        Ok(self
+            .spawn_blocking(move |d| d.symlink(&src_path, &dest_path))
+            .await?)
+    }
+
+    async fn readlink_at(&self, path: &str) -> FsResult<String> {
+        let path = path.to_string();
+        let link = self.spawn_blocking(move |d| d.read_link(&path)).await?;
+        Ok(link
+            .into_os_string()
+            .into_string()
+            .map_err(|_| ErrorCode::IllegalByteSequence)?)
+    }
+
+    async fn access_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        access: types::AccessType,
+    ) -> FsResult<()> {
+        let path = path.to_string();
+        let follow = symlink_follow(path_flags);
+        let meta = self
+            .spawn_blocking(move |d| {
+                if follow {
+                    d.metadata(&path)
+                } else {
+                    d.symlink_metadata(&path)
+                }
+            })
+            .await?;
+        match access {
+            types::AccessType::Exists => Ok(()),
+            types::AccessType::Access(modes) if has_access(&meta, modes) => Ok(()),
+            types::AccessType::Access(_) => Err(ErrorCode::Access.into()),
+        }
+    }
+
+    async fn change_file_permissions_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        modes: types::Modes,
+    ) -> FsResult<()> {
+        self.set_permissions(path_flags, path, modes, false).await
+    }
+
+    async fn change_directory_permissions_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        modes: types::Modes,
+    ) -> FsResult<()> {
+        self.set_permissions(path_flags, path, modes, true).await
+    }
+
+    async fn set_times(
+        &self,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        use fs_set_times::SetTimes;
+
+        let atim = systemtimespec_from(atim)?;
+        let mtim = systemtimespec_from(mtim)?;
+        self.spawn_blocking(|d| d.set_times(atim, mtim)).await?;
+        Ok(())
+    }
+
+    async fn set_times_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        use cap_fs_ext::DirExt;
+
+        let atim = systemtimespec_from(atim)?;
+        let mtim = systemtimespec_from(mtim)?;
+        let path = path.to_string();
+        if symlink_follow(path_flags) {
+            self.spawn_blocking(move |d| {
+                d.set_times(
+                    &path,
+                    atim.map(cap_fs_ext::SystemTimeSpec::from_std),
+                    mtim.map(cap_fs_ext::SystemTimeSpec::from_std),
+                )
+            })
+            .await?;
+        } else {
+            self.spawn_blocking(move |d| {
+                d.set_symlink_times(
+                    &path,
+                    atim.map(cap_fs_ext::SystemTimeSpec::from_std),
+                    mtim.map(cap_fs_ext::SystemTimeSpec::from_std),
+                )
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn metadata_hash(&self) -> FsResult<types::MetadataHashValue> {
+        let meta = self.spawn_blocking(|d| d.dir_metadata()).await?;
+        Ok(calculate_metadata_hash(&meta))
+    }
+
+    async fn metadata_hash_at(
+        &self,
+        path_flags: types::PathFlags,
+        path: &str,
+    ) -> FsResult<types::MetadataHashValue> {
+        let path = path.to_string();
+        let meta = self
+            .spawn_blocking(move |d| {
+                if symlink_follow(path_flags) {
+                    d.metadata(path)
+                } else {
+                    d.symlink_metadata(path)
+                }
+            })
+            .await?;
+        Ok(calculate_metadata_hash(&meta))
+    }
+
+    async fn get_flags(&self) -> FsResult<types::DescriptorFlags> {
+        use system_interface::fs::GetSetFdFlags;
+        let flags = self.spawn_blocking(|d| d.get_fd_flags()).await?;
+        Ok(descriptorflags_from(flags))
+    }
+
+    async fn sync_data(&self) -> FsResult<()> {
+        self.spawn_blocking(|d| Ok(d.open(std::path::Component::CurDir)?.sync_data()?))
+            .await
+    }
+
+    async fn sync(&self) -> FsResult<()> {
+        self.spawn_blocking(|d| Ok(d.open(std::path::Component::CurDir)?.sync_all()?))
+            .await
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl HostFile for OsFile {
+    async fn read_at(&self, len: usize, offset: u64) -> FsResult<Bytes> {
+        use system_interface::fs::FileIoExt;
+
+        let (r, mut buf) = self
+            .spawn_blocking(move |f| {
+                let mut buf = BytesMut::zeroed(len);
+                loop {
+                    match f.read_at(&mut buf, offset) {
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                        r => break (r, buf),
+                    }
+                }
+            })
+            .await;
+        buf.truncate(r?);
+        Ok(buf.freeze())
+    }
+
+    async fn write_at(&self, buf: Bytes, offset: u64) -> FsResult<usize> {
+        use system_interface::fs::FileIoExt;
+        Ok(self
+            .spawn_blocking(move |f| f.write_at(buf.as_ref(), offset))
+            .await?)
+    }
+
+    async fn append(&self, buf: Bytes) -> FsResult<usize> {
+        use system_interface::fs::FileIoExt;
+        Ok(self.spawn_blocking(move |f| f.append(buf.as_ref())).await?)
+    }
+
+    async fn set_size(&self, size: u64) -> FsResult<()> {
+        self.spawn_blocking(move |f| f.set_len(size)).await?;
+        Ok(())
+    }
+
+    async fn stat(&self) -> FsResult<types::DescriptorStat> {
+        let meta = self.spawn_blocking(|f| f.metadata()).await?;
+        Ok(descriptorstat_from(meta))
+    }
+
+    async fn set_times(
+        &self,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        use fs_set_times::SetTimes;
+
+        let atim = systemtimespec_from(atim)?;
+        let mtim = systemtimespec_from(mtim)?;
+        self.spawn_blocking(|f| f.set_times(atim, mtim)).await?;
+        Ok(())
+    }
+
+    async fn metadata_hash(&self) -> FsResult<types::MetadataHashValue> {
+        let meta = self.spawn_blocking(|f| f.metadata()).await?;
+        Ok(calculate_metadata_hash(&meta))
+    }
+
+    async fn get_flags(&self) -> FsResult<types::DescriptorFlags> {
+        use system_interface::fs::GetSetFdFlags;
+        let flags = self.spawn_blocking(|f| f.get_fd_flags()).await?;
+        Ok(descriptorflags_from(flags))
+    }
+
+    async fn advise(&self, offset: u64, len: u64, advice: types::Advice) -> FsResult<()> {
+        use system_interface::fs::{Advice as A, FileIoExt};
+        use types::Advice;
+
+        let advice = match advice {
+            Advice::Normal => A::Normal,
+            Advice::Sequential => A::Sequential,
+            Advice::Random => A::Random,
+            Advice::WillNeed => A::WillNeed,
+            Advice::DontNeed => A::DontNeed,
+            Advice::NoReuse => A::NoReuse,
+        };
+
+        self.spawn_blocking(move |f| f.advise(offset, len, advice))
+            .await?;
+        Ok(())
+    }
+
+    async fn sync_data(&self) -> FsResult<()> {
+        match self.spawn_blocking(|f| f.sync_data()).await {
+            Ok(()) => Ok(()),
+            // On windows, `sync_data` uses `FileFlushBuffers` which fails with
+            // `ERROR_ACCESS_DENIED` if the file is not upen for writing. Ignore
+            // this error, for POSIX compatibility.
+            #[cfg(windows)]
+            Err(e)
+                if e.raw_os_error()
+                    == Some(windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED as _) =>
+            {
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn sync(&self) -> FsResult<()> {
+        match self.spawn_blocking(|f| f.sync_all()).await {
+            Ok(()) => Ok(()),
+            // On windows, `sync_data` uses `FileFlushBuffers` which fails with
+            // `ERROR_ACCESS_DENIED` if the file is not upen for writing. Ignore
+            // this error, for POSIX compatibility.
+            #[cfg(windows)]
+            Err(e)
+                if e.raw_os_error()
+                    == Some(windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED as _) =>
+            {
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn lock_shared(&self) -> FsResult<()> {
+        Ok(self.spawn_blocking(|f| lock(f, Lock::Shared, true)).await?)
+    }
+
+    async fn lock_exclusive(&self) -> FsResult<()> {
+        Ok(self
+            .spawn_blocking(|f| lock(f, Lock::Exclusive, true))
+            .await?)
+    }
+
+    async fn try_lock_shared(&self) -> FsResult<()> {
+        Ok(self
+            .spawn_blocking(|f| lock(f, Lock::Shared, false))
+            .await?)
+    }
+
+    async fn try_lock_exclusive(&self) -> FsResult<()> {
+        Ok(self
+            .spawn_blocking(|f| lock(f, Lock::Exclusive, false))
+            .await?)
+    }
+
+    async fn unlock(&self) -> FsResult<()> {
+        Ok(self.spawn_blocking(|f| unlock(f)).await?)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Lock {
+    Shared,
+    Exclusive,
+}
+
+#[cfg(unix)]
+fn lock(f: &cap_std::fs::File, lock: Lock, blocking: bool) -> std::io::Result<()> {
+    use rustix::fs::FlockOperation;
+    let op = match (lock, blocking) {
+        (Lock::Shared, true) => FlockOperation::LockShared,
+        (Lock::Shared, false) => FlockOperation::NonBlockingLockShared,
+        (Lock::Exclusive, true) => FlockOperation::LockExclusive,
+        (Lock::Exclusive, false) => FlockOperation::NonBlockingLockExclusive,
+    };
+    Ok(rustix::fs::flock(f, op)?)
+}
+
+#[cfg(unix)]
+fn unlock(f: &cap_std::fs::File) -> std::io::Result<()> {
+    Ok(rustix::fs::flock(f, rustix::fs::FlockOperation::Unlock)?)
+}
+
+#[cfg(windows)]
+fn lock(f: &cap_std::fs::File, lock: Lock, blocking: bool) -> std::io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        LockFileEx, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY,
+    };
+
+    // Unlike `flock`, Windows doesn't convert between shared and exclusive
+    // locks, so release any lock which is already held first. As with
+    // `flock`, this means the conversion isn't atomic.
+    unlock(f)?;
+
+    let mut flags = 0;
+    if let Lock::Exclusive = lock {
+        flags |= LOCKFILE_EXCLUSIVE_LOCK;
+    }
+    if !blocking {
+        flags |= LOCKFILE_FAIL_IMMEDIATELY;
+    }
+    // Lock the whole file, including any part of it which doesn't exist yet.
+    let ret = unsafe {
+        let mut overlapped = std::mem::zeroed();
+        LockFileEx(
+            f.as_raw_handle() as _,
+            flags,
+            0,
+            u32::MAX,
+            u32::MAX,
+            &mut overlapped,
+        )
+    };
+    if ret == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn unlock(f: &cap_std::fs::File) -> std::io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::ERROR_NOT_LOCKED;
+    use windows_sys::Win32::Storage::FileSystem::UnlockFile;
+
+    let ret = unsafe { UnlockFile(f.as_raw_handle() as _, 0, 0, u32::MAX, u32::MAX) };
+    if ret == 0 {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() != Some(ERROR_NOT_LOCKED as i32) {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Applies `modes` to `perms`. On Unix, modes are granted to the owner of the
+/// file, and modes which aren't requested are revoked from everyone.
+fn permissions_from(
+    mut perms: cap_std::fs::Permissions,
+    modes: types::Modes,
+    is_dir: bool,
+) -> cap_std::fs::Permissions {
+    use types::Modes;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Readable directories are also searchable.
+        let search = if is_dir {
+            Modes::READABLE
+        } else {
+            Modes::EXECUTABLE
+        };
+        let mut mode = perms.mode();
+        for (flag, bits) in [
+            (Modes::READABLE, 0o444),
+            (Modes::WRITABLE, 0o222),
+            (search, 0o111),
+        ] {
+            if modes.contains(flag) {
+                mode |= bits & 0o700;
+            } else {
+                mode &= !bits;
+            }
+        }
+        perms.set_mode(mode);
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = is_dir;
+        perms.set_readonly(!modes.contains(Modes::WRITABLE));
+    }
+    perms
+}
+
+/// Whether the host process has all of `modes` on the file described by
+/// `meta`. On Unix this follows the rules of `access(2)` for the effective
+/// user and groups; elsewhere only a read-only file is inaccessible, for
+/// writing.
+fn has_access(meta: &cap_std::fs::Metadata, modes: types::Modes) -> bool {
+    use types::Modes;
+
+    #[cfg(unix)]
+    {
+        use rustix::process::{getegid, geteuid, getgroups};
+        use std::os::unix::fs::MetadataExt;
+
+        let mode = meta.mode();
+        let (read, write, exec) = if geteuid().is_root() {
+            // Root can read and write anything, and execute anything that
+            // someone can.
+            (true, true, meta.is_dir() || mode & 0o111 != 0)
+        } else {
+            let in_group = |gid| {
+                getegid().as_raw() == gid
+                    || getgroups().map_or(false, |groups| groups.iter().any(|g| g.as_raw() == gid))
+            };
+            let bits = if meta.uid() == geteuid().as_raw() {
+                mode >> 6
+            } else if in_group(meta.gid()) {
+                mode >> 3
+            } else {
+                mode
+            };
+            (bits & 0o4 != 0, bits & 0o2 != 0, bits & 0o1 != 0)
+        };
+        (read || !modes.contains(Modes::READABLE))
+            && (write || !modes.contains(Modes::WRITABLE))
+            && (exec || !modes.contains(Modes::EXECUTABLE))
+    }
+    #[cfg(not(unix))]
+    {
+        !(modes.contains(Modes::WRITABLE) && meta.permissions().readonly())
+    }
+}
+
+fn descriptorflags_from(flags: system_interface::fs::FdFlags) -> types::DescriptorFlags {
+    use system_interface::fs::FdFlags;
+    use types::DescriptorFlags;
+
+    let mut out = DescriptorFlags::empty();
+    if flags.contains(FdFlags::DSYNC) {
+        out |= DescriptorFlags::REQUESTED_WRITE_SYNC;
+    }
+    if flags.contains(FdFlags::RSYNC) {
+        out |= DescriptorFlags::DATA_INTEGRITY_SYNC;
+    }
+    if flags.contains(FdFlags::SYNC) {
+        out |= DescriptorFlags::FILE_INTEGRITY_SYNC;
+    }
+    out
+}
+
+fn calculate_metadata_hash(meta: &cap_std::fs::Metadata) -> types::MetadataHashValue {
+    use cap_fs_ext::MetadataExt;
+    // Without incurring any deps, std provides us with a 64 bit hash
+    // function:
+    use std::hash::Hasher;
+    // Note that this means that the metadata hash (which becomes a preview1 ino) may
+    // change when a different rustc release is used to build this host implementation:
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    hasher.write_u64(meta.dev());
+    hasher.write_u64(meta.ino());
+    let lower = hasher.finish();
+    // MetadataHashValue has a pair of 64-bit members for representing a
+    // single 128-bit number. However, we only have 64 bits of entropy. To
+    // synthesize the upper 64 bits, lets xor the lower half with an arbitrary
+    // constant, in this case the 64 bit integer corresponding to the IEEE
+    // double representation of (a number as close as possible to) pi.
+    // This seems better than just repeating the same bits in the upper and
+    // lower parts outright, which could make folks wonder if the struct was
+    // mangled in the ABI, or worse yet, lead to consumers of this interface
+    // expecting them to be equal.
+    let upper = lower ^ 4614256656552045848u64;
+    types::MetadataHashValue { lower, upper }
+}
+
+fn descriptortype_from(ft: cap_std::fs::FileType) -> types::DescriptorType {
+    use cap_fs_ext::FileTypeExt;
+    use types::DescriptorType;
+    if ft.is_dir() {
+        DescriptorType::Directory
+    } else if ft.is_symlink() {
+        DescriptorType::SymbolicLink
+    } else if ft.is_block_device() {
+        DescriptorType::BlockDevice
+    } else if ft.is_char_device() {
+        DescriptorType::CharacterDevice
+    } else if ft.is_file() {
+        DescriptorType::RegularFile
+    } else {
+        DescriptorType::Unknown
+    }
+}
+
+fn systemtimespec_from(
+    t: types::NewTimestamp,
+) -> Result<Option<fs_set_times::SystemTimeSpec>, types::Error> {
+    use fs_set_times::SystemTimeSpec;
+    use types::NewTimestamp;
+    match t {
+        NewTimestamp::NoChange => Ok(None),
+        NewTimestamp::Now => Ok(Some(SystemTimeSpec::SymbolicNow)),
+        NewTimestamp::Timestamp(st) => {
+            Ok(Some(SystemTimeSpec::Absolute(super::systemtime_from(st)?)))
+        }
+    }
+}
+
+fn descriptorstat_from(meta: cap_std::fs::Metadata) -> types::DescriptorStat {
+    use cap_fs_ext::MetadataExt;
+    types::DescriptorStat {
+        type_: descriptortype_from(meta.file_type()),
+        link_count: meta.nlink(),
+        size: meta.len(),
+        // FIXME change the wit to make these timestamps optional
+        data_access_timestamp: meta
+            .accessed()
+            .map(|t| super::datetime_from(t.into_std()))
+            .unwrap_or(wall_clock::Datetime {
+                seconds: 0,
+                nanoseconds: 0,
+            }),
+        data_modification_timestamp: meta
+            .modified()
+            .map(|t| super::datetime_from(t.into_std()))
+            .unwrap_or(wall_clock::Datetime {
+                seconds: 0,
+                nanoseconds: 0,
+            }),
+        status_change_timestamp: meta
+            .created()
+            .map(|t| super::datetime_from(t.into_std()))
+            .unwrap_or(wall_clock::Datetime {
+                seconds: 0,
+                nanoseconds: 0,
+            }),
+    }
+}
+
+fn symlink_follow(path_flags: types::PathFlags) -> bool {
+    path_flags.contains(types::PathFlags::SYMLINK_FOLLOW)
+}
@@ -0,0 +1,54 @@
+use crate::preview2::bindings::logging::handler::Level;
+
+/// A sink for the messages guests emit through `wasi:logging/handler`.
+pub trait HostLogger: Send + Sync {
+    /// Handles a message at `level`. The meaning of `context` is up to the
+    /// guest; it's typically the name of the module the message came from.
+    fn log(&self, level: Level, context: &str, message: &str);
+}
+
+/// The default [`HostLogger`], which forwards messages to the [`log`] crate
+/// using the guest's context as the target.
+///
+/// As the target is only known at runtime, this goes through `log` rather
+/// than `tracing`; `tracing` subscribers can still observe these messages
+/// through `tracing-log`. Messages with an empty context use the target
+/// `wasi_logging`.
+pub struct ForwardingLogger;
+
+impl HostLogger for ForwardingLogger {
+    fn log(&self, level: Level, context: &str, message: &str) {
+        let level = match level {
+            Level::Trace => log::Level::Trace,
+            Level::Debug => log::Level::Debug,
+            Level::Info => log::Level::Info,
+            Level::Warn => log::Level::Warn,
+            Level::Error => log::Level::Error,
+        };
+        let target = if context.is_empty() {
+            "wasi_logging"
+        } else {
+            context
+        };
+        log::log!(target: target, level, "{message}");
+    }
+}
+
+/// Returns whether a message at `level` passes the filter `min_level`, where
+/// `None` filters out every message.
+pub(crate) fn enabled(level: Level, min_level: Option<Level>) -> bool {
+    match min_level {
+        Some(min_level) => severity(level) >= severity(min_level),
+        None => false,
+    }
+}
+
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::Trace => 0,
+        Level::Debug => 1,
+        Level::Info => 2,
+        Level::Warn => 3,
+        Level::Error => 4,
+    }
+}
@@ -20,24 +20,33 @@ pub mod command;
 mod ctx;
 mod error;
 mod filesystem;
+mod logging;
 pub mod pipe;
 mod poll;
 #[cfg(feature = "preview1-on-preview2")]
 pub mod preview1;
 mod preview2;
+mod quota;
 mod random;
 mod stdio;
 mod stream;
 mod table;
+mod tcp;
+mod terminal;
 
-pub use self::clocks::{HostMonotonicClock, HostWallClock};
+pub use self::clocks::{HostMonotonicClock, HostTimezone, HostWallClock, UtcTimezone};
 pub use self::ctx::{WasiCtx, WasiCtxBuilder, WasiView};
 pub use self::error::I32Exit;
-pub use self::filesystem::{DirPerms, FilePerms};
+pub use self::filesystem::{
+    AclDir, DirPerms, FilePerms, FsResult, HostDir, HostFile, MemoryDir, OpenResult, PathAccess,
+    PathAcl,
+};
+pub use self::logging::{ForwardingLogger, HostLogger};
 pub use self::poll::{ClosureFuture, HostPollable, MakeFuture, PollableFuture, TablePollableExt};
 pub use self::random::{thread_rng, Deterministic};
 pub use self::stream::{HostInputStream, HostOutputStream, StreamState, TableStreamExt};
 pub use self::table::{OccupiedEntry, Table, TableError};
+pub use self::terminal::{HostTerminal, TtyTerminal};
 pub use cap_fs_ext::SystemTimeSpec;
 pub use cap_rand::RngCore;
 
@@ -115,7 +124,8 @@ pub mod bindings {
     pub use self::_internal_io::wasi::{io, poll};
 
     pub(crate) mod _internal_rest {
-        wasmtime::component::bindgen!({
+        // The `wasmtime:wasi` package's bindings are a `wasmtime` module.
+        ::wasmtime::component::bindgen!({
         path: "wit",
         interfaces: "
               import wasi:filesystem/preopens
@@ -127,6 +137,12 @@ pub mod bindings {
               import wasi:cli-base/stdin
               import wasi:cli-base/stdout
               import wasi:cli-base/stderr
+              import wasmtime:wasi/terminal-input
+              import wasmtime:wasi/terminal-output
+              import wasmtime:wasi/terminal-stdin
+              import wasmtime:wasi/terminal-stdout
+              import wasmtime:wasi/terminal-stderr
+              import wasi:logging/handler
             ",
         tracing: true,
         trappable_error_type: {
@@ -142,7 +158,12 @@ pub mod bindings {
         });
     }
 
-    pub use self::_internal_rest::wasi::{cli_base, random};
+    pub use self::_internal_rest::wasi::{cli_base, logging, random};
+    pub mod terminal {
+        pub use super::_internal_rest::wasmtime::wasi::{
+            terminal_input, terminal_output, terminal_stderr, terminal_stdin, terminal_stdout,
+        };
+    }
     pub mod filesystem {
         pub use super::_internal_io::wasi::filesystem::types;
         pub use super::_internal_rest::wasi::filesystem::preopens;
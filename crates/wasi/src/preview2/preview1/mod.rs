@@ -4,12 +4,14 @@ use crate::preview2::bindings::filesystem::{preopens, types as filesystem};
 use crate::preview2::bindings::io::streams;
 use crate::preview2::filesystem::TableFsExt;
 use crate::preview2::preview2::filesystem::TableReaddirExt;
-use crate::preview2::{bindings, TableError, WasiView};
+use crate::preview2::tcp::TableTcpExt;
+use crate::preview2::{bindings, TableError, TableStreamExt, WasiView};
 use anyhow::{anyhow, bail, Context};
 use std::borrow::Borrow;
 use std::cell::Cell;
 use std::collections::BTreeMap;
 use std::mem::{size_of, size_of_val};
+use std::net::Shutdown;
 use std::ops::{Deref, DerefMut};
 use std::slice;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -34,12 +36,32 @@ struct File {
     blocking: bool,
 }
 
+#[derive(Clone, Debug)]
+struct TcpStream {
+    /// The handle to the preview2 socket, which is used to shut it down.
+    socket: u32,
+
+    /// The stream the data received on the socket is read from.
+    input: streams::InputStream,
+
+    /// The stream the data sent on the socket is written to.
+    output: streams::OutputStream,
+
+    /// In blocking mode, receiving and sending dispatch to blocking_read and
+    /// blocking_write on the underlying streams.
+    blocking: bool,
+}
+
 #[derive(Clone, Debug)]
 enum Descriptor {
     Stdin(streams::InputStream),
     Stdout(streams::OutputStream),
     Stderr(streams::OutputStream),
     PreopenDirectory((filesystem::Descriptor, String)),
+    /// The handle to a listening socket preopened with
+    /// [`crate::preview2::WasiCtxBuilder::preopened_tcp_listener`].
+    TcpListener(u32),
+    TcpStream(TcpStream),
     File(File),
 }
 
@@ -71,7 +93,12 @@ impl DerefMut for Descriptors {
 impl Descriptors {
     /// Initializes [Self] using `preopens`
     fn new(
-        preopens: &mut (impl preopens::Host + stdin::Host + stdout::Host + stderr::Host + ?Sized),
+        preopens: &mut (impl preopens::Host
+                  + stdin::Host
+                  + stdout::Host
+                  + stderr::Host
+                  + WasiView
+                  + ?Sized),
     ) -> Result<Self, types::Error> {
         let stdin = preopens
             .get_stdin()
@@ -94,6 +121,9 @@ impl Descriptors {
         descriptors.push(Descriptor::Stdin(stdin))?;
         descriptors.push(Descriptor::Stdout(stdout))?;
         descriptors.push(Descriptor::Stderr(stderr))?;
+        for listener in preopens.ctx().preopened_sockets.iter() {
+            descriptors.push(Descriptor::TcpListener(*listener))?;
+        }
         for dir in directories {
             descriptors.push(Descriptor::PreopenDirectory(dir))?;
         }
@@ -232,7 +262,12 @@ impl<T: WasiPreview1View + ?Sized> Transaction<'_, T> {
             Some(Descriptor::File(file @ File { fd, .. })) if self.view.table().is_file(*fd) => {
                 Ok(file)
             }
-            Some(Descriptor::Stdin(..) | Descriptor::Stdout(..) | Descriptor::Stderr(..)) => {
+            Some(
+                Descriptor::Stdin(..)
+                | Descriptor::Stdout(..)
+                | Descriptor::Stderr(..)
+                | Descriptor::TcpStream(..),
+            ) => {
                 // NOTE: legacy implementation returns SPIPE here
                 Err(types::Errno::Spipe.into())
             }
@@ -247,6 +282,8 @@ impl<T: WasiPreview1View + ?Sized> Transaction<'_, T> {
             Descriptor::PreopenDirectory((fd, _)) => Ok(*fd),
             Descriptor::Stdin(stream) => Ok(*stream),
             Descriptor::Stdout(stream) | Descriptor::Stderr(stream) => Ok(*stream),
+            Descriptor::TcpListener(listener) => Ok(*listener),
+            Descriptor::TcpStream(TcpStream { input, .. }) => Ok(*input),
         }
     }
 
@@ -346,10 +383,12 @@ wiggle::from_witx!({
             fd_filestat_set_times, fd_read, fd_pread, fd_seek, fd_sync, fd_readdir, fd_write,
             fd_pwrite, poll_oneoff, path_create_directory, path_filestat_get,
             path_filestat_set_times, path_link, path_open, path_readlink, path_remove_directory,
-            path_rename, path_symlink, path_unlink_file
+            path_rename, path_symlink, path_unlink_file, sock_accept, sock_recv, sock_send,
+            sock_shutdown
         }
     },
     errors: { errno => trappable Error },
+    pointer_sized: [size],
 });
 
 impl wiggle::GuestErrorType for types::Errno {
@@ -566,6 +605,20 @@ impl From<TableError> for types::Error {
     }
 }
 
+/// Converts an error from an operation on a socket.
+fn socket_error(err: std::io::Error) -> types::Error {
+    use std::io::ErrorKind;
+    match err.kind() {
+        ErrorKind::WouldBlock => types::Errno::Again.into(),
+        ErrorKind::ConnectionAborted => types::Errno::Connaborted.into(),
+        ErrorKind::ConnectionReset => types::Errno::Connreset.into(),
+        ErrorKind::NotConnected => types::Errno::Notconn.into(),
+        ErrorKind::BrokenPipe => types::Errno::Pipe.into(),
+        ErrorKind::InvalidInput => types::Errno::Inval.into(),
+        _ => types::Errno::Io.into(),
+    }
+}
+
 type Result<T, E = types::Error> = std::result::Result<T, E>;
 
 fn write_bytes<'a>(
@@ -818,6 +871,34 @@ impl<
                 .drop_descriptor(fd)
                 .await
                 .context("failed to call `drop-descriptor`"),
+            Descriptor::TcpListener(listener) => self
+                .table_mut()
+                .delete_tcp_listener(listener)
+                .map(|_| ())
+                .context("failed to delete listener"),
+            Descriptor::TcpStream(TcpStream {
+                socket,
+                input,
+                output,
+                ..
+            }) => {
+                // Wait for what was sent to be written to the socket.
+                if let Ok(stream) = self.table_mut().get_output_stream_mut(output) {
+                    let _ = stream.ready().await;
+                }
+                let input = streams::Host::drop_input_stream(self, input)
+                    .await
+                    .context("failed to call `drop-input-stream`");
+                let output = streams::Host::drop_output_stream(self, output)
+                    .await
+                    .context("failed to call `drop-output-stream`");
+                let socket = self
+                    .table_mut()
+                    .delete_tcp_stream(socket)
+                    .map(|_| ())
+                    .context("failed to delete socket");
+                input.and(output).and(socket)
+            }
         }
         .map_err(types::Error::trap)
     }
@@ -857,6 +938,33 @@ impl<
                     fs_rights_inheriting: fs_rights_base,
                 });
             }
+            Descriptor::TcpListener(..) => {
+                let fs_rights_base = types::Rights::SOCK_ACCEPT | types::Rights::POLL_FD_READWRITE;
+                return Ok(types::Fdstat {
+                    fs_filetype: types::Filetype::SocketStream,
+                    fs_flags: types::Fdflags::empty(),
+                    fs_rights_base,
+                    fs_rights_inheriting: fs_rights_base,
+                });
+            }
+            Descriptor::TcpStream(TcpStream { blocking, .. }) => {
+                let fs_rights_base = types::Rights::FD_READ
+                    | types::Rights::FD_WRITE
+                    | types::Rights::FD_FDSTAT_SET_FLAGS
+                    | types::Rights::POLL_FD_READWRITE
+                    | types::Rights::SOCK_SHUTDOWN;
+                let fs_flags = if *blocking {
+                    types::Fdflags::empty()
+                } else {
+                    types::Fdflags::NONBLOCK
+                };
+                return Ok(types::Fdstat {
+                    fs_filetype: types::Filetype::SocketStream,
+                    fs_flags,
+                    fs_rights_base,
+                    fs_rights_inheriting: fs_rights_base,
+                });
+            }
             Descriptor::PreopenDirectory((_, _)) => {
                 // Hard-coded set or rights expected by many userlands:
                 let fs_rights_base = types::Rights::PATH_CREATE_DIRECTORY
@@ -963,6 +1071,18 @@ impl<
         flags: types::Fdflags,
     ) -> Result<(), types::Error> {
         let mut st = self.transact()?;
+
+        // Only support changing the NONBLOCK flag of sockets.
+        if let Some(Descriptor::TcpStream(TcpStream { blocking, .. })) =
+            st.descriptors.get_mut().get_mut(&u32::from(fd))
+        {
+            if (flags & !types::Fdflags::NONBLOCK) != types::Fdflags::empty() {
+                return Err(types::Errno::Inval.into());
+            }
+            *blocking = !flags.contains(types::Fdflags::NONBLOCK);
+            return Ok(());
+        }
+
         let File {
             append, blocking, ..
         } = st.get_file_mut(fd)?;
@@ -1008,6 +1128,16 @@ impl<
                     ctim: 0,
                 })
             }
+            Descriptor::TcpListener(..) | Descriptor::TcpStream(..) => Ok(types::Filestat {
+                dev: 0,
+                ino: 0,
+                filetype: types::Filetype::SocketStream,
+                nlink: 0,
+                size: 0,
+                atim: 0,
+                mtim: 0,
+                ctim: 0,
+            }),
             Descriptor::PreopenDirectory((fd, _)) | Descriptor::File(File { fd, .. }) => {
                 let filesystem::DescriptorStat {
                     type_,
@@ -1139,6 +1269,16 @@ impl<
                         .map_err(|_| types::Errno::Io)?;
                 (buf, read, state)
             }
+            Descriptor::TcpStream(..) => {
+                let (n, _) = wasi_snapshot_preview1::WasiSnapshotPreview1::sock_recv(
+                    self,
+                    fd,
+                    iovs,
+                    types::Riflags::empty(),
+                )
+                .await?;
+                return Ok(n);
+            }
             _ => return Err(types::Errno::Badf.into()),
         };
         if read.len() > buf.len() {
@@ -1184,7 +1324,7 @@ impl<
 
                 (buf, read, state)
             }
-            Descriptor::Stdin(..) => {
+            Descriptor::Stdin(..) | Descriptor::TcpStream(..) => {
                 // NOTE: legacy implementation returns SPIPE here
                 return Err(types::Errno::Spipe.into());
             }
@@ -1258,6 +1398,10 @@ impl<
                     .map_err(|_| types::Errno::Io)?;
                 n
             }
+            Descriptor::TcpStream(..) => {
+                return wasi_snapshot_preview1::WasiSnapshotPreview1::sock_send(self, fd, ciovs, 0)
+                    .await;
+            }
             _ => return Err(types::Errno::Badf.into()),
         };
         let n = n.try_into()?;
@@ -1291,7 +1435,7 @@ impl<
                 }
                 .map_err(|_| types::Errno::Io)?
             }
-            Descriptor::Stdout(..) | Descriptor::Stderr(..) => {
+            Descriptor::Stdout(..) | Descriptor::Stderr(..) | Descriptor::TcpStream(..) => {
                 // NOTE: legacy implementation returns SPIPE here
                 return Err(types::Errno::Spipe.into());
             }
@@ -1845,41 +1989,158 @@ impl<
         Ok(())
     }
 
-    #[allow(unused_variables)]
     #[instrument(skip(self))]
-    fn sock_accept(
+    async fn sock_accept(
         &mut self,
         fd: types::Fd,
         flags: types::Fdflags,
     ) -> Result<types::Fd, types::Error> {
-        todo!("preview1 sock_accept is not implemented")
+        let blocking = if flags == types::Fdflags::NONBLOCK {
+            false
+        } else if flags.is_empty() {
+            true
+        } else {
+            return Err(types::Errno::Inval.into());
+        };
+        let listener = match self.transact()?.get_descriptor(fd)? {
+            Descriptor::TcpListener(listener) => *listener,
+            _ => return Err(types::Errno::Notsock.into()),
+        };
+        let (socket, input, output) = self
+            .table()
+            .get_tcp_listener(listener)?
+            .accept()
+            .await
+            .map_err(socket_error)?;
+        let table = self.table_mut();
+        let socket = table.push_tcp_stream(socket)?;
+        let input = table.push_input_stream(Box::new(input))?;
+        let output = table.push_output_stream(Box::new(output))?;
+        let fd = self
+            .transact()?
+            .descriptors
+            .get_mut()
+            .push(Descriptor::TcpStream(TcpStream {
+                socket,
+                input,
+                output,
+                blocking,
+            }))?;
+        Ok(fd.into())
     }
 
-    #[allow(unused_variables)]
+    /// Receive data from a socket.
+    ///
+    /// Data is received through a preview2 stream, which can't be peeked at,
+    /// so `RECV_PEEK` is not supported.
     #[instrument(skip(self))]
-    fn sock_recv<'a>(
+    async fn sock_recv<'a>(
         &mut self,
         fd: types::Fd,
         ri_data: &types::IovecArray<'a>,
         ri_flags: types::Riflags,
     ) -> Result<(types::Size, types::Roflags), types::Error> {
-        todo!("preview1 sock_recv is not implemented")
+        let (input, blocking) = match self.transact()?.get_descriptor(fd)? {
+            Descriptor::TcpStream(TcpStream {
+                input, blocking, ..
+            }) => (*input, *blocking),
+            _ => return Err(types::Errno::Notsock.into()),
+        };
+        if ri_flags.contains(types::Riflags::RECV_PEEK) {
+            return Err(types::Errno::Notsup.into());
+        }
+        let Some(mut buf) = first_non_empty_iovec(ri_data)? else {
+            return Ok((0, types::Roflags::empty()))
+        };
+        let wait_all = ri_flags.contains(types::Riflags::RECV_WAITALL);
+
+        let mut n = 0;
+        loop {
+            let max = (buf.len() - n).try_into().unwrap_or(u64::MAX);
+            let (read, state) = if blocking || wait_all {
+                streams::Host::blocking_read(self, input, max).await
+            } else {
+                streams::Host::read(self, input, max).await
+            }
+            .map_err(|_| types::Errno::Io)?;
+            buf.get_mut(n..n + read.len())
+                .ok_or(types::Errno::Range)?
+                .copy_from_slice(&read);
+            n += read.len();
+            if state == streams::StreamStatus::Open && n == 0 {
+                return Err(types::Errno::Again.into());
+            }
+            if !wait_all || n == buf.len() || state == streams::StreamStatus::Ended {
+                break;
+            }
+        }
+        Ok((n.try_into()?, types::Roflags::empty()))
     }
 
-    #[allow(unused_variables)]
+    /// Send data on a socket.
     #[instrument(skip(self))]
-    fn sock_send<'a>(
+    async fn sock_send<'a>(
         &mut self,
         fd: types::Fd,
         si_data: &types::CiovecArray<'a>,
-        _si_flags: types::Siflags,
+        si_flags: types::Siflags,
     ) -> Result<types::Size, types::Error> {
-        todo!("preview1 sock_send is not implemented")
+        let (output, blocking) = match self.transact()?.get_descriptor(fd)? {
+            Descriptor::TcpStream(TcpStream {
+                output, blocking, ..
+            }) => (*output, *blocking),
+            _ => return Err(types::Errno::Notsock.into()),
+        };
+        if si_flags != 0 {
+            return Err(types::Errno::Notsup.into());
+        }
+        let Some(buf) = first_non_empty_ciovec(si_data)? else {
+            return Ok(0)
+        };
+        let (n, _stat) = if blocking {
+            streams::Host::blocking_write(self, output, buf).await
+        } else {
+            streams::Host::write(self, output, buf).await
+        }
+        .map_err(|_| types::Errno::Io)?;
+        if n == 0 {
+            return Err(types::Errno::Again.into());
+        }
+        Ok(n.try_into()?)
     }
 
-    #[allow(unused_variables)]
+    /// Shut down socket send and receive channels.
     #[instrument(skip(self))]
-    fn sock_shutdown(&mut self, fd: types::Fd, how: types::Sdflags) -> Result<(), types::Error> {
-        todo!("preview1 sock_shutdown is not implemented")
+    async fn sock_shutdown(
+        &mut self,
+        fd: types::Fd,
+        how: types::Sdflags,
+    ) -> Result<(), types::Error> {
+        let (socket, output) = match self.transact()?.get_descriptor(fd)? {
+            Descriptor::TcpStream(TcpStream { socket, output, .. }) => (*socket, *output),
+            _ => return Err(types::Errno::Notsock.into()),
+        };
+        let how = if how == types::Sdflags::RD | types::Sdflags::WR {
+            Shutdown::Both
+        } else if how == types::Sdflags::RD {
+            Shutdown::Read
+        } else if how == types::Sdflags::WR {
+            Shutdown::Write
+        } else {
+            return Err(types::Errno::Inval.into());
+        };
+        if how != Shutdown::Read {
+            // What was sent is written to the socket in the background, so
+            // wait for that before shutting it down.
+            self.table_mut()
+                .get_output_stream_mut(output)?
+                .ready()
+                .await
+                .map_err(|_| types::Errno::Io)?;
+        }
+        self.table()
+            .get_tcp_stream(socket)?
+            .shutdown(how)
+            .map_err(socket_error)
     }
 }
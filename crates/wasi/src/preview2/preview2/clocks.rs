@@ -7,7 +7,7 @@ use crate::preview2::bindings::{
     poll::poll::Pollable,
 };
 use crate::preview2::{HostPollable, TablePollableExt, WasiView};
-use cap_std::time::SystemTime;
+use cap_std::time::{Duration, SystemTime};
 
 impl TryFrom<SystemTime> for Datetime {
     type Error = anyhow::Error;
@@ -51,7 +51,6 @@ impl<T: WasiView> monotonic_clock::Host for T {
     }
 
     fn subscribe(&mut self, when: Instant, absolute: bool) -> anyhow::Result<Pollable> {
-        use std::time::Duration;
         // Calculate time relative to clock object, which may not have the same zero
         // point as tokio Inst::now()
         let clock_now = self.ctx().monotonic_clock.now();
@@ -92,16 +91,22 @@ impl<T: WasiView> monotonic_clock::Host for T {
     }
 }
 
+fn duration_from(when: Datetime) -> Duration {
+    Duration::from_secs(when.seconds).saturating_add(Duration::from_nanos(when.nanoseconds.into()))
+}
+
+// There's no way for a guest to acquire a `timezone` handle other than
+// making one up, so every handle refers to the timezone of the context.
 impl<T: WasiView> timezone::Host for T {
     fn display(&mut self, timezone: Timezone, when: Datetime) -> anyhow::Result<TimezoneDisplay> {
-        todo!("timezone display is not implemented")
+        Ok(self.ctx().timezone.display(duration_from(when)))
     }
 
     fn utc_offset(&mut self, timezone: Timezone, when: Datetime) -> anyhow::Result<i32> {
-        todo!("timezone utc_offset is not implemented")
+        Ok(self.ctx().timezone.utc_offset(duration_from(when)))
     }
 
     fn drop_timezone(&mut self, timezone: Timezone) -> anyhow::Result<()> {
-        todo!("timezone drop is not implemented")
+        Ok(())
     }
 }
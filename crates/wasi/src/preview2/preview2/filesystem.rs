@@ -1,7 +1,6 @@
-use crate::preview2::bindings::clocks::wall_clock;
 use crate::preview2::bindings::filesystem::{preopens, types};
 use crate::preview2::bindings::io::streams;
-use crate::preview2::filesystem::{Dir, File, TableFsExt};
+use crate::preview2::filesystem::{charge_gap, Dir, File, OpenResult, TableFsExt};
 use crate::preview2::{DirPerms, FilePerms, Table, TableError, WasiView};
 
 use types::ErrorCode;
@@ -10,7 +9,12 @@ mod sync;
 
 impl From<TableError> for types::Error {
     fn from(error: TableError) -> Self {
-        Self::trap(error.into())
+        match error {
+            // The table only fills up before running out of keys if the
+            // embedder limited its size.
+            TableError::Full => ErrorCode::Quota.into(),
+            _ => Self::trap(error.into()),
+        }
     }
 }
 
@@ -29,46 +33,16 @@ impl<T: WasiView> types::Host for T {
         len: types::Filesize,
         advice: types::Advice,
     ) -> Result<(), types::Error> {
-        use system_interface::fs::{Advice as A, FileIoExt};
-        use types::Advice;
-
-        let advice = match advice {
-            Advice::Normal => A::Normal,
-            Advice::Sequential => A::Sequential,
-            Advice::Random => A::Random,
-            Advice::WillNeed => A::WillNeed,
-            Advice::DontNeed => A::DontNeed,
-            Advice::NoReuse => A::NoReuse,
-        };
-
         let f = self.table().get_file(fd)?;
-        f.spawn_blocking(move |f| f.advise(offset, len, advice))
-            .await?;
-        Ok(())
+        f.file.advise(offset, len, advice).await
     }
 
     async fn sync_data(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
         let table = self.table();
         if table.is_file(fd) {
-            let f = table.get_file(fd)?;
-            match f.spawn_blocking(|f| f.sync_data()).await {
-                Ok(()) => Ok(()),
-                // On windows, `sync_data` uses `FileFlushBuffers` which fails with
-                // `ERROR_ACCESS_DENIED` if the file is not upen for writing. Ignore
-                // this error, for POSIX compatibility.
-                #[cfg(windows)]
-                Err(e)
-                    if e.raw_os_error()
-                        == Some(windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED as _) =>
-                {
-                    Ok(())
-                }
-                Err(e) => Err(e.into()),
-            }
+            table.get_file(fd)?.file.sync_data().await
         } else if table.is_dir(fd) {
-            let d = table.get_dir(fd)?;
-            d.spawn_blocking(|d| Ok(d.open(std::path::Component::CurDir)?.sync_data()?))
-                .await
+            table.get_dir(fd)?.dir.sync_data().await
         } else {
             Err(ErrorCode::BadDescriptor.into())
         }
@@ -78,28 +52,12 @@ impl<T: WasiView> types::Host for T {
         &mut self,
         fd: types::Descriptor,
     ) -> Result<types::DescriptorFlags, types::Error> {
-        use system_interface::fs::{FdFlags, GetSetFdFlags};
         use types::DescriptorFlags;
 
-        fn get_from_fdflags(flags: FdFlags) -> DescriptorFlags {
-            let mut out = DescriptorFlags::empty();
-            if flags.contains(FdFlags::DSYNC) {
-                out |= DescriptorFlags::REQUESTED_WRITE_SYNC;
-            }
-            if flags.contains(FdFlags::RSYNC) {
-                out |= DescriptorFlags::DATA_INTEGRITY_SYNC;
-            }
-            if flags.contains(FdFlags::SYNC) {
-                out |= DescriptorFlags::FILE_INTEGRITY_SYNC;
-            }
-            out
-        }
-
         let table = self.table();
         if table.is_file(fd) {
             let f = table.get_file(fd)?;
-            let flags = f.spawn_blocking(|f| f.get_fd_flags()).await?;
-            let mut flags = get_from_fdflags(flags);
+            let mut flags = f.file.get_flags().await?;
             if f.perms.contains(FilePerms::READ) {
                 flags |= DescriptorFlags::READ;
             }
@@ -109,8 +67,7 @@ impl<T: WasiView> types::Host for T {
             Ok(flags)
         } else if table.is_dir(fd) {
             let d = table.get_dir(fd)?;
-            let flags = d.spawn_blocking(|d| d.get_fd_flags()).await?;
-            let mut flags = get_from_fdflags(flags);
+            let mut flags = d.dir.get_flags().await?;
             if d.perms.contains(DirPerms::READ) {
                 flags |= DescriptorFlags::READ;
             }
@@ -131,8 +88,7 @@ impl<T: WasiView> types::Host for T {
 
         if table.is_file(fd) {
             let f = table.get_file(fd)?;
-            let meta = f.spawn_blocking(|f| f.metadata()).await?;
-            Ok(descriptortype_from(meta.file_type()))
+            Ok(f.file.stat().await?.type_)
         } else if table.is_dir(fd) {
             Ok(types::DescriptorType::Directory)
         } else {
@@ -149,8 +105,19 @@ impl<T: WasiView> types::Host for T {
         if !f.perms.contains(FilePerms::WRITE) {
             Err(ErrorCode::NotPermitted)?;
         }
-        f.spawn_blocking(move |f| f.set_len(size)).await?;
-        Ok(())
+
+        // Growing a file writes zeros to it, so that counts against the quota
+        // of bytes written too.
+        let quota = &self.ctx().bytes_written;
+        let growth = size.saturating_sub(f.file.stat().await?.size);
+        if !quota.take_all(growth) {
+            return Err(ErrorCode::Quota.into());
+        }
+        let result = f.file.set_size(size).await;
+        if result.is_err() {
+            quota.give_back(growth);
+        }
+        result
     }
 
     async fn set_times(
@@ -159,27 +126,19 @@ impl<T: WasiView> types::Host for T {
         atim: types::NewTimestamp,
         mtim: types::NewTimestamp,
     ) -> Result<(), types::Error> {
-        use fs_set_times::SetTimes;
-
         let table = self.table();
         if table.is_file(fd) {
             let f = table.get_file(fd)?;
             if !f.perms.contains(FilePerms::WRITE) {
                 return Err(ErrorCode::NotPermitted.into());
             }
-            let atim = systemtimespec_from(atim)?;
-            let mtim = systemtimespec_from(mtim)?;
-            f.spawn_blocking(|f| f.set_times(atim, mtim)).await?;
-            Ok(())
+            f.file.set_times(atim, mtim).await
         } else if table.is_dir(fd) {
             let d = table.get_dir(fd)?;
             if !d.perms.contains(DirPerms::MUTATE) {
                 return Err(ErrorCode::NotPermitted.into());
             }
-            let atim = systemtimespec_from(atim)?;
-            let mtim = systemtimespec_from(mtim)?;
-            d.spawn_blocking(|d| d.set_times(atim, mtim)).await?;
-            Ok(())
+            d.dir.set_times(atim, mtim).await
         } else {
             Err(ErrorCode::BadDescriptor.into())
         }
@@ -191,9 +150,6 @@ impl<T: WasiView> types::Host for T {
         len: types::Filesize,
         offset: types::Filesize,
     ) -> Result<(Vec<u8>, bool), types::Error> {
-        use std::io::IoSliceMut;
-        use system_interface::fs::FileIoExt;
-
         let table = self.table();
 
         let f = table.get_file(fd)?;
@@ -201,23 +157,11 @@ impl<T: WasiView> types::Host for T {
             return Err(ErrorCode::NotPermitted.into());
         }
 
-        let (mut buffer, r) = f
-            .spawn_blocking(move |f| {
-                let mut buffer = vec![0; len.try_into().unwrap_or(usize::MAX)];
-                let r = f.read_vectored_at(&mut [IoSliceMut::new(&mut buffer)], offset);
-                (buffer, r)
-            })
-            .await;
-
-        let (bytes_read, state) = crate::preview2::filesystem::read_result(r)?;
+        let len = len.try_into().unwrap_or(usize::MAX);
+        let buffer = f.file.read_at(len, offset).await?;
+        let eof = buffer.is_empty() && len > 0;
 
-        buffer.truncate(
-            bytes_read
-                .try_into()
-                .expect("bytes read into memory as u64 fits in usize"),
-        );
-
-        Ok((buffer, state.is_closed()))
+        Ok((buffer.to_vec(), eof))
     }
 
     async fn write(
@@ -226,18 +170,27 @@ impl<T: WasiView> types::Host for T {
         buf: Vec<u8>,
         offset: types::Filesize,
     ) -> Result<types::Filesize, types::Error> {
-        use std::io::IoSlice;
-        use system_interface::fs::FileIoExt;
-
         let table = self.table();
         let f = table.get_file(fd)?;
         if !f.perms.contains(FilePerms::WRITE) {
             return Err(ErrorCode::NotPermitted.into());
         }
 
-        let bytes_written = f
-            .spawn_blocking(move |f| f.write_vectored_at(&[IoSlice::new(&buf)], offset))
-            .await?;
+        let quota = &self.ctx().bytes_written;
+        let mut buf = bytes::Bytes::from(buf);
+        let gap = charge_gap(&*f.file, offset, quota).await?;
+        if !quota.reserve(&mut buf) {
+            quota.give_back(gap);
+            return Err(ErrorCode::Quota.into());
+        }
+        let reserved = buf.len();
+        let result = f.file.write_at(buf, offset).await;
+        let written = *result.as_ref().unwrap_or(&0);
+        quota.settle(reserved, written);
+        if written == 0 {
+            quota.give_back(gap);
+        }
+        let bytes_written = result?;
 
         Ok(types::Filesize::try_from(bytes_written).expect("usize fits in Filesize"))
     }
@@ -252,59 +205,8 @@ impl<T: WasiView> types::Host for T {
             return Err(ErrorCode::NotPermitted.into());
         }
 
-        enum ReaddirError {
-            Io(std::io::Error),
-            IllegalSequence,
-        }
-        impl From<std::io::Error> for ReaddirError {
-            fn from(e: std::io::Error) -> ReaddirError {
-                ReaddirError::Io(e)
-            }
-        }
-
-        let entries = d
-            .spawn_blocking(|d| {
-                // Both `entries` and `metadata` perform syscalls, which is why they are done
-                // within this `block` call, rather than delay calculating the metadata
-                // for entries when they're demanded later in the iterator chain.
-                Ok::<_, std::io::Error>(
-                    d.entries()?
-                        .map(|entry| {
-                            let entry = entry?;
-                            let meta = entry.metadata()?;
-                            let type_ = descriptortype_from(meta.file_type());
-                            let name = entry
-                                .file_name()
-                                .into_string()
-                                .map_err(|_| ReaddirError::IllegalSequence)?;
-                            Ok(types::DirectoryEntry { type_, name })
-                        })
-                        .collect::<Vec<Result<types::DirectoryEntry, ReaddirError>>>(),
-                )
-            })
-            .await?
-            .into_iter();
-
-        // On windows, filter out files like `C:\DumpStack.log.tmp` which we
-        // can't get full metadata for.
-        #[cfg(windows)]
-        let entries = entries.filter(|entry| {
-            use windows_sys::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_SHARING_VIOLATION};
-            if let Err(ReaddirError::Io(err)) = entry {
-                if err.raw_os_error() == Some(ERROR_SHARING_VIOLATION as i32)
-                    || err.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32)
-                {
-                    return false;
-                }
-            }
-            true
-        });
-        let entries = entries.map(|r| match r {
-            Ok(r) => Ok(r),
-            Err(ReaddirError::Io(e)) => Err(types::Error::from(e)),
-            Err(ReaddirError::IllegalSequence) => Err(ErrorCode::IllegalByteSequence.into()),
-        });
-        Ok(table.push_readdir(ReaddirIterator::new(entries))?)
+        let entries = d.dir.read_directory().await?;
+        Ok(table.push_readdir(ReaddirIterator::new(entries.into_iter()))?)
     }
 
     async fn read_directory_entry(
@@ -327,25 +229,9 @@ impl<T: WasiView> types::Host for T {
     async fn sync(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
         let table = self.table();
         if table.is_file(fd) {
-            let f = table.get_file(fd)?;
-            match f.spawn_blocking(|f| f.sync_all()).await {
-                Ok(()) => Ok(()),
-                // On windows, `sync_data` uses `FileFlushBuffers` which fails with
-                // `ERROR_ACCESS_DENIED` if the file is not upen for writing. Ignore
-                // this error, for POSIX compatibility.
-                #[cfg(windows)]
-                Err(e)
-                    if e.raw_os_error()
-                        == Some(windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED as _) =>
-                {
-                    Ok(())
-                }
-                Err(e) => Err(e.into()),
-            }
+            table.get_file(fd)?.file.sync().await
         } else if table.is_dir(fd) {
-            let d = table.get_dir(fd)?;
-            d.spawn_blocking(|d| Ok(d.open(std::path::Component::CurDir)?.sync_all()?))
-                .await
+            table.get_dir(fd)?.dir.sync().await
         } else {
             Err(ErrorCode::BadDescriptor.into())
         }
@@ -361,8 +247,15 @@ impl<T: WasiView> types::Host for T {
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
-        d.spawn_blocking(move |d| d.create_dir(&path)).await?;
-        Ok(())
+        let quota = &self.ctx().files_created;
+        if quota.take(1) == 0 {
+            return Err(ErrorCode::Quota.into());
+        }
+        let result = d.dir.create_directory_at(&path).await;
+        if result.is_err() {
+            quota.give_back(1);
+        }
+        result
     }
 
     async fn stat(&mut self, fd: types::Descriptor) -> Result<types::DescriptorStat, types::Error> {
@@ -370,13 +263,11 @@ impl<T: WasiView> types::Host for T {
         if table.is_file(fd) {
             let f = table.get_file(fd)?;
             // No permissions check on stat: if opened, allowed to stat it
-            let meta = f.spawn_blocking(|f| f.metadata()).await?;
-            Ok(descriptorstat_from(meta))
+            f.file.stat().await
         } else if table.is_dir(fd) {
             let d = table.get_dir(fd)?;
             // No permissions check on stat: if opened, allowed to stat it
-            let meta = d.spawn_blocking(|d| d.dir_metadata()).await?;
-            Ok(descriptorstat_from(meta))
+            d.dir.stat().await
         } else {
             Err(ErrorCode::BadDescriptor.into())
         }
@@ -393,13 +284,7 @@ impl<T: WasiView> types::Host for T {
         if !d.perms.contains(DirPerms::READ) {
             return Err(ErrorCode::NotPermitted.into());
         }
-
-        let meta = if symlink_follow(path_flags) {
-            d.spawn_blocking(move |d| d.metadata(&path)).await?
-        } else {
-            d.spawn_blocking(move |d| d.symlink_metadata(&path)).await?
-        };
-        Ok(descriptorstat_from(meta))
+        d.dir.stat_at(path_flags, &path).await
     }
 
     async fn set_times_at(
@@ -410,35 +295,12 @@ impl<T: WasiView> types::Host for T {
         atim: types::NewTimestamp,
         mtim: types::NewTimestamp,
     ) -> Result<(), types::Error> {
-        use cap_fs_ext::DirExt;
-
         let table = self.table();
         let d = table.get_dir(fd)?;
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
-        let atim = systemtimespec_from(atim)?;
-        let mtim = systemtimespec_from(mtim)?;
-        if symlink_follow(path_flags) {
-            d.spawn_blocking(move |d| {
-                d.set_times(
-                    &path,
-                    atim.map(cap_fs_ext::SystemTimeSpec::from_std),
-                    mtim.map(cap_fs_ext::SystemTimeSpec::from_std),
-                )
-            })
-            .await?;
-        } else {
-            d.spawn_blocking(move |d| {
-                d.set_symlink_times(
-                    &path,
-                    atim.map(cap_fs_ext::SystemTimeSpec::from_std),
-                    mtim.map(cap_fs_ext::SystemTimeSpec::from_std),
-                )
-            })
-            .await?;
-        }
-        Ok(())
+        d.dir.set_times_at(path_flags, &path, atim, mtim).await
     }
 
     async fn link_at(
@@ -459,14 +321,21 @@ impl<T: WasiView> types::Host for T {
         if !new_dir.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
-        if symlink_follow(old_path_flags) {
+        if old_path_flags.contains(types::PathFlags::SYMLINK_FOLLOW) {
             return Err(ErrorCode::Invalid.into());
         }
-        let new_dir_handle = std::sync::Arc::clone(&new_dir.dir);
-        old_dir
-            .spawn_blocking(move |d| d.hard_link(&old_path, &new_dir_handle, &new_path))
-            .await?;
-        Ok(())
+        let quota = &self.ctx().files_created;
+        if quota.take(1) == 0 {
+            return Err(ErrorCode::Quota.into());
+        }
+        let result = old_dir
+            .dir
+            .link_at(&old_path, &*new_dir.dir, &new_path)
+            .await;
+        if result.is_err() {
+            quota.give_back(1);
+        }
+        result
     }
 
     async fn open_at(
@@ -480,10 +349,9 @@ impl<T: WasiView> types::Host for T {
         // Not implemented yet.
         _mode: types::Modes,
     ) -> Result<types::Descriptor, types::Error> {
-        use cap_fs_ext::{FollowSymlinks, OpenOptionsFollowExt, OpenOptionsMaybeDirExt};
-        use system_interface::fs::{FdFlags, GetSetFdFlags};
         use types::{DescriptorFlags, OpenFlags};
 
+        let quota = self.ctx().files_created.clone();
         let table = self.table_mut();
         if table.is_file(fd) {
             Err(ErrorCode::NotDirectory)?;
@@ -502,36 +370,8 @@ impl<T: WasiView> types::Host for T {
             }
         }
 
-        let mut opts = cap_std::fs::OpenOptions::new();
-        opts.maybe_dir(true);
-
-        if oflags.contains(OpenFlags::CREATE | OpenFlags::EXCLUSIVE) {
-            opts.create_new(true);
-            opts.write(true);
-        } else if oflags.contains(OpenFlags::CREATE) {
-            opts.create(true);
-            opts.write(true);
-        }
-        if oflags.contains(OpenFlags::TRUNCATE) {
-            opts.truncate(true);
-        }
-        if flags.contains(DescriptorFlags::READ) {
-            opts.read(true);
-        }
-        if flags.contains(DescriptorFlags::WRITE) {
-            opts.write(true);
-        } else {
-            // If not opened write, open read. This way the OS lets us open
-            // the file, but we can use perms to reject use of the file later.
-            opts.read(true);
-        }
-        if symlink_follow(path_flags) {
-            opts.follow(FollowSymlinks::Yes);
-        } else {
-            opts.follow(FollowSymlinks::No);
-        }
-
-        // These flags are not yet supported in cap-std:
+        // These flags are not yet supported by any of the filesystem
+        // implementations:
         if flags.contains(DescriptorFlags::FILE_INTEGRITY_SYNC)
             | flags.contains(DescriptorFlags::DATA_INTEGRITY_SYNC)
             | flags.contains(DescriptorFlags::REQUESTED_WRITE_SYNC)
@@ -548,42 +388,38 @@ impl<T: WasiView> types::Host for T {
             }
         }
 
-        // Represents each possible outcome from the spawn_blocking operation.
-        // This makes sure we don't have to give spawn_blocking any way to
-        // manipulate the table.
-        enum OpenResult {
-            Dir(cap_std::fs::Dir),
-            File(cap_std::fs::File),
-            NotDir,
-        }
-
-        let opened = d
-            .spawn_blocking::<_, std::io::Result<OpenResult>>(move |d| {
-                let mut opened = d.open_with(&path, &opts)?;
-                if opened.metadata()?.is_dir() {
-                    Ok(OpenResult::Dir(cap_std::fs::Dir::from_std_file(
-                        opened.into_std(),
-                    )))
-                } else if oflags.contains(OpenFlags::DIRECTORY) {
-                    Ok(OpenResult::NotDir)
-                } else {
-                    // FIXME cap-std needs a nonblocking open option so that files reads and writes
-                    // are nonblocking. Instead we set it after opening here:
-                    let set_fd_flags = opened.new_set_fd_flags(FdFlags::NONBLOCK)?;
-                    opened.set_fd_flags(set_fd_flags)?;
-                    Ok(OpenResult::File(opened))
+        // Only count opens which actually create a file against the quota.
+        // Whether a file was created is only known from the result of the
+        // open, so the open is first tried with `EXCLUSIVE`, and repeated
+        // with the original flags if the file turns out to exist already.
+        let (perms, file_perms) = (d.perms, d.file_perms);
+        let opened = if oflags.contains(OpenFlags::CREATE) {
+            if quota.take(1) == 0 {
+                Err(ErrorCode::Quota)?;
+            }
+            let exclusive = oflags | OpenFlags::EXCLUSIVE;
+            match d.dir.open_at(&path, path_flags, exclusive, flags).await {
+                Ok(opened) => opened,
+                Err(e) => {
+                    quota.give_back(1);
+                    match e.downcast_ref() {
+                        Some(ErrorCode::Exist) if !oflags.contains(OpenFlags::EXCLUSIVE) => {
+                            d.dir.open_at(&path, path_flags, oflags, flags).await?
+                        }
+                        _ => return Err(e),
+                    }
                 }
-            })
-            .await?;
+            }
+        } else {
+            d.dir.open_at(&path, path_flags, oflags, flags).await?
+        };
 
         match opened {
-            OpenResult::Dir(dir) => Ok(table.push_dir(Dir::new(dir, d.perms, d.file_perms))?),
+            OpenResult::Dir(dir) => Ok(table.push_dir(Dir::new(dir, perms, file_perms))?),
 
             OpenResult::File(file) => {
-                Ok(table.push_file(File::new(file, mask_file_perms(d.file_perms, flags)))?)
+                Ok(table.push_file(File::new(file, mask_file_perms(file_perms, flags)))?)
             }
-
-            OpenResult::NotDir => Err(ErrorCode::NotDirectory.into()),
         }
     }
 
@@ -612,11 +448,7 @@ impl<T: WasiView> types::Host for T {
         if !d.perms.contains(DirPerms::READ) {
             return Err(ErrorCode::NotPermitted.into());
         }
-        let link = d.spawn_blocking(move |d| d.read_link(&path)).await?;
-        Ok(link
-            .into_os_string()
-            .into_string()
-            .map_err(|_| ErrorCode::IllegalByteSequence)?)
+        d.dir.readlink_at(&path).await
     }
 
     async fn remove_directory_at(
@@ -629,7 +461,7 @@ impl<T: WasiView> types::Host for T {
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
-        Ok(d.spawn_blocking(move |d| d.remove_dir(&path)).await?)
+        d.dir.remove_directory_at(&path).await
     }
 
     async fn rename_at(
@@ -648,10 +480,10 @@ impl<T: WasiView> types::Host for T {
         if !new_dir.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
-        let new_dir_handle = std::sync::Arc::clone(&new_dir.dir);
-        Ok(old_dir
-            .spawn_blocking(move |d| d.rename(&old_path, &new_dir_handle, &new_path))
-            .await?)
+        old_dir
+            .dir
+            .rename_at(&old_path, &*new_dir.dir, &new_path)
+            .await
     }
 
     async fn symlink_at(
@@ -660,17 +492,20 @@ impl<T: WasiView> types::Host for T {
         src_path: String,
         dest_path: String,
     ) -> Result<(), types::Error> {
-        // On windows, Dir.symlink is provided by DirExt
-        #[cfg(windows)]
-        use cap_fs_ext::DirExt;
-
         let table = self.table();
         let d = table.get_dir(fd)?;
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
-        Ok(d.spawn_blocking(move |d| d.symlink(&src_path, &dest_path))
-            .await?)
+        let quota = &self.ctx().files_created;
+        if quota.take(1) == 0 {
+            return Err(ErrorCode::Quota.into());
+        }
+        let result = d.dir.symlink_at(&src_path, &dest_path).await;
+        if result.is_err() {
+            quota.give_back(1);
+        }
+        result
     }
 
     async fn unlink_file_at(
@@ -678,65 +513,119 @@ impl<T: WasiView> types::Host for T {
         fd: types::Descriptor,
         path: String,
     ) -> Result<(), types::Error> {
-        use cap_fs_ext::DirExt;
-
         let table = self.table();
         let d = table.get_dir(fd)?;
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
-        Ok(d.spawn_blocking(move |d| d.remove_file_or_symlink(&path))
-            .await?)
+        d.dir.unlink_file_at(&path).await
     }
 
     async fn access_at(
         &mut self,
-        _fd: types::Descriptor,
-        _path_flags: types::PathFlags,
-        _path: String,
-        _access: types::AccessType,
+        fd: types::Descriptor,
+        path_flags: types::PathFlags,
+        path: String,
+        access: types::AccessType,
     ) -> Result<(), types::Error> {
-        todo!("filesystem access_at is not implemented")
+        let table = self.table();
+        let d = table.get_dir(fd)?;
+        if !d.perms.contains(DirPerms::READ) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        if let types::AccessType::Access(modes) = access {
+            // Report the access the guest would actually get through this
+            // descriptor, which may be less than what the host allows.
+            let stat = d.dir.stat_at(path_flags, &path).await?;
+            let mut allowed = types::Modes::EXECUTABLE;
+            if stat.type_ == types::DescriptorType::Directory {
+                allowed |= types::Modes::READABLE;
+                if d.perms.contains(DirPerms::MUTATE) {
+                    allowed |= types::Modes::WRITABLE;
+                }
+            } else {
+                if d.file_perms.contains(FilePerms::READ) {
+                    allowed |= types::Modes::READABLE;
+                }
+                if d.file_perms.contains(FilePerms::WRITE) {
+                    allowed |= types::Modes::WRITABLE;
+                }
+            }
+            if !allowed.contains(modes) {
+                return Err(ErrorCode::Access.into());
+            }
+        }
+        d.dir.access_at(path_flags, &path, access).await
     }
 
     async fn change_file_permissions_at(
         &mut self,
-        _fd: types::Descriptor,
-        _path_flags: types::PathFlags,
-        _path: String,
-        _mode: types::Modes,
+        fd: types::Descriptor,
+        path_flags: types::PathFlags,
+        path: String,
+        modes: types::Modes,
     ) -> Result<(), types::Error> {
-        todo!("filesystem change_file_permissions_at is not implemented")
+        let table = self.table();
+        let d = table.get_dir(fd)?;
+        if !d.perms.contains(DirPerms::MUTATE) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        d.dir
+            .change_file_permissions_at(path_flags, &path, modes)
+            .await
     }
 
     async fn change_directory_permissions_at(
         &mut self,
-        _fd: types::Descriptor,
-        _path_flags: types::PathFlags,
-        _path: String,
-        _mode: types::Modes,
+        fd: types::Descriptor,
+        path_flags: types::PathFlags,
+        path: String,
+        modes: types::Modes,
     ) -> Result<(), types::Error> {
-        todo!("filesystem change_directory_permissions_at is not implemented")
+        let table = self.table();
+        let d = table.get_dir(fd)?;
+        if !d.perms.contains(DirPerms::MUTATE) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        // Directories are searchable whenever they're readable, so
+        // `executable` isn't meaningful for them.
+        if modes.contains(types::Modes::EXECUTABLE) {
+            return Err(ErrorCode::Invalid.into());
+        }
+        d.dir
+            .change_directory_permissions_at(path_flags, &path, modes)
+            .await
     }
 
-    async fn lock_shared(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem lock_shared is not implemented")
+    async fn lock_shared(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        f.file.lock_shared().await
     }
 
-    async fn lock_exclusive(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem lock_exclusive is not implemented")
+    async fn lock_exclusive(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        if !f.perms.contains(FilePerms::WRITE) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        f.file.lock_exclusive().await
     }
 
-    async fn try_lock_shared(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem try_lock_shared is not implemented")
+    async fn try_lock_shared(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        f.file.try_lock_shared().await
     }
 
-    async fn try_lock_exclusive(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem try_lock_exclusive is not implemented")
+    async fn try_lock_exclusive(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        if !f.perms.contains(FilePerms::WRITE) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        f.file.try_lock_exclusive().await
     }
 
-    async fn unlock(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem unlock is not implemented")
+    async fn unlock(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        f.file.unlock().await
     }
 
     async fn read_via_stream(
@@ -790,9 +679,9 @@ impl<T: WasiView> types::Host for T {
         let clone = std::sync::Arc::clone(&f.file);
 
         // Create a stream view for it.
-        let writer = FileOutputStream::write_at(clone, offset);
+        let writer = FileOutputStream::write_at(clone, offset, self.ctx().bytes_written.clone());
 
-        // Insert the stream view into the table. Trap if the table is full.
+        // Insert the stream view into the table.
         let index = self
             .table_mut()
             .push_internal_output_stream(InternalOutputStream::File(writer))?;
@@ -819,9 +708,9 @@ impl<T: WasiView> types::Host for T {
         let clone = std::sync::Arc::clone(&f.file);
 
         // Create a stream view for it.
-        let appender = FileOutputStream::append(clone);
+        let appender = FileOutputStream::append(clone, self.ctx().bytes_written.clone());
 
-        // Insert the stream view into the table. Trap if the table is full.
+        // Insert the stream view into the table.
         let index = self
             .table_mut()
             .push_internal_output_stream(InternalOutputStream::File(appender))?;
@@ -834,34 +723,20 @@ impl<T: WasiView> types::Host for T {
         a: types::Descriptor,
         b: types::Descriptor,
     ) -> anyhow::Result<bool> {
-        use cap_fs_ext::MetadataExt;
         let table = self.table();
-        let meta_a = get_descriptor_metadata(table, a).await?;
-        let meta_b = get_descriptor_metadata(table, b).await?;
-        if meta_a.dev() == meta_b.dev() && meta_a.ino() == meta_b.ino() {
-            // MetadataHashValue does not derive eq, so use a pair of
-            // comparisons to check equality:
-            debug_assert_eq!(
-                calculate_metadata_hash(&meta_a).upper,
-                calculate_metadata_hash(&meta_b).upper
-            );
-            debug_assert_eq!(
-                calculate_metadata_hash(&meta_a).lower,
-                calculate_metadata_hash(&meta_b).lower
-            );
-            Ok(true)
-        } else {
-            // Hash collisions are possible, so don't assert the negative here
-            Ok(false)
-        }
+        let hash_a = get_descriptor_metadata_hash(table, a).await?;
+        let hash_b = get_descriptor_metadata_hash(table, b).await?;
+        // MetadataHashValue does not derive eq, so use a pair of
+        // comparisons to check equality. Hash collisions are possible, but
+        // the implementations make them unlikely.
+        Ok(hash_a.lower == hash_b.lower && hash_a.upper == hash_b.upper)
     }
     async fn metadata_hash(
         &mut self,
         fd: types::Descriptor,
     ) -> Result<types::MetadataHashValue, types::Error> {
         let table = self.table();
-        let meta = get_descriptor_metadata(table, fd).await?;
-        Ok(calculate_metadata_hash(&meta))
+        get_descriptor_metadata_hash(table, fd).await
     }
     async fn metadata_hash_at(
         &mut self,
@@ -872,60 +747,27 @@ impl<T: WasiView> types::Host for T {
         let table = self.table();
         let d = table.get_dir(fd)?;
         // No permissions check on metadata: if dir opened, allowed to stat it
-        let meta = d
-            .spawn_blocking(move |d| {
-                if symlink_follow(path_flags) {
-                    d.metadata(path)
-                } else {
-                    d.symlink_metadata(path)
-                }
-            })
-            .await?;
-        Ok(calculate_metadata_hash(&meta))
+        d.dir.metadata_hash_at(path_flags, &path).await
     }
 }
 
-async fn get_descriptor_metadata(
+async fn get_descriptor_metadata_hash(
     table: &Table,
     fd: types::Descriptor,
-) -> Result<cap_std::fs::Metadata, types::Error> {
+) -> Result<types::MetadataHashValue, types::Error> {
     if table.is_file(fd) {
         let f = table.get_file(fd)?;
         // No permissions check on metadata: if opened, allowed to stat it
-        Ok(f.spawn_blocking(|f| f.metadata()).await?)
+        f.file.metadata_hash().await
     } else if table.is_dir(fd) {
         let d = table.get_dir(fd)?;
         // No permissions check on metadata: if opened, allowed to stat it
-        Ok(d.spawn_blocking(|d| d.dir_metadata()).await?)
+        d.dir.metadata_hash().await
     } else {
         Err(ErrorCode::BadDescriptor.into())
     }
 }
 
-fn calculate_metadata_hash(meta: &cap_std::fs::Metadata) -> types::MetadataHashValue {
-    use cap_fs_ext::MetadataExt;
-    // Without incurring any deps, std provides us with a 64 bit hash
-    // function:
-    use std::hash::Hasher;
-    // Note that this means that the metadata hash (which becomes a preview1 ino) may
-    // change when a different rustc release is used to build this host implementation:
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    hasher.write_u64(meta.dev());
-    hasher.write_u64(meta.ino());
-    let lower = hasher.finish();
-    // MetadataHashValue has a pair of 64-bit members for representing a
-    // single 128-bit number. However, we only have 64 bits of entropy. To
-    // synthesize the upper 64 bits, lets xor the lower half with an arbitrary
-    // constant, in this case the 64 bit integer corresponding to the IEEE
-    // double representation of (a number as close as possible to) pi.
-    // This seems better than just repeating the same bits in the upper and
-    // lower parts outright, which could make folks wonder if the struct was
-    // mangled in the ABI, or worse yet, lead to consumers of this interface
-    // expecting them to be equal.
-    let upper = lower ^ 4614256656552045848u64;
-    types::MetadataHashValue { lower, upper }
-}
-
 #[cfg(unix)]
 fn from_raw_os_error(err: Option<i32>) -> Option<types::Error> {
     use rustix::io::Errno as RustixErrno;
@@ -958,6 +800,9 @@ fn from_raw_os_error(err: Option<i32>) -> Option<types::Error> {
         RustixErrno::ALREADY => ErrorCode::Already.into(),
         RustixErrno::INPROGRESS => ErrorCode::InProgress.into(),
         RustixErrno::INTR => ErrorCode::Interrupted.into(),
+        RustixErrno::AGAIN => ErrorCode::WouldBlock.into(),
+        RustixErrno::NOLCK => ErrorCode::NoLock.into(),
+        RustixErrno::DEADLK => ErrorCode::Deadlock.into(),
 
         // On some platforms.into(), these have the same value as other errno values.
         #[allow(unreachable_patterns)]
@@ -992,6 +837,7 @@ fn from_raw_os_error(raw_os_error: Option<i32>) -> Option<types::Error> {
         Some(Foundation::ERROR_ALREADY_EXISTS) => ErrorCode::Exist.into(),
         Some(Foundation::ERROR_STOPPED_ON_SYMLINK) => ErrorCode::Loop.into(),
         Some(Foundation::ERROR_DIRECTORY_NOT_SUPPORTED) => ErrorCode::IsDirectory.into(),
+        Some(Foundation::ERROR_LOCK_VIOLATION) => ErrorCode::WouldBlock.into(),
         _ => return None,
     })
 }
@@ -1024,83 +870,6 @@ impl From<std::num::TryFromIntError> for types::Error {
     }
 }
 
-fn descriptortype_from(ft: cap_std::fs::FileType) -> types::DescriptorType {
-    use cap_fs_ext::FileTypeExt;
-    use types::DescriptorType;
-    if ft.is_dir() {
-        DescriptorType::Directory
-    } else if ft.is_symlink() {
-        DescriptorType::SymbolicLink
-    } else if ft.is_block_device() {
-        DescriptorType::BlockDevice
-    } else if ft.is_char_device() {
-        DescriptorType::CharacterDevice
-    } else if ft.is_file() {
-        DescriptorType::RegularFile
-    } else {
-        DescriptorType::Unknown
-    }
-}
-
-fn systemtimespec_from(
-    t: types::NewTimestamp,
-) -> Result<Option<fs_set_times::SystemTimeSpec>, types::Error> {
-    use fs_set_times::SystemTimeSpec;
-    use types::NewTimestamp;
-    match t {
-        NewTimestamp::NoChange => Ok(None),
-        NewTimestamp::Now => Ok(Some(SystemTimeSpec::SymbolicNow)),
-        NewTimestamp::Timestamp(st) => Ok(Some(SystemTimeSpec::Absolute(systemtime_from(st)?))),
-    }
-}
-
-fn systemtime_from(t: wall_clock::Datetime) -> Result<std::time::SystemTime, types::Error> {
-    use std::time::{Duration, SystemTime};
-    SystemTime::UNIX_EPOCH
-        .checked_add(Duration::new(t.seconds, t.nanoseconds))
-        .ok_or_else(|| ErrorCode::Overflow.into())
-}
-
-fn datetime_from(t: std::time::SystemTime) -> wall_clock::Datetime {
-    // FIXME make this infallible or handle errors properly
-    wall_clock::Datetime::try_from(cap_std::time::SystemTime::from_std(t)).unwrap()
-}
-
-fn descriptorstat_from(meta: cap_std::fs::Metadata) -> types::DescriptorStat {
-    use cap_fs_ext::MetadataExt;
-    types::DescriptorStat {
-        type_: descriptortype_from(meta.file_type()),
-        link_count: meta.nlink(),
-        size: meta.len(),
-        // FIXME change the wit to make these timestamps optional
-        data_access_timestamp: meta
-            .accessed()
-            .map(|t| datetime_from(t.into_std()))
-            .unwrap_or(wall_clock::Datetime {
-                seconds: 0,
-                nanoseconds: 0,
-            }),
-        data_modification_timestamp: meta
-            .modified()
-            .map(|t| datetime_from(t.into_std()))
-            .unwrap_or(wall_clock::Datetime {
-                seconds: 0,
-                nanoseconds: 0,
-            }),
-        status_change_timestamp: meta
-            .created()
-            .map(|t| datetime_from(t.into_std()))
-            .unwrap_or(wall_clock::Datetime {
-                seconds: 0,
-                nanoseconds: 0,
-            }),
-    }
-}
-
-fn symlink_follow(path_flags: types::PathFlags) -> bool {
-    path_flags.contains(types::PathFlags::SYMLINK_FOLLOW)
-}
-
 pub(crate) struct ReaddirIterator(
     std::sync::Mutex<
         Box<dyn Iterator<Item = Result<types::DirectoryEntry, types::Error>> + Send + 'static>,
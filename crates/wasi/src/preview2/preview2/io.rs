@@ -23,7 +23,12 @@ impl From<anyhow::Error> for streams::Error {
 
 impl From<TableError> for streams::Error {
     fn from(error: TableError) -> streams::Error {
-        streams::Error::trap(anyhow!(error))
+        match error {
+            // The table only fills up before running out of keys if the
+            // embedder limited its size.
+            TableError::Full => StreamError { dummy: 0 }.into(),
+            _ => streams::Error::trap(anyhow!(error)),
+        }
     }
 }
 
@@ -0,0 +1,12 @@
+use crate::preview2::bindings::logging::handler::{self, Level};
+use crate::preview2::{logging, WasiView};
+
+impl<T: WasiView> handler::Host for T {
+    fn log(&mut self, level: Level, context: String, message: String) -> anyhow::Result<()> {
+        let ctx = self.ctx();
+        if logging::enabled(level, ctx.log_level) {
+            ctx.logger.log(level, &context, &message);
+        }
+        Ok(())
+    }
+}
@@ -3,4 +3,6 @@ mod env;
 mod exit;
 pub(crate) mod filesystem;
 mod io;
+mod logging;
 mod random;
+mod terminal;
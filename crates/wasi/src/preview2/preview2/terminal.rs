@@ -0,0 +1,80 @@
+use crate::preview2::bindings::terminal::{
+    terminal_input, terminal_output, terminal_stderr, terminal_stdin, terminal_stdout,
+};
+use crate::preview2::terminal::{TerminalInput, TerminalOutput};
+use crate::preview2::WasiView;
+
+impl<T: WasiView> terminal_input::Host for T {
+    fn set_raw_mode(
+        &mut self,
+        this: terminal_input::TerminalInput,
+        enabled: bool,
+    ) -> anyhow::Result<Result<(), ()>> {
+        let TerminalInput(terminal) = self.table().get(this)?;
+        match terminal.set_raw_mode(enabled) {
+            Ok(()) => Ok(Ok(())),
+            Err(e) => {
+                tracing::debug!("failed to set raw mode: {e}");
+                Ok(Err(()))
+            }
+        }
+    }
+
+    fn drop_terminal_input(&mut self, this: terminal_input::TerminalInput) -> anyhow::Result<()> {
+        self.table_mut().delete::<TerminalInput>(this)?;
+        Ok(())
+    }
+}
+
+impl<T: WasiView> terminal_output::Host for T {
+    fn get_window_size(
+        &mut self,
+        this: terminal_output::TerminalOutput,
+    ) -> anyhow::Result<Option<terminal_output::WindowSize>> {
+        let TerminalOutput(terminal) = self.table().get(this)?;
+        Ok(terminal
+            .window_size()
+            .map(|(columns, rows)| terminal_output::WindowSize { columns, rows }))
+    }
+
+    fn drop_terminal_output(
+        &mut self,
+        this: terminal_output::TerminalOutput,
+    ) -> anyhow::Result<()> {
+        self.table_mut().delete::<TerminalOutput>(this)?;
+        Ok(())
+    }
+}
+
+impl<T: WasiView> terminal_stdin::Host for T {
+    fn get_terminal_stdin(&mut self) -> anyhow::Result<Option<terminal_input::TerminalInput>> {
+        match self.ctx().stdin_terminal.clone() {
+            Some(terminal) => Ok(Some(
+                self.table_mut().push(Box::new(TerminalInput(terminal)))?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T: WasiView> terminal_stdout::Host for T {
+    fn get_terminal_stdout(&mut self) -> anyhow::Result<Option<terminal_output::TerminalOutput>> {
+        match self.ctx().stdout_terminal.clone() {
+            Some(terminal) => Ok(Some(
+                self.table_mut().push(Box::new(TerminalOutput(terminal)))?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T: WasiView> terminal_stderr::Host for T {
+    fn get_terminal_stderr(&mut self) -> anyhow::Result<Option<terminal_output::TerminalOutput>> {
+        match self.ctx().stderr_terminal.clone() {
+            Some(terminal) => Ok(Some(
+                self.table_mut().push(Box::new(TerminalOutput(terminal)))?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
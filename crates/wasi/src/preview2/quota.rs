@@ -0,0 +1,97 @@
+use crate::preview2::{HostOutputStream, StreamState};
+use anyhow::Error;
+use bytes::Bytes;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// An amount of some resource, such as bytes written to disk, which a guest
+/// may use over its lifetime. Quotas are configured through the `max_*`
+/// methods of [`WasiCtxBuilder`](crate::preview2::WasiCtxBuilder).
+#[derive(Debug)]
+pub(crate) struct Quota {
+    limit: u64,
+    used: AtomicU64,
+}
+
+impl Quota {
+    pub fn new(limit: Option<u64>) -> Arc<Self> {
+        Arc::new(Quota {
+            limit: limit.unwrap_or(u64::MAX),
+            used: AtomicU64::new(0),
+        })
+    }
+
+    /// Takes up to `amount` from the quota, returning how much was taken.
+    pub fn take(&self, amount: u64) -> u64 {
+        let mut taken = 0;
+        let _ = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                taken = amount.min(self.limit.saturating_sub(used));
+                Some(used + taken)
+            });
+        taken
+    }
+
+    /// Takes all of `amount` from the quota, or nothing if less is left.
+    /// Returns whether it was taken.
+    pub fn take_all(&self, amount: u64) -> bool {
+        let taken = self.take(amount);
+        if taken < amount {
+            self.give_back(taken);
+            return false;
+        }
+        true
+    }
+
+    /// Returns `amount`, which was taken but not used, to the quota.
+    pub fn give_back(&self, amount: u64) {
+        self.used.fetch_sub(amount, Ordering::Relaxed);
+    }
+
+    /// Truncates `bytes` to what's left of the quota, and takes that much.
+    /// Returns `false` if `bytes` is non-empty and the quota is used up.
+    pub fn reserve(&self, bytes: &mut Bytes) -> bool {
+        let wanted = bytes.len();
+        let taken = self.take(wanted as u64);
+        bytes.truncate(taken as usize);
+        taken > 0 || wanted == 0
+    }
+
+    /// Gives back whatever a write which [reserved](Quota::reserve)
+    /// `reserved` bytes didn't end up writing.
+    pub fn settle(&self, reserved: usize, written: usize) {
+        self.give_back(reserved.saturating_sub(written) as u64);
+    }
+}
+
+/// An output stream which fails once the guest has written its quota of
+/// bytes to it. This is used for stdout and stderr.
+pub(crate) struct LimitedOutputStream {
+    inner: Box<dyn HostOutputStream>,
+    quota: Arc<Quota>,
+}
+
+impl LimitedOutputStream {
+    pub fn new(inner: Box<dyn HostOutputStream>, quota: Arc<Quota>) -> Self {
+        LimitedOutputStream { inner, quota }
+    }
+}
+
+#[async_trait::async_trait]
+impl HostOutputStream for LimitedOutputStream {
+    fn write(&mut self, mut bytes: Bytes) -> Result<(usize, StreamState), Error> {
+        if !self.quota.reserve(&mut bytes) {
+            anyhow::bail!("output quota exceeded");
+        }
+        let reserved = bytes.len();
+        let result = self.inner.write(bytes);
+        let written = result.as_ref().map_or(0, |(n, _)| *n);
+        self.quota.settle(reserved, written);
+        result
+    }
+
+    async fn ready(&mut self) -> Result<(), Error> {
+        self.inner.ready().await
+    }
+}
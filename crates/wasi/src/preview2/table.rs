@@ -24,6 +24,7 @@ pub enum TableError {
 pub struct Table {
     map: HashMap<u32, TableEntry>,
     next_key: u32,
+    max_entries: usize,
 }
 
 /// This structure tracks parent and child relationships for a given table entry.
@@ -99,9 +100,16 @@ impl Table {
             // Once we have a full implementation of resources, this confusion should hopefully be
             // impossible :)
             next_key: 3,
+            max_entries: u32::MAX as usize,
         }
     }
 
+    /// Limits the number of entries which may be in the table at once.
+    /// Inserting more fails with [`TableError::Full`].
+    pub fn set_max_entries(&mut self, max_entries: usize) {
+        self.max_entries = max_entries.min(u32::MAX as usize);
+    }
+
     /// Insert a resource at the next available index.
     pub fn push(&mut self, entry: Box<dyn Any + Send + Sync>) -> Result<u32, TableError> {
         self.push_(TableEntry::new(entry, None))
@@ -145,7 +153,7 @@ impl Table {
     fn push_(&mut self, e: TableEntry) -> Result<u32, TableError> {
         // NOTE: The performance of this new key calculation could be very bad once keys wrap
         // around.
-        if self.map.len() == u32::MAX as usize {
+        if self.map.len() >= self.max_entries {
             return Err(TableError::Full);
         }
         loop {
@@ -0,0 +1,92 @@
+//! TCP sockets which are preopened by the host. Until `wasi:sockets` is
+//! implemented, guests can only use these through the preview1 `sock_*`
+//! calls.
+
+use crate::preview2::pipe::{AsyncReadStream, AsyncWriteStream};
+use crate::preview2::{Table, TableError};
+use cap_std::net::{Shutdown, TcpListener, TcpStream};
+use std::io;
+use std::sync::Arc;
+
+/// A listening socket, preopened with
+/// [`WasiCtxBuilder::preopened_tcp_listener`](crate::preview2::WasiCtxBuilder::preopened_tcp_listener).
+pub(crate) struct HostTcpListener {
+    listener: Arc<TcpListener>,
+}
+
+impl HostTcpListener {
+    pub fn new(listener: TcpListener) -> Self {
+        HostTcpListener {
+            listener: Arc::new(listener),
+        }
+    }
+
+    /// Accepts a connection, returning the socket along with the streams its
+    /// data is read from and written to.
+    ///
+    /// Like the listener's own `accept`, this fails with
+    /// [`io::ErrorKind::WouldBlock`] if the listener is in non-blocking mode
+    /// and no connection is pending.
+    pub async fn accept(&self) -> io::Result<(HostTcpStream, AsyncReadStream, AsyncWriteStream)> {
+        let listener = self.listener.clone();
+        let (stream, _) = tokio::task::spawn_blocking(move || listener.accept())
+            .await
+            .unwrap()?;
+        let socket = stream.try_clone()?;
+        #[cfg(unix)]
+        let stream = std::net::TcpStream::from(std::os::unix::io::OwnedFd::from(stream));
+        #[cfg(windows)]
+        let stream = std::net::TcpStream::from(std::os::windows::io::OwnedSocket::from(stream));
+        stream.set_nonblocking(true)?;
+        let (reader, writer) = tokio::net::TcpStream::from_std(stream)?.into_split();
+        Ok((
+            HostTcpStream { socket },
+            AsyncReadStream::new(reader),
+            AsyncWriteStream::new(writer),
+        ))
+    }
+}
+
+/// A connected socket. Its data goes through a pair of streams, so this
+/// only holds on to the socket to shut it down.
+pub(crate) struct HostTcpStream {
+    socket: TcpStream,
+}
+
+impl HostTcpStream {
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.socket.shutdown(how)
+    }
+}
+
+pub(crate) trait TableTcpExt {
+    fn push_tcp_listener(&mut self, listener: HostTcpListener) -> Result<u32, TableError>;
+    fn delete_tcp_listener(&mut self, fd: u32) -> Result<HostTcpListener, TableError>;
+    fn get_tcp_listener(&self, fd: u32) -> Result<&HostTcpListener, TableError>;
+
+    fn push_tcp_stream(&mut self, stream: HostTcpStream) -> Result<u32, TableError>;
+    fn delete_tcp_stream(&mut self, fd: u32) -> Result<HostTcpStream, TableError>;
+    fn get_tcp_stream(&self, fd: u32) -> Result<&HostTcpStream, TableError>;
+}
+
+impl TableTcpExt for Table {
+    fn push_tcp_listener(&mut self, listener: HostTcpListener) -> Result<u32, TableError> {
+        self.push(Box::new(listener))
+    }
+    fn delete_tcp_listener(&mut self, fd: u32) -> Result<HostTcpListener, TableError> {
+        self.delete(fd)
+    }
+    fn get_tcp_listener(&self, fd: u32) -> Result<&HostTcpListener, TableError> {
+        self.get(fd)
+    }
+
+    fn push_tcp_stream(&mut self, stream: HostTcpStream) -> Result<u32, TableError> {
+        self.push(Box::new(stream))
+    }
+    fn delete_tcp_stream(&mut self, fd: u32) -> Result<HostTcpStream, TableError> {
+        self.delete(fd)
+    }
+    fn get_tcp_stream(&self, fd: u32) -> Result<&HostTcpStream, TableError> {
+        self.get(fd)
+    }
+}
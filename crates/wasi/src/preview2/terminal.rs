@@ -0,0 +1,203 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// A terminal which one of the guest's stdio streams is connected to.
+///
+/// Terminals are configured with
+/// [`WasiCtxBuilder::stdin_terminal`](crate::preview2::WasiCtxBuilder::stdin_terminal)
+/// and friends, and guests reach them through the `wasmtime:wasi/terminal-*`
+/// interfaces.
+pub trait HostTerminal: Send + Sync {
+    /// Turns raw mode on or off. In raw mode, input is neither echoed nor
+    /// buffered into lines.
+    fn set_raw_mode(&self, enabled: bool) -> io::Result<()>;
+
+    /// Returns the size of the terminal's window as `(columns, rows)`, if
+    /// it's known.
+    fn window_size(&self) -> Option<(u16, u16)>;
+}
+
+/// The table entry for a `terminal-input` handle.
+pub(crate) struct TerminalInput(pub Arc<dyn HostTerminal>);
+
+/// The table entry for a `terminal-output` handle.
+pub(crate) struct TerminalOutput(pub Arc<dyn HostTerminal>);
+
+/// A [`HostTerminal`] for a terminal on the host, such as the host's own
+/// stdio or one side of a pseudo-terminal. Changes the guest makes to its
+/// mode are undone when this is dropped.
+pub struct TtyTerminal {
+    #[cfg(unix)]
+    fd: std::os::fd::OwnedFd,
+    #[cfg(windows)]
+    handle: std::os::windows::io::OwnedHandle,
+    /// The mode of the terminal before the guest first changed it.
+    original: Mutex<Option<sys::Mode>>,
+}
+
+impl TtyTerminal {
+    /// Wraps `fd`, failing if it's not a terminal.
+    #[cfg(unix)]
+    pub fn new(fd: impl Into<std::os::fd::OwnedFd>) -> io::Result<Self> {
+        let fd = fd.into();
+        if !rustix::termios::isatty(&fd) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a terminal",
+            ));
+        }
+        Ok(TtyTerminal {
+            fd,
+            original: Mutex::new(None),
+        })
+    }
+
+    /// Wraps `handle`, failing if it's not a console.
+    #[cfg(windows)]
+    pub fn new(handle: impl Into<std::os::windows::io::OwnedHandle>) -> io::Result<Self> {
+        let handle = handle.into();
+        sys::get_mode(&handle)?;
+        Ok(TtyTerminal {
+            handle,
+            original: Mutex::new(None),
+        })
+    }
+
+    /// Returns the host's stdin, if it's a terminal.
+    pub fn stdin() -> Option<Self> {
+        Self::host(io::stdin())
+    }
+
+    /// Returns the host's stdout, if it's a terminal.
+    pub fn stdout() -> Option<Self> {
+        Self::host(io::stdout())
+    }
+
+    /// Returns the host's stderr, if it's a terminal.
+    pub fn stderr() -> Option<Self> {
+        Self::host(io::stderr())
+    }
+
+    #[cfg(unix)]
+    fn host(stdio: impl std::os::fd::AsFd) -> Option<Self> {
+        Self::new(stdio.as_fd().try_clone_to_owned().ok()?).ok()
+    }
+
+    #[cfg(windows)]
+    fn host(stdio: impl std::os::windows::io::AsHandle) -> Option<Self> {
+        Self::new(stdio.as_handle().try_clone_to_owned().ok()?).ok()
+    }
+
+    #[cfg(unix)]
+    fn tty(&self) -> &std::os::fd::OwnedFd {
+        &self.fd
+    }
+
+    #[cfg(windows)]
+    fn tty(&self) -> &std::os::windows::io::OwnedHandle {
+        &self.handle
+    }
+}
+
+impl HostTerminal for TtyTerminal {
+    fn set_raw_mode(&self, enabled: bool) -> io::Result<()> {
+        let mut original = self.original.lock().unwrap();
+        if enabled {
+            let mode = match original.take() {
+                Some(mode) => mode,
+                None => sys::get_mode(self.tty())?,
+            };
+            let result = sys::set_mode(self.tty(), &sys::raw(&mode));
+            *original = Some(mode);
+            result
+        } else if let Some(mode) = original.take() {
+            sys::set_mode(self.tty(), &mode)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn window_size(&self) -> Option<(u16, u16)> {
+        sys::window_size(self.tty())
+    }
+}
+
+impl Drop for TtyTerminal {
+    fn drop(&mut self) {
+        if let Some(mode) = self.original.get_mut().unwrap().take() {
+            let _ = sys::set_mode(self.tty(), &mode);
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use rustix::termios::{self, OptionalActions, Termios};
+    use std::io;
+    use std::os::fd::OwnedFd;
+
+    pub type Mode = Termios;
+
+    pub fn get_mode(fd: &OwnedFd) -> io::Result<Mode> {
+        Ok(termios::tcgetattr(fd)?)
+    }
+
+    pub fn set_mode(fd: &OwnedFd, mode: &Mode) -> io::Result<()> {
+        Ok(termios::tcsetattr(fd, OptionalActions::Now, mode)?)
+    }
+
+    pub fn raw(mode: &Mode) -> Mode {
+        let mut raw = mode.clone();
+        raw.make_raw();
+        raw
+    }
+
+    pub fn window_size(fd: &OwnedFd) -> Option<(u16, u16)> {
+        match termios::tcgetwinsize(fd) {
+            Ok(size) if size.ws_col != 0 && size.ws_row != 0 => Some((size.ws_col, size.ws_row)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::io;
+    use std::os::windows::io::{AsRawHandle, OwnedHandle};
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetConsoleScreenBufferInfo, SetConsoleMode, CONSOLE_MODE,
+        CONSOLE_SCREEN_BUFFER_INFO, ENABLE_ECHO_INPUT, ENABLE_LINE_INPUT, ENABLE_PROCESSED_INPUT,
+    };
+
+    pub type Mode = CONSOLE_MODE;
+
+    pub fn get_mode(handle: &OwnedHandle) -> io::Result<Mode> {
+        let mut mode = 0;
+        if unsafe { GetConsoleMode(handle.as_raw_handle() as _, &mut mode) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(mode)
+    }
+
+    pub fn set_mode(handle: &OwnedHandle, mode: &Mode) -> io::Result<()> {
+        if unsafe { SetConsoleMode(handle.as_raw_handle() as _, *mode) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn raw(mode: &Mode) -> Mode {
+        *mode & !(ENABLE_ECHO_INPUT | ENABLE_LINE_INPUT | ENABLE_PROCESSED_INPUT)
+    }
+
+    pub fn window_size(handle: &OwnedHandle) -> Option<(u16, u16)> {
+        let mut info: CONSOLE_SCREEN_BUFFER_INFO = unsafe { std::mem::zeroed() };
+        if unsafe { GetConsoleScreenBufferInfo(handle.as_raw_handle() as _, &mut info) } == 0 {
+            return None;
+        }
+        let window = info.srWindow;
+        let columns = window.Right - window.Left + 1;
+        let rows = window.Bottom - window.Top + 1;
+        Some((columns.try_into().ok()?, rows.try_into().ok()?))
+    }
+}
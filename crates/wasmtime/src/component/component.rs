@@ -283,6 +283,10 @@ impl Component {
         &self.inner.static_modules[idx]
     }
 
+    pub(crate) fn static_modules(&self) -> impl Iterator<Item = (StaticModuleIndex, &Module)> {
+        self.inner.static_modules.iter()
+    }
+
     pub(crate) fn types(&self) -> &Arc<ComponentTypes> {
         self.inner.component_types()
     }
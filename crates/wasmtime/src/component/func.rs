@@ -89,6 +89,7 @@ union ParamsAndResults<Params: Copy, Return: Copy> {
 //
 // FIXME: write more docs here
 #[derive(Copy, Clone, Debug)]
+#[repr(transparent)]
 pub struct Func(Stored<FuncData>);
 
 #[doc(hidden)]
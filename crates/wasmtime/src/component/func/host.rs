@@ -13,6 +13,7 @@ use wasmtime_environ::component::{
     CanonicalAbiInfo, ComponentTypes, InterfaceType, StringEncoding, TypeFuncIndex,
     MAX_FLAT_PARAMS, MAX_FLAT_RESULTS,
 };
+use wasmtime_environ::PrimaryMap;
 use wasmtime_runtime::component::{
     InstanceFlags, VMComponentContext, VMLowering, VMLoweringCallee,
 };
@@ -77,8 +78,32 @@ impl HostFunc {
         types: &Arc<ComponentTypes>,
     ) -> Arc<HostFunc>
     where
-        F: Fn(StoreContextMut<'_, T>, &[Val], &mut [Val]) -> Result<()> + Send + Sync + 'static,
+        F: Fn(StoreContextMut<'_, T>, &[Val], &[Type], &mut [Val]) -> Result<()>
+            + Send
+            + Sync
+            + 'static,
     {
+        // The types of the results are computed once here instead of on each
+        // call, except when they involve resources whose types depend on the
+        // instance which the function is lowered into.
+        let results = &types[types[index].results];
+        let result_types = if results.types.iter().any(|ty| mentions_resource(types, ty)) {
+            None
+        } else {
+            let resources = Arc::new(PrimaryMap::new());
+            let instance = InstanceType {
+                types,
+                resources: &resources,
+            };
+            Some(
+                results
+                    .types
+                    .iter()
+                    .map(|ty| Type::from(ty, &instance))
+                    .collect(),
+            )
+        };
+
         Arc::new(HostFunc {
             entrypoint: dynamic_entrypoint::<T, F>,
             typecheck: Box::new({
@@ -92,7 +117,7 @@ impl HostFunc {
                     }
                 }
             }),
-            func: Box::new(func),
+            func: Box::new(DynamicFunc { func, result_types }),
         })
     }
 
@@ -317,10 +342,11 @@ unsafe fn call_host_dynamic<T, F>(
     realloc: *mut VMFuncRef,
     string_encoding: StringEncoding,
     storage: &mut [MaybeUninit<ValRaw>],
+    result_types: Option<&[Type]>,
     closure: F,
 ) -> Result<()>
 where
-    F: FnOnce(StoreContextMut<'_, T>, &[Val], &mut [Val]) -> Result<()>,
+    F: FnOnce(StoreContextMut<'_, T>, &[Val], &[Type], &mut [Val]) -> Result<()>,
 {
     let cx = VMComponentContext::from_opaque(cx);
     let instance = (*cx).instance();
@@ -375,11 +401,29 @@ where
         ret_index = 1;
     };
 
+    let instance_result_types;
+    let result_types = match result_types {
+        Some(types) => types,
+        None => {
+            let instance = InstanceType::new(&*instance);
+            instance_result_types = result_tys
+                .types
+                .iter()
+                .map(|ty| Type::from(ty, &instance))
+                .collect::<Box<[_]>>();
+            &instance_result_types
+        }
+    };
     let mut result_vals = Vec::with_capacity(result_tys.types.len());
     for _ in result_tys.types.iter() {
         result_vals.push(Val::Bool(false));
     }
-    closure(store.as_context_mut(), &args, &mut result_vals)?;
+    closure(
+        store.as_context_mut(),
+        &args,
+        result_types,
+        &mut result_vals,
+    )?;
     flags.set_may_leave(false);
 
     let mut cx = LowerContext::new(store, &options, types, instance);
@@ -436,9 +480,12 @@ extern "C" fn dynamic_entrypoint<T, F>(
     storage: *mut MaybeUninit<ValRaw>,
     storage_len: usize,
 ) where
-    F: Fn(StoreContextMut<'_, T>, &[Val], &mut [Val]) -> Result<()> + Send + Sync + 'static,
+    F: Fn(StoreContextMut<'_, T>, &[Val], &[Type], &mut [Val]) -> Result<()>
+        + Send
+        + Sync
+        + 'static,
 {
-    let data = data as *const F;
+    let data = data as *const DynamicFunc<F>;
     unsafe {
         handle_result(|| {
             call_host_dynamic::<T, _>(
@@ -449,8 +496,37 @@ extern "C" fn dynamic_entrypoint<T, F>(
                 realloc,
                 string_encoding,
                 std::slice::from_raw_parts_mut(storage, storage_len),
-                |store, params, results| (*data)(store, params, results),
+                (*data).result_types.as_deref(),
+                |store, params, result_types, results| {
+                    ((*data).func)(store, params, result_types, results)
+                },
             )
         })
     }
 }
+
+/// A host function defined with [`HostFunc::new_dynamic`], along with the
+/// types of its results if they're the same for all instances.
+struct DynamicFunc<F> {
+    func: F,
+    result_types: Option<Box<[Type]>>,
+}
+
+/// Whether `ty` is, or contains, a resource handle.
+fn mentions_resource(types: &ComponentTypes, ty: &InterfaceType) -> bool {
+    let mentions = |ty: &InterfaceType| mentions_resource(types, ty);
+    match ty {
+        InterfaceType::Own(_) | InterfaceType::Borrow(_) => true,
+        InterfaceType::List(i) => mentions(&types[*i].element),
+        InterfaceType::Record(i) => types[*i].fields.iter().any(|f| mentions(&f.ty)),
+        InterfaceType::Tuple(i) => types[*i].types.iter().any(mentions),
+        InterfaceType::Variant(i) => types[*i].cases.iter().any(|c| c.ty.iter().any(mentions)),
+        InterfaceType::Union(i) => types[*i].types.iter().any(mentions),
+        InterfaceType::Option(i) => mentions(&types[*i].ty),
+        InterfaceType::Result(i) => {
+            let result = &types[*i];
+            result.ok.iter().chain(&result.err).any(mentions)
+        }
+        _ => false,
+    }
+}
@@ -23,6 +23,7 @@ use wasmtime_runtime::VMFuncRef;
 //
 // FIXME: need to write more docs here.
 #[derive(Copy, Clone)]
+#[repr(transparent)]
 pub struct Instance(pub(crate) Stored<Option<Box<InstanceData>>>);
 
 pub(crate) struct InstanceData {
@@ -145,6 +146,20 @@ impl Instance {
     }
 }
 
+/// Returns, for each component instance within `store`, the core instances it
+/// has created in the order that they were created.
+///
+/// Note that nested component instances are flattened at compile time so
+/// their core instances are included in their root component instance.
+pub(crate) fn core_instances(store: &StoreOpaque) -> Vec<Vec<crate::Instance>> {
+    store
+        .store_data()
+        .iter::<Option<Box<InstanceData>>>()
+        .filter_map(|data| store[data].as_ref())
+        .map(|data| data.instances.values().copied().collect())
+        .collect()
+}
+
 impl InstanceData {
     pub fn lookup_def(&self, store: &mut StoreOpaque, def: &CoreDef) -> wasmtime_runtime::Export {
         match def {
@@ -2,7 +2,7 @@ use crate::component::func::HostFunc;
 use crate::component::instance::RuntimeImport;
 use crate::component::matching::TypeChecker;
 use crate::component::{
-    Component, ComponentNamedList, Instance, InstancePre, Lift, Lower, ResourceType, Val,
+    Component, ComponentNamedList, Instance, InstancePre, Lift, Lower, ResourceType, Type, Val,
 };
 use crate::{AsContextMut, Engine, Module, StoreContextMut};
 use anyhow::{anyhow, bail, Context, Result};
@@ -329,6 +329,28 @@ impl<T> LinkerInstance<'_, T> {
         component: &Component,
         name: &str,
         func: F,
+    ) -> Result<()> {
+        self.func_new_with_result_types(component, name, move |store, params, _, results| {
+            func(store, params, results)
+        })
+    }
+
+    /// Same as [`LinkerInstance::func_new`], except that `func` is
+    /// additionally given the types of the results it must produce.
+    ///
+    /// This is useful for embedders which construct results from an untyped
+    /// representation, such as the C API, since building compound values
+    /// such as records or lists requires their type.
+    pub fn func_new_with_result_types<
+        F: Fn(StoreContextMut<'_, T>, &[Val], &[Type], &mut [Val]) -> Result<()>
+            + Send
+            + Sync
+            + 'static,
+    >(
+        &mut self,
+        component: &Component,
+        name: &str,
+        func: F,
     ) -> Result<()> {
         let mut map = &component
             .env_component()
@@ -48,6 +48,7 @@ pub mod __internal {
     pub use wasmtime_environ::component::{CanonicalAbiInfo, ComponentTypes, InterfaceType};
 }
 
+pub(crate) use self::instance::core_instances;
 pub(crate) use self::store::ComponentStoreData;
 
 /// Generate bindings for a WIT package.
@@ -283,6 +284,19 @@ pub(crate) use self::store::ComponentStoreData;
 ///     // This option defaults to `false`.
 ///     async: true,
 ///
+///     // Alternatively `async` can be restricted to a subset of imports,
+///     // either by listing the only imports which are async or by listing
+///     // the imports which are not. Functions are named by their WIT name,
+///     // optionally qualified with their interface such as
+///     // "wasi:filesystem/types#read".
+///     //
+///     // Exports are all async in this case since the store needs
+///     // `Config::async_support`, where synchronous calls aren't possible.
+///     async: {
+///         only_imports: ["read", "write"],
+///         // or: except_imports: ["get-size"],
+///     },
+///
 ///     // This can be used to translate WIT return values of the form
 ///     // `result<T, error-type>` into `Result<T, RustErrorType>` in Rust.
 ///     // The `RustErrorType` structure will have an automatically generated
@@ -306,9 +320,21 @@ pub(crate) use self::store::ComponentStoreData;
 ///     // for the names mentioned in the mapping, assuming instead that the
 ///     // names mentioned come from a previous use of the `bindgen!` macro
 ///     // with `only_interfaces: true`.
+///     //
+///     // Keys of the form "interface#type" instead remap a single WIT type to
+///     // an existing Rust type, which must implement `ComponentType`, `Lift`
+///     // and `Lower`. The path should be absolute, for example starting
+///     // with `crate::`.
 ///     with: {
 ///         "a": somewhere::else::a,
+///         "b#point": crate::geometry::Point,
 ///     },
+///
+///     // Additional derive attributes to add to generated records, variants,
+///     // unions and enums. Derives that are already generated are skipped.
+///     //
+///     // By default this option is not specified.
+///     additional_derives: [Hash, serde::Serialize],
 /// });
 /// ```
 ///
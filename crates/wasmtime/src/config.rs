@@ -932,6 +932,25 @@ impl Config {
         self
     }
 
+    /// Configures whether Cranelift inlines small functions into their callers.
+    ///
+    /// When enabled, direct calls to small functions defined in the same
+    /// module are replaced with the body of the callee. This saves the cost of
+    /// the call at the expense of some code size and compile time. Backtraces
+    /// still contain a frame for each inlined function, although the position
+    /// of the call within the caller is unknown if a function was inlined at
+    /// several call sites within it.
+    ///
+    /// Inlining is not performed when native debug information is generated.
+    ///
+    /// The default value for this is `false`
+    #[cfg(any(feature = "cranelift", feature = "winch"))]
+    #[cfg_attr(nightlydoc, doc(cfg(any(feature = "cranelift", feature = "winch"))))]
+    pub fn cranelift_inlining(&mut self, enable: bool) -> &mut Self {
+        self.tunables.inlining = enable;
+        self
+    }
+
     /// Configures whether Cranelift should perform a NaN-canonicalization pass.
     ///
     /// When Cranelift is used as a code generation backend this will configure
@@ -950,6 +969,28 @@ impl Config {
         self
     }
 
+    /// Configures whether Cranelift should place the cold blocks of functions,
+    /// such as the paths to traps, apart from the rest of their code.
+    ///
+    /// When enabled, the cold blocks of all functions in a module are placed
+    /// after all of the module's other code, which makes the hot code of each
+    /// function smaller and denser in the instruction cache.
+    ///
+    /// Native unwind info and DWARF debug info aren't generated for cold code,
+    /// so this requires [`Config::native_unwind_info`] and
+    /// [`Config::debug_info`] to be disabled, and isn't supported on Windows.
+    ///
+    /// The default value for this is `false`
+    #[cfg(any(feature = "cranelift", feature = "winch"))]
+    #[cfg_attr(nightlydoc, doc(cfg(any(feature = "cranelift", feature = "winch"))))]
+    pub fn cranelift_hot_cold_splitting(&mut self, enable: bool) -> &mut Self {
+        let val = if enable { "true" } else { "false" };
+        self.compiler_config
+            .settings
+            .insert("enable_hot_cold_splitting".to_string(), val.to_string());
+        self
+    }
+
     /// Allows setting a Cranelift boolean flag or preset. This allows
     /// fine-tuning of Cranelift settings.
     ///
@@ -1642,6 +1683,30 @@ impl Config {
             }
         }
 
+        // The cold parts of split functions have no unwind info or DWARF, so
+        // splitting can only be enabled when neither is generated.
+        let hot_cold_splitting = self
+            .compiler_config
+            .settings
+            .get("enable_hot_cold_splitting")
+            .map_or(false, |v| v == "true")
+            || self
+                .compiler_config
+                .flags
+                .contains("enable_hot_cold_splitting");
+        if hot_cold_splitting {
+            let target = self.compiler_config.target.as_ref().unwrap_or(&host);
+            if target.operating_system == target_lexicon::OperatingSystem::Windows {
+                bail!("hot/cold splitting is not supported on Windows");
+            }
+            if self.native_unwind_info {
+                bail!("hot/cold splitting requires native unwind info to be disabled");
+            }
+            if self.tunables.generate_native_debuginfo {
+                bail!("hot/cold splitting requires debug info to be disabled");
+            }
+        }
+
         // We require frame pointers for correct stack walking, which is safety
         // critical in the presence of reference types, and otherwise it is just
         // really bad developer experience to get wrong.
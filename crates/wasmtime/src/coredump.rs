@@ -1,6 +1,12 @@
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 
-use crate::{store::StoreOpaque, FrameInfo, Global, Instance, Memory, Module, WasmBacktrace};
+use crate::{
+    store::StoreOpaque, FrameInfo, Global, Instance, Memory, Module, ValType, WasmBacktrace,
+};
+use wasmtime_environ::{EntityRef, GlobalIndex, MemoryIndex, WASM_PAGE_SIZE};
+use wasmtime_runtime::{ExportGlobal, ExportMemory};
 
 /// Representation of a core dump of a WebAssembly module
 ///
@@ -30,23 +36,44 @@ pub struct WasmCoreDump {
     instances: Vec<Instance>,
     store_memories: Vec<Memory>,
     store_globals: Vec<Global>,
+    component_instances: Vec<Vec<usize>>,
     backtrace: WasmBacktrace,
+    snapshot: Snapshot,
 }
 
 impl WasmCoreDump {
-    pub(crate) fn new(store: &StoreOpaque, backtrace: WasmBacktrace) -> WasmCoreDump {
+    pub(crate) fn new(store: &mut StoreOpaque, backtrace: WasmBacktrace) -> WasmCoreDump {
         let modules: Vec<_> = store.modules().all_modules().cloned().collect();
         let instances: Vec<Instance> = store.all_instances().collect();
         let store_memories: Vec<Memory> = store.all_memories().collect();
         let store_globals: Vec<Global> = store.all_globals().collect();
 
+        // Each component instance is recorded as the indices, within
+        // `instances`, of the core instances it created.
+        #[cfg(feature = "component-model")]
+        let component_instances = crate::component::core_instances(store)
+            .into_iter()
+            .map(|core_instances| {
+                core_instances
+                    .iter()
+                    .filter_map(|i| instances.iter().position(|j| j.0 == i.0))
+                    .collect()
+            })
+            .collect();
+        #[cfg(not(feature = "component-model"))]
+        let component_instances = Vec::new();
+
+        let snapshot = Snapshot::new(store, &modules, &instances, &backtrace);
+
         WasmCoreDump {
             name: String::from("store_name"),
             modules,
             instances,
             store_memories,
             store_globals,
+            component_instances,
             backtrace,
+            snapshot,
         }
     }
 
@@ -76,6 +103,243 @@ impl WasmCoreDump {
     pub fn store_memories(&self) -> &[Memory] {
         self.store_memories.as_ref()
     }
+
+    /// The component instances involved in the CoreDump.
+    ///
+    /// Each component instance is described by the indices, within
+    /// [`WasmCoreDump::instances`], of the core instances that it created in
+    /// the order they were created. Wasmtime flattens nested component
+    /// instances when compiling a component so their core instances are
+    /// attributed to the outermost component instance.
+    pub fn component_instances(&self) -> &[Vec<usize>] {
+        self.component_instances.as_ref()
+    }
+
+    /// Serialize this core dump into the [standard core dump binary
+    /// format][spec].
+    ///
+    /// The `name` parameter may be a file path, URL, or arbitrary name for the
+    /// "main" Wasm service or executable that was running in this store.
+    ///
+    /// The resulting binary is a wasm module containing the `core`,
+    /// `coremodules`, `coreinstances` and `corestack` custom sections along
+    /// with the contents of all memories and globals of the instances in the
+    /// core dump. When components are involved the component instances are
+    /// additionally recorded in a `wasmtime-component-instances` custom
+    /// section as a vector of component instances, each of which is a vector
+    /// of indices into the core instances.
+    ///
+    /// The contents of memories and the values of globals are those at the
+    /// time the core dump was captured, even if the store has been used since.
+    ///
+    /// Note that the frames of the stack are attributed to the first instance
+    /// of the frame's module, which may not be precise when a module is
+    /// instantiated more than once.
+    ///
+    /// [spec]: https://github.com/WebAssembly/tool-conventions/blob/main/Coredump.md
+    pub fn serialize(&self, name: &str) -> Vec<u8> {
+        let mut core_dump = wasm_encoder::Module::new();
+        core_dump.section(&wasm_encoder::CoreDumpSection::new(name));
+
+        let mut modules = wasm_encoder::CoreDumpModulesSection::new();
+        for module in self.modules.iter() {
+            modules.module(module.name().unwrap_or("<module>"));
+        }
+
+        core_dump.section(&modules);
+        core_dump.section(&self.snapshot.instances);
+        core_dump.section(&self.snapshot.stack);
+        if !self.component_instances.is_empty() {
+            use wasm_encoder::Encode;
+            let mut component_instances = Vec::new();
+            (self.component_instances.len() as u32).encode(&mut component_instances);
+            for core_instances in self.component_instances.iter() {
+                let core_instances = core_instances.iter().map(|i| *i as u32).collect::<Vec<_>>();
+                core_instances.as_slice().encode(&mut component_instances);
+            }
+            core_dump.section(&wasm_encoder::CustomSection {
+                name: "wasmtime-component-instances".into(),
+                data: component_instances.into(),
+            });
+        }
+        core_dump.section(&self.snapshot.memories);
+        core_dump.section(&self.snapshot.globals);
+        core_dump.section(&self.snapshot.data);
+        core_dump.finish()
+    }
+}
+
+/// The sections of a serialized core dump which describe the state of the
+/// store. They're encoded when the core dump is captured since the store may
+/// be used again, or dropped, before the core dump is serialized.
+struct Snapshot {
+    instances: wasm_encoder::CoreDumpInstancesSection,
+    stack: wasm_encoder::CoreDumpStackSection,
+    memories: wasm_encoder::MemorySection,
+    globals: wasm_encoder::GlobalSection,
+    data: wasm_encoder::DataSection,
+    // Memories and globals are deduplicated by their definition since the
+    // same item may be defined by one instance and imported by others, or may
+    // have been created by the host and already be in the store.
+    memory_indices: HashMap<usize, u32>,
+    global_indices: HashMap<usize, u32>,
+}
+
+impl Snapshot {
+    fn new(
+        store: &mut StoreOpaque,
+        modules: &[Module],
+        instances: &[Instance],
+        backtrace: &WasmBacktrace,
+    ) -> Snapshot {
+        let mut snapshot = Snapshot {
+            instances: wasm_encoder::CoreDumpInstancesSection::new(),
+            stack: wasm_encoder::CoreDumpStackSection::new("main"),
+            memories: wasm_encoder::MemorySection::new(),
+            globals: wasm_encoder::GlobalSection::new(),
+            data: wasm_encoder::DataSection::new(),
+            memory_indices: HashMap::new(),
+            global_indices: HashMap::new(),
+        };
+
+        let mut instance_modules = Vec::with_capacity(instances.len());
+        for instance in instances {
+            let id = instance.id(store);
+            let handle = store.instance_mut(id);
+            let module = handle.module().clone();
+            let memory_indices = (0..module.memory_plans.len())
+                .map(|i| snapshot.add_memory(&handle.get_exported_memory(MemoryIndex::new(i))))
+                .collect::<Vec<_>>();
+            let global_indices = (0..module.globals.len())
+                .map(|i| snapshot.add_global(&handle.get_exported_global(GlobalIndex::new(i))))
+                .collect::<Vec<_>>();
+
+            let module_index = modules
+                .iter()
+                .position(|m| Arc::ptr_eq(m.compiled_module().module(), &module))
+                .expect("instance's module is registered in its store");
+            snapshot
+                .instances
+                .instance(module_index as u32, memory_indices, global_indices);
+            instance_modules.push(module);
+        }
+
+        // Memories and globals created by the host but not used by any
+        // instance are still included in the dump.
+        let data = store.store_data();
+        for memory in data.iter::<ExportMemory>() {
+            snapshot.add_memory(&data[memory]);
+        }
+        for global in data.iter::<ExportGlobal>() {
+            snapshot.add_global(&data[global]);
+        }
+
+        for frame in backtrace.frames() {
+            let instance = instance_modules
+                .iter()
+                .position(|m| Arc::ptr_eq(m, frame.module().compiled_module().module()))
+                .unwrap_or(0);
+            snapshot.stack.frame(
+                instance as u32,
+                frame.func_index(),
+                u32::try_from(frame.func_offset().unwrap_or(0)).unwrap(),
+                // Locals and values on the operand stack aren't recovered yet.
+                [],
+                [],
+            );
+        }
+
+        snapshot
+    }
+
+    fn add_memory(&mut self, memory: &ExportMemory) -> u32 {
+        let Snapshot {
+            memories,
+            data,
+            memory_indices,
+            ..
+        } = self;
+        *memory_indices
+            .entry(memory.definition as usize)
+            .or_insert_with(|| {
+                let ty = &memory.memory.memory;
+                let contents = unsafe {
+                    let definition = &*memory.definition;
+                    std::slice::from_raw_parts(definition.base, definition.current_length())
+                };
+                let index = memories.len();
+                memories.memory(wasm_encoder::MemoryType {
+                    minimum: (contents.len() / WASM_PAGE_SIZE as usize) as u64,
+                    maximum: ty.maximum,
+                    memory64: ty.memory64,
+                    shared: ty.shared,
+                });
+                let offset = if ty.memory64 {
+                    wasm_encoder::ConstExpr::i64_const(0)
+                } else {
+                    wasm_encoder::ConstExpr::i32_const(0)
+                };
+                data.active(index, &offset, contents.iter().copied());
+                index
+            })
+    }
+
+    fn add_global(&mut self, global: &ExportGlobal) -> u32 {
+        let Snapshot {
+            globals,
+            global_indices,
+            ..
+        } = self;
+        *global_indices
+            .entry(global.definition as usize)
+            .or_insert_with(|| {
+                let ty = ValType::from_wasm_type(&global.global.wasm_ty);
+                let definition = unsafe { &*global.definition };
+                let index = globals.len();
+                globals.global(
+                    wasm_encoder::GlobalType {
+                        val_type: match ty {
+                            ValType::I32 => wasm_encoder::ValType::I32,
+                            ValType::I64 => wasm_encoder::ValType::I64,
+                            ValType::F32 => wasm_encoder::ValType::F32,
+                            ValType::F64 => wasm_encoder::ValType::F64,
+                            ValType::V128 => wasm_encoder::ValType::V128,
+                            ValType::FuncRef => wasm_encoder::ValType::FUNCREF,
+                            ValType::ExternRef => wasm_encoder::ValType::EXTERNREF,
+                        },
+                        mutable: global.global.mutability,
+                    },
+                    // References can't be meaningfully serialized so they're
+                    // always recorded as null.
+                    &unsafe {
+                        match ty {
+                            ValType::I32 => {
+                                wasm_encoder::ConstExpr::i32_const(*definition.as_i32())
+                            }
+                            ValType::I64 => {
+                                wasm_encoder::ConstExpr::i64_const(*definition.as_i64())
+                            }
+                            ValType::F32 => wasm_encoder::ConstExpr::f32_const(f32::from_bits(
+                                *definition.as_u32(),
+                            )),
+                            ValType::F64 => wasm_encoder::ConstExpr::f64_const(f64::from_bits(
+                                *definition.as_u64(),
+                            )),
+                            ValType::V128 => {
+                                wasm_encoder::ConstExpr::v128_const(*definition.as_u128() as i128)
+                            }
+                            ValType::FuncRef => {
+                                wasm_encoder::ConstExpr::ref_null(wasm_encoder::HeapType::Func)
+                            }
+                            ValType::ExternRef => {
+                                wasm_encoder::ConstExpr::ref_null(wasm_encoder::HeapType::Extern)
+                            }
+                        }
+                    },
+                );
+                index
+            })
+    }
 }
 
 impl fmt::Display for WasmCoreDump {
@@ -101,6 +365,13 @@ impl fmt::Display for WasmCoreDump {
             writeln!(f, "  {:?}", global)?;
         }
 
+        if !self.component_instances.is_empty() {
+            writeln!(f, "component instances:")?;
+            for core_instances in self.component_instances.iter() {
+                writeln!(f, "  core instances {:?}", core_instances)?;
+            }
+        }
+
         writeln!(f, "backtrace:")?;
         write!(f, "{}", self.backtrace)?;
 
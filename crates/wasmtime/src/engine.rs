@@ -407,6 +407,16 @@ impl Engine {
                 }
             }
 
+            // Windows requires unwind info for all code, which cold parts of
+            // split functions don't have.
+            "enable_hot_cold_splitting" => {
+                if target.operating_system == target_lexicon::OperatingSystem::Windows {
+                    *value == FlagValue::Bool(false)
+                } else {
+                    return Ok(())
+                }
+            }
+
             // These settings don't affect the interface or functionality of
             // the module itself, so their configuration values shouldn't
             // matter.
@@ -428,6 +438,7 @@ impl Engine {
             | "probestack_size_log2" // probestack above asserted disabled
             | "regalloc" // shouldn't change semantics
             | "enable_incremental_compilation_cache_checks" // shouldn't change semantics
+            | "enable_shrink_wrapping" // no effect with frame pointers preserved
             | "enable_atomics" => return Ok(()),
 
             // Everything else is unknown and needs to be added somewhere to
@@ -346,6 +346,10 @@ impl Metadata {
 
             // Just a debugging aid, doesn't affect functionality at all.
             debug_adapter_modules: _,
+
+            // Only changes how code is optimized. Modules record the functions
+            // they inlined themselves, so they can be loaded either way.
+            inlining: _,
         } = self.tunables;
 
         Self::check_int(
@@ -30,7 +30,7 @@ use wasmtime_runtime::{
 /// available as [`Instance::new`].
 #[derive(Copy, Clone, Debug)]
 #[repr(transparent)]
-pub struct Instance(Stored<InstanceData>);
+pub struct Instance(pub(crate) Stored<InstanceData>);
 
 pub(crate) struct InstanceData {
     /// The id of the instance within the store, used to find the original
@@ -525,7 +525,6 @@ impl Instance {
         self.get_export(store, name)?.into_global()
     }
 
-    #[cfg(feature = "component-model")]
     pub(crate) fn id(&self, store: &StoreOpaque) -> InstanceId {
         store[self.0].id
     }
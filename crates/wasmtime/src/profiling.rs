@@ -1,3 +1,5 @@
+#[cfg(feature = "component-model")]
+use crate::component::Component;
 use crate::{AsContext, Module};
 use anyhow::Result;
 use fxprof_processed_profile::debugid::DebugId;
@@ -5,9 +7,17 @@ use fxprof_processed_profile::{
     CategoryHandle, CpuDelta, Frame, FrameFlags, FrameInfo, LibraryInfo, Profile,
     ReferenceTimestamp, Symbol, SymbolTable, Timestamp,
 };
+#[cfg(feature = "component-model")]
+use std::collections::HashMap;
 use std::ops::Range;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+#[cfg(feature = "component-model")]
+use wasmtime_environ::component::{
+    CoreDef, Export, ExportItem, GlobalInitializer, InstantiateModule, StaticModuleIndex,
+};
+#[cfg(feature = "component-model")]
+use wasmtime_environ::{EntityIndex, FuncIndex};
 use wasmtime_jit::CompiledModule;
 use wasmtime_runtime::Backtrace;
 
@@ -98,21 +108,62 @@ impl GuestProfiler {
     pub fn new(module_name: &str, interval: Duration, modules: Vec<(String, Module)>) -> Self {
         let zero = ReferenceTimestamp::from_millis_since_unix_epoch(0.0);
         let mut profile = Profile::new(module_name, zero, interval.into());
+        let modules = modules
+            .into_iter()
+            .filter_map(|(name, module)| module_library(&mut profile, name, &module))
+            .collect();
+        Self::from_parts(profile, module_name, modules)
+    }
 
+    /// Begin profiling a new guest which is a component. When this function
+    /// is called, the current wall-clock time is recorded as the start time
+    /// for the guest.
+    ///
+    /// This behaves the same as [`GuestProfiler::new`] except that all core
+    /// wasm modules within the `component`, including the adapter modules
+    /// generated by Wasmtime to implement the canonical ABI, appear in stack
+    /// traces. Frames are named by the core instance which the function
+    /// belongs to and, if the function has no name in the `name` section,
+    /// by the path under which the component exports it.
+    ///
+    /// Any additional modules which aren't part of the component, but
+    /// whose frames should appear in stack traces, can be supplied in
+    /// `modules`. The same guidance as for [`GuestProfiler::new`] applies.
+    #[cfg(feature = "component-model")]
+    #[cfg_attr(nightlydoc, doc(cfg(feature = "component-model")))]
+    pub fn new_component(
+        component_name: &str,
+        interval: Duration,
+        component: Component,
+        modules: Vec<(String, Module)>,
+    ) -> Self {
+        let zero = ReferenceTimestamp::from_millis_since_unix_epoch(0.0);
+        let mut profile = Profile::new(component_name, zero, interval.into());
         let mut modules: Vec<_> = modules
             .into_iter()
-            .filter_map(|(name, module)| {
-                let compiled = module.compiled_module();
-                let text = compiled.text().as_ptr_range();
-                let address_range = text.start as usize..text.end as usize;
-                module_symbols(name, compiled).map(|lib| (address_range, profile.add_lib(lib)))
-            })
+            .filter_map(|(name, module)| module_library(&mut profile, name, &module))
             .collect();
 
+        // All core modules within a component share the component's text
+        // section, so they're all described by one library.
+        let text = component.text().as_ptr_range();
+        let address_range = text.start as usize..text.end as usize;
+        if let Some(lib) = component_symbols(component_name.to_string(), &component) {
+            modules.push((address_range, profile.add_lib(lib)));
+        }
+
+        Self::from_parts(profile, component_name, modules)
+    }
+
+    fn from_parts(
+        mut profile: Profile,
+        name: &str,
+        mut modules: Vec<(Range<usize>, fxprof_processed_profile::LibraryHandle)>,
+    ) -> Self {
         modules.sort_unstable_by_key(|(range, _)| range.start);
 
         profile.set_reference_timestamp(std::time::SystemTime::now().into());
-        let process = profile.add_process(module_name, 0, Timestamp::from_nanos_since_reference(0));
+        let process = profile.add_process(name, 0, Timestamp::from_nanos_since_reference(0));
         let thread = profile.add_thread(process, 0, Timestamp::from_nanos_since_reference(0), true);
         let start = Instant::now();
         Self {
@@ -181,6 +232,17 @@ impl GuestProfiler {
     }
 }
 
+fn module_library(
+    profile: &mut Profile,
+    name: String,
+    module: &Module,
+) -> Option<(Range<usize>, fxprof_processed_profile::LibraryHandle)> {
+    let compiled = module.compiled_module();
+    let text = compiled.text().as_ptr_range();
+    let address_range = text.start as usize..text.end as usize;
+    module_symbols(name, compiled).map(|lib| (address_range, profile.add_lib(lib)))
+}
+
 fn module_symbols(name: String, compiled: &CompiledModule) -> Option<LibraryInfo> {
     let symbols = Vec::from_iter(compiled.finished_functions().map(|(defined_idx, _)| {
         let loc = compiled.func_loc(defined_idx);
@@ -195,6 +257,95 @@ fn module_symbols(name: String, compiled: &CompiledModule) -> Option<LibraryInfo
             name,
         }
     }));
+    library_info(name, symbols)
+}
+
+#[cfg(feature = "component-model")]
+fn component_symbols(name: String, component: &Component) -> Option<LibraryInfo> {
+    let env = component.env_component();
+
+    // Each core instance within the component is created by one of the
+    // `InstantiateModule` initializers, in order. Record which instance each
+    // static module was instantiated as to name its frames.
+    let mut instances = Vec::new();
+    for init in env.initializers.iter() {
+        if let GlobalInitializer::InstantiateModule(instantiate) = init {
+            instances.push(match instantiate {
+                InstantiateModule::Static(idx, _) => Some(*idx),
+                InstantiateModule::Import(..) => None,
+            });
+        }
+    }
+    let instance_of = |module: StaticModuleIndex| instances.iter().position(|m| *m == Some(module));
+
+    // Functions without a name in the `name` section are named after the
+    // path under which the component exports them, if any.
+    let mut export_names = HashMap::new();
+    let mut exports = Vec::from_iter(env.exports.iter().map(|(n, e)| (n.clone(), e)));
+    while let Some((path, export)) = exports.pop() {
+        match export {
+            Export::LiftedFunction {
+                func: CoreDef::Export(core_export),
+                ..
+            } => {
+                if let ExportItem::Index(EntityIndex::Function(func)) = core_export.item {
+                    if let Some(Some(module)) =
+                        instances.get(core_export.instance.as_u32() as usize)
+                    {
+                        export_names.entry((*module, func)).or_insert(path);
+                    }
+                }
+            }
+            Export::Instance(items) => {
+                exports.extend(items.iter().map(|(n, e)| (format!("{path}#{n}"), e)));
+            }
+            _ => {}
+        }
+    }
+
+    let mut symbols = Vec::new();
+    for (module_idx, module) in component.static_modules() {
+        let compiled = module.compiled_module();
+        let instance = match (module.name(), instance_of(module_idx)) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(i)) => format!("core-instance{i}"),
+            (None, None) => format!("core-module{}", module_idx.as_u32()),
+        };
+        // Core exports are a good fallback name, notably for adapter
+        // modules which name each adapter through an export.
+        let core_exports: HashMap<FuncIndex, &str> = compiled
+            .module()
+            .exports
+            .iter()
+            .filter_map(|(name, entity)| match entity {
+                EntityIndex::Function(f) => Some((*f, name.as_str())),
+                _ => None,
+            })
+            .collect();
+        for (defined_idx, _) in compiled.finished_functions() {
+            let loc = compiled.func_loc(defined_idx);
+            let func_idx = compiled.module().func_index(defined_idx);
+            let func = match compiled.func_name(func_idx) {
+                Some(name) => name.to_string(),
+                None => match export_names.get(&(module_idx, func_idx)) {
+                    Some(path) => path.clone(),
+                    None => match core_exports.get(&func_idx) {
+                        Some(name) => name.to_string(),
+                        None => format!("wasm_function_{}", defined_idx.as_u32()),
+                    },
+                },
+            };
+            symbols.push(Symbol {
+                address: loc.start,
+                size: Some(loc.length),
+                name: format!("{instance}!{func}"),
+            });
+        }
+    }
+    library_info(name, symbols)
+}
+
+fn library_info(name: String, symbols: Vec<Symbol>) -> Option<LibraryInfo> {
     if symbols.is_empty() {
         return None;
     }
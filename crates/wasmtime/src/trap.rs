@@ -3,7 +3,7 @@ use crate::store::StoreOpaque;
 use crate::{AsContext, Module};
 use anyhow::Error;
 use std::fmt;
-use wasmtime_environ::{EntityRef, FilePos};
+use wasmtime_environ::{EntityRef, FilePos, FuncIndex};
 use wasmtime_jit::{demangle_function_name, demangle_function_name_or_index};
 
 /// Representation of a WebAssembly trap and what caused it to occur.
@@ -77,7 +77,7 @@ pub(crate) unsafe fn raise(error: anyhow::Error) -> ! {
 
 #[cold] // traps are exceptional, this helps move handling off the main path
 pub(crate) fn from_runtime_box(
-    store: &StoreOpaque,
+    store: &mut StoreOpaque,
     runtime_trap: Box<wasmtime_runtime::Trap>,
 ) -> Error {
     let wasmtime_runtime::Trap {
@@ -331,8 +331,8 @@ impl WasmBacktrace {
             // Some(..)` instead of the `unwrap` you might otherwise expect and
             // we ignore frames from modules that were not registered in this
             // store's module registry.
-            if let Some((info, module)) = store.modules().lookup_frame_info(pc_to_lookup) {
-                wasm_trace.push(info);
+            if let Some((frames, module)) = store.modules().lookup_frame_info(pc_to_lookup) {
+                wasm_trace.extend(frames);
 
                 // If this frame has unparsed debug information and the
                 // store's configuration indicates that we were
@@ -425,6 +425,7 @@ impl fmt::Display for WasmBacktrace {
 /// to acquire this `FrameInfo`. For more information see [`WasmBacktrace`].
 #[derive(Debug)]
 pub struct FrameInfo {
+    module: Module,
     module_name: Option<String>,
     func_index: u32,
     func_name: Option<String>,
@@ -436,14 +437,18 @@ pub struct FrameInfo {
 impl FrameInfo {
     /// Fetches frame information about a program counter in a backtrace.
     ///
-    /// Returns an object if this `pc` is known to this module, or returns `None`
-    /// if no information can be found.
-    pub(crate) fn new(module: &Module, text_offset: usize) -> Option<FrameInfo> {
-        let module = module.compiled_module();
-        let (index, _func_offset) = module.func_by_text_offset(text_offset)?;
-        let info = module.wasm_func_info(index);
-        let instr =
-            wasmtime_environ::lookup_file_pos(module.code_memory().address_map_data(), text_offset);
+    /// Returns the frames at this `pc`, innermost first, if it is known to
+    /// this module, or returns `None` if no information can be found. There is
+    /// more than one frame if `pc` is in the body of a function that was
+    /// inlined into the function containing it.
+    pub(crate) fn new(module: &Module, text_offset: usize) -> Option<Vec<FrameInfo>> {
+        let compiled_module = module.compiled_module();
+        let (index, _func_offset) = compiled_module.func_by_text_offset(text_offset)?;
+        let info = compiled_module.wasm_func_info(index);
+        let instr = wasmtime_environ::lookup_file_pos(
+            compiled_module.code_memory().address_map_data(),
+            text_offset,
+        );
 
         // In debug mode for now assert that we found a mapping for `pc` within
         // the function, because otherwise something is buggy along the way and
@@ -453,11 +458,57 @@ impl FrameInfo {
         // Note that if the module doesn't even have an address map due to
         // compilation settings then it's expected that `instr` is `None`.
         debug_assert!(
-            instr.is_some() || !module.has_address_map(),
+            instr.is_some() || !compiled_module.has_address_map(),
             "failed to find instruction for {:#x}",
             text_offset
         );
 
+        let index = compiled_module.module().func_index(index);
+
+        // Instructions of inlined functions keep their offsets in the original
+        // wasm file, so an `instr` within the body of an inlined function means
+        // that we're in that function, called from this one. If the function
+        // was inlined at several call sites then we can't tell which of them
+        // this is.
+        let offset = instr.and_then(|i| i.file_offset());
+        let mut inlined = info.inlined.iter().filter(|f| {
+            offset.map_or(false, |o| {
+                f.start_srcloc.file_offset() <= Some(o) && Some(o) < f.end_srcloc.file_offset()
+            })
+        });
+        let mut frames = Vec::new();
+        let mut caller_instr = instr;
+        if let Some(callee) = inlined.next() {
+            frames.push(FrameInfo::for_function(
+                module,
+                callee.func_index,
+                callee.start_srcloc,
+                instr,
+            ));
+            caller_instr = if inlined.all(|f| f.call_site == callee.call_site) {
+                Some(callee.call_site)
+            } else {
+                None
+            };
+        }
+        frames.push(FrameInfo::for_function(
+            module,
+            index,
+            info.start_srcloc,
+            caller_instr,
+        ));
+        Some(frames)
+    }
+
+    fn for_function(
+        module: &Module,
+        index: FuncIndex,
+        func_start: FilePos,
+        instr: Option<FilePos>,
+    ) -> FrameInfo {
+        let frame_module = module.clone();
+        let module = module.compiled_module();
+
         // Use our wasm-relative pc to symbolize this frame. If there's a
         // symbolication context (dwarf debug info) available then we can try to
         // look this up there.
@@ -492,16 +543,15 @@ impl FrameInfo {
             }
         }
 
-        let index = module.module().func_index(index);
-
-        Some(FrameInfo {
+        FrameInfo {
+            module: frame_module,
             module_name: module.module().name.clone(),
             func_index: index.index() as u32,
             func_name: module.func_name(index).map(|s| s.to_string()),
             instr,
-            func_start: info.start_srcloc,
+            func_start,
             symbols,
-        })
+        }
     }
 
     /// Returns the WebAssembly function index for this frame.
@@ -512,6 +562,11 @@ impl FrameInfo {
         self.func_index
     }
 
+    /// Returns the module that this frame's function is defined in.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
     /// Returns the identifer of the module that this frame is for.
     ///
     /// Module identifiers are present in the `name` section of a WebAssembly
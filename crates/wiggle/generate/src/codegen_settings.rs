@@ -1,10 +1,10 @@
-use crate::config::{AsyncConf, ErrorConf, ErrorConfField, TracingConf};
+use crate::config::{AsyncConf, ErrorConf, ErrorConfField, PointerSizedConf, TracingConf};
 use anyhow::{anyhow, Error};
 use proc_macro2::{Ident, TokenStream};
 use quote::quote;
 use std::collections::HashMap;
 use std::rc::Rc;
-use witx::{Document, Id, InterfaceFunc, Module, NamedType, TypeRef};
+use witx::{BuiltinType, Document, Id, InterfaceFunc, Module, NamedType, Type, TypeRef};
 
 pub use crate::config::Asyncness;
 
@@ -19,6 +19,8 @@ pub struct CodegenSettings {
     /// Determine whether the context structure will use `&mut self` (true) or
     /// simply `&self`.
     pub mutable: bool,
+    /// The `u32` typenames which are as wide as the guest's pointers.
+    pub pointer_sized: Vec<Id>,
 }
 impl CodegenSettings {
     pub fn new(
@@ -28,14 +30,29 @@ impl CodegenSettings {
         wasmtime: bool,
         tracing: &TracingConf,
         mutable: bool,
+        pointer_sized: &PointerSizedConf,
     ) -> Result<Self, Error> {
         let errors = ErrorTransform::new(error_conf, doc)?;
+        let pointer_sized = pointer_sized
+            .iter()
+            .map(|ident| {
+                let id = Id::new(ident.to_string());
+                match doc.typename(&id) {
+                    Some(nt) => match &**nt.type_() {
+                        Type::Builtin(BuiltinType::U32 { .. }) => Ok(id),
+                        _ => Err(anyhow!("pointer sized typename \"{}\" is not a u32", ident)),
+                    },
+                    None => Err(anyhow!("No witx typename \"{}\" found", ident)),
+                }
+            })
+            .collect::<Result<_, Error>>()?;
         Ok(Self {
             errors,
             async_: async_.clone(),
             wasmtime,
             tracing: tracing.clone(),
             mutable,
+            pointer_sized,
         })
     }
     pub fn get_async(&self, module: &Module, func: &InterfaceFunc) -> Asyncness {
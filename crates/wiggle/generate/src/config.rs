@@ -17,6 +17,7 @@ pub struct Config {
     pub wasmtime: bool,
     pub tracing: TracingConf,
     pub mutable: bool,
+    pub pointer_sized: PointerSizedConf,
 }
 
 mod kw {
@@ -30,6 +31,7 @@ mod kw {
     syn::custom_keyword!(tracing);
     syn::custom_keyword!(disable_for);
     syn::custom_keyword!(trappable);
+    syn::custom_keyword!(pointer_sized);
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +42,7 @@ pub enum ConfigField {
     Wasmtime(bool),
     Tracing(TracingConf),
     Mutable(bool),
+    PointerSized(PointerSizedConf),
 }
 
 impl Parse for ConfigField {
@@ -83,6 +86,10 @@ impl Parse for ConfigField {
             input.parse::<kw::mutable>()?;
             input.parse::<Token![:]>()?;
             Ok(ConfigField::Mutable(input.parse::<syn::LitBool>()?.value))
+        } else if lookahead.peek(kw::pointer_sized) {
+            input.parse::<kw::pointer_sized>()?;
+            input.parse::<Token![:]>()?;
+            Ok(ConfigField::PointerSized(input.parse()?))
         } else {
             Err(lookahead.error())
         }
@@ -97,6 +104,7 @@ impl Config {
         let mut wasmtime = None;
         let mut tracing = None;
         let mut mutable = None;
+        let mut pointer_sized = None;
         for f in fields {
             match f {
                 ConfigField::Witx(c) => {
@@ -135,6 +143,12 @@ impl Config {
                     }
                     mutable = Some(c);
                 }
+                ConfigField::PointerSized(c) => {
+                    if pointer_sized.is_some() {
+                        return Err(Error::new(err_loc, "duplicate `pointer_sized` field"));
+                    }
+                    pointer_sized = Some(c);
+                }
             }
         }
         Ok(Config {
@@ -146,6 +160,7 @@ impl Config {
             wasmtime: wasmtime.unwrap_or(true),
             tracing: tracing.unwrap_or_default(),
             mutable: mutable.unwrap_or(true),
+            pointer_sized: pointer_sized.take().unwrap_or_default(),
         })
     }
 
@@ -623,12 +638,42 @@ impl Parse for WasmtimeConfigField {
             Ok(WasmtimeConfigField::Core(ConfigField::Mutable(
                 input.parse::<syn::LitBool>()?.value,
             )))
+        } else if lookahead.peek(kw::pointer_sized) {
+            input.parse::<kw::pointer_sized>()?;
+            input.parse::<Token![:]>()?;
+            Ok(WasmtimeConfigField::Core(ConfigField::PointerSized(
+                input.parse()?,
+            )))
         } else {
             Err(lookahead.error())
         }
     }
 }
 
+/// The witx typenames whose values are as wide as the guest's pointers, e.g.
+/// `pointer_sized: [size]`.
+///
+/// These are 32 bits wide in the witx document, which describes guests with
+/// 32-bit pointers, and become 64 bits wide for guests with a 64-bit memory.
+#[derive(Clone, Debug, Default)]
+pub struct PointerSizedConf(Vec<Ident>);
+
+impl PointerSizedConf {
+    pub fn iter(&self) -> impl Iterator<Item = &Ident> {
+        self.0.iter()
+    }
+}
+
+impl Parse for PointerSizedConf {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let _ = bracketed!(content in input);
+        let names: Punctuated<Ident, Token![,]> =
+            content.parse_terminated(Parse::parse, Token![,])?;
+        Ok(PointerSizedConf(names.into_iter().collect()))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TracingConf {
     enabled: bool,
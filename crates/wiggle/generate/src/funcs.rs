@@ -1,10 +1,12 @@
 use crate::codegen_settings::{CodegenSettings, ErrorType};
+use crate::layout;
 use crate::lifetimes::anon_lifetime;
 use crate::module_trait::passed_by_reference;
 use crate::names;
 use crate::types::WiggleType;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
+use std::collections::BTreeSet;
 use std::mem;
 use witx::Instruction;
 
@@ -26,6 +28,68 @@ pub fn func_bounds(
     bounds
 }
 
+/// Returns the indices of the wasm parameters of `func` which are passed with
+/// the width of the guest's pointers: pointers, list pointers and lengths,
+/// `usize`s and `pointer_sized` typenames, and the pointers results are
+/// returned through. These are `i64`s
+/// rather than `i32`s for guests with a 64-bit memory.
+///
+/// This follows the order of parameters in `witx::InterfaceFunc::wasm_signature`.
+pub fn func_pointer_params(
+    func: &witx::InterfaceFunc,
+    settings: &CodegenSettings,
+) -> BTreeSet<usize> {
+    let mut pointer_sized = Vec::new();
+    for param in func.params.iter() {
+        match &**param.tref.type_() {
+            witx::Type::List(_) => pointer_sized.extend([true, true]),
+            _ => pointer_sized.push(layout::is_pointer_sized(&param.tref, settings)),
+        }
+    }
+    for result in func.results.iter() {
+        if let witx::Type::Variant(v) = &**result.tref.type_() {
+            if v.is_enum() {
+                continue;
+            }
+            if let Some(ty) = &v.cases[0].tref {
+                match &**ty.type_() {
+                    witx::Type::Record(r) if r.is_tuple() => {
+                        pointer_sized.extend(r.members.iter().map(|_| true))
+                    }
+                    _ => pointer_sized.push(true),
+                }
+            }
+        }
+    }
+    assert_eq!(pointer_sized.len(), func.wasm_signature().0.len());
+    pointer_sized
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, pointer)| if pointer { Some(i) } else { None })
+        .collect()
+}
+
+/// Returns the indices of the wasm parameters of `func` which are
+/// `pointer_sized` typenames. These are lifted as `u32`s, so they're converted
+/// from the guest's pointer width first.
+fn func_size_params(func: &witx::InterfaceFunc, settings: &CodegenSettings) -> BTreeSet<usize> {
+    let mut sizes = BTreeSet::new();
+    let mut nth = 0;
+    for param in func.params.iter() {
+        match &**param.tref.type_() {
+            witx::Type::List(_) => nth += 1,
+            witx::Type::Builtin(witx::BuiltinType::U32 {
+                lang_ptr_size: false,
+            }) if layout::is_usize(&param.tref, settings) => {
+                sizes.insert(nth);
+            }
+            _ => {}
+        }
+        nth += 1;
+    }
+    sizes
+}
+
 fn _define_func(
     module: &witx::Module,
     func: &witx::InterfaceFunc,
@@ -37,10 +101,6 @@ fn _define_func(
     let param_names = (0..wasm_params.len())
         .map(|i| Ident::new(&format!("arg{}", i), Span::call_site()))
         .collect::<Vec<_>>();
-    let abi_params = wasm_params.iter().zip(&param_names).map(|(arg, name)| {
-        let wasm = names::wasm_type(*arg);
-        quote!(#name : #wasm)
-    });
 
     let abi_ret = match wasm_results.len() {
         0 => quote!(()),
@@ -53,11 +113,13 @@ fn _define_func(
 
     let mut body = TokenStream::new();
     let mut bounds = vec![names::trait_name(&module.name)];
+    let sizes = func_size_params(func, settings);
     func.call_interface(
         &module.name,
         &mut Rust {
             src: &mut body,
             params: &param_names,
+            sizes: &sizes,
             block_storage: Vec::new(),
             blocks: Vec::new(),
             module,
@@ -67,6 +129,28 @@ fn _define_func(
         },
     );
 
+    // Pointer parameters are generic so that the same function serves guests
+    // with 32-bit and 64-bit memories.
+    let pointers = func_pointer_params(func, settings);
+    let abi_params = wasm_params
+        .iter()
+        .zip(&param_names)
+        .enumerate()
+        .map(|(i, (arg, name))| {
+            if pointers.contains(&i) {
+                quote!(#name : A)
+            } else {
+                let wasm = names::wasm_type(*arg);
+                quote!(#name : #wasm)
+            }
+        })
+        .collect::<Vec<_>>();
+    let addr_param = if pointers.is_empty() {
+        quote!()
+    } else {
+        quote!(, A: wiggle::GuestAddr)
+    };
+
     let mod_name = &module.name.as_str();
     let func_name = &func.name.as_str();
     let mk_span = quote!(
@@ -96,7 +180,7 @@ fn _define_func(
         (
             quote!(
                 #[allow(unreachable_code)] // deals with warnings in noreturn functions
-                pub fn #ident<'a>(
+                pub fn #ident<'a #addr_param>(
                     ctx: #ctx_type (impl #(#bounds)+*),
                     memory: &dyn wiggle::GuestMemory,
                     #(#abi_params),*
@@ -126,7 +210,7 @@ fn _define_func(
         (
             quote!(
                 #[allow(unreachable_code)] // deals with warnings in noreturn functions
-                pub fn #ident<'a>(
+                pub fn #ident<'a #addr_param>(
                     ctx: #ctx_type (impl #(#bounds)+*),
                     memory: &'a dyn wiggle::GuestMemory,
                     #(#abi_params),*
@@ -143,6 +227,7 @@ fn _define_func(
 struct Rust<'a> {
     src: &'a mut TokenStream,
     params: &'a [Ident],
+    sizes: &'a BTreeSet<usize>,
     block_storage: Vec<TokenStream>,
     blocks: Vec<TokenStream>,
     module: &'a witx::Module,
@@ -217,20 +302,28 @@ impl witx::Bindgen for Rust<'_> {
         match inst {
             Instruction::GetArg { nth } => {
                 let param = &self.params[*nth];
-                results.push(quote!(#param));
+                if self.sizes.contains(nth) {
+                    let wrap_err = wrap_err(&format!("convert {}", param));
+                    results.push(quote! {
+                        (wiggle::GuestAddr::to_len(#param).map_err(#wrap_err)? as i32)
+                    });
+                } else {
+                    results.push(quote!(#param));
+                }
             }
 
             Instruction::PointerFromI32 { ty } | Instruction::ConstPointerFromI32 { ty } => {
                 let val = operands.pop().unwrap();
                 let pointee_type = names::type_ref(ty, anon_lifetime());
                 results.push(quote! {
-                    wiggle::GuestPtr::<#pointee_type>::new(memory, #val as u32)
+                    wiggle::GuestPtr::<#pointee_type>::new(memory, wiggle::GuestAddr::to_offset(#val))
                 });
             }
 
             Instruction::ListFromPointerLength { ty } => {
-                let ptr = &operands[0];
-                let len = &operands[1];
+                let ptr = operands[0].clone();
+                let len = operands[1].clone();
+                let wrap_err = wrap_err("convert list length");
                 let ty = match &**ty.type_() {
                     witx::Type::Builtin(witx::BuiltinType::Char) => quote!(str),
                     _ => {
@@ -239,7 +332,13 @@ impl witx::Bindgen for Rust<'_> {
                     }
                 };
                 results.push(quote! {
-                    wiggle::GuestPtr::<#ty>::new(memory, (#ptr as u32, #len as u32));
+                    wiggle::GuestPtr::<#ty>::new(
+                        memory,
+                        (
+                            wiggle::GuestAddr::to_offset(#ptr),
+                            wiggle::GuestAddr::to_len(#len).map_err(#wrap_err)?,
+                        ),
+                    );
                 })
             }
 
@@ -367,9 +466,14 @@ impl witx::Bindgen for Rust<'_> {
                 let val = operands.pop().unwrap();
                 let wrap_err = wrap_err(&format!("write {}", ty.name.as_str()));
                 let pointee_type = names::type_(&ty.name);
+                let write = if layout::is_usize_name(ty, self.settings) {
+                    quote!(write_usize)
+                } else {
+                    quote!(write)
+                };
                 self.src.extend(quote! {
-                    wiggle::GuestPtr::<#pointee_type>::new(memory, #ptr as u32)
-                        .write(#val)
+                    wiggle::GuestPtr::<#pointee_type>::new(memory, wiggle::GuestAddr::to_offset(#ptr))
+                        .#write(#val)
                         .map_err(#wrap_err)?;
                 });
             }
@@ -378,9 +482,14 @@ impl witx::Bindgen for Rust<'_> {
                 let ptr = operands.pop().unwrap();
                 let wrap_err = wrap_err(&format!("read {}", ty.name.as_str()));
                 let pointee_type = names::type_(&ty.name);
+                let read = if layout::is_usize_name(ty, self.settings) {
+                    quote!(read_usize)
+                } else {
+                    quote!(read)
+                };
                 results.push(quote! {
-                    wiggle::GuestPtr::<#pointee_type>::new(memory, #ptr as u32)
-                        .read()
+                    wiggle::GuestPtr::<#pointee_type>::new(memory, wiggle::GuestAddr::to_offset(#ptr))
+                        .#read()
                         .map_err(#wrap_err)?
                 });
             }
@@ -401,10 +510,16 @@ impl witx::Bindgen for Rust<'_> {
 
             // Conversions with matching bit-widths but different signededness
             // use `as` since we're basically just reinterpreting the bits.
-            Instruction::U32FromI32 | Instruction::UsizeFromI32 => {
+            Instruction::U32FromI32 => {
                 let val = operands.pop().unwrap();
                 results.push(quote!(#val as u32));
             }
+            // `usize`s are passed with the width of the guest's pointers.
+            Instruction::UsizeFromI32 => {
+                let val = operands.pop().unwrap();
+                let wrap_err = wrap_err("convert usize");
+                results.push(quote!(wiggle::GuestAddr::to_len(#val).map_err(#wrap_err)?));
+            }
             Instruction::U64FromI64 => {
                 let val = operands.pop().unwrap();
                 results.push(quote!(#val as u64));
@@ -0,0 +1,187 @@
+//! Layouts of witx types in the memory of guests with either pointer width.
+//!
+//! `witx::Layout` describes guests with 32-bit pointers. Guests with 64-bit
+//! pointers store pointers, list pointers and lengths, `usize`s, and the
+//! typenames configured with `pointer_sized` in eight bytes instead, which
+//! changes the layout of anything containing them.
+
+use crate::codegen_settings::CodegenSettings;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use witx::{BuiltinType, Layout, NamedType, SizeAlign, Type, TypeRef};
+
+/// The pointer widths of the guests bindings are generated for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointerWidth {
+    Bits32,
+    Bits64,
+}
+
+impl PointerWidth {
+    /// The wasm type of pointers of this width.
+    pub fn wasm_type(self) -> TokenStream {
+        match self {
+            PointerWidth::Bits32 => quote!(i32),
+            PointerWidth::Bits64 => quote!(i64),
+        }
+    }
+
+    fn pointer(self) -> SizeAlign {
+        match self {
+            PointerWidth::Bits32 => SizeAlign { size: 4, align: 4 },
+            PointerWidth::Bits64 => SizeAlign { size: 8, align: 8 },
+        }
+    }
+}
+
+impl ToTokens for PointerWidth {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            PointerWidth::Bits32 => quote!(wiggle::PointerWidth::Bits32),
+            PointerWidth::Bits64 => quote!(wiggle::PointerWidth::Bits64),
+        })
+    }
+}
+
+/// Whether `tref` is a witx `usize` or one of the typenames configured with
+/// `pointer_sized`, which are stored and passed with the width of the guest's
+/// pointers.
+pub fn is_usize(tref: &TypeRef, settings: &CodegenSettings) -> bool {
+    match tref {
+        TypeRef::Name(nt) => is_usize_name(nt, settings),
+        TypeRef::Value(ty) => matches!(
+            &**ty,
+            Type::Builtin(BuiltinType::U32 {
+                lang_ptr_size: true
+            })
+        ),
+    }
+}
+
+/// Like [`is_usize`], for the named type `nt`.
+pub fn is_usize_name(nt: &NamedType, settings: &CodegenSettings) -> bool {
+    settings.pointer_sized.contains(&nt.name) || is_usize(&nt.tref, settings)
+}
+
+/// Whether values of `tref` are passed to host functions with the width of the
+/// guest's pointers.
+pub fn is_pointer_sized(tref: &TypeRef, settings: &CodegenSettings) -> bool {
+    is_usize(tref, settings) || matches!(&**tref.type_(), Type::Pointer(_) | Type::ConstPointer(_))
+}
+
+pub fn size_align(tref: &TypeRef, width: PointerWidth, settings: &CodegenSettings) -> SizeAlign {
+    let ty = tref.type_();
+    match &**ty {
+        _ if width == PointerWidth::Bits32 => ty.mem_size_align(),
+        Type::Record(r) if r.bitflags_repr().is_none() => record_layout(r, width, settings).0,
+        Type::Variant(v) => variant_layout(v, width, settings).0,
+        Type::List(_) => {
+            let ptr = width.pointer();
+            SizeAlign {
+                size: ptr.size * 2,
+                align: ptr.align,
+            }
+        }
+        Type::Pointer(_) | Type::ConstPointer(_) => width.pointer(),
+        _ if is_usize(tref, settings) => width.pointer(),
+        _ => ty.mem_size_align(),
+    }
+}
+
+/// Returns the layout of `r` and the offsets of its members.
+pub fn record_layout(
+    r: &witx::RecordDatatype,
+    width: PointerWidth,
+    settings: &CodegenSettings,
+) -> (SizeAlign, Vec<usize>) {
+    if width == PointerWidth::Bits32 {
+        let offsets = r.member_layout().iter().map(|ml| ml.offset).collect();
+        return (r.mem_size_align(), offsets);
+    }
+    let mut sa = SizeAlign { size: 0, align: 0 };
+    let mut offsets = Vec::new();
+    for m in r.members.iter() {
+        let member = size_align(&m.tref, width, settings);
+        sa.align = sa.align.max(member.align);
+        sa.size = align_to(sa.size, member.align);
+        offsets.push(sa.size);
+        sa.size += member.size;
+    }
+    sa.size = align_to(sa.size, sa.align);
+    (sa, offsets)
+}
+
+/// Returns the layout of `v` and the offset of its payload.
+pub fn variant_layout(
+    v: &witx::Variant,
+    width: PointerWidth,
+    settings: &CodegenSettings,
+) -> (SizeAlign, usize) {
+    if width == PointerWidth::Bits32 {
+        return (v.mem_size_align(), v.payload_offset());
+    }
+    let tag = v.tag_repr.mem_size_align();
+    let mut sa = SizeAlign { size: 0, align: 0 };
+    let mut offset = tag.size;
+    for case in v.cases.iter() {
+        let mut case_sa = tag;
+        if let Some(payload) = &case.tref {
+            let payload = size_align(payload, width, settings);
+            offset = offset.max(align_to(offset, payload.align));
+            case_sa.align = case_sa.align.max(payload.align);
+            case_sa.size = align_to(case_sa.size, payload.align) + payload.size;
+        }
+        case_sa.size = align_to(case_sa.size, case_sa.align);
+        sa.size = sa.size.max(case_sa.size);
+        sa.align = sa.align.max(case_sa.align);
+    }
+    (sa, offset)
+}
+
+/// Returns an expression for `value` at the pointer width `width` evaluates
+/// to, which is a constant when both widths agree.
+pub fn per_width<T: ToTokens + PartialEq>(
+    width: TokenStream,
+    value: impl Fn(PointerWidth) -> T,
+) -> TokenStream {
+    let bits32 = value(PointerWidth::Bits32);
+    let bits64 = value(PointerWidth::Bits64);
+    if bits32 == bits64 {
+        quote!(#bits32)
+    } else {
+        quote! {
+            match #width {
+                wiggle::PointerWidth::Bits32 => #bits32,
+                wiggle::PointerWidth::Bits64 => #bits64,
+            }
+        }
+    }
+}
+
+/// Returns the `guest_size_for` and `guest_align_for` methods of a
+/// `GuestType` whose layout depends on the pointer width, or nothing if it
+/// doesn't.
+pub fn guest_type_methods(layout: impl Fn(PointerWidth) -> SizeAlign) -> TokenStream {
+    if layout(PointerWidth::Bits32) == layout(PointerWidth::Bits64) {
+        return quote!();
+    }
+    let size = per_width(quote!(width), |w| layout(w).size as u32);
+    let align = per_width(quote!(width), |w| layout(w).align);
+    quote! {
+        #[inline]
+        fn guest_size_for(width: wiggle::PointerWidth) -> u32 {
+            #size
+        }
+
+        #[inline]
+        fn guest_align_for(width: wiggle::PointerWidth) -> usize {
+            #align
+        }
+    }
+}
+
+/// If the next free byte in a record is `offs`, returns where the next member
+/// with the given alignment goes.
+fn align_to(offs: usize, alignment: usize) -> usize {
+    offs + alignment - 1 - ((offs + alignment - 1) % alignment)
+}
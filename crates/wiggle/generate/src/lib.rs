@@ -1,6 +1,7 @@
 mod codegen_settings;
 pub mod config;
 mod funcs;
+mod layout;
 mod lifetimes;
 mod module_trait;
 pub mod names;
@@ -19,9 +20,7 @@ pub use module_trait::define_module_trait;
 pub use types::define_datatype;
 
 pub fn generate(doc: &witx::Document, settings: &CodegenSettings) -> TokenStream {
-    let types = doc
-        .typenames()
-        .map(|t| define_datatype(&t, settings.errors.for_name(&t)));
+    let types = doc.typenames().map(|t| define_datatype(&t, settings));
 
     let constants = doc.constants().map(|c| {
         let name = quote::format_ident!(
@@ -5,28 +5,28 @@ mod handle;
 mod record;
 mod variant;
 
-use crate::codegen_settings::ErrorType;
+use crate::codegen_settings::{CodegenSettings, ErrorType};
 use crate::lifetimes::LifetimeExt;
 use crate::names;
 
 use proc_macro2::TokenStream;
 use quote::quote;
 
-pub fn define_datatype(namedtype: &witx::NamedType, error: Option<&ErrorType>) -> TokenStream {
+pub fn define_datatype(namedtype: &witx::NamedType, settings: &CodegenSettings) -> TokenStream {
     match &namedtype.tref {
         witx::TypeRef::Name(alias_to) => define_alias(&namedtype.name, &alias_to),
         witx::TypeRef::Value(v) => match &**v {
             witx::Type::Record(r) => match r.bitflags_repr() {
                 Some(repr) => flags::define_flags(&namedtype.name, repr, &r),
-                None => record::define_struct(&namedtype.name, &r),
+                None => record::define_struct(&namedtype.name, &r, settings),
             },
-            witx::Type::Variant(v) => match error {
+            witx::Type::Variant(v) => match settings.errors.for_name(namedtype) {
                 Some(ErrorType::Generated(error)) => {
-                    let d = variant::define_variant(&namedtype.name, &v, true);
+                    let d = variant::define_variant(&namedtype.name, &v, true, settings);
                     let e = error::define_error(&namedtype.name, &v, error);
                     quote!( #d #e )
                 }
-                _ => variant::define_variant(&namedtype.name, &v, false),
+                _ => variant::define_variant(&namedtype.name, &v, false, settings),
             },
             witx::Type::Handle(h) => handle::define_handle(&namedtype.name, &h),
             witx::Type::Builtin(b) => define_builtin(&namedtype.name, *b),
@@ -1,3 +1,5 @@
+use crate::codegen_settings::CodegenSettings;
+use crate::layout;
 use crate::lifetimes::{anon_lifetime, LifetimeExt};
 use crate::names;
 
@@ -5,10 +7,21 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use witx::Layout;
 
-pub(super) fn define_struct(name: &witx::Id, s: &witx::RecordDatatype) -> TokenStream {
+pub(super) fn define_struct(
+    name: &witx::Id,
+    s: &witx::RecordDatatype,
+    settings: &CodegenSettings,
+) -> TokenStream {
     let ident = names::type_(name);
     let size = s.mem_size_align().size as u32;
     let align = s.mem_size_align().align as usize;
+    let width_methods = layout::guest_type_methods(|w| layout::record_layout(s, w, settings).0);
+    let member_location = |i: usize| {
+        let offset = layout::per_width(quote!(location.mem().pointer_width()), |w| {
+            layout::record_layout(s, w, settings).1[i] as u32
+        });
+        quote!(location.cast::<u8>().add(#offset)?)
+    };
 
     let member_names = s.members.iter().map(|m| names::struct_member(&m.name));
     let member_decls = s.members.iter().map(|m| {
@@ -34,28 +47,32 @@ pub(super) fn define_struct(name: &witx::Id, s: &witx::RecordDatatype) -> TokenS
         quote!(pub #name: #type_)
     });
 
-    let member_reads = s.member_layout().into_iter().map(|ml| {
-        let name = names::struct_member(&ml.member.name);
-        let offset = ml.offset as u32;
-        let location = quote!(location.cast::<u8>().add(#offset)?.cast());
-        match &ml.member.tref {
+    let member_reads = s.members.iter().enumerate().map(|(i, m)| {
+        let name = names::struct_member(&m.name);
+        let location = member_location(i);
+        if layout::is_usize(&m.tref, settings) {
+            return quote! {
+                let #name = #location.cast::<u32>().read_usize()?;
+            };
+        }
+        match &m.tref {
             witx::TypeRef::Name(nt) => {
                 let type_ = names::type_(&nt.name);
                 quote! {
-                    let #name = <#type_ as wiggle::GuestType>::read(&#location)?;
+                    let #name = <#type_ as wiggle::GuestType>::read(&#location.cast())?;
                 }
             }
             witx::TypeRef::Value(ty) => match &**ty {
                 witx::Type::Builtin(builtin) => {
                     let type_ = names::builtin_type(*builtin);
                     quote! {
-                        let #name = <#type_ as wiggle::GuestType>::read(&#location)?;
+                        let #name = <#type_ as wiggle::GuestType>::read(&#location.cast())?;
                     }
                 }
                 witx::Type::Pointer(pointee) | witx::Type::ConstPointer(pointee) => {
                     let pointee_type = names::type_ref(&pointee, anon_lifetime());
                     quote! {
-                        let #name = <wiggle::GuestPtr::<#pointee_type> as wiggle::GuestType>::read(&#location)?;
+                        let #name = <wiggle::GuestPtr::<#pointee_type> as wiggle::GuestType>::read(&#location.cast())?;
                     }
                 }
                 _ => unimplemented!("other anonymous struct members: {:?}", ty),
@@ -63,14 +80,17 @@ pub(super) fn define_struct(name: &witx::Id, s: &witx::RecordDatatype) -> TokenS
         }
     });
 
-    let member_writes = s.member_layout().into_iter().map(|ml| {
-        let name = names::struct_member(&ml.member.name);
-        let offset = ml.offset as u32;
-        quote! {
-            wiggle::GuestType::write(
-                &location.cast::<u8>().add(#offset)?.cast(),
-                val.#name,
-            )?;
+    let member_writes = s.members.iter().enumerate().map(|(i, m)| {
+        let name = names::struct_member(&m.name);
+        let location = member_location(i);
+        if layout::is_usize(&m.tref, settings) {
+            quote! {
+                #location.cast::<u32>().write_usize(val.#name)?;
+            }
+        } else {
+            quote! {
+                wiggle::GuestType::write(&#location.cast(), val.#name)?;
+            }
         }
     });
 
@@ -97,6 +117,8 @@ pub(super) fn define_struct(name: &witx::Id, s: &witx::RecordDatatype) -> TokenS
                 #align
             }
 
+            #width_methods
+
             fn read(location: &wiggle::GuestPtr<'a, Self>) -> Result<Self, wiggle::GuestError> {
                 #(#member_reads)*
                 Ok(#ident { #(#member_names),* })
@@ -1,3 +1,5 @@
+use crate::codegen_settings::CodegenSettings;
+use crate::layout;
 use crate::lifetimes::LifetimeExt;
 use crate::names;
 
@@ -9,11 +11,15 @@ pub(super) fn define_variant(
     name: &witx::Id,
     v: &witx::Variant,
     derive_std_error: bool,
+    settings: &CodegenSettings,
 ) -> TokenStream {
     let ident = names::type_(name);
     let size = v.mem_size_align().size as u32;
     let align = v.mem_size_align().align as usize;
-    let contents_offset = v.payload_offset() as u32;
+    let width_methods = layout::guest_type_methods(|w| layout::variant_layout(v, w, settings).0);
+    let contents_offset = layout::per_width(quote!(location.mem().pointer_width()), |w| {
+        layout::variant_layout(v, w, settings).1 as u32
+    });
 
     let lifetime = quote!('a);
     let tag_ty = super::int_repr_tokens(v.tag_repr);
@@ -33,10 +39,15 @@ pub(super) fn define_variant(
         let variantname = names::enum_variant(&c.name);
         if let Some(tref) = &c.tref {
             let varianttype = names::type_ref(tref, lifetime.clone());
+            let read = if layout::is_usize(tref, settings) {
+                quote!(variant_ptr.cast::<u32>().read_usize()?)
+            } else {
+                quote!(<#varianttype as wiggle::GuestType>::read(&variant_ptr.cast())?)
+            };
             quote! {
                 #i => {
                     let variant_ptr = location.cast::<u8>().add(#contents_offset)?;
-                    let variant_val = <#varianttype as wiggle::GuestType>::read(&variant_ptr.cast())?;
+                    let variant_val = #read;
                     Ok(#ident::#variantname(variant_val))
                 }
             }
@@ -52,11 +63,16 @@ pub(super) fn define_variant(
         };
         if let Some(tref) = &c.tref {
             let varianttype = names::type_ref(tref, lifetime.clone());
+            let write = if layout::is_usize(tref, settings) {
+                quote!(variant_ptr.cast::<u32>().write_usize(contents)?)
+            } else {
+                quote!(<#varianttype as wiggle::GuestType>::write(&variant_ptr.cast(), contents)?)
+            };
             quote! {
                 #ident::#variantname(contents) => {
                     #write_tag
                     let variant_ptr = location.cast::<u8>().add(#contents_offset)?;
-                    <#varianttype as wiggle::GuestType>::write(&variant_ptr.cast(), contents)?;
+                    #write;
                 }
             }
         } else {
@@ -161,6 +177,8 @@ pub(super) fn define_variant(
                 #align
             }
 
+            #width_methods
+
             fn read(location: &wiggle::GuestPtr<'a, Self>)
                 -> Result<Self, wiggle::GuestError>
             {
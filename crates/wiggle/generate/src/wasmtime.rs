@@ -1,10 +1,11 @@
 use crate::config::Asyncness;
-use crate::funcs::func_bounds;
+use crate::funcs::{func_bounds, func_pointer_params};
+use crate::layout::PointerWidth;
 use crate::names;
 use crate::CodegenSettings;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{format_ident, quote};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
 pub fn link_module(
     module: &witx::Module,
@@ -19,11 +20,28 @@ pub fn link_module(
         quote! {}
     };
 
-    let mut bodies = Vec::new();
+    let mut bodies32 = Vec::new();
+    let mut bodies64 = Vec::new();
     let mut bounds = HashSet::new();
     for f in module.funcs() {
         let asyncness = settings.async_.get(module.name.as_str(), f.name.as_str());
-        bodies.push(generate_func(&module, &f, target_path, asyncness));
+        let pointers = func_pointer_params(&f, settings);
+        bodies32.push(generate_func(
+            &module,
+            &f,
+            target_path,
+            asyncness,
+            &pointers,
+            PointerWidth::Bits32,
+        ));
+        bodies64.push(generate_func(
+            &module,
+            &f,
+            target_path,
+            asyncness,
+            &pointers,
+            PointerWidth::Bits64,
+        ));
         let bound = func_bounds(module, &f, settings);
         for b in bound {
             bounds.insert(b);
@@ -45,6 +63,7 @@ pub fn link_module(
     } else {
         format_ident!("add_{}_to_linker", module_ident)
     };
+    let width_func_name = format_ident!("{}_with_pointer_width", func_name);
 
     let u = if settings.mutable {
         quote!(&mut U)
@@ -60,7 +79,23 @@ pub fn link_module(
             where
                 U: #ctx_bound #send_bound
         {
-            #(#bodies)*
+            #width_func_name(linker, wiggle::PointerWidth::Bits32, get_cx)
+        }
+
+        /// Adds all instance items to the specified `Linker`, with the
+        /// signatures used by guests whose pointers have the given `width`.
+        pub fn #width_func_name<T, U>(
+            linker: &mut wiggle::wasmtime_crate::Linker<T>,
+            width: wiggle::PointerWidth,
+            get_cx: impl Fn(&mut T) -> #u + Send + Sync + Copy + 'static,
+        ) -> wiggle::anyhow::Result<()>
+            where
+                U: #ctx_bound #send_bound
+        {
+            match width {
+                wiggle::PointerWidth::Bits32 => { #(#bodies32)* }
+                wiggle::PointerWidth::Bits64 => { #(#bodies64)* }
+            }
             Ok(())
         }
     }
@@ -71,6 +106,8 @@ fn generate_func(
     func: &witx::InterfaceFunc,
     target_path: Option<&syn::Path>,
     asyncness: Asyncness,
+    pointers: &BTreeSet<usize>,
+    width: PointerWidth,
 ) -> TokenStream {
     let module_str = module.name.as_str();
     let module_ident = names::module(&module.name);
@@ -88,7 +125,11 @@ fn generate_func(
         .enumerate()
         .map(|(i, ty)| {
             let name = &arg_names[i];
-            let wasm = names::wasm_type(*ty);
+            let wasm = if pointers.contains(&i) {
+                width.wasm_type()
+            } else {
+                names::wasm_type(*ty)
+            };
             quote! { #name: #wasm }
         })
         .collect::<Vec<_>>();
@@ -117,11 +158,11 @@ fn generate_func(
             Some(wiggle::wasmtime_crate::Extern::Memory(m)) => {
                 let (mem, ctx) = m.data_and_store_mut(&mut caller);
                 let ctx = get_cx(ctx);
-                (wiggle::wasmtime::WasmtimeGuestMemory::new(mem), ctx)
+                (wiggle::wasmtime::WasmtimeGuestMemory::new(mem).with_pointer_width(#width), ctx)
             }
             Some(wiggle::wasmtime_crate::Extern::SharedMemory(m)) => {
                 let ctx = get_cx(caller.data_mut());
-                (wiggle::wasmtime::WasmtimeGuestMemory::shared(m.data()), ctx)
+                (wiggle::wasmtime::WasmtimeGuestMemory::shared(m.data()).with_pointer_width(#width), ctx)
             }
             _ => wiggle::anyhow::bail!("missing required memory export"),
         };
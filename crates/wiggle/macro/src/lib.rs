@@ -45,6 +45,9 @@ use syn::parse_macro_input;
 ///       `errno => trappable AnErrorType`.
 /// * Optional: `async` takes a set of witx modules and functions which are
 ///   made Rust `async` functions in the module trait.
+/// * Optional: `pointer_sized` takes a list of `u32` witx typenames, e.g.
+///   `[size]`, which are as wide as the guest's pointers. Guests with a 64-bit
+///   memory pass and store them as 64-bit values.
 ///
 /// ## Example
 ///
@@ -158,6 +161,7 @@ pub fn from_witx(args: TokenStream) -> TokenStream {
         config.wasmtime,
         &config.tracing,
         config.mutable,
+        &config.pointer_sized,
     )
     .expect("validating codegen settings");
 
@@ -200,6 +204,7 @@ pub fn wasmtime_integration(args: TokenStream) -> TokenStream {
         true,
         &config.c.tracing,
         config.c.mutable,
+        &config.c.pointer_sized,
     )
     .expect("validating codegen settings");
 
@@ -1,4 +1,4 @@
-use crate::{GuestError, GuestPtr};
+use crate::{GuestError, GuestPtr, PointerWidth};
 use std::mem;
 use std::sync::atomic::{
     AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicU16, AtomicU32, AtomicU64, AtomicU8, Ordering,
@@ -22,13 +22,30 @@ pub trait GuestErrorType {
 /// the host representation of a type, if necessary. It also allows for
 /// validation when reading/writing.
 pub trait GuestType<'a>: Sized {
-    /// Returns the size, in bytes, of this type in the guest memory.
+    /// Returns the size, in bytes, of this type in the memory of a guest with
+    /// 32-bit pointers.
     fn guest_size() -> u32;
 
     /// Returns the required alignment of this type, in bytes, for both guest
-    /// and host memory.
+    /// and host memory, in guests with 32-bit pointers.
     fn guest_align() -> usize;
 
+    /// Returns the size, in bytes, of this type in the memory of a guest whose
+    /// pointers are `width` wide. This only differs from
+    /// [`GuestType::guest_size`] for types containing pointers, lists or witx
+    /// `usize`s.
+    fn guest_size_for(width: PointerWidth) -> u32 {
+        let _ = width;
+        Self::guest_size()
+    }
+
+    /// Returns the required alignment of this type, in bytes, in guests whose
+    /// pointers are `width` wide.
+    fn guest_align_for(width: PointerWidth) -> usize {
+        let _ = width;
+        Self::guest_align()
+    }
+
     /// Reads this value from the provided `ptr`.
     ///
     /// Must internally perform any safety checks necessary and is allowed to
@@ -185,7 +202,8 @@ float_primitives! {
     [f32, u32, AtomicU32], [f64, u64, AtomicU64],
 }
 
-// Support pointers-to-pointers where pointers are always 32-bits in wasm land
+// Support pointers-to-pointers, which are stored with the width of the
+// guest's pointers.
 impl<'a, T> GuestType<'a> for GuestPtr<'a, T> {
     #[inline]
     fn guest_size() -> u32 {
@@ -197,17 +215,43 @@ impl<'a, T> GuestType<'a> for GuestPtr<'a, T> {
         u32::guest_align()
     }
 
+    #[inline]
+    fn guest_size_for(width: PointerWidth) -> u32 {
+        match width {
+            PointerWidth::Bits32 => u32::guest_size(),
+            PointerWidth::Bits64 => u64::guest_size(),
+        }
+    }
+
+    #[inline]
+    fn guest_align_for(width: PointerWidth) -> usize {
+        match width {
+            PointerWidth::Bits32 => u32::guest_align(),
+            PointerWidth::Bits64 => u64::guest_align(),
+        }
+    }
+
     fn read(ptr: &GuestPtr<'a, Self>) -> Result<Self, GuestError> {
-        let offset = ptr.cast::<u32>().read()?;
+        let offset = match ptr.mem().pointer_width() {
+            PointerWidth::Bits32 => u64::from(ptr.cast::<u32>().read()?),
+            PointerWidth::Bits64 => ptr.cast::<u64>().read()?,
+        };
         Ok(GuestPtr::new(ptr.mem(), offset))
     }
 
     fn write(ptr: &GuestPtr<'_, Self>, val: Self) -> Result<(), GuestError> {
-        ptr.cast::<u32>().write(val.offset())
+        match ptr.mem().pointer_width() {
+            PointerWidth::Bits32 => {
+                let offset = u32::try_from(val.offset()).map_err(|_| GuestError::PtrOverflow)?;
+                ptr.cast::<u32>().write(offset)
+            }
+            PointerWidth::Bits64 => ptr.cast::<u64>().write(val.offset()),
+        }
     }
 }
 
-// Support pointers-to-arrays where pointers are always 32-bits in wasm land
+// Support pointers-to-arrays, where both the pointer and the length are stored
+// with the width of the guest's pointers.
 impl<'a, T> GuestType<'a> for GuestPtr<'a, [T]>
 where
     T: GuestType<'a>,
@@ -222,16 +266,52 @@ where
         u32::guest_align()
     }
 
+    #[inline]
+    fn guest_size_for(width: PointerWidth) -> u32 {
+        match width {
+            PointerWidth::Bits32 => u32::guest_size() * 2,
+            PointerWidth::Bits64 => u64::guest_size() * 2,
+        }
+    }
+
+    #[inline]
+    fn guest_align_for(width: PointerWidth) -> usize {
+        match width {
+            PointerWidth::Bits32 => u32::guest_align(),
+            PointerWidth::Bits64 => u64::guest_align(),
+        }
+    }
+
     fn read(ptr: &GuestPtr<'a, Self>) -> Result<Self, GuestError> {
-        let offset = ptr.cast::<u32>().read()?;
-        let len = ptr.cast::<u32>().add(1)?.read()?;
+        let (offset, len) = match ptr.mem().pointer_width() {
+            PointerWidth::Bits32 => {
+                let offset = ptr.cast::<u32>().read()?;
+                let len = ptr.cast::<u32>().add(1)?.read()?;
+                (u64::from(offset), len)
+            }
+            PointerWidth::Bits64 => {
+                let offset = ptr.cast::<u64>().read()?;
+                let len = ptr.cast::<u64>().add(1)?.read()?;
+                (offset, u32::try_from(len)?)
+            }
+        };
         Ok(GuestPtr::new(ptr.mem(), offset).as_array(len))
     }
 
     fn write(ptr: &GuestPtr<'_, Self>, val: Self) -> Result<(), GuestError> {
         let (offs, len) = val.offset();
-        let len_ptr = ptr.cast::<u32>().add(1)?;
-        ptr.cast::<u32>().write(offs)?;
-        len_ptr.write(len)
+        match ptr.mem().pointer_width() {
+            PointerWidth::Bits32 => {
+                let len_ptr = ptr.cast::<u32>().add(1)?;
+                let offs = u32::try_from(offs).map_err(|_| GuestError::PtrOverflow)?;
+                ptr.cast::<u32>().write(offs)?;
+                len_ptr.write(len)
+            }
+            PointerWidth::Bits64 => {
+                let len_ptr = ptr.cast::<u64>().add(1)?;
+                ptr.cast::<u64>().write(offs)?;
+                len_ptr.write(u64::from(len))
+            }
+        }
     }
 }
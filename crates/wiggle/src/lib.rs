@@ -103,8 +103,8 @@ pub unsafe trait GuestMemory: Send + Sync {
 
     /// Convenience method for creating a `GuestPtr` at a particular offset.
     ///
-    /// Note that `T` can be almost any type, and typically `offset` is a `u32`.
-    /// The exception is slices and strings, in which case `offset` is a `(u32,
+    /// Note that `T` can be almost any type, and typically `offset` is a `u64`.
+    /// The exception is slices and strings, in which case `offset` is a `(u64,
     /// u32)` of `(offset, length)`.
     fn ptr<'a, T>(&'a self, offset: T::Pointer) -> GuestPtr<'a, T>
     where
@@ -151,6 +151,12 @@ pub unsafe trait GuestMemory: Send + Sync {
     fn is_shared_memory(&self) -> bool {
         false
     }
+
+    /// Returns the width of the pointers the guest stores in this memory,
+    /// which also decides the layout of list lengths and witx `usize`s.
+    fn pointer_width(&self) -> PointerWidth {
+        PointerWidth::Bits32
+    }
 }
 
 /// Validates a guest-relative pointer given various attributes, and returns
@@ -168,7 +174,7 @@ pub unsafe trait GuestMemory: Send + Sync {
 /// later on.
 fn validate_size_align<'a, T: GuestTypeTransparent<'a>>(
     mem: &'a dyn GuestMemory,
-    offset: u32,
+    offset: u64,
     len: u32,
 ) -> Result<(&[UnsafeCell<T>], Region), GuestError> {
     let base = mem.base();
@@ -233,6 +239,9 @@ unsafe impl<'a, T: ?Sized + GuestMemory> GuestMemory for &'a T {
     fn shared_unborrow(&self, h: BorrowHandle) {
         T::shared_unborrow(self, h)
     }
+    fn pointer_width(&self) -> PointerWidth {
+        T::pointer_width(self)
+    }
 }
 
 unsafe impl<'a, T: ?Sized + GuestMemory> GuestMemory for &'a mut T {
@@ -260,6 +269,9 @@ unsafe impl<'a, T: ?Sized + GuestMemory> GuestMemory for &'a mut T {
     fn shared_unborrow(&self, h: BorrowHandle) {
         T::shared_unborrow(self, h)
     }
+    fn pointer_width(&self) -> PointerWidth {
+        T::pointer_width(self)
+    }
 }
 
 unsafe impl<T: ?Sized + GuestMemory> GuestMemory for Box<T> {
@@ -287,6 +299,9 @@ unsafe impl<T: ?Sized + GuestMemory> GuestMemory for Box<T> {
     fn shared_unborrow(&self, h: BorrowHandle) {
         T::shared_unborrow(self, h)
     }
+    fn pointer_width(&self) -> PointerWidth {
+        T::pointer_width(self)
+    }
 }
 
 unsafe impl<T: ?Sized + GuestMemory> GuestMemory for Arc<T> {
@@ -314,6 +329,9 @@ unsafe impl<T: ?Sized + GuestMemory> GuestMemory for Arc<T> {
     fn shared_unborrow(&self, h: BorrowHandle) {
         T::shared_unborrow(self, h)
     }
+    fn pointer_width(&self) -> PointerWidth {
+        T::pointer_width(self)
+    }
 }
 
 /// A *guest* pointer into host memory.
@@ -354,8 +372,9 @@ unsafe impl<T: ?Sized + GuestMemory> GuestMemory for Arc<T> {
 /// construct a `T` from a `U`.
 ///
 /// For example `GuestPtr<GuestPtr<T>>` is a valid type, but this is actually
-/// more equivalent to `GuestPtr<u32>` because guest pointers are always
-/// 32-bits. That being said you can create a `GuestPtr<T>` from a `u32`.
+/// more equivalent to `GuestPtr<u32>` because pointers stored in guest memory
+/// are always 32-bits, following the witx layout of records. That being said
+/// you can create a `GuestPtr<T>` from a `u32`.
 ///
 /// Additionally `GuestPtr<MyEnum>` will actually delegate, typically, to and
 /// implementation which loads the underlying data as `GuestPtr<u8>` (or
@@ -375,16 +394,16 @@ impl<'a, T: ?Sized + Pointee> GuestPtr<'a, T> {
     /// Creates a new `GuestPtr` from the given `mem` and `pointer` values.
     ///
     /// Note that for sized types like `u32`, `GuestPtr<T>`, etc, the `pointer`
-    /// value is a `u32` offset into guest memory. For slices and strings,
-    /// `pointer` is a `(u32, u32)` offset/length pair.
+    /// value is a `u64` offset into guest memory. For slices and strings,
+    /// `pointer` is a `(u64, u32)` offset/length pair.
     pub fn new(mem: &'a (dyn GuestMemory + 'a), pointer: T::Pointer) -> GuestPtr<'a, T> {
         GuestPtr { mem, pointer }
     }
 
     /// Returns the offset of this pointer in guest memory.
     ///
-    /// Note that for sized types this returns a `u32`, but for slices and
-    /// strings it returns a `(u32, u32)` pointer/length pair.
+    /// Note that for sized types this returns a `u64`, but for slices and
+    /// strings it returns a `(u64, u32)` pointer/length pair.
     pub fn offset(&self) -> T::Pointer {
         self.pointer
     }
@@ -402,7 +421,7 @@ impl<'a, T: ?Sized + Pointee> GuestPtr<'a, T> {
     /// etc of the returned pointer.
     pub fn cast<U>(&self) -> GuestPtr<'a, U>
     where
-        T: Pointee<Pointer = u32>,
+        T: Pointee<Pointer = u64>,
     {
         GuestPtr::new(self.mem, self.pointer)
     }
@@ -462,11 +481,11 @@ impl<'a, T: ?Sized + Pointee> GuestPtr<'a, T> {
     /// space.
     pub fn add(&self, amt: u32) -> Result<GuestPtr<'a, T>, GuestError>
     where
-        T: GuestType<'a> + Pointee<Pointer = u32>,
+        T: GuestType<'a> + Pointee<Pointer = u64>,
     {
         let offset = amt
-            .checked_mul(T::guest_size())
-            .and_then(|o| self.pointer.checked_add(o));
+            .checked_mul(T::guest_size_for(self.mem.pointer_width()))
+            .and_then(|o| self.pointer.checked_add(u64::from(o)));
         let offset = match offset {
             Some(o) => o,
             None => return Err(GuestError::PtrOverflow),
@@ -478,7 +497,7 @@ impl<'a, T: ?Sized + Pointee> GuestPtr<'a, T> {
     /// base.
     pub fn as_array(&self, elems: u32) -> GuestPtr<'a, [T]>
     where
-        T: GuestType<'a> + Pointee<Pointer = u32>,
+        T: GuestType<'a> + Pointee<Pointer = u64>,
     {
         GuestPtr::new(self.mem, (self.pointer, elems))
     }
@@ -494,7 +513,7 @@ impl<'a, T> GuestPtr<'a, [T]> {
     /// array.
     ///
     /// This is similar to `<[T]>::as_ptr()`
-    pub fn offset_base(&self) -> u32 {
+    pub fn offset_base(&self) -> u64 {
         self.pointer.0
     }
 
@@ -694,7 +713,7 @@ impl<'a, T> GuestPtr<'a, [T]> {
 impl<'a> GuestPtr<'a, str> {
     /// For strings, returns the relative pointer to the base of the string
     /// allocation.
-    pub fn offset_base(&self) -> u32 {
+    pub fn offset_base(&self) -> u64 {
         self.pointer.0
     }
 
@@ -777,6 +796,26 @@ impl<'a> GuestPtr<'a, [u8]> {
     }
 }
 
+impl<'a> GuestPtr<'a, u32> {
+    /// Reads a witx `usize` from this pointer. Guests with 64-bit pointers
+    /// store these as `u64`s, which must still fit in a `u32`.
+    pub fn read_usize(&self) -> Result<u32, GuestError> {
+        match self.mem.pointer_width() {
+            PointerWidth::Bits32 => self.read(),
+            PointerWidth::Bits64 => Ok(u32::try_from(self.cast::<u64>().read()?)?),
+        }
+    }
+
+    /// Writes a witx `usize` to this pointer, as a `u64` for guests with
+    /// 64-bit pointers.
+    pub fn write_usize(&self, val: u32) -> Result<(), GuestError> {
+        match self.mem.pointer_width() {
+            PointerWidth::Bits32 => self.write(val),
+            PointerWidth::Bits64 => self.cast::<u64>().write(u64::from(val)),
+        }
+    }
+}
+
 impl<T: ?Sized + Pointee> Clone for GuestPtr<'_, T> {
     fn clone(&self) -> Self {
         *self
@@ -1127,6 +1166,71 @@ mod private {
     impl<T> Sealed for T {}
     impl<T> Sealed for [T] {}
     impl Sealed for str {}
+
+    pub trait SealedAddr {}
+    impl SealedAddr for i32 {}
+    impl SealedAddr for i64 {}
+}
+
+/// A pointer argument as the guest passes it to a host function: an `i32`
+/// for a 32-bit memory, or an `i64` for a 64-bit one from the memory64
+/// proposal.
+///
+/// Generated bindings take their pointer arguments as any `GuestAddr`, so the
+/// same bindings work for guests of either pointer width.
+pub trait GuestAddr: Copy + Send + 'static + private::SealedAddr {
+    /// Returns the offset into guest memory this pointer refers to.
+    fn to_offset(self) -> u64;
+
+    /// Returns this argument as the length of a list or string, which is
+    /// passed with the same width as pointers.
+    fn to_len(self) -> Result<u32, GuestError>;
+}
+
+impl GuestAddr for i32 {
+    #[inline]
+    fn to_offset(self) -> u64 {
+        u64::from(self as u32)
+    }
+
+    #[inline]
+    fn to_len(self) -> Result<u32, GuestError> {
+        Ok(self as u32)
+    }
+}
+
+impl GuestAddr for i64 {
+    #[inline]
+    fn to_offset(self) -> u64 {
+        self as u64
+    }
+
+    #[inline]
+    fn to_len(self) -> Result<u32, GuestError> {
+        Ok(u32::try_from(self as u64)?)
+    }
+}
+
+/// The width of the pointers a guest passes to host functions, which follows
+/// the index type of the guest's memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PointerWidth {
+    /// Pointers are `i32`s into a 32-bit memory.
+    Bits32,
+    /// Pointers are `i64`s into a 64-bit memory.
+    Bits64,
+}
+
+impl PointerWidth {
+    /// Returns the pointer width of `module`, from the type of the `memory`
+    /// it exports. Modules which don't export a 64-bit memory use 32-bit
+    /// pointers.
+    pub fn of_module(module: &::wasmtime::Module) -> PointerWidth {
+        match module.get_export("memory") {
+            Some(::wasmtime::ExternType::Memory(m)) if m.is_64() => PointerWidth::Bits64,
+            _ => PointerWidth::Bits32,
+        }
+    }
 }
 
 /// Types that can be pointed to by `GuestPtr<T>`.
@@ -1141,21 +1245,21 @@ pub trait Pointee: private::Sealed {
 }
 
 impl<T> Pointee for T {
-    type Pointer = u32;
+    type Pointer = u64;
     fn debug(pointer: Self::Pointer, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "*guest {:#x}", pointer)
     }
 }
 
 impl<T> Pointee for [T] {
-    type Pointer = (u32, u32);
+    type Pointer = (u64, u32);
     fn debug(pointer: Self::Pointer, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "*guest {:#x}/{}", pointer.0, pointer.1)
     }
 }
 
 impl Pointee for str {
-    type Pointer = (u32, u32);
+    type Pointer = (u64, u32);
     fn debug(pointer: Self::Pointer, f: &mut fmt::Formatter) -> fmt::Result {
         <[u8]>::debug(pointer, f)
     }
@@ -1,12 +1,12 @@
 /// Represents a contiguous region in memory.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Region {
-    pub start: u32,
+    pub start: u64,
     pub len: u32,
 }
 
 impl Region {
-    pub fn new(start: u32, len: u32) -> Self {
+    pub fn new(start: u64, len: u32) -> Self {
         Self { start, len }
     }
 
@@ -17,11 +17,11 @@ impl Region {
             return false;
         }
 
-        let self_start = self.start as u64;
-        let self_end = self_start + (self.len - 1) as u64;
+        let self_start = u128::from(self.start);
+        let self_end = self_start + u128::from(self.len - 1);
 
-        let rhs_start = rhs.start as u64;
-        let rhs_end = rhs_start + (rhs.len - 1) as u64;
+        let rhs_start = u128::from(rhs.start);
+        let rhs_end = rhs_start + u128::from(rhs.len - 1);
 
         if self_start <= rhs_start {
             self_end >= rhs_start
@@ -83,4 +83,15 @@ mod test {
         let r2 = Region::new(0, 10);
         assert!(r1.overlaps(r2));
     }
+
+    #[test]
+    fn above_4gib() {
+        let r1 = Region::new(u64::MAX - 4, 5);
+        let r2 = Region::new(u64::MAX, 1);
+        assert!(r1.overlaps(r2));
+
+        let r1 = Region::new(1 << 32, 10);
+        let r2 = Region::new(0, 10);
+        assert!(!r1.overlaps(r2));
+    }
 }
@@ -1,5 +1,5 @@
 use crate::borrow::BorrowChecker;
-use crate::{BorrowHandle, GuestError, GuestMemory, Region};
+use crate::{BorrowHandle, GuestError, GuestMemory, PointerWidth, Region};
 use std::cell::UnsafeCell;
 
 /// Lightweight `wasmtime::Memory` wrapper so we can implement the
@@ -8,6 +8,7 @@ pub struct WasmtimeGuestMemory<'a> {
     mem: &'a [UnsafeCell<u8>],
     bc: BorrowChecker,
     shared: bool,
+    width: PointerWidth,
 }
 
 // These need to be reapplied due to the usage of `UnsafeCell` internally.
@@ -38,6 +39,7 @@ impl<'a> WasmtimeGuestMemory<'a> {
             // https://github.com/bytecodealliance/wasmtime/issues/1917
             bc: BorrowChecker::new(),
             shared: false,
+            width: PointerWidth::Bits32,
         }
     }
 
@@ -46,8 +48,16 @@ impl<'a> WasmtimeGuestMemory<'a> {
             mem,
             bc: BorrowChecker::new(),
             shared: true,
+            width: PointerWidth::Bits32,
         }
     }
+
+    /// Sets the width of the pointers the guest stores in this memory, which
+    /// defaults to [`PointerWidth::Bits32`].
+    pub fn with_pointer_width(mut self, width: PointerWidth) -> Self {
+        self.width = width;
+        self
+    }
 }
 
 unsafe impl GuestMemory for WasmtimeGuestMemory<'_> {
@@ -98,4 +108,8 @@ unsafe impl GuestMemory for WasmtimeGuestMemory<'_> {
     fn is_shared_memory(&self) -> bool {
         self.shared
     }
+    #[inline]
+    fn pointer_width(&self) -> PointerWidth {
+        self.width
+    }
 }
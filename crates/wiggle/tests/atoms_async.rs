@@ -83,7 +83,7 @@ impl DoubleIntExercise {
         .unwrap();
 
         let return_val = host_memory
-            .ptr::<types::AliasToFloat>(self.return_loc.ptr)
+            .ptr::<types::AliasToFloat>(u64::from(self.return_loc.ptr))
             .read()
             .expect("failed to read return");
         assert_eq!(e, types::Errno::Ok as i32, "errno");
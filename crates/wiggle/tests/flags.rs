@@ -67,7 +67,7 @@ impl ConfigureCarExercise {
 
         // Populate input ptr
         host_memory
-            .ptr(self.other_config_by_ptr.ptr)
+            .ptr(u64::from(self.other_config_by_ptr.ptr))
             .write(self.other_config)
             .expect("deref ptr mut to CarConfig");
 
@@ -82,7 +82,7 @@ impl ConfigureCarExercise {
         assert_eq!(res, types::Errno::Ok as i32, "configure car errno");
 
         let res_config = host_memory
-            .ptr::<types::CarConfig>(self.return_ptr_loc.ptr)
+            .ptr::<types::CarConfig>(u64::from(self.return_ptr_loc.ptr))
             .read()
             .expect("deref to CarConfig value");
 
@@ -40,7 +40,7 @@ impl HandleExercise {
         assert_eq!(e, types::Errno::Ok as i32, "fd_create error");
 
         let h_got: u32 = host_memory
-            .ptr(self.return_loc.ptr)
+            .ptr(u64::from(self.return_loc.ptr))
             .read()
             .expect("return ref_mut");
 
@@ -56,7 +56,7 @@ impl CookieCutterExercise {
         assert_eq!(res, types::Errno::Ok as i32, "cookie cutter errno");
 
         let is_cookie_start = host_memory
-            .ptr::<types::Bool>(self.return_ptr_loc.ptr)
+            .ptr::<types::Bool>(u64::from(self.return_ptr_loc.ptr))
             .read()
             .expect("deref to Bool value");
 
@@ -80,18 +80,20 @@ impl ReduceExcusesExcercise {
         // Populate memory with pointers to generated Excuse values
         for (&excuse, ptr) in self.excuse_values.iter().zip(self.excuse_ptr_locs.iter()) {
             host_memory
-                .ptr(ptr.ptr)
+                .ptr(u64::from(ptr.ptr))
                 .write(excuse)
                 .expect("deref ptr mut to Excuse value");
         }
 
         // Populate the array with pointers to generated Excuse values
         {
-            let array: GuestPtr<'_, [GuestPtr<types::Excuse>]> =
-                host_memory.ptr((self.array_ptr_loc.ptr, self.excuse_ptr_locs.len() as u32));
+            let array: GuestPtr<'_, [GuestPtr<types::Excuse>]> = host_memory.ptr((
+                u64::from(self.array_ptr_loc.ptr),
+                self.excuse_ptr_locs.len() as u32,
+            ));
             for (slot, ptr) in array.iter().zip(&self.excuse_ptr_locs) {
                 let slot = slot.expect("array should be in bounds");
-                slot.write(host_memory.ptr(ptr.ptr))
+                slot.write(host_memory.ptr(u64::from(ptr.ptr)))
                     .expect("should succeed in writing array");
             }
         }
@@ -112,7 +114,7 @@ impl ReduceExcusesExcercise {
             .last()
             .expect("generated vec of excuses should be non-empty");
         let given: types::Excuse = host_memory
-            .ptr(self.return_ptr_loc.ptr)
+            .ptr(u64::from(self.return_ptr_loc.ptr))
             .read()
             .expect("deref ptr to returned value");
         assert_eq!(expected, given, "reduce excuses return val");
@@ -168,12 +170,12 @@ impl PopulateExcusesExcercise {
 
         // Populate array with valid pointers to Excuse type in memory
         let ptr = host_memory.ptr::<[GuestPtr<'_, types::Excuse>]>((
-            self.array_ptr_loc.ptr,
+            u64::from(self.array_ptr_loc.ptr),
             self.elements.len() as u32,
         ));
         for (ptr, val) in ptr.iter().zip(&self.elements) {
             ptr.expect("should be valid pointer")
-                .write(host_memory.ptr(val.ptr))
+                .write(host_memory.ptr(u64::from(val.ptr)))
                 .expect("failed to write value");
         }
 
@@ -186,8 +188,10 @@ impl PopulateExcusesExcercise {
         .unwrap();
         assert_eq!(res, types::Errno::Ok as i32, "populate excuses errno");
 
-        let arr: GuestPtr<'_, [GuestPtr<'_, types::Excuse>]> =
-            host_memory.ptr((self.array_ptr_loc.ptr, self.elements.len() as u32));
+        let arr: GuestPtr<'_, [GuestPtr<'_, types::Excuse>]> = host_memory.ptr((
+            u64::from(self.array_ptr_loc.ptr),
+            self.elements.len() as u32,
+        ));
         for el in arr.iter() {
             let ptr_to_ptr = el
                 .expect("valid ptr to ptr")
@@ -294,8 +298,10 @@ impl SumElementsExercise {
         let host_memory = HostMemory::new();
 
         // Populate array
-        let ptr = host_memory
-            .ptr::<[types::PairInts]>((self.element_loc.ptr, self.elements.len() as u32));
+        let ptr = host_memory.ptr::<[types::PairInts]>((
+            u64::from(self.element_loc.ptr),
+            self.elements.len() as u32,
+        ));
         for (ptr, val) in ptr.iter().zip(&self.elements) {
             ptr.expect("should be valid pointer")
                 .write(val.clone())
@@ -312,7 +318,7 @@ impl SumElementsExercise {
         )
         .unwrap();
         assert_eq!(res, types::Errno::Ok as i32, "sum_of_element errno");
-        let result_ptr = host_memory.ptr::<i32>(self.return_loc.ptr);
+        let result_ptr = host_memory.ptr::<i32>(u64::from(self.return_loc.ptr));
         let result = result_ptr.read().expect("read result");
 
         let e = self
@@ -353,7 +359,7 @@ impl SumElementsExercise {
                 types::Errno::Ok as i32,
                 "expected ok sum_of_elements errno"
             );
-            let result_ptr = host_memory.ptr::<i32>(self.return_loc.ptr);
+            let result_ptr = host_memory.ptr::<i32>(u64::from(self.return_loc.ptr));
             let result = result_ptr.read().expect("read result");
 
             let mut expected_sum: i32 = 0;
@@ -106,11 +106,11 @@ impl SumOfPairExercise {
         let host_memory = HostMemory::new();
 
         host_memory
-            .ptr(self.input_loc.ptr)
+            .ptr(u64::from(self.input_loc.ptr))
             .write(self.input.first)
             .expect("input ref_mut");
         host_memory
-            .ptr(self.input_loc.ptr + 4)
+            .ptr(u64::from(self.input_loc.ptr + 4))
             .write(self.input.second)
             .expect("input ref_mut");
         let sum_err = records::sum_of_pair(
@@ -124,7 +124,7 @@ impl SumOfPairExercise {
         assert_eq!(sum_err, types::Errno::Ok as i32, "sum errno");
 
         let return_val: i64 = host_memory
-            .ptr(self.return_loc.ptr)
+            .ptr(u64::from(self.return_loc.ptr))
             .read()
             .expect("return ref");
 
@@ -195,20 +195,20 @@ impl SumPairPtrsExercise {
         let host_memory = HostMemory::new();
 
         host_memory
-            .ptr(self.input_first_loc.ptr)
+            .ptr(u64::from(self.input_first_loc.ptr))
             .write(self.input_first)
             .expect("input_first ref");
         host_memory
-            .ptr(self.input_second_loc.ptr)
+            .ptr(u64::from(self.input_second_loc.ptr))
             .write(self.input_second)
             .expect("input_second ref");
 
         host_memory
-            .ptr(self.input_struct_loc.ptr)
+            .ptr(u64::from(self.input_struct_loc.ptr))
             .write(self.input_first_loc.ptr)
             .expect("input_struct ref");
         host_memory
-            .ptr(self.input_struct_loc.ptr + 4)
+            .ptr(u64::from(self.input_struct_loc.ptr + 4))
             .write(self.input_second_loc.ptr)
             .expect("input_struct ref");
 
@@ -223,7 +223,7 @@ impl SumPairPtrsExercise {
         assert_eq!(res, types::Errno::Ok as i32, "sum of pair of ptrs errno");
 
         let doubled: i64 = host_memory
-            .ptr(self.return_loc.ptr)
+            .ptr(u64::from(self.return_loc.ptr))
             .read()
             .expect("return ref");
 
@@ -280,15 +280,15 @@ impl SumIntAndPtrExercise {
         let host_memory = HostMemory::new();
 
         host_memory
-            .ptr(self.input_first_loc.ptr)
+            .ptr(u64::from(self.input_first_loc.ptr))
             .write(self.input_first)
             .expect("input_first ref");
         host_memory
-            .ptr(self.input_struct_loc.ptr)
+            .ptr(u64::from(self.input_struct_loc.ptr))
             .write(self.input_first_loc.ptr)
             .expect("input_struct ref");
         host_memory
-            .ptr(self.input_struct_loc.ptr + 4)
+            .ptr(u64::from(self.input_struct_loc.ptr + 4))
             .write(self.input_second)
             .expect("input_struct ref");
 
@@ -303,7 +303,7 @@ impl SumIntAndPtrExercise {
         assert_eq!(res, types::Errno::Ok as i32, "sum of int and ptr errno");
 
         let doubled: i64 = host_memory
-            .ptr(self.return_loc.ptr)
+            .ptr(u64::from(self.return_loc.ptr))
             .read()
             .expect("return ref");
 
@@ -343,7 +343,7 @@ impl ReturnPairInts {
         assert_eq!(err, types::Errno::Ok as i32, "return struct errno");
 
         let return_struct: types::PairInts = host_memory
-            .ptr(self.return_loc.ptr)
+            .ptr(u64::from(self.return_loc.ptr))
             .read()
             .expect("return ref");
 
@@ -404,11 +404,11 @@ impl ReturnPairPtrsExercise {
         let host_memory = HostMemory::new();
 
         host_memory
-            .ptr(self.input_first_loc.ptr)
+            .ptr(u64::from(self.input_first_loc.ptr))
             .write(self.input_first)
             .expect("input_first ref");
         host_memory
-            .ptr(self.input_second_loc.ptr)
+            .ptr(u64::from(self.input_second_loc.ptr))
             .write(self.input_second)
             .expect("input_second ref");
 
@@ -424,7 +424,7 @@ impl ReturnPairPtrsExercise {
         assert_eq!(res, types::Errno::Ok as i32, "return pair of ptrs errno");
 
         let ptr_pair_int_ptrs: types::PairIntPtrs<'_> = host_memory
-            .ptr(self.return_loc.ptr)
+            .ptr(u64::from(self.return_loc.ptr))
             .read()
             .expect("failed to read return location");
         let ret_first_ptr = ptr_pair_int_ptrs.first;
@@ -505,18 +505,18 @@ impl SumArrayExercise {
         for (ix, val) in self.inputs.iter().enumerate() {
             let ix = ix as u32;
             host_memory
-                .ptr(self.input_array_loc.ptr + ix)
+                .ptr(u64::from(self.input_array_loc.ptr + ix))
                 .write(*val)
                 .expect("write val to array memory");
         }
 
         // Write struct that contains the array
         host_memory
-            .ptr(self.input_struct_loc.ptr)
+            .ptr(u64::from(self.input_struct_loc.ptr))
             .write(self.input_array_loc.ptr)
             .expect("write ptr to struct memory");
         host_memory
-            .ptr(self.input_struct_loc.ptr + 4)
+            .ptr(u64::from(self.input_struct_loc.ptr + 4))
             .write(self.inputs.len() as u32)
             .expect("write len to struct memory");
 
@@ -537,7 +537,7 @@ impl SumArrayExercise {
 
         // Wiggle stored output value in memory as u16
         let given: u16 = host_memory
-            .ptr(self.output_loc.ptr)
+            .ptr(u64::from(self.output_loc.ptr))
             .read()
             .expect("deref ptr to returned value");
 
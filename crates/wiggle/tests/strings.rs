@@ -85,7 +85,10 @@ impl HelloStringExercise {
         let host_memory = HostMemory::new();
 
         // Populate string in guest's memory
-        let ptr = host_memory.ptr::<str>((self.string_ptr_loc.ptr, self.test_word.len() as u32));
+        let ptr = host_memory.ptr::<str>((
+            u64::from(self.string_ptr_loc.ptr),
+            self.test_word.len() as u32,
+        ));
         for (slot, byte) in ptr.as_bytes().iter().zip(self.test_word.bytes()) {
             slot.expect("should be valid pointer")
                 .write(byte)
@@ -103,7 +106,7 @@ impl HelloStringExercise {
         assert_eq!(res, types::Errno::Ok as i32, "hello string errno");
 
         let given = host_memory
-            .ptr::<u32>(self.return_ptr_loc.ptr)
+            .ptr::<u32>(u64::from(self.return_ptr_loc.ptr))
             .read()
             .expect("deref ptr to return value");
         assert_eq!(self.test_word.len() as u32, given);
@@ -198,7 +201,7 @@ impl MultiStringExercise {
         let host_memory = HostMemory::new();
 
         let write_string = |val: &str, loc: MemArea| {
-            let ptr = host_memory.ptr::<str>((loc.ptr, val.len() as u32));
+            let ptr = host_memory.ptr::<str>((u64::from(loc.ptr), val.len() as u32));
             for (slot, byte) in ptr.as_bytes().iter().zip(val.bytes()) {
                 slot.expect("should be valid pointer")
                     .write(byte)
@@ -225,7 +228,7 @@ impl MultiStringExercise {
         assert_eq!(res, types::Errno::Ok as i32, "multi string errno");
 
         let given = host_memory
-            .ptr::<u32>(self.return_ptr_loc.ptr)
+            .ptr::<u32>(u64::from(self.return_ptr_loc.ptr))
             .read()
             .expect("deref ptr to return value");
         assert_eq!((self.a.len() + self.b.len() + self.c.len()) as u32, given);
@@ -278,7 +281,7 @@ impl OverlappingStringExercise {
         let host_memory = HostMemory::new();
 
         let write_string = |val: &str, loc: MemArea| {
-            let ptr = host_memory.ptr::<str>((loc.ptr, val.len() as u32));
+            let ptr = host_memory.ptr::<str>((u64::from(loc.ptr), val.len() as u32));
             for (slot, byte) in ptr.as_bytes().iter().zip(val.bytes()) {
                 slot.expect("should be valid pointer")
                     .write(byte)
@@ -304,7 +307,7 @@ impl OverlappingStringExercise {
         assert_eq!(res, types::Errno::Ok as i32, "multi string errno");
 
         let given = host_memory
-            .ptr::<u32>(self.return_ptr_loc.ptr)
+            .ptr::<u32>(u64::from(self.return_ptr_loc.ptr))
             .read()
             .expect("deref ptr to return value");
         assert_eq!(
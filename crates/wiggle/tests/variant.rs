@@ -113,18 +113,18 @@ impl GetTagExercise {
 
         let discriminant = reason_tag(&self.input) as u8;
         host_memory
-            .ptr(self.input_loc.ptr)
+            .ptr(u64::from(self.input_loc.ptr))
             .write(discriminant)
             .expect("input discriminant ptr");
         match self.input {
             types::Reason::DogAte(f) => {
                 host_memory
-                    .ptr(self.input_loc.ptr + 4)
+                    .ptr(u64::from(self.input_loc.ptr + 4))
                     .write(f)
                     .expect("input contents ref_mut");
             }
             types::Reason::Traffic(v) => host_memory
-                .ptr(self.input_loc.ptr + 4)
+                .ptr(u64::from(self.input_loc.ptr + 4))
                 .write(v)
                 .expect("input contents ref_mut"),
             types::Reason::Sleeping => {} // Do nothing
@@ -140,7 +140,7 @@ impl GetTagExercise {
         assert_eq!(e, types::Errno::Ok as i32, "get_tag errno");
 
         let return_val: types::Excuse = host_memory
-            .ptr(self.return_loc.ptr)
+            .ptr(u64::from(self.return_loc.ptr))
             .read()
             .expect("return ref");
 
@@ -191,24 +191,24 @@ impl ReasonMultExercise {
 
         let discriminant = reason_tag(&self.input) as u8;
         host_memory
-            .ptr(self.input_loc.ptr)
+            .ptr(u64::from(self.input_loc.ptr))
             .write(discriminant)
             .expect("input discriminant ref_mut");
         host_memory
-            .ptr(self.input_loc.ptr + 4)
+            .ptr(u64::from(self.input_loc.ptr + 4))
             .write(self.input_pointee_loc.ptr)
             .expect("input pointer ref_mut");
 
         match self.input {
             types::Reason::DogAte(f) => {
                 host_memory
-                    .ptr(self.input_pointee_loc.ptr)
+                    .ptr(u64::from(self.input_pointee_loc.ptr))
                     .write(f)
                     .expect("input contents ref_mut");
             }
             types::Reason::Traffic(v) => {
                 host_memory
-                    .ptr(self.input_pointee_loc.ptr)
+                    .ptr(u64::from(self.input_pointee_loc.ptr))
                     .write(v)
                     .expect("input contents ref_mut");
             }
@@ -227,7 +227,7 @@ impl ReasonMultExercise {
         match self.input {
             types::Reason::DogAte(f) => {
                 let f_result: f32 = host_memory
-                    .ptr(self.input_pointee_loc.ptr)
+                    .ptr(u64::from(self.input_pointee_loc.ptr))
                     .read()
                     .expect("input contents ref_mut");
                 assert_eq!(
@@ -238,7 +238,7 @@ impl ReasonMultExercise {
             }
             types::Reason::Traffic(v) => {
                 let v_result: i32 = host_memory
-                    .ptr(self.input_pointee_loc.ptr)
+                    .ptr(u64::from(self.input_pointee_loc.ptr))
                     .read()
                     .expect("input contents ref_mut");
                 assert_eq!(
@@ -0,0 +1,133 @@
+use wasmtime::{Config, Engine, Linker, Module, Store, Val};
+
+wiggle::from_witx!({
+    witx: ["$CARGO_MANIFEST_DIR/tests/atoms.witx"],
+});
+
+pub struct Ctx;
+impl wiggle::GuestErrorType for types::Errno {
+    fn success() -> Self {
+        types::Errno::Ok
+    }
+}
+
+impl atoms::Atoms for Ctx {
+    fn int_float_args(&mut self, an_int: u32, an_float: f32) -> Result<(), types::Errno> {
+        println!("INT FLOAT ARGS: {} {}", an_int, an_float);
+        Ok(())
+    }
+    fn double_int_return_float(
+        &mut self,
+        an_int: u32,
+    ) -> Result<types::AliasToFloat, types::Errno> {
+        Ok((an_int as f32) * 2.0)
+    }
+}
+
+#[test]
+fn test_memory64_host_func() {
+    let engine = engine();
+    let shim_mod = shim_module(&engine);
+    let width = wiggle::PointerWidth::of_module(&shim_mod);
+    assert_eq!(width, wiggle::PointerWidth::Bits64);
+
+    let mut linker = Linker::new(&engine);
+    atoms::add_to_linker_with_pointer_width(&mut linker, width, |cx| cx).unwrap();
+    let mut store = Store::new(&engine, Ctx);
+    let shim_inst = linker.instantiate(&mut store, &shim_mod).unwrap();
+
+    let input: i32 = 123;
+    let result_location: i64 = 8;
+
+    let mut results = [Val::I32(0)];
+    shim_inst
+        .get_func(&mut store, "double_int_return_float_shim")
+        .unwrap()
+        .call(
+            &mut store,
+            &[input.into(), result_location.into()],
+            &mut results,
+        )
+        .unwrap();
+
+    assert_eq!(
+        results[0].unwrap_i32(),
+        types::Errno::Ok as i32,
+        "double_int_return_float errno"
+    );
+
+    let mem = shim_inst.get_memory(&mut store, "memory").unwrap();
+    let mut result_bytes: [u8; 4] = [0, 0, 0, 0];
+    mem.read(&store, result_location as usize, &mut result_bytes)
+        .unwrap();
+    let result = f32::from_le_bytes(result_bytes);
+    assert_eq!((input * 2) as f32, result);
+}
+
+#[test]
+fn test_memory64_out_of_bounds() {
+    let engine = engine();
+    let shim_mod = shim_module(&engine);
+
+    let mut linker = Linker::new(&engine);
+    atoms::add_to_linker_with_pointer_width(&mut linker, wiggle::PointerWidth::Bits64, |cx| cx)
+        .unwrap();
+    let mut store = Store::new(&engine, Ctx);
+    let shim_inst = linker.instantiate(&mut store, &shim_mod).unwrap();
+
+    // An offset past 4GiB must be rejected rather than truncated to 32 bits.
+    let result_location: i64 = 1 << 32;
+    let mut results = [Val::I32(0)];
+    shim_inst
+        .get_func(&mut store, "double_int_return_float_shim")
+        .unwrap()
+        .call(
+            &mut store,
+            &[1i32.into(), result_location.into()],
+            &mut results,
+        )
+        .unwrap_err();
+}
+
+#[test]
+fn test_memory64_needs_64bit_bindings() {
+    let engine = engine();
+    let shim_mod = shim_module(&engine);
+
+    // The default bindings take `i32` pointers, which don't match the
+    // imports of a memory64 module.
+    let mut linker = Linker::new(&engine);
+    atoms::add_to_linker(&mut linker, |cx| cx).unwrap();
+    let mut store = Store::new(&engine, Ctx);
+    assert!(linker.instantiate(&mut store, &shim_mod).is_err());
+}
+
+fn engine() -> Engine {
+    let mut config = Config::new();
+    config.wasm_memory64(true);
+    Engine::new(&config).unwrap()
+}
+
+// Same as the shim in `wasmtime_sync.rs`, but with a 64-bit memory and
+// therefore `i64` pointers.
+fn shim_module(engine: &Engine) -> Module {
+    Module::new(
+        engine,
+        r#"
+        (module
+            (import "atoms" "double_int_return_float" (func $double_int_return_float (param i32 i64) (result i32)))
+
+            (memory i64 1)
+            (export "memory" (memory 0))
+
+            (func $double_int_return_float_shim (param i32 i64) (result i32)
+                local.get 0
+                local.get 1
+                call $double_int_return_float
+            )
+            (export "double_int_return_float_shim" (func $double_int_return_float_shim))
+        )
+    "#,
+    )
+    .unwrap()
+}
@@ -79,6 +79,7 @@ impl wasmtime_environ::Compiler for Compiler {
             WasmFunctionInfo {
                 start_srcloc,
                 stack_maps: Box::new([]),
+                inlined: Box::new([]),
             },
             Box::new(compiled_function),
         ))
@@ -161,6 +162,8 @@ impl wasmtime_environ::Compiler for Compiler {
             let info = FunctionLoc {
                 start: u32::try_from(range.start).unwrap(),
                 length: u32::try_from(range.end - range.start).unwrap(),
+                cold_start: 0,
+                cold_length: 0,
             };
             ret.push((sym, info));
         }
@@ -3,7 +3,7 @@ use crate::types::{TypeInfo, Types};
 use anyhow::{anyhow, bail, Context};
 use heck::*;
 use indexmap::IndexMap;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write as _;
 use std::io::{Read, Write};
 use std::mem;
@@ -47,6 +47,9 @@ struct Wasmtime {
     sizes: SizeAlign,
     interface_names: HashMap<InterfaceId, InterfaceName>,
     with_name_counter: usize,
+    /// WIT types which have been remapped to existing Rust types through the
+    /// use of `with` in the `bindgen!` macro invocation.
+    remapped_types: HashMap<TypeId, String>,
 }
 
 struct ImportInterface {
@@ -56,6 +59,7 @@ struct ImportInterface {
 struct ImportFunction {
     add_to_linker: String,
     sig: String,
+    async_: bool,
 }
 
 #[derive(Default)]
@@ -92,8 +96,8 @@ pub struct Opts {
     /// Whether or not to emit `tracing` macro calls on function entry/exit.
     pub tracing: bool,
 
-    /// Whether or not to use async rust functions and traits.
-    pub async_: bool,
+    /// Which imports and exports use async rust functions and traits.
+    pub async_: AsyncConfig,
 
     /// A list of "trappable errors" which are used to replace the `E` in
     /// `result<T, E>` found in WIT.
@@ -107,7 +111,15 @@ pub struct Opts {
 
     /// Remapping of interface names to rust module names.
     /// TODO: is there a better type to use for the value of this map?
+    ///
+    /// Keys of the form `interface#type` instead remap a single WIT type to
+    /// an existing Rust type which implements `ComponentType`, `Lift` and
+    /// `Lower`. Types defined at the world level use just their name as key.
     pub with: HashMap<String, String>,
+
+    /// Additional derive attributes to add to generated records, variants,
+    /// unions and enums.
+    pub additional_derive_attributes: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -122,6 +134,83 @@ pub struct TrappableError {
     pub rust_type_name: String,
 }
 
+/// Configuration of which functions generated by `bindgen!` are `async`.
+///
+/// Only imports can be selected individually. As soon as any function is
+/// `async` the store must have `Config::async_support` enabled, which makes
+/// `Func::call` panic, so the generated `instantiate` functions and all
+/// exports are `async` as well.
+#[derive(Default, Debug, Clone)]
+pub struct AsyncConfig {
+    /// Which imported functions are `async` in the generated `Host` traits.
+    pub imports: AsyncFunctions,
+}
+
+/// Selection of a set of functions which should be generated as `async`.
+///
+/// Functions are named either by their bare WIT name, for example `"read"`,
+/// or qualified with the name of the interface they're defined in, for
+/// example `"wasi:filesystem/types#read"`. World-level functions can only be
+/// named by their bare name.
+#[derive(Default, Debug, Clone)]
+pub enum AsyncFunctions {
+    /// No functions are `async`.
+    #[default]
+    None,
+    /// All functions are `async`.
+    All,
+    /// All functions are `async` except for those listed.
+    AllExcept(HashSet<String>),
+    /// Only the functions listed are `async`, all others are synchronous.
+    Only(HashSet<String>),
+}
+
+impl AsyncConfig {
+    /// Configuration where every import and export is `async`, the
+    /// equivalent of `async: true` in `bindgen!`.
+    pub fn all() -> AsyncConfig {
+        AsyncConfig {
+            imports: AsyncFunctions::All,
+        }
+    }
+
+    /// Returns whether any function at all may be `async`, meaning that the
+    /// generated bindings require `Config::async_support`.
+    pub fn maybe_async(&self) -> bool {
+        self.imports.maybe_async()
+    }
+
+    /// Returns whether the imported function `func`, optionally defined in
+    /// the interface `iface`, is `async`.
+    pub fn is_import_async(&self, iface: Option<&str>, func: &str) -> bool {
+        self.imports.is_async(iface, func)
+    }
+
+    /// Returns whether exported functions are invoked through `call_async`,
+    /// which is the case whenever any function is `async`.
+    pub fn is_export_async(&self) -> bool {
+        self.maybe_async()
+    }
+}
+
+impl AsyncFunctions {
+    fn maybe_async(&self) -> bool {
+        !matches!(self, AsyncFunctions::None)
+    }
+
+    fn is_async(&self, iface: Option<&str>, func: &str) -> bool {
+        let listed = |set: &HashSet<String>| {
+            set.contains(func) || iface.map_or(false, |i| set.contains(&format!("{i}#{func}")))
+        };
+        match self {
+            AsyncFunctions::None => false,
+            AsyncFunctions::All => true,
+            AsyncFunctions::AllExcept(set) => !listed(set),
+            AsyncFunctions::Only(set) => listed(set),
+        }
+    }
+}
+
 impl Opts {
     pub fn generate(&self, resolve: &Resolve, world: WorldId) -> String {
         let mut r = Wasmtime::default();
@@ -171,6 +260,7 @@ impl Wasmtime {
     fn generate(&mut self, resolve: &Resolve, id: WorldId) -> String {
         self.types.analyze(resolve, id);
         let world = &resolve.worlds[id];
+        self.find_remapped_types(resolve, id);
         for (name, import) in world.imports.iter() {
             if !self.opts.only_interfaces || matches!(import, WorldItem::Interface(_)) {
                 self.import(resolve, name, import);
@@ -184,6 +274,30 @@ impl Wasmtime {
         self.finish(resolve, id)
     }
 
+    fn find_remapped_types(&mut self, resolve: &Resolve, id: WorldId) {
+        let world = &resolve.worlds[id];
+        for (name, item) in world.imports.iter().chain(world.exports.iter()) {
+            let (prefix, types) = match (name, item) {
+                (WorldKey::Name(name), WorldItem::Type(ty)) => {
+                    if let Some(path) = self.opts.with.get(name) {
+                        self.remapped_types.insert(*ty, path.clone());
+                    }
+                    continue;
+                }
+                (_, WorldItem::Interface(iface)) => (
+                    resolve.name_world_key(name),
+                    &resolve.interfaces[*iface].types,
+                ),
+                _ => continue,
+            };
+            for (ty_name, ty) in types {
+                if let Some(path) = self.opts.with.get(&format!("{prefix}#{ty_name}")) {
+                    self.remapped_types.insert(*ty, path.clone());
+                }
+            }
+        }
+    }
+
     fn import(&mut self, resolve: &Resolve, name: &WorldKey, item: &WorldItem) {
         let mut gen = InterfaceGenerator::new(self, resolve);
         match item {
@@ -192,8 +306,12 @@ impl Wasmtime {
                 let sig = mem::take(&mut gen.src).into();
                 gen.generate_add_function_to_linker(TypeOwner::None, func, "linker");
                 let add_to_linker = gen.src.into();
-                self.import_functions
-                    .push(ImportFunction { sig, add_to_linker });
+                let async_ = self.opts.async_.is_import_async(None, &func.name);
+                self.import_functions.push(ImportFunction {
+                    sig,
+                    add_to_linker,
+                    async_,
+                });
             }
             WorldItem::Interface(id) => {
                 if gen.gen.name_interface(resolve, *id, name) {
@@ -382,7 +500,7 @@ impl Wasmtime {
         }
         self.src.push_str("}\n");
 
-        let (async_, async__, send, await_) = if self.opts.async_ {
+        let (async_, async__, send, await_) = if self.opts.async_.maybe_async() {
             ("async", "_async", ":Send", ".await")
         } else {
             ("", "", "", "")
@@ -543,7 +661,7 @@ impl Wasmtime {
         }
 
         let world_camel = to_rust_upper_camel_case(&resolve.worlds[world].name);
-        if self.opts.async_ {
+        if self.import_functions.iter().any(|f| f.async_) {
             uwriteln!(self.src, "#[wasmtime::component::__internal::async_trait]")
         }
         uwriteln!(self.src, "pub trait {world_camel}Imports {{");
@@ -600,7 +718,7 @@ impl Wasmtime {
             }
             self.src.push_str(&name);
         }
-        let maybe_send = if self.opts.async_ {
+        let maybe_send = if self.opts.async_.imports.maybe_async() {
             " + Send, T: Send"
         } else {
             ""
@@ -756,6 +874,10 @@ impl<'a> InterfaceGenerator<'a> {
     }
 
     fn define_type(&mut self, name: &str, id: TypeId) {
+        if let Some(path) = self.gen.remapped_types.get(&id).cloned() {
+            self.type_remapped(id, &path);
+            return;
+        }
         let ty = &self.resolve.types[id];
         match &ty.kind {
             TypeDefKind::Record(record) => self.type_record(id, name, record, &ty.docs),
@@ -776,6 +898,34 @@ impl<'a> InterfaceGenerator<'a> {
         }
     }
 
+    fn type_remapped(&mut self, id: TypeId, path: &str) {
+        // Remapped types are always owned, and the Rust type is used as-is
+        // regardless of ownership mode, so only one name is ever needed.
+        let name = self.result_name(id);
+        uwriteln!(self.src, "pub type {name} = {path};");
+        self.assert_type(id, &name);
+    }
+
+    /// Emits the `additional_derive_attributes` configured for this
+    /// generation, skipping those already provided in `builtin` for the type
+    /// being generated.
+    fn push_additional_derives(&mut self, builtin: &[&str]) {
+        let derives = self
+            .gen
+            .opts
+            .additional_derive_attributes
+            .iter()
+            .filter(|d| {
+                let last = d.rsplit("::").next().unwrap().trim();
+                !builtin.contains(&last)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if !derives.is_empty() {
+            uwriteln!(self.src, "#[derive({})]", derives.join(", "));
+        }
+    }
+
     fn type_record(&mut self, id: TypeId, _name: &str, record: &Record, docs: &Docs) {
         let info = self.info(id);
         for (name, mode) in self.modes_of(id) {
@@ -794,6 +944,7 @@ impl<'a> InterfaceGenerator<'a> {
             } else {
                 self.push_str("#[derive(Clone)]\n");
             }
+            self.push_additional_derives(&["Copy", "Clone", "Debug"]);
             self.push_str(&format!("pub struct {}", name));
             self.print_generics(lt);
             self.push_str(" {\n");
@@ -971,6 +1122,7 @@ impl<'a> InterfaceGenerator<'a> {
             } else {
                 self.push_str("#[derive(Clone)]\n");
             }
+            self.push_additional_derives(&["Copy", "Clone", "Debug"]);
             self.push_str(&format!("pub enum {name}"));
             self.print_generics(lt);
             self.push_str("{\n");
@@ -1092,6 +1244,7 @@ impl<'a> InterfaceGenerator<'a> {
         self.push_str("#[derive(wasmtime::component::Lower)]\n");
         self.push_str("#[component(enum)]\n");
         self.push_str("#[derive(Clone, Copy, PartialEq, Eq)]\n");
+        self.push_additional_derives(&["Copy", "Clone", "PartialEq", "Eq", "Debug"]);
         self.push_str(&format!("pub enum {} {{\n", name));
         for case in enum_.cases.iter() {
             self.rustdoc(&case.docs);
@@ -1227,6 +1380,19 @@ impl<'a> InterfaceGenerator<'a> {
         }
     }
 
+    /// Returns whether the host implementation of the imported `func` is an
+    /// `async` function, taking into account the interface currently being
+    /// generated, if any.
+    fn is_import_async(&self, func: &Function) -> bool {
+        let iface = self
+            .current_interface
+            .map(|(_, key, _)| self.resolve.name_world_key(key));
+        self.gen
+            .opts
+            .async_
+            .is_import_async(iface.as_deref(), &func.name)
+    }
+
     fn special_case_trappable_error(
         &self,
         results: &Results,
@@ -1259,8 +1425,12 @@ impl<'a> InterfaceGenerator<'a> {
     fn generate_add_to_linker(&mut self, id: InterfaceId, name: &str) {
         let iface = &self.resolve.interfaces[id];
         let owner = TypeOwner::Interface(id);
+        let any_async = iface
+            .functions
+            .values()
+            .any(|func| self.gen.opts.async_.is_import_async(Some(name), &func.name));
 
-        if self.gen.opts.async_ {
+        if any_async {
             uwriteln!(self.src, "#[wasmtime::component::__internal::async_trait]")
         }
         // Generate the `pub trait` which represents the host functionality for
@@ -1271,7 +1441,7 @@ impl<'a> InterfaceGenerator<'a> {
         }
         uwriteln!(self.src, "}}");
 
-        let where_clause = if self.gen.opts.async_ {
+        let where_clause = if any_async {
             "T: Send, U: Host + Send".to_string()
         } else {
             "U: Host".to_string()
@@ -1299,7 +1469,7 @@ impl<'a> InterfaceGenerator<'a> {
         uwrite!(
             self.src,
             "{linker}.{}(\"{}\", ",
-            if self.gen.opts.async_ {
+            if self.is_import_async(func) {
                 "func_wrap_async"
             } else {
                 "func_wrap"
@@ -1326,7 +1496,8 @@ impl<'a> InterfaceGenerator<'a> {
             self.src.push_str(", ");
         }
         self.src.push_str(") |");
-        if self.gen.opts.async_ {
+        let async_ = self.is_import_async(func);
+        if async_ {
             self.src.push_str(" Box::new(async move { \n");
         } else {
             self.src.push_str(" { \n");
@@ -1377,7 +1548,7 @@ impl<'a> InterfaceGenerator<'a> {
         for (i, _) in func.params.iter().enumerate() {
             uwrite!(self.src, "arg{},", i);
         }
-        if self.gen.opts.async_ {
+        if async_ {
             uwrite!(self.src, ").await;\n");
         } else {
             uwrite!(self.src, ");\n");
@@ -1407,7 +1578,7 @@ impl<'a> InterfaceGenerator<'a> {
             uwrite!(self.src, "r\n");
         }
 
-        if self.gen.opts.async_ {
+        if async_ {
             // Need to close Box::new and async block
             self.src.push_str("})");
         } else {
@@ -1418,7 +1589,7 @@ impl<'a> InterfaceGenerator<'a> {
     fn generate_function_trait_sig(&mut self, func: &Function) {
         self.rustdoc(&func.docs);
 
-        if self.gen.opts.async_ {
+        if self.is_import_async(func) {
             self.push_str("async ");
         }
         self.push_str("fn ");
@@ -1494,7 +1665,9 @@ impl<'a> InterfaceGenerator<'a> {
         ns: Option<&WorldKey>,
         func: &Function,
     ) {
-        let (async_, async__, await_) = if self.gen.opts.async_ {
+        let iface = ns.map(|key| resolve.name_world_key(key));
+        let is_async = self.gen.opts.async_.is_export_async();
+        let (async_, async__, await_) = if is_async {
             ("async", "_async", ".await")
         } else {
             ("", "", "")
@@ -1514,7 +1687,7 @@ impl<'a> InterfaceGenerator<'a> {
         self.src.push_str(") -> wasmtime::Result<");
         self.print_result_ty(&func.results, TypeMode::Owned);
 
-        if self.gen.opts.async_ {
+        if is_async {
             self.src
                 .push_str("> where <S as wasmtime::AsContext>::Data: Send {\n");
         } else {
@@ -1522,10 +1695,7 @@ impl<'a> InterfaceGenerator<'a> {
         }
 
         if self.gen.opts.tracing {
-            let ns = match ns {
-                Some(key) => resolve.name_world_key(key),
-                None => "default".to_string(),
-            };
+            let ns = iface.as_deref().unwrap_or("default");
             self.src.push_str(&format!(
                 "
                    let span = tracing::span!(
@@ -1690,6 +1860,10 @@ impl<'a> RustGenerator<'a> for InterfaceGenerator<'a> {
     fn info(&self, ty: TypeId) -> TypeInfo {
         self.gen.types.get(ty)
     }
+
+    fn is_remapped(&self, ty: TypeId) -> bool {
+        self.gen.remapped_types.contains_key(&ty)
+    }
 }
 
 /// When an interface `use`s a type from another interface, it creates a new TypeId
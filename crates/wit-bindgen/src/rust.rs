@@ -17,6 +17,10 @@ pub trait RustGenerator<'a> {
     fn info(&self, ty: TypeId) -> TypeInfo;
     fn path_to_interface(&self, interface: InterfaceId) -> Option<String>;
 
+    /// Whether the type has been remapped to an existing Rust type, in which
+    /// case it's always referred to by its owned name without lifetimes.
+    fn is_remapped(&self, ty: TypeId) -> bool;
+
     /// This determines whether we generate owning types or (where appropriate)
     /// borrowing types.
     ///
@@ -68,6 +72,17 @@ pub trait RustGenerator<'a> {
         let info = self.info(id);
         let lt = self.lifetime_for(&info, mode);
         let ty = &self.resolve().types[id];
+        if ty.name.is_some() && self.is_remapped(id) {
+            if let TypeOwner::Interface(id) = ty.owner {
+                if let Some(path) = self.path_to_interface(id) {
+                    self.push_str(&path);
+                    self.push_str("::");
+                }
+            }
+            let name = self.result_name(id);
+            self.push_str(&name);
+            return;
+        }
         if ty.name.is_some() {
             // If this type has a list internally, no lifetime is being printed,
             // but we're in a borrowed mode, then that means we're in a borrowed
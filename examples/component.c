@@ -0,0 +1,142 @@
+/*
+Example of instantiating a WebAssembly component, providing a host function
+for its import, and invoking its exported function.
+
+You can compile and run this example on Linux with:
+
+   cargo build --release -p wasmtime-c-api --features component-model
+   cc examples/component.c \
+       -I crates/c-api/include \
+       -I crates/c-api/wasm-c-api/include \
+       target/release/libwasmtime.a \
+       -lpthread -ldl -lm \
+       -o component
+   ./component
+
+Note that on Windows and macOS the command will be similar, but you'll need
+to tweak the `-lpthread` and such annotations as well as the name of the
+`libwasmtime.a` file on Windows.
+
+You can also build using cmake:
+
+mkdir build && cd build && cmake .. && cmake --build . --target wasmtime-component
+*/
+
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <wasm.h>
+#include <wasmtime.h>
+
+static void exit_with_error(const char *message, wasmtime_error_t *error);
+
+static wasmtime_error_t* add_callback(
+    void *env,
+    wasmtime_context_t *context,
+    const wasmtime_component_val_t *args,
+    size_t nargs,
+    wasmtime_component_val_t *results,
+    size_t nresults
+) {
+  assert(nargs == 2 && nresults == 1);
+  assert(args[0].kind == WASMTIME_COMPONENT_U32);
+  assert(args[1].kind == WASMTIME_COMPONENT_U32);
+  printf("Calling back...\n");
+  printf("> %u + %u\n", args[0].of.u32, args[1].of.u32);
+  results[0].kind = WASMTIME_COMPONENT_U32;
+  results[0].of.u32 = args[0].of.u32 + args[1].of.u32;
+  return NULL;
+}
+
+int main() {
+  // The component model is not enabled by default, so turn it on in the
+  // configuration used to create our engine.
+  printf("Initializing...\n");
+  wasm_config_t *config = wasm_config_new();
+  assert(config != NULL);
+  wasmtime_config_wasm_component_model_set(config, true);
+  wasm_engine_t *engine = wasm_engine_new_with_config(config);
+  assert(engine != NULL);
+  wasmtime_store_t *store = wasmtime_store_new(engine, NULL, NULL);
+  assert(store != NULL);
+  wasmtime_context_t *context = wasmtime_store_context(store);
+
+  // Read our input file, which in this case is a wasm text file.
+  FILE* file = fopen("examples/component.wat", "r");
+  assert(file != NULL);
+  fseek(file, 0L, SEEK_END);
+  size_t file_size = ftell(file);
+  fseek(file, 0L, SEEK_SET);
+  wasm_byte_vec_t wat;
+  wasm_byte_vec_new_uninitialized(&wat, file_size);
+  assert(fread(wat.data, file_size, 1, file) == 1);
+  fclose(file);
+
+  // Parse the wat into the binary component format
+  wasm_byte_vec_t wasm;
+  wasmtime_error_t *error = wasmtime_wat2wasm(wat.data, wat.size, &wasm);
+  if (error != NULL)
+    exit_with_error("failed to parse wat", error);
+  wasm_byte_vec_delete(&wat);
+
+  printf("Compiling component...\n");
+  wasmtime_component_t *component = NULL;
+  error = wasmtime_component_new(engine, (uint8_t*) wasm.data, wasm.size, &component);
+  wasm_byte_vec_delete(&wasm);
+  if (error != NULL)
+    exit_with_error("failed to compile component", error);
+
+  // Define the `add` import in the root of a linker. The type of the host
+  // function is taken from the component's import of the same name.
+  printf("Defining imports...\n");
+  wasmtime_component_linker_t *linker = wasmtime_component_linker_new(engine);
+  wasmtime_component_linker_instance_t *root = wasmtime_component_linker_root(linker);
+  error = wasmtime_component_linker_instance_define_func(root, component, "add", 3, add_callback, NULL, NULL);
+  wasmtime_component_linker_instance_delete(root);
+  if (error != NULL)
+    exit_with_error("failed to define `add`", error);
+
+  printf("Instantiating component...\n");
+  wasmtime_component_instance_t instance;
+  error = wasmtime_component_linker_instantiate(linker, context, component, &instance);
+  if (error != NULL)
+    exit_with_error("failed to instantiate", error);
+
+  // Lookup our `run` export function
+  printf("Extracting export...\n");
+  wasmtime_component_func_t run;
+  bool ok = wasmtime_component_instance_get_func(&instance, context, "", 0, "run", 3, &run);
+  assert(ok);
+
+  // And call it!
+  printf("Calling export...\n");
+  wasmtime_component_val_t param;
+  param.kind = WASMTIME_COMPONENT_U32;
+  param.of.u32 = 41;
+  wasmtime_component_val_t result;
+  error = wasmtime_component_func_call(&run, context, &param, 1, &result, 1);
+  if (error != NULL)
+    exit_with_error("failed to call function", error);
+  assert(result.kind == WASMTIME_COMPONENT_U32);
+  printf("> run(41) = %u\n", result.of.u32);
+  wasmtime_component_val_delete(&result);
+
+  // Clean up after ourselves at this point
+  printf("All finished!\n");
+
+  wasmtime_component_linker_delete(linker);
+  wasmtime_component_delete(component);
+  wasmtime_store_delete(store);
+  wasm_engine_delete(engine);
+  return 0;
+}
+
+static void exit_with_error(const char *message, wasmtime_error_t *error) {
+  fprintf(stderr, "error: %s\n", message);
+  wasm_byte_vec_t error_message;
+  wasmtime_error_message(error, &error_message);
+  wasmtime_error_delete(error);
+  fprintf(stderr, "%.*s\n", (int) error_message.size, error_message.data);
+  wasm_byte_vec_delete(&error_message);
+  exit(1);
+}
@@ -0,0 +1,18 @@
+(component
+  (import "add" (func $add (param "a" u32) (param "b" u32) (result u32)))
+  (core func $add_lowered (canon lower (func $add)))
+
+  (core module $m
+    (import "host" "add" (func $add (param i32 i32) (result i32)))
+    (func (export "run") (param i32) (result i32)
+      local.get 0
+      i32.const 1
+      call $add)
+  )
+  (core instance $i (instantiate $m
+    (with "host" (instance (export "add" (func $add_lowered))))
+  ))
+
+  (func (export "run") (param "x" u32) (result u32)
+    (canon lift (core func $i "run")))
+)
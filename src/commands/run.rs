@@ -17,8 +17,13 @@ use wasmtime_cli_flags::{CommonOptions, WasiModules};
 use wasmtime_wasi::maybe_exit_on_error;
 use wasmtime_wasi::sync::{ambient_authority, Dir, TcpListener, WasiCtxBuilder};
 
+#[cfg(feature = "component-model")]
+use wasmtime::component::Component;
+#[cfg(feature = "component-model")]
+use wasmtime_wasi::preview2;
+
 #[cfg(feature = "wasi-nn")]
-use wasmtime_wasi_nn::WasiNnCtx;
+use wasmtime_wasi_nn::{GraphEncoding, OpenvinoBackend, WasiNnCtx};
 
 #[cfg(feature = "wasi-threads")]
 use wasmtime_wasi_threads::WasiThreadsCtx;
@@ -34,6 +39,30 @@ fn parse_env_var(s: &str) -> Result<(String, Option<String>)> {
     ))
 }
 
+fn parse_dir(s: &str) -> Result<(String, DirOptions)> {
+    // Options follow the first `:` which is followed by valid options, so
+    // that Windows paths such as `C:\data` keep working.
+    for (i, _) in s.match_indices(':') {
+        if let Ok(options) = parse_dir_options(&s[i + 1..]) {
+            return Ok((s[..i].to_string(), options));
+        }
+    }
+    Ok((s.to_string(), DirOptions::default()))
+}
+
+fn parse_dir_options(s: &str) -> Result<DirOptions> {
+    let mut options = DirOptions::default();
+    for option in s.split(',') {
+        match option.split_once('=') {
+            None if option == "ro" => options.read_only = true,
+            Some(("ro", pattern)) => options.read_only_paths.push(pattern.to_string()),
+            Some(("deny", pattern)) => options.deny.push(pattern.to_string()),
+            _ => bail!("unknown directory option: {option}"),
+        }
+    }
+    Ok(options)
+}
+
 fn parse_map_dirs(s: &str) -> Result<(String, String)> {
     let parts: Vec<&str> = s.split("::").collect();
     if parts.len() != 2 {
@@ -52,6 +81,16 @@ fn parse_dur(s: &str) -> Result<Duration> {
     Ok(dur)
 }
 
+fn parse_wasi_nn_graph(s: &str) -> Result<(String, String, PathBuf)> {
+    let (name, rest) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("must contain an equals character ('=')"))?;
+    let (encoding, path) = rest
+        .split_once(':')
+        .ok_or_else(|| anyhow!("must contain a colon (':') after the encoding"))?;
+    Ok((name.into(), encoding.into(), path.into()))
+}
+
 fn parse_preloads(s: &str) -> Result<(String, PathBuf)> {
     let parts: Vec<&str> = s.splitn(2, '=').collect();
     if parts.len() != 2 {
@@ -82,6 +121,18 @@ fn parse_profile(s: &str) -> Result<Profile> {
     }
 }
 
+fn parse_guest_log_level(s: &str) -> Result<GuestLogLevel> {
+    Ok(match s {
+        "off" => GuestLogLevel::Off,
+        "error" => GuestLogLevel::Error,
+        "warn" => GuestLogLevel::Warn,
+        "info" => GuestLogLevel::Info,
+        "debug" => GuestLogLevel::Debug,
+        "trace" => GuestLogLevel::Trace,
+        _ => bail!("unknown log level: {s}"),
+    })
+}
+
 static AFTER_HELP: Lazy<String> = Lazy::new(|| crate::FLAG_EXPLANATIONS.to_string());
 
 /// Runs a WebAssembly module
@@ -128,8 +179,23 @@ pub struct RunCommand {
     tcplisten: Vec<String>,
 
     /// Grant access to the given host directory
-    #[clap(long = "dir", number_of_values = 1, value_name = "DIRECTORY")]
-    dirs: Vec<String>,
+    ///
+    /// The directory may be followed by a `:` and comma-separated options,
+    /// which are only supported with components:
+    ///
+    ///     --dir=DIRECTORY[:ro][,ro=GLOB]...[,deny=GLOB]...
+    ///
+    /// where `ro` makes the whole directory read-only, `ro=GLOB` makes the
+    /// paths matching `GLOB` read-only and `deny=GLOB` hides them, for
+    /// example `--dir=.:deny=**/.git`. Globs are matched against paths
+    /// relative to the directory.
+    #[clap(
+        long = "dir",
+        number_of_values = 1,
+        value_name = "DIRECTORY[:OPTIONS]",
+        value_parser = parse_dir,
+    )]
+    dirs: Vec<(String, DirOptions)>,
 
     /// Pass an environment variable to the program.
     ///
@@ -158,6 +224,19 @@ pub struct RunCommand {
     )]
     preloads: Vec<(String, PathBuf)>,
 
+    /// Preload a wasi-nn graph which guests can load with `load_by_name`
+    ///
+    /// The graph is loaded from PATH by the backend for ENCODING; for
+    /// `openvino` this is a directory containing `model.xml` and `model.bin`.
+    /// Preloaded graphs always execute on the CPU.
+    #[clap(
+        long = "wasi-nn-graph",
+        number_of_values = 1,
+        value_name = "NAME=ENCODING:PATH",
+        value_parser = parse_wasi_nn_graph,
+    )]
+    wasi_nn_graphs: Vec<(String, String, PathBuf)>,
+
     /// Maximum execution time of wasm code before timing out (1, 2s, 100ms, etc)
     #[clap(
         long = "wasm-timeout",
@@ -172,7 +251,8 @@ pub struct RunCommand {
     /// with external profilers such as `perf`. The guest profiling strategy
     /// enables in-process sampling and will write the captured profile to
     /// `wasmtime-guest-profile.json` by default which can be viewed at
-    /// https://profiler.firefox.com/.
+    /// https://profiler.firefox.com/. Guest profiling supports both core
+    /// modules and components.
     ///
     /// The `guest` option can be additionally configured as:
     ///
@@ -189,9 +269,26 @@ pub struct RunCommand {
     profile: Option<Profile>,
 
     /// Enable coredump generation after a WebAssembly trap.
+    ///
+    /// The coredump records every core instance in the store, including all
+    /// core instances within a component, along with their memories and
+    /// globals.
     #[clap(long = "coredump-on-trap", value_name = "PATH")]
     coredump_on_trap: Option<String>,
 
+    /// Minimum level of the messages components log through `wasi:logging`
+    /// (valid options are: off, error, warn, info, debug, trace)
+    ///
+    /// Messages which pass this filter are forwarded to Wasmtime's own
+    /// logger, using the context given by the component as the target, so
+    /// they're subject to `RUST_LOG` as well. All messages pass by default.
+    #[clap(
+        long,
+        value_name = "LEVEL",
+        value_parser = parse_guest_log_level,
+    )]
+    guest_log_level: Option<GuestLogLevel>,
+
     /// Maximum size, in bytes, that a linear memory is allowed to reach.
     ///
     /// Growth beyond this limit will cause `memory.grow` instructions in
@@ -239,6 +336,37 @@ enum Profile {
     Guest { path: String, interval: Duration },
 }
 
+/// Restrictions on a directory preopened with `--dir`.
+#[derive(Clone, Default)]
+struct DirOptions {
+    read_only: bool,
+    read_only_paths: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl DirOptions {
+    fn is_restricted(&self) -> bool {
+        self.read_only || !self.read_only_paths.is_empty() || !self.deny.is_empty()
+    }
+}
+
+/// The main input of `wasmtime run`.
+enum MainInput {
+    Module(Module),
+    #[cfg(feature = "component-model")]
+    Component(Component),
+}
+
+#[derive(Clone, Copy)]
+enum GuestLogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
 impl RunCommand {
     /// Executes the command.
     pub fn execute(&self) -> Result<()> {
@@ -249,6 +377,9 @@ impl RunCommand {
         if self.wasm_timeout.is_some() {
             config.epoch_interruption(true);
         }
+        if self.coredump_on_trap.is_some() {
+            config.coredump_on_trap(true);
+        }
         match self.profile {
             Some(Profile::Native(s)) => {
                 config.profiler(s);
@@ -275,11 +406,39 @@ impl RunCommand {
         let preopen_dirs = self.compute_preopen_dirs()?;
         let argv = self.compute_argv()?;
 
+        let module = match self.load_main(&engine)? {
+            MainInput::Module(module) => module,
+            #[cfg(feature = "component-model")]
+            MainInput::Component(component) => {
+                return self
+                    .run_component(&engine, component, preopen_dirs, &argv)
+                    .with_context(|| {
+                        format!(
+                            "failed to run main component `{}`",
+                            self.module_and_args[0].display()
+                        )
+                    })
+                    .map_err(maybe_exit_on_error);
+            }
+        };
+
+        if self.guest_log_level.is_some() {
+            bail!("`--guest-log-level` is only supported with components");
+        }
+        if preopen_dirs
+            .iter()
+            .any(|(_, _, options)| options.is_restricted())
+        {
+            bail!("options on `--dir` are only supported with components");
+        }
+        let preopen_dirs = preopen_dirs
+            .into_iter()
+            .map(|(name, dir, _)| (name, dir))
+            .collect();
+
         let mut linker = Linker::new(&engine);
         linker.allow_unknown_exports(self.allow_unknown_exports);
 
-        // Read the wasm module binary either as `*.wat` or a raw binary.
-        let module = self.load_module(linker.engine(), &self.module_and_args[0])?;
         let mut modules = vec![(String::new(), module.clone())];
 
         let host = Host::default();
@@ -294,34 +453,10 @@ impl RunCommand {
             &self.common.wasi_modules.unwrap_or(WasiModules::default()),
             self.listenfd,
             preopen_sockets,
+            &self.wasi_nn_graphs,
         )?;
 
-        let mut limits = StoreLimitsBuilder::new();
-        if let Some(max) = self.max_memory_size {
-            limits = limits.memory_size(max);
-        }
-        if let Some(max) = self.max_table_elements {
-            limits = limits.table_elements(max);
-        }
-        if let Some(max) = self.max_instances {
-            limits = limits.instances(max);
-        }
-        if let Some(max) = self.max_tables {
-            limits = limits.tables(max);
-        }
-        if let Some(max) = self.max_memories {
-            limits = limits.memories(max);
-        }
-        store.data_mut().limits = limits
-            .trap_on_grow_failure(self.trap_on_grow_failure)
-            .build();
-        store.limiter(|t| &mut t.limits);
-
-        // If fuel has been configured, we want to add the configured
-        // fuel amount to this store.
-        if let Some(fuel) = self.common.fuel {
-            store.add_fuel(fuel)?;
-        }
+        self.configure_store(&mut store)?;
 
         // Load the preload wasm modules.
         for (name, path) in self.preloads.iter() {
@@ -358,14 +493,45 @@ impl RunCommand {
         Ok(())
     }
 
-    fn compute_preopen_dirs(&self) -> Result<Vec<(String, Dir)>> {
+    fn configure_store(&self, store: &mut Store<Host>) -> Result<()> {
+        let mut limits = StoreLimitsBuilder::new();
+        if let Some(max) = self.max_memory_size {
+            limits = limits.memory_size(max);
+        }
+        if let Some(max) = self.max_table_elements {
+            limits = limits.table_elements(max);
+        }
+        if let Some(max) = self.max_instances {
+            limits = limits.instances(max);
+        }
+        if let Some(max) = self.max_tables {
+            limits = limits.tables(max);
+        }
+        if let Some(max) = self.max_memories {
+            limits = limits.memories(max);
+        }
+        store.data_mut().limits = limits
+            .trap_on_grow_failure(self.trap_on_grow_failure)
+            .build();
+        store.limiter(|t| &mut t.limits);
+
+        // If fuel has been configured, we want to add the configured
+        // fuel amount to this store.
+        if let Some(fuel) = self.common.fuel {
+            store.add_fuel(fuel)?;
+        }
+        Ok(())
+    }
+
+    fn compute_preopen_dirs(&self) -> Result<Vec<(String, Dir, DirOptions)>> {
         let mut preopen_dirs = Vec::new();
 
-        for dir in self.dirs.iter() {
+        for (dir, options) in self.dirs.iter() {
             preopen_dirs.push((
                 dir.clone(),
                 Dir::open_ambient_dir(dir, ambient_authority())
                     .with_context(|| format!("failed to open directory '{}'", dir))?,
+                options.clone(),
             ));
         }
 
@@ -374,6 +540,7 @@ impl RunCommand {
                 guest.clone(),
                 Dir::open_ambient_dir(host, ambient_authority())
                     .with_context(|| format!("failed to open directory '{}'", host))?,
+                DirOptions::default(),
             ));
         }
 
@@ -418,13 +585,11 @@ impl RunCommand {
     fn setup_epoch_handler(
         &self,
         store: &mut Store<Host>,
-        module_name: &str,
-        modules: Vec<(String, Module)>,
+        new_profiler: impl FnOnce(Duration) -> GuestProfiler,
     ) -> Box<dyn FnOnce(&mut Store<Host>)> {
         if let Some(Profile::Guest { path, interval }) = &self.profile {
             let interval = *interval;
-            store.data_mut().guest_profiler =
-                Some(Arc::new(GuestProfiler::new(module_name, interval, modules)));
+            store.data_mut().guest_profiler = Some(Arc::new(new_profiler(interval)));
 
             fn sample(mut store: impl AsContextMut<Data = Host>) {
                 let mut profiler = store
@@ -526,7 +691,9 @@ impl RunCommand {
         };
 
         // Finish all lookups before starting any epoch timers.
-        let finish_epoch_handler = self.setup_epoch_handler(store, module_name, modules);
+        let finish_epoch_handler = self.setup_epoch_handler(store, |interval| {
+            GuestProfiler::new(module_name, interval, modules)
+        });
         let result = self.invoke_func(store, func);
         finish_epoch_handler(store);
         result
@@ -597,26 +764,7 @@ impl RunCommand {
         });
 
         if let Err(err) = invoke_res {
-            let err = if err.is::<wasmtime::Trap>() {
-                if let Some(coredump_path) = self.coredump_on_trap.as_ref() {
-                    let source_name = self.module_and_args[0]
-                        .to_str()
-                        .unwrap_or_else(|| "unknown");
-
-                    if let Err(coredump_err) = generate_coredump(&err, &source_name, coredump_path)
-                    {
-                        eprintln!("warning: coredump failed to generate: {}", coredump_err);
-                        err
-                    } else {
-                        err.context(format!("core dumped at {}", coredump_path))
-                    }
-                } else {
-                    err
-                }
-            } else {
-                err
-            };
-            return Err(err);
+            return Err(self.handle_coredump(err));
         }
 
         if !results.is_empty() {
@@ -641,6 +789,25 @@ impl RunCommand {
         Ok(())
     }
 
+    /// Writes a coredump for `err` if it's a trap and `--coredump-on-trap` was
+    /// passed, returning the error to report.
+    fn handle_coredump(&self, err: anyhow::Error) -> anyhow::Error {
+        let coredump_path = match &self.coredump_on_trap {
+            Some(path) if err.is::<wasmtime::Trap>() => path,
+            _ => return err,
+        };
+        let source_name = self.module_and_args[0]
+            .to_str()
+            .unwrap_or_else(|| "unknown");
+
+        if let Err(coredump_err) = generate_coredump(&err, &source_name, coredump_path) {
+            eprintln!("warning: coredump failed to generate: {}", coredump_err);
+            err
+        } else {
+            err.context(format!("core dumped at {}", coredump_path))
+        }
+    }
+
     fn load_module(&self, engine: &Engine, path: &Path) -> Result<Module> {
         let path = match path.to_str() {
             #[cfg(unix)]
@@ -655,6 +822,150 @@ impl RunCommand {
                 .context("if you're trying to run a precompiled module, pass --allow-precompiled")
         }
     }
+
+    /// Loads the main input, reading it only once to detect whether it's a
+    /// core wasm module or a component.
+    fn load_main(&self, engine: &Engine) -> Result<MainInput> {
+        let path = self.module_and_args[0].as_path();
+        let path = match path.to_str() {
+            #[cfg(unix)]
+            Some("-") => "/dev/stdin".as_ref(),
+            _ => path,
+        };
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("failed to read input file: {}", path.display()))?;
+
+        // Precompiled artifacts are mapped from the file instead of being
+        // copied out of `bytes`, so they can't be piped in.
+        match engine.detect_precompiled(&bytes) {
+            Some(wasmtime::Precompiled::Module) if self.allow_precompiled => {
+                let module = unsafe { Module::deserialize_file(engine, path)? };
+                return Ok(MainInput::Module(module));
+            }
+            #[cfg(feature = "component-model")]
+            Some(wasmtime::Precompiled::Component) => {
+                if !self.allow_precompiled {
+                    bail!("running a precompiled component requires --allow-precompiled");
+                }
+                let component = unsafe { Component::deserialize_file(engine, path)? };
+                return Ok(MainInput::Component(component));
+            }
+            _ => {}
+        }
+
+        let bytes = wat::parse_bytes(&bytes).map_err(|mut e| {
+            e.set_path(path);
+            e
+        })?;
+        #[cfg(feature = "component-model")]
+        if wasmparser::Parser::is_component(&bytes) {
+            let component = Component::from_binary(engine, &bytes)?;
+            return Ok(MainInput::Component(component));
+        }
+        let module = Module::from_binary(engine, &bytes)
+            .context("if you're trying to run a precompiled module, pass --allow-precompiled")?;
+        Ok(MainInput::Module(module))
+    }
+
+    /// Runs a component which implements the `wasi:preview/command` world.
+    #[cfg(feature = "component-model")]
+    fn run_component(
+        &self,
+        engine: &Engine,
+        component: Component,
+        preopen_dirs: Vec<(String, Dir, DirOptions)>,
+        argv: &[String],
+    ) -> Result<()> {
+        if self.invoke.is_some() {
+            bail!("using `--invoke` with components is not supported yet");
+        }
+        if !self.preloads.is_empty() {
+            bail!("using `--preload` with components is not supported yet");
+        }
+        if !self.tcplisten.is_empty() || self.listenfd {
+            bail!("preopened sockets are not supported with components yet");
+        }
+
+        let mut linker = wasmtime::component::Linker::new(engine);
+        preview2::command::sync::add_to_linker(&mut linker)?;
+
+        let mut builder = preview2::WasiCtxBuilder::new();
+        builder.inherit_stdio().args(argv);
+        for (key, value) in self.vars.iter() {
+            let value = match value {
+                Some(value) => value.clone(),
+                None => std::env::var(key)
+                    .map_err(|_| anyhow!("environment variable `{key}` not found"))?,
+            };
+            builder.env(key, &value);
+        }
+        if let Some(level) = self.guest_log_level {
+            use preview2::bindings::logging::handler::Level;
+            builder.log_level(match level {
+                GuestLogLevel::Off => None,
+                GuestLogLevel::Error => Some(Level::Error),
+                GuestLogLevel::Warn => Some(Level::Warn),
+                GuestLogLevel::Info => Some(Level::Info),
+                GuestLogLevel::Debug => Some(Level::Debug),
+                GuestLogLevel::Trace => Some(Level::Trace),
+            });
+        }
+        for (name, dir, options) in preopen_dirs {
+            let (perms, file_perms) = if options.read_only {
+                (preview2::DirPerms::READ, preview2::FilePerms::READ)
+            } else {
+                (preview2::DirPerms::all(), preview2::FilePerms::all())
+            };
+            if options.read_only_paths.is_empty() && options.deny.is_empty() {
+                builder.preopened_dir(dir, perms, file_perms, name);
+                continue;
+            }
+            let mut acl = preview2::PathAcl::new();
+            for pattern in options.read_only_paths.iter() {
+                acl.read_only(pattern)
+                    .with_context(|| format!("invalid pattern `{pattern}` for `{name}`"))?;
+            }
+            for pattern in options.deny.iter() {
+                acl.hide(pattern)
+                    .with_context(|| format!("invalid pattern `{pattern}` for `{name}`"))?;
+            }
+            builder.preopened_dir_with_acl(dir, perms, file_perms, acl, name);
+        }
+        let mut table = preview2::Table::new();
+        let ctx = builder.build(&mut table)?;
+
+        let mut store = Store::new(
+            engine,
+            Host {
+                preview2_ctx: Some(Arc::new(ctx)),
+                preview2_table: Some(Arc::new(table)),
+                ..Host::default()
+            },
+        );
+        self.configure_store(&mut store)?;
+
+        let (command, _instance) =
+            preview2::command::sync::Command::instantiate(&mut store, &component, &linker)?;
+
+        let component_name = argv[0].clone();
+        let finish_epoch_handler = self.setup_epoch_handler(&mut store, |interval| {
+            GuestProfiler::new_component(&component_name, interval, component, Vec::new())
+        });
+        let result = command.call_run(&mut store);
+        finish_epoch_handler(&mut store);
+        let result = result.map_err(|e| self.handle_coredump(e));
+
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(())) => Err(wasmtime_wasi::I32Exit(1).into()),
+            // Exits requested through the preview2 `exit` interface are
+            // translated so `maybe_exit_on_error` can handle them.
+            Err(e) => match e.downcast_ref::<preview2::I32Exit>() {
+                Some(exit) => Err(wasmtime_wasi::I32Exit(exit.0).into()),
+                None => Err(e),
+            },
+        }
+    }
 }
 
 #[derive(Default, Clone)]
@@ -666,10 +977,35 @@ struct Host {
     wasi_threads: Option<Arc<WasiThreadsCtx<Host>>>,
     #[cfg(feature = "wasi-http")]
     wasi_http: Option<WasiHttp>,
+    #[cfg(feature = "component-model")]
+    preview2_ctx: Option<Arc<preview2::WasiCtx>>,
+    #[cfg(feature = "component-model")]
+    preview2_table: Option<Arc<preview2::Table>>,
     limits: StoreLimits,
     guest_profiler: Option<Arc<GuestProfiler>>,
 }
 
+#[cfg(feature = "component-model")]
+impl preview2::WasiView for Host {
+    fn table(&self) -> &preview2::Table {
+        self.preview2_table.as_ref().unwrap()
+    }
+
+    fn table_mut(&mut self) -> &mut preview2::Table {
+        Arc::get_mut(self.preview2_table.as_mut().unwrap())
+            .expect("preview2 is not compatible with threads")
+    }
+
+    fn ctx(&self) -> &preview2::WasiCtx {
+        self.preview2_ctx.as_ref().unwrap()
+    }
+
+    fn ctx_mut(&mut self) -> &mut preview2::WasiCtx {
+        Arc::get_mut(self.preview2_ctx.as_mut().unwrap())
+            .expect("preview2 is not compatible with threads")
+    }
+}
+
 /// Populates the given `Linker` with WASI APIs.
 fn populate_with_wasi(
     linker: &mut Linker<Host>,
@@ -681,9 +1017,14 @@ fn populate_with_wasi(
     wasi_modules: &WasiModules,
     listenfd: bool,
     mut tcplisten: Vec<TcpListener>,
+    wasi_nn_graphs: &[(String, String, PathBuf)],
 ) -> Result<()> {
     if wasi_modules.wasi_common {
-        wasmtime_wasi::add_to_linker(linker, |host| host.wasi.as_mut().unwrap())?;
+        wasmtime_wasi::add_to_linker_with_pointer_width(
+            linker,
+            wasmtime_wasi::PointerWidth::of_module(&module),
+            |host| host.wasi.as_mut().unwrap(),
+        )?;
 
         let mut builder = WasiCtxBuilder::new();
         builder.inherit_stdio().args(argv)?;
@@ -733,8 +1074,17 @@ fn populate_with_wasi(
                 Arc::get_mut(host.wasi_nn.as_mut().unwrap())
                     .expect("wasi-nn is not implemented with multi-threading support")
             })?;
-            store.data_mut().wasi_nn = Some(Arc::new(WasiNnCtx::new()?));
+            let mut builder = WasiNnCtx::builder();
+            builder.backend(GraphEncoding::Openvino, OpenvinoBackend::default());
+            for (name, encoding, path) in wasi_nn_graphs {
+                builder
+                    .preload(name, encoding.parse()?, path)
+                    .with_context(|| format!("failed to preload wasi-nn graph `{name}`"))?;
+            }
+            store.data_mut().wasi_nn = Some(Arc::new(builder.build()));
         }
+    } else if !wasi_nn_graphs.is_empty() {
+        bail!("`--wasi-nn-graph` requires wasi-nn to be enabled");
     }
 
     if wasi_modules.wasi_threads {
@@ -751,10 +1101,19 @@ fn populate_with_wasi(
             wasmtime_wasi_threads::add_to_linker(linker, store, &module, |host| {
                 host.wasi_threads.as_ref().unwrap()
             })?;
-            store.data_mut().wasi_threads = Some(Arc::new(WasiThreadsCtx::new(
-                module,
-                Arc::new(linker.clone()),
-            )?));
+            let wasi_threads = Arc::new(WasiThreadsCtx::new(module, Arc::new(linker.clone()))?);
+
+            // By specification, a trap or `proc_exit` in any thread ends
+            // execution for all of them, which for the CLI means exiting.
+            let watcher = wasi_threads.clone();
+            thread::spawn(move || {
+                if let Some(e) = watcher.wait_for_termination() {
+                    let e = maybe_exit_on_error(e);
+                    eprintln!("Error: {:?}", e);
+                    std::process::exit(1);
+                }
+            });
+            store.data_mut().wasi_threads = Some(wasi_threads);
         }
     }
 
@@ -806,35 +1165,17 @@ fn ctx_set_listenfd(mut num_fd: usize, builder: &mut WasiCtxBuilder) -> Result<u
 }
 
 fn generate_coredump(err: &anyhow::Error, source_name: &str, coredump_path: &str) -> Result<()> {
-    let bt = err
-        .downcast_ref::<wasmtime::WasmBacktrace>()
-        .ok_or_else(|| anyhow!("no wasm backtrace found to generate coredump with"))?;
-
-    let coredump = wasm_encoder::CoreDumpSection::new(source_name);
-    let mut stacksection = wasm_encoder::CoreDumpStackSection::new("main");
-    for f in bt.frames() {
-        // We don't have the information at this point to map frames to
-        // individual instances of a module, so we won't be able to create the
-        // "frame ∈ instance ∈ module" hierarchy described in the core dump spec
-        // until we move core dump generation into the runtime. So for now
-        // instanceidx will be 0 for all frames
-        let instanceidx = 0;
-        stacksection.frame(
-            instanceidx,
-            f.func_index(),
-            u32::try_from(f.func_offset().unwrap_or(0)).unwrap(),
-            // We don't currently have access to locals/stack values
-            [],
-            [],
-        );
-    }
-    let mut module = wasm_encoder::Module::new();
-    module.section(&coredump);
-    module.section(&stacksection);
+    let coredump = err
+        .downcast_ref::<wasmtime::WasmCoreDump>()
+        .ok_or_else(|| anyhow!("no wasm coredump found to generate coredump with"))?;
+
+    // The coredump covers every core instance in the store, which for
+    // components includes all of the component's core instances.
+    let coredump = coredump.serialize(source_name);
 
     let mut f = File::create(coredump_path)
         .context(format!("failed to create file at `{}`", coredump_path))?;
-    f.write_all(module.as_slice())
+    f.write_all(&coredump)
         .with_context(|| format!("failed to write coredump file at `{}`", coredump_path))?;
     Ok(())
 }
@@ -199,6 +199,20 @@ fn hello_wasi_snapshot1() -> Result<()> {
     Ok(())
 }
 
+// Hello world from a guest with a 64-bit memory, which passes pointers and
+// sizes to WASI as `i64`s and lays out its iovecs with them.
+#[test]
+fn hello_wasi_snapshot1_memory64() -> Result<()> {
+    let wasm = build_wasm("tests/all/cli_tests/hello_wasi_snapshot1_memory64.wat")?;
+    let stdout = run_wasmtime(&[
+        "--disable-cache",
+        "--wasm-features=memory64",
+        wasm.path().to_str().unwrap(),
+    ])?;
+    assert_eq!(stdout, "Hello, world!\n");
+    Ok(())
+}
+
 #[test]
 fn timeout_in_start() -> Result<()> {
     let wasm = build_wasm("tests/all/cli_tests/iloop-start.wat")?;
@@ -576,6 +590,28 @@ fn run_threads() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "wasi-threads")]
+#[test]
+fn run_threads_trap() -> Result<()> {
+    let wasm = build_wasm("tests/all/cli_tests/threads-trap.wat")?;
+    let output = run_wasmtime_for_output(
+        &[
+            "run",
+            "--wasi-modules",
+            "experimental-wasi-threads",
+            "--wasm-features",
+            "threads",
+            "--disable-cache",
+            wasm.path().to_str().unwrap(),
+        ],
+        None,
+    )?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unreachable"), "bad stderr: {stderr}");
+    Ok(())
+}
+
 #[cfg(feature = "wasi-threads")]
 #[test]
 fn run_simple_with_wasi_threads() -> Result<()> {
@@ -731,3 +767,110 @@ fn wasi_misaligned_pointer() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "component-model")]
+fn run_component_with_guest_profile() -> Result<()> {
+    let profile = TempDir::new()?;
+    let profile = profile.path().join("profile.json");
+    let profile_arg = format!("--profile=guest,{},1ms", profile.display());
+    run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--wasm-features=component-model",
+        &profile_arg,
+        "tests/all/cli_tests/component-spin.wat",
+    ])?;
+
+    // The spinning guest function is sampled, so its frames are named in the
+    // profile after it and the core module it belongs to.
+    fn names_spin(value: &serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::String(s) => s == "m!spin",
+            serde_json::Value::Array(values) => values.iter().any(names_spin),
+            serde_json::Value::Object(values) => values.values().any(names_spin),
+            _ => false,
+        }
+    }
+    let profile: serde_json::Value = serde_json::from_slice(&std::fs::read(&profile)?)?;
+    assert!(names_spin(&profile), "{profile}");
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "component-model")]
+fn run_component_with_dir_options() -> Result<()> {
+    let dir = TempDir::new()?;
+    let dir_arg = format!("--dir={}:ro,deny=**/.git,ro=config", dir.path().display());
+    run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--wasm-features=component-model",
+        &dir_arg,
+        "tests/all/cli_tests/component-command.wat",
+    ])?;
+
+    // Options aren't supported for core modules.
+    let err = run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        &dir_arg,
+        "tests/all/cli_tests/minimal-command.wat",
+    ])
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("options on `--dir` are only supported with components"));
+
+    let bad_arg = format!("--dir={}:deny=[", dir.path().display());
+    let err = run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--wasm-features=component-model",
+        &bad_arg,
+        "tests/all/cli_tests/component-command.wat",
+    ])
+    .unwrap_err();
+    assert!(err.to_string().contains("invalid pattern"), "{err}");
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "component-model")]
+fn run_component_coredump() -> Result<()> {
+    let coredump_file = NamedTempFile::new()?;
+    let coredump_arg = format!("--coredump-on-trap={}", coredump_file.path().display());
+    let err = run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--wasm-features=component-model",
+        &coredump_arg,
+        "tests/all/cli_tests/component-trap.wat",
+    ])
+    .unwrap_err();
+    assert!(err.to_string().contains(&format!(
+        "core dumped at {}",
+        coredump_file.path().display()
+    )));
+
+    let coredump = std::fs::read(coredump_file.path())?;
+    let mut custom_sections = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&coredump) {
+        if let wasmparser::Payload::CustomSection(s) = payload? {
+            custom_sections.push(s.name().to_string());
+        }
+    }
+    for name in [
+        "core",
+        "coremodules",
+        "coreinstances",
+        "corestack",
+        "wasmtime-component-instances",
+    ] {
+        assert!(
+            custom_sections.iter().any(|s| s == name),
+            "missing `{name}` in {custom_sections:?}"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,14 @@
+(component
+  (core module $m
+    (func $spin (export "run") (result i32)
+      (local $i i32)
+      (local.set $i (i32.const 100000000))
+      (loop $loop
+        (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+        (br_if $loop (local.get $i)))
+      i32.const 0)
+  )
+  (core instance $i (instantiate $m))
+  (func (export "run") (result (result))
+    (canon lift (core func $i "run")))
+)
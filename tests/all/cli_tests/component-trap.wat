@@ -0,0 +1,11 @@
+(component
+  (core module $m
+    (memory (export "memory") 1)
+    (global $g (mut i32) (i32.const 42))
+    (func (export "run") (result i32)
+      unreachable)
+  )
+  (core instance $i (instantiate $m))
+  (func (export "run") (result (result))
+    (canon lift (core func $i "run")))
+)
@@ -0,0 +1,28 @@
+(module
+  (import "wasi_snapshot_preview1" "proc_exit"
+    (func $__wasi_proc_exit (param i32)))
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $__wasi_fd_write (param i32 i64 i64 i64) (result i32)))
+  (func $_start
+    ;; With a 64-bit memory the iovec's pointer and length are 8 bytes each,
+    ;; as is the number of bytes written.
+    (i64.store (i64.const 32) (i64.const 0))
+    (i64.store (i64.const 40) (i64.const 14))
+    (i64.store (i64.const 16) (i64.const -1))
+    (block
+      (br_if 0
+        (call $__wasi_fd_write
+          (i32.const 1)
+          (i64.const 32)
+          (i64.const 1)
+          (i64.const 16)))
+      (br_if 0 (i64.ne (i64.load (i64.const 16)) (i64.const 14)))
+      (br 1)
+    )
+    (call $__wasi_proc_exit (i32.const 1))
+  )
+  (memory i64 1)
+  (export "memory" (memory 0))
+  (export "_start" (func $_start))
+  (data (i64.const 0) "Hello, world!\0a")
+)
@@ -0,0 +1,19 @@
+(module
+  (import "" "memory" (memory $shmem 1 1 shared))
+  (import "wasi" "thread-spawn"
+    (func $__wasi_thread_spawn (param i32) (result i32)))
+
+  (func (export "_start")
+    ;; Spawn a thread which traps, then wait forever for a notification
+    ;; which never comes; the trap must end execution for the main thread
+    ;; as well.
+    (drop (call $__wasi_thread_spawn (i32.const 0)))
+    (drop (memory.atomic.wait32 (i32.const 128) (i32.const 0) (i64.const -1)))
+  )
+
+  (func (export "wasi_thread_start") (param $tid i32) (param $start_arg i32)
+    unreachable
+  )
+
+  (export "memory" (memory $shmem))
+)
@@ -1,6 +1,6 @@
 #![cfg(not(miri))]
 
-use super::engine;
+use super::{async_engine, engine};
 use anyhow::Result;
 use wasmtime::{
     component::{Component, Linker},
@@ -120,3 +120,195 @@ mod one_import {
         Ok(())
     }
 }
+
+mod selective_async {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world selective-async {
+                import foo: interface {
+                    slow: func() -> u32
+                    fast: func() -> u32
+                }
+
+                export bar: func() -> u32
+                export baz: func() -> u32
+            }
+        ",
+        async: {
+            only_imports: ["slow"],
+        },
+    });
+
+    #[tokio::test]
+    async fn run() -> Result<()> {
+        let engine = async_engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import "foo" (instance $i
+                        (export "slow" (func (result u32)))
+                        (export "fast" (func (result u32)))
+                    ))
+                    (core module $m
+                        (import "" "slow" (func $slow (result i32)))
+                        (import "" "fast" (func $fast (result i32)))
+                        (func (export "") (result i32)
+                            call $slow
+                            call $fast
+                            i32.add)
+                        (func (export "fast") (result i32)
+                            call $fast)
+                    )
+                    (core func $slow (canon lower (func $i "slow")))
+                    (core func $fast (canon lower (func $i "fast")))
+                    (core instance $i (instantiate $m
+                        (with "" (instance
+                            (export "slow" (func $slow))
+                            (export "fast" (func $fast))
+                        ))
+                    ))
+
+                    (func $f (export "bar") (result u32)
+                        (canon lift (core func $i "")))
+                    (func $g (export "baz") (result u32)
+                        (canon lift (core func $i "fast")))
+                )
+            "#,
+        )?;
+
+        struct MyImports;
+
+        #[async_trait::async_trait]
+        impl foo::Host for MyImports {
+            async fn slow(&mut self) -> Result<u32> {
+                tokio::task::yield_now().await;
+                Ok(1)
+            }
+
+            fn fast(&mut self) -> Result<u32> {
+                Ok(2)
+            }
+        }
+
+        let mut linker = Linker::new(&engine);
+        foo::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports);
+        let (selective, _) =
+            SelectiveAsync::instantiate_async(&mut store, &component, &linker).await?;
+        assert_eq!(selective.call_bar(&mut store).await?, 3);
+        // `baz` only calls synchronous imports, but the store has async
+        // support so it's still called with `call_async`.
+        assert_eq!(selective.call_baz(&mut store).await?, 2);
+        Ok(())
+    }
+}
+
+mod additional_derives_and_types {
+    use super::*;
+    use std::collections::HashSet;
+    use std::hash::Hash;
+    use wasmtime::component::{ComponentType, Lift, Lower};
+
+    #[derive(ComponentType, Lift, Lower, Clone, Copy, Debug, PartialEq)]
+    #[component(record)]
+    pub struct MySize {
+        w: u32,
+        h: u32,
+    }
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world derives {
+                import host: interface {
+                    record point {
+                        x: s32,
+                        y: s32,
+                    }
+                    enum color { red, green }
+                    variant shape { circle(u32), square(u32), empty }
+
+                    paint: func(p: point, c: color, s: shape)
+                }
+
+                export sizes: interface {
+                    record size {
+                        w: u32,
+                        h: u32,
+                    }
+
+                    area: func(s: size) -> u32
+                }
+            }
+        ",
+        additional_derives: [PartialEq, Eq, Hash],
+        with: {
+            "sizes#size": crate::component_model::bindgen::additional_derives_and_types::MySize,
+        },
+    });
+
+    fn assert_hash_eq<T: Hash + Eq>() {}
+
+    #[test]
+    fn derives() {
+        assert_hash_eq::<host::Point>();
+        assert_hash_eq::<host::Color>();
+        assert_hash_eq::<host::Shape>();
+
+        let mut set = HashSet::new();
+        set.insert(host::Shape::Circle(1));
+        set.insert(host::Shape::Circle(1));
+        set.insert(host::Shape::Empty);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn remapped_type() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (core module $m
+                        (func (export "area") (param i32 i32) (result i32)
+                            (i32.mul (local.get 0) (local.get 1)))
+                    )
+                    (core instance $i (instantiate $m))
+
+                    (type $size (record (field "w" u32) (field "h" u32)))
+
+                    (func $f_area (param "s" $size) (result u32)
+                        (canon lift (core func $i "area"))
+                    )
+
+                    (component $c_sizes
+                        (import "import-size" (type $import-size (eq $size)))
+                        (import "import-area" (func $f (param "s" $import-size) (result u32)))
+                        (export $export-size "size" (type $size))
+                        (export "area" (func $f) (func (param "s" $export-size) (result u32)))
+                    )
+                    (instance $i_sizes (instantiate $c_sizes
+                        (with "import-size" (type $size))
+                        (with "import-area" (func $f_area))
+                    ))
+                    (export "sizes" (instance $i_sizes))
+                )
+            "#,
+        )?;
+
+        let linker = Linker::new(&engine);
+        let mut store = Store::new(&engine, ());
+        let (derives, _) = Derives::instantiate(&mut store, &component, &linker)?;
+        let size: exports::sizes::Size = MySize { w: 3, h: 4 };
+        assert_eq!(derives.sizes().call_area(&mut store, size)?, 12);
+        Ok(())
+    }
+}
@@ -581,6 +581,59 @@ fn dynamic_val() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn dynamic_host_func_result_types() -> Result<()> {
+    let engine = super::engine();
+    let c = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "t" (type $t (sub resource)))
+                (import "f" (func $f (param "x" (own $t)) (result (tuple (own $t)))))
+                (core func $f (canon lower (func $f)))
+
+                (core module $m
+                    (import "" "f" (func $f (param i32) (result i32)))
+                    (func (export "run") (param i32) (result i32)
+                        (call $f (local.get 0))))
+                (core instance $i (instantiate $m
+                    (with "" (instance (export "f" (func $f))))
+                ))
+
+                (func (export "run") (param "x" (own $t)) (result (tuple (own $t)))
+                    (canon lift (core func $i "run")))
+            )
+        "#,
+    )?;
+
+    struct MyType;
+
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    linker.root().resource::<MyType>("t", |_, _| {})?;
+    linker
+        .root()
+        .func_new_with_result_types(&c, "f", |_, params, result_types, results| {
+            match &result_types[0] {
+                Type::Tuple(t) => {
+                    let types = t.types().collect::<Vec<_>>();
+                    assert_eq!(types, [Type::Own(ResourceType::host::<MyType>())]);
+                    results[0] = t.new_val(Box::new([params[0].clone()]))?;
+                }
+                _ => unreachable!(),
+            }
+            Ok(())
+        })?;
+    let i = linker.instantiate(&mut store, &c)?;
+
+    let run = i.get_typed_func::<(Resource<MyType>,), ((Resource<MyType>,),)>(&mut store, "run")?;
+    let ((t,),) = run.call(&mut store, (Resource::new_own(100),))?;
+    run.post_return(&mut store)?;
+    assert_eq!(t.rep(), 100);
+
+    Ok(())
+}
+
 #[test]
 fn cannot_reenter_during_import() -> Result<()> {
     let engine = super::engine();
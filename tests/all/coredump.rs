@@ -145,3 +145,71 @@ fn test_coredump_has_import_globals_and_memory() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_coredump_serializes_state_at_trap() -> Result<()> {
+    let mut config = Config::default();
+    config.coredump_on_trap(true);
+    config.wasm_memory64(true);
+    let engine = Engine::new(&config).unwrap();
+    let mut store = Store::<()>::new(&engine, ());
+
+    let wat = r#"
+      (module
+        (memory (export "memory") i64 1)
+        (global $g (export "g") (mut i32) (i32.const 0))
+        (func (export "a")
+          (i64.store8 (i64.const 0) (i64.const 42))
+          (global.set $g (i32.const 42))
+          unreachable
+        )
+      )
+    "#;
+
+    let module = Module::new(store.engine(), wat)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let a_func = instance.get_typed_func::<(), ()>(&mut store, "a")?;
+    let e = a_func.call(&mut store, ()).unwrap_err();
+    let cd = e.downcast_ref::<WasmCoreDump>().unwrap();
+
+    // Changes to the store after the trap aren't part of the core dump.
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    memory.data_mut(&mut store)[0] = 7;
+    let global = instance.get_global(&mut store, "g").unwrap();
+    global.set(&mut store, Val::I32(7))?;
+
+    let serialized = cd.serialize("test");
+    let mut data = None;
+    let mut global_init = None;
+    for payload in wasmparser::Parser::new(0).parse_all(&serialized) {
+        match payload? {
+            wasmparser::Payload::DataSection(reader) => {
+                let segment = reader.into_iter().next().unwrap()?;
+                match segment.kind {
+                    wasmparser::DataKind::Active { offset_expr, .. } => {
+                        let offset = offset_expr.get_operators_reader().read()?;
+                        assert!(matches!(
+                            offset,
+                            wasmparser::Operator::I64Const { value: 0 }
+                        ));
+                    }
+                    wasmparser::DataKind::Passive => panic!("memory data should be active"),
+                }
+                data = Some(segment.data[0]);
+            }
+            wasmparser::Payload::GlobalSection(reader) => {
+                let global = reader.into_iter().next().unwrap()?;
+                global_init = Some(global.init_expr.get_operators_reader().read()?);
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(data, Some(42));
+    assert!(matches!(
+        global_init,
+        Some(wasmparser::Operator::I32Const { value: 42 })
+    ));
+
+    Ok(())
+}
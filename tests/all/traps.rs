@@ -145,6 +145,81 @@ fn test_trap_trace() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_trap_trace_inlined() -> Result<()> {
+    let mut config = Config::new();
+    config.cranelift_inlining(true);
+    let engine = Engine::new(&config)?;
+    let mut store = Store::new(&engine, ());
+    let wat = r#"
+        (module $hello_mod
+            (func (export "run") (call $hello))
+            (func $hello (unreachable))
+        )
+    "#;
+
+    let module = Module::new(store.engine(), wat)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run_func = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+
+    let e = run_func.call(&mut store, ()).unwrap_err();
+
+    // `hello` is inlined into `run`, but still shows up in the backtrace.
+    let trace = e.downcast_ref::<WasmBacktrace>().unwrap().frames();
+    assert_eq!(trace.len(), 2);
+    assert_eq!(trace[0].func_index(), 1);
+    assert_eq!(trace[0].func_name(), Some("hello"));
+    assert_eq!(trace[0].func_offset(), Some(1));
+    assert_eq!(trace[0].module_offset(), Some(0x26));
+    assert_eq!(trace[1].func_index(), 0);
+    assert_eq!(trace[1].func_offset(), Some(1));
+    assert_eq!(trace[1].module_offset(), Some(0x21));
+    assert_eq!(e.downcast::<Trap>()?, Trap::UnreachableCodeReached);
+
+    Ok(())
+}
+
+#[test]
+fn test_trap_trace_hot_cold_splitting() -> Result<()> {
+    // Cold code has no native unwind info, which Windows requires.
+    if cfg!(windows) {
+        return Ok(());
+    }
+
+    let mut config = Config::new();
+    config.cranelift_hot_cold_splitting(true);
+    assert!(Engine::new(&config).is_err());
+    config.native_unwind_info(false);
+    let engine = Engine::new(&config)?;
+    let mut store = Store::new(&engine, ());
+    let wat = r#"
+        (module $hello_mod
+            (table 1 funcref)
+            (func (export "run") (call $hello))
+            (func $hello (call_indirect (i32.const 0)))
+        )
+    "#;
+
+    let module = Module::new(store.engine(), wat)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run_func = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+
+    let e = run_func.call(&mut store, ()).unwrap_err();
+
+    // The null check's trap is in the cold part of `hello`, which is still
+    // attributed to it.
+    let trace = e.downcast_ref::<WasmBacktrace>().unwrap().frames();
+    assert_eq!(trace.len(), 2);
+    assert_eq!(trace[0].func_index(), 1);
+    assert_eq!(trace[0].func_name(), Some("hello"));
+    assert_eq!(trace[0].func_offset(), Some(3));
+    assert_eq!(trace[1].func_index(), 0);
+    assert_eq!(trace[1].func_offset(), Some(1));
+    assert_eq!(e.downcast::<Trap>()?, Trap::IndirectCallToNull);
+
+    Ok(())
+}
+
 #[test]
 fn test_trap_through_host() -> Result<()> {
     let wat = r#"